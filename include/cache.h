@@ -41,7 +41,13 @@ using schema_patches = std::map<SchemaVersion, std::vector<std::string>>;
 
 class Cache {
 public:
-	Cache(const std::string& cachefile, ConfigContainer* c);
+	/// Opens (and, unless `read_only` is set, creates and upgrades) the cache
+	/// stored in `cachefile`.
+	///
+	/// A read-only cache never writes to the database: methods that would
+	/// modify it do nothing instead.
+	Cache(const std::string& cachefile, ConfigContainer* c,
+		bool read_only = false);
 	~Cache();
 	void externalize_rssfeed(std::shared_ptr<RssFeed> feed,
 		bool reset_unread);
@@ -75,11 +81,16 @@ public:
 	void remove_old_deleted_items(RssFeed* feed);
 	void mark_items_read_by_guid(const std::vector<std::string>& guids);
 	std::vector<std::string> get_read_item_guids();
+	/// Returns those of `guids` that don't belong to any article in the
+	/// cache, in the same order.
+	std::vector<std::string> get_unknown_item_guids(
+		const std::vector<std::string>& guids);
 	void fetch_descriptions(RssFeed* feed);
 	std::string fetch_description(const RssItem& item);
 
 private:
 	SchemaVersion get_schema_version();
+	void check_schema_version();
 	void populate_tables();
 	void set_pragmas();
 	void delete_item(const std::shared_ptr<RssItem>& item);
@@ -110,6 +121,7 @@ private:
 
 	sqlite3* db;
 	ConfigContainer* cfg;
+	const bool read_only;
 	std::mutex mtx;
 };
 
@@ -3,11 +3,14 @@
 
 #include "cliargsparser.rs.h"
 
+#include <chrono>
 #include <string>
 #include <vector>
 
+#include "3rd-party/expected.hpp"
 #include "3rd-party/optional.hpp"
 
+#include "keymap.h"
 #include "logger.h"
 
 namespace newsboat {
@@ -77,8 +80,75 @@ public:
 
 	nonstd::optional<std::string> log_file() const;
 
+	/// If non-null, Newsboat should record how long the measured scopes
+	/// took, and write that to this file in Chrome's trace event format
+	/// before quitting.
+	nonstd::optional<std::string> trace_file() const;
+
 	nonstd::optional<Level> log_level() const;
 
+	/// Log levels for specific modules, like `info,libnewsboat::fslock=debug`.
+	/// Includes log_level(), if it's set. Pass this to
+	/// Logger::set_log_filter().
+	nonstd::optional<std::string> log_filter() const;
+
+	nonstd::optional<LogFormat> log_format() const;
+
+	/// If non-null, the logs should be rotated to stay within these limits.
+	nonstd::optional<LogRotation> log_rotation() const;
+
+	/// If non-null, Newsboat should print the effective key bindings in
+	/// this format, and quit.
+	nonstd::optional<KeymapDumpFormat> keymap_dump_format() const;
+
+	/// If non-null, Newsboat should wait up to this long for the lock held
+	/// by another instance to be released, instead of quitting right away.
+	nonstd::optional<std::chrono::seconds> wait_for_lock() const;
+
+	/// If true, Newsboat should open the cache read-only instead of
+	/// taking the lock.
+	bool read_only() const;
+
+	/// If true and the lock is held by a process that is gone (or runs on
+	/// another host), Newsboat should take the lock over.
+	bool take_over_lock() const;
+
+	/// If non-null, Newsboat should use configs and data of the profile
+	/// with this name.
+	nonstd::optional<std::string> profile() const;
+
+	/// If true, Newsboat should print the names of existing profiles and
+	/// quit.
+	bool list_profiles() const;
+
+	/// If true, Newsboat should print the newest saved crash report and
+	/// quit.
+	bool last_crash_report() const;
+
+	/// If true, Newsboat should describe what migration from other feed
+	/// readers would do, and quit without touching any files.
+	bool migration_dry_run() const;
+
+	/// If true, Newsboat should check the config file for mistakes, report
+	/// them, and quit.
+	bool check_config() const;
+
+	/// If non-null, Newsboat should print this script, which sets up
+	/// completions of the command line in the shell chosen by the user, and
+	/// quit.
+	nonstd::optional<std::string> completions_script() const;
+
+	/// If true, Newsboat should run the query about the cache that the user
+	/// asked for, print the answer, and quit.
+	bool has_query() const;
+
+	/// Runs the query against the cache and the list of subscriptions in
+	/// the urls file, and returns the answer formatted the way the user
+	/// asked. If the query can't be answered, returns an explanation.
+	nonstd::expected<std::string, std::string> run_query(
+		const std::string& cache_file,
+		const std::string& url_file) const;
+
 	/// Returns the pointer to the Rust object.
 	///
 	/// This is only meant to be used in situations when one wants to pass
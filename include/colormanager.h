@@ -15,6 +15,7 @@ class View;
 
 namespace newsboat {
 
+class ConfigChecker;
 class ConfigParser;
 
 struct TextStyle {
@@ -28,6 +29,8 @@ public:
 	ColorManager();
 	~ColorManager() override;
 	void register_commands(ConfigParser& cfgparser);
+	/// Tells the checker about the elements that `color` accepts.
+	void register_elements(ConfigChecker& checker) const;
 	void handle_action(const std::string& action,
 		const std::vector<std::string>& params) override;
 	void dump_config(std::vector<std::string>& config_output) const override;
@@ -0,0 +1,51 @@
+#ifndef NEWSBOAT_CONFIGCHECK_H_
+#define NEWSBOAT_CONFIGCHECK_H_
+
+#include "configcheck.rs.h"
+
+#include <string>
+#include <vector>
+
+#include "3rd-party/expected.hpp"
+
+namespace newsboat {
+
+class ConfigData;
+
+/// Checks config files for mistakes without applying them, reporting every
+/// problem rather than stopping at the first one.
+class ConfigChecker {
+public:
+	ConfigChecker();
+	~ConfigChecker() = default;
+
+	/// Makes `name` a valid command. Commands that aren't settings and
+	/// whose syntax the checker doesn't know accept any parameters.
+	void add_command(const std::string& name);
+
+	/// Makes `name` a valid command that accepts the same values as `data`.
+	void add_setting(const std::string& name, const ConfigData& data);
+
+	/// Makes `name` a valid operation for `bind-key`, `macro` and
+	/// `run-on-startup`.
+	void add_operation(const std::string& name);
+
+	/// Makes `name` a valid context for `bind-key` and `unbind-key`.
+	void add_context(const std::string& name);
+
+	/// Makes `name` a valid element for the `color` command.
+	void add_color_element(const std::string& name);
+
+	/// Checks `config_file` and the files it includes. Returns descriptions
+	/// of the problems found, each starting with the file and line, or an
+	/// error message if `config_file` couldn't be read.
+	nonstd::expected<std::vector<std::string>, std::string> check(
+		const std::string& config_file) const;
+
+private:
+	rust::Box<configcheck::bridged::ConfigChecker> rs_object;
+};
+
+} // namespace newsboat
+
+#endif /* NEWSBOAT_CONFIGCHECK_H_ */
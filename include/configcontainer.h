@@ -10,6 +10,7 @@
 
 namespace newsboat {
 
+class ConfigChecker;
 class ConfigData;
 class ConfigParser;
 
@@ -61,6 +62,8 @@ public:
 	ConfigContainer();
 	~ConfigContainer() override;
 	void register_commands(ConfigParser& cfgparser);
+	/// Tells the checker about all the settings and the values they accept.
+	void register_settings(ConfigChecker& checker) const;
 	void handle_action(const std::string& action,
 		const std::vector<std::string>& params) override;
 	void dump_config(std::vector<std::string>& config_output) const override;
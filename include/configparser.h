@@ -8,6 +8,8 @@
 
 namespace newsboat {
 
+class ConfigChecker;
+
 enum class ActionHandlerStatus {
 	VALID = 0,
 	INVALID_PARAMS,
@@ -22,6 +24,8 @@ public:
 	~ConfigParser() override;
 	void register_handler(const std::string& cmd,
 		ConfigActionHandler& handler);
+	/// Tells the checker about all the commands that have a handler.
+	void register_commands(ConfigChecker& checker) const;
 	void handle_action(const std::string& action,
 		const std::vector<std::string>& params) override;
 	void dump_config(std::vector<std::string>&) const override
@@ -2,6 +2,7 @@
 #define NEWSBOAT_CONFIGPATHS_H_
 
 #include <string>
+#include <vector>
 
 #include "cliargsparser.h"
 
@@ -31,8 +32,18 @@ public:
 
 	/// If user didn't specify paths to configs on the command line, and the
 	/// config file wasn't found in the standard directories, looks for
-	/// Newsbeuter's directories, and copies their contents if found. Returns
-	/// true if copied something, false otherwise.
+	/// data of other feed readers (the OPML file given with
+	/// `--migrate-from-opml`, or else Newsbeuter's directories), and imports
+	/// it if found. Returns true if imported something, false otherwise.
+	bool try_migrate();
+
+	/// Describes what `try_migrate()` would do, without touching any files:
+	/// directories and files that would be created, files that would be
+	/// skipped, conflicts and permission problems. The description is
+	/// human-readable and ends with a newline.
+	std::string describe_migration_plan() const;
+
+	/// Same as `try_migrate()`, but only looks for Newsbeuter's directories.
 	bool try_migrate_from_newsbeuter();
 
 	/// Creates Newsboat's dotdir or XDG config & data dirs (depending on what
@@ -60,6 +71,12 @@ public:
 	/// \note This changes when path to config file changes.
 	std::string lock_file() const;
 
+	/// Path to the socket through which other processes can control this
+	/// instance.
+	///
+	/// \note This changes when path to cache file changes.
+	std::string control_socket_file() const;
+
 	/// \brief Path to the queue file.
 	///
 	/// Queue file stores enqueued podcasts. It's written by Newsboat, and
@@ -71,6 +88,37 @@ public:
 
 	/// Path to the file with command-line history.
 	std::string cmdline_file() const;
+
+	/// Path to the file with previous queries entered at the article search
+	/// prompt.
+	std::string article_search_file() const;
+
+	/// Path to the file with previous queries entered at the feed search
+	/// prompt.
+	std::string feed_search_file() const;
+
+	/// Path to the file with previous expressions entered at the filter
+	/// prompt.
+	std::string filter_file() const;
+
+	/// Path to the file with GUIDs of articles that were imported as read
+	/// before they were in the cache, and should be marked read after the
+	/// next reload.
+	std::string imported_read_guids_file() const;
+
+	/// Path to the directory where crash reports are saved.
+	std::string crash_reports_dir() const;
+
+	/// Whether the path to the cache file was given on the command line or
+	/// through the environment, in which case `cache-file` setting is
+	/// ignored.
+	bool has_explicit_cache_file() const;
+
+	/// Name of the profile in use, or an empty string if none was chosen.
+	std::string profile() const;
+
+	/// Names of the profiles that exist, in alphabetical order.
+	std::vector<std::string> list_profiles() const;
 };
 } // namespace newsboat
 
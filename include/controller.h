@@ -1,12 +1,18 @@
 #ifndef NEWSBOAT_CONTROLLER_H_
 #define NEWSBOAT_CONTROLLER_H_
 
+#include <atomic>
+#include <deque>
+#include <future>
 #include <libxml/tree.h>
+#include <mutex>
+#include <thread>
 
 #include "cache.h"
 #include "colormanager.h"
 #include "configcontainer.h"
 #include "configparser.h"
+#include "controlsocket.h"
 #include "feedcontainer.h"
 #include "filtercontainer.h"
 #include "fslock.h"
@@ -21,7 +27,9 @@
 namespace newsboat {
 
 class CliArgsParser;
+class ConfigChecker;
 class ConfigPaths;
+class KeyMap;
 class View;
 
 class CurlHandle;
@@ -37,6 +45,11 @@ public:
 	}
 	int run(const CliArgsParser& args);
 
+	/// Tells the checker about everything the config may contain: commands,
+	/// settings, operations, key binding contexts and color elements. These
+	/// are the same ones run() accepts.
+	void register_config_checks(ConfigChecker& checker);
+
 	std::vector<std::shared_ptr<RssItem>> search_for_items(
 			const std::string& query,
 			std::shared_ptr<RssFeed> feed);
@@ -55,6 +68,16 @@ public:
 	{
 		return refresh_on_start;
 	}
+	/// Returns true if the cache is opened read-only, i.e. operations that
+	/// would change it should be refused.
+	bool is_read_only() const
+	{
+		return read_only;
+	}
+	/// Marks read the articles that `newsboat -I` couldn't mark because they
+	/// weren't in the cache yet, then forgets them. Meant to be called once
+	/// the feeds were reloaded, so that the articles are in the cache.
+	void apply_imported_read_information();
 	EnqueueResult enqueue_url(std::shared_ptr<RssItem> item,
 		std::shared_ptr<RssFeed> feed);
 
@@ -114,7 +137,16 @@ public:
 		return colorman;
 	}
 
+	/// \brief Handles the requests that arrived on the control socket.
+	///
+	/// Requests are received on a separate thread, but they change the
+	/// feeds and the UI, so they are queued until the View's main loop calls
+	/// this. Must be called from the thread that runs the View.
+	void handle_control_requests();
+
 private:
+	void register_config_handlers(ConfigParser& parser, KeyMap& keys);
+
 	int import_opml(const std::string& opmlFile, const std::string& urlFile);
 	void export_opml();
 	void rec_find_rss_outlines(xmlNode* node, std::string tag);
@@ -123,10 +155,26 @@ private:
 	void import_read_information(const std::string& readinfofile);
 	void export_read_information(const std::string& readinfofile);
 
+	void start_control_server();
+	void stop_control_server();
+	struct ControlResponse {
+		bool success;
+		std::string message;
+	};
+
+	struct PendingControlRequest {
+		ControlRequest request;
+		std::promise<ControlResponse> response;
+	};
+
+	void serve_control_requests();
+	ControlResponse handle_control_request(const ControlRequest& request);
+
 	View* v;
 	UrlReader* urlcfg;
 	Cache* rsscache;
 	bool refresh_on_start;
+	bool read_only;
 	ConfigContainer cfg;
 	RssIgnores ign;
 	FeedContainer feedcontainer;
@@ -139,6 +187,12 @@ private:
 
 	FsLock fslock;
 
+	std::unique_ptr<ControlServer> control_server;
+	std::thread control_thread;
+	std::atomic<bool> stop_control_thread;
+	std::mutex control_requests_mtx;
+	std::deque<PendingControlRequest> control_requests;
+
 	ConfigPaths& configpaths;
 
 	std::unique_ptr<Reloader> reloader;
@@ -0,0 +1,71 @@
+#ifndef NEWSBOAT_CONTROLSOCKET_H_
+#define NEWSBOAT_CONTROLSOCKET_H_
+
+#include "controlsocket.rs.h"
+
+#include <chrono>
+#include <cstdint>
+#include <string>
+#include <vector>
+
+#include "3rd-party/optional.hpp"
+
+namespace newsboat {
+
+// This has to be in sync with the constants in
+// rust/libnewsboat-ffi/src/controlsocket.rs
+enum class ControlRequestType : std::uint8_t {
+	RELOAD = 1,
+	MARK_READ,
+	QUIT,
+	STATUS
+};
+
+struct ControlRequest {
+	ControlRequestType type;
+
+	/// URL of the feed the request applies to. Empty means all feeds.
+	std::string url;
+};
+
+/// \brief Listens on a Unix domain socket for requests from other processes.
+///
+/// Requests are read one at a time with `next_request()`, and each must be
+/// answered with `respond()` before the next one is read.
+class ControlServer {
+public:
+	explicit ControlServer(const std::string& socket_path);
+	~ControlServer() = default;
+
+	/// Returns `false` if the socket couldn't be created; `error_message()`
+	/// explains why.
+	bool is_listening() const;
+	std::string error_message() const;
+
+	/// Waits up to \a timeout for the next request.
+	nonstd::optional<ControlRequest> next_request(
+		std::chrono::milliseconds timeout);
+
+	void respond(bool success, const std::string& message = "");
+
+private:
+	std::string error;
+	rust::Box<controlsocket::bridged::ControlServer> rs_object;
+};
+
+namespace controlsocket {
+
+/// Sends commands passed to `newsboat -x` to the instance listening at
+/// \a socket_path, and returns the messages it responded with.
+///
+/// If some commands can't be forwarded, the instance can't be contacted, or
+/// it responds with an error, \a error_message is set to the explanation.
+std::vector<std::string> forward_commands(const std::string& socket_path,
+	const std::vector<std::string>& commands,
+	std::string& error_message);
+
+} // namespace controlsocket
+
+} // namespace newsboat
+
+#endif /* NEWSBOAT_CONTROLSOCKET_H_ */
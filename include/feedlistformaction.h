@@ -4,7 +4,6 @@
 #include "3rd-party/optional.hpp"
 
 #include "configcontainer.h"
-#include "history.h"
 #include "listformaction.h"
 #include "matcher.h"
 #include "regexmanager.h"
@@ -87,8 +86,6 @@ private:
 	Matcher matcher;
 	bool apply_filter;
 
-	History filterhistory;
-
 	unsigned int filterpos;
 	bool set_filterpos;
 
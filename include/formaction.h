@@ -12,6 +12,7 @@
 namespace newsboat {
 
 class ConfigContainer;
+class ConfigPaths;
 class RssFeed;
 class View;
 
@@ -53,6 +54,9 @@ public:
 	virtual void finished_qna(Operation op);
 
 	void start_cmdline(std::string default_value = "");
+	/// Asks for the arguments of the macro bound to `key`, then runs the
+	/// macro with them.
+	void start_macro_qna(const std::string& key);
 
 	std::string get_qna_response(unsigned int i)
 	{
@@ -77,11 +81,10 @@ public:
 	virtual std::vector<std::string> get_suggestions(
 		const std::string& fragment);
 
-	static void load_histories(const std::string& searchfile,
-		const std::string& cmdlinefile);
-	static void save_histories(const std::string& searchfile,
-		const std::string& cmdlinefile,
-		unsigned int limit);
+	static void load_histories(const ConfigPaths& configpaths);
+	static void save_histories(const ConfigPaths& configpaths,
+		unsigned int limit,
+		bool timestamps);
 
 	std::string bookmark(const std::string& url,
 		const std::string& title,
@@ -106,7 +109,16 @@ protected:
 
 	std::vector<std::string> qna_responses;
 
+	/// Queries entered at the search prompt of the help dialog.
 	static History searchhistory;
+	/// Queries entered at the search prompts of the article list and the
+	/// article view.
+	static History articlesearchhistory;
+	/// Queries entered at the search prompt of the feed list.
+	static History feedsearchhistory;
+	/// Expressions entered at the filter prompts of the feed list and the
+	/// article list.
+	static History filterhistory;
 	static History cmdlinehistory;
 
 	std::vector<std::string> valid_cmds;
@@ -118,6 +130,7 @@ private:
 	std::vector<QnaPair> qna_prompts;
 	Operation finish_operation;
 	History* qna_history;
+	std::string macro_key;
 	std::shared_ptr<FormAction> parent_formaction;
 };
 
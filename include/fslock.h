@@ -3,6 +3,7 @@
 
 #include "fslock.rs.h"
 
+#include <chrono>
 #include <string>
 #include <sys/types.h>
 
@@ -16,6 +17,29 @@ public:
 	bool try_lock(const std::string& lock_file, pid_t& pid,
 		std::string& error_message);
 
+	/// Like `try_lock()`, but if the lock is held by another process, keeps
+	/// retrying until it's released or `timeout` passes.
+	bool lock_with_timeout(const std::string& lock_file,
+		std::chrono::seconds timeout, pid_t& pid, std::string& error_message);
+
+	/// Takes a shared lock for a read-only instance. Any number of readers
+	/// can hold it alongside the exclusive lock taken by `try_lock()`.
+	bool try_lock_shared(const std::string& lock_file,
+		std::string& error_message);
+
+	/// Returns true if another process holds a shared lock for `lock_file`.
+	static bool has_readers(const std::string& lock_file);
+
+	/// Takes over `lock_file` if the process that holds it is gone, waiting
+	/// up to `timeout` for the lock to be released. Locks taken on other
+	/// hosts are only taken over if `force` is true.
+	bool take_over_stale_lock(const std::string& lock_file,
+		std::chrono::seconds timeout, bool force, std::string& error_message);
+
+	/// Returns true if the process that holds `lock_file` is no longer
+	/// running, or runs on another host and thus can't be checked.
+	static bool owner_might_be_gone(const std::string& lock_file);
+
 private:
 	rust::Box<fslock::bridged::FsLock> rs_object;
 };
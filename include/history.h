@@ -9,6 +9,7 @@ class History {
 public:
 	History();
 	~History();
+	void set_timestamps(bool timestamps);
 	void add_line(const std::string& line);
 	std::string previous_line();
 	std::string next_line();
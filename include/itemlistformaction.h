@@ -5,7 +5,6 @@
 
 #include "3rd-party/optional.hpp"
 
-#include "history.h"
 #include "listformaction.h"
 #include "listformatter.h"
 #include "regexmanager.h"
@@ -133,8 +132,6 @@ private:
 	bool show_searchresult;
 	std::string search_phrase;
 
-	History filterhistory;
-
 	std::mutex redraw_mtx;
 
 	bool set_filterpos;
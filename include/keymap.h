@@ -6,6 +6,7 @@
 #include <utility>
 #include <vector>
 
+#include "3rd-party/expected.hpp"
 #include "configactionhandler.h"
 
 // in configuration: bind-key <key> <operation>
@@ -134,6 +135,7 @@ enum Operation {
 	OP_INT_QNA_PREVHIST,
 
 	OP_INT_SET,
+	OP_INT_MACRO_ARGS_END,
 
 	OP_INT_MAX,
 	OP_OPEN_URL_1 = 3001,
@@ -166,11 +168,17 @@ struct KeyMapDesc {
 	unsigned short flags;
 };
 
+// This has to be in sync with keymap::KeymapDumpFormat in
+// rust/libnewsboat/src/keymap.rs
+enum class KeymapDumpFormat { CONFIG = 1, JSON };
+
 struct MacroCmd {
 	Operation op;
 	std::vector<std::string> args;
 };
 
+class ConfigChecker;
+
 class KeyMap : public ConfigActionHandler {
 public:
 	explicit KeyMap(unsigned int flags);
@@ -184,16 +192,37 @@ public:
 	Operation get_operation(const std::string& keycode,
 		const std::string& context);
 	std::vector<MacroCmd> get_macro(const std::string& key);
+	/// Returns `true` if the macro bound to `key` contains placeholders like
+	/// `$1`, which are filled with arguments given when the macro is run.
+	bool macro_takes_arguments(const std::string& key) const;
+	/// Names of the variables that the macro bound to `key` refers to with
+	/// `${name}` placeholders.
+	std::vector<std::string> get_macro_variable_names(
+		const std::string& key) const;
+	/// Fills the placeholders of the macro bound to `key` with `args` and
+	/// `variables`. Returns an error message if an argument is missing or
+	/// an operation name turns out to be invalid.
+	nonstd::expected<std::vector<MacroCmd>, std::string> expand_macro(
+		const std::string& key,
+		const std::vector<std::string>& args,
+		const std::map<std::string, std::string>& variables);
 	char get_key(const std::string& keycode);
 	std::vector<std::string> get_keys(Operation op, const std::string& context);
 	void handle_action(const std::string& action,
 		const std::string& params) override;
 	void dump_config(std::vector<std::string>& config_output) const override;
+	/// Serializes key bindings and macros that are currently in effect,
+	/// i.e. after defaults and user's config were applied.
+	std::string dump_effective_bindings(KeymapDumpFormat format) const;
 	std::vector<KeyMapDesc> get_keymap_descriptions(std::string context);
 
 	std::vector<MacroCmd> parse_operation_sequence(const std::string& line);
 	std::vector<MacroCmd> get_startup_operation_sequence();
 
+	/// Tells the checker about all the operations and contexts that can be
+	/// used in the config.
+	void register_operations(ConfigChecker& checker) const;
+
 private:
 	bool is_valid_context(const std::string& context);
 	unsigned short get_flag_from_context(const std::string& context);
@@ -201,6 +230,9 @@ private:
 	std::string getopname(Operation op) const;
 	std::map<std::string, std::map<std::string, Operation>> keymap_;
 	std::map<std::string, std::vector<MacroCmd>> macros_;
+	/// Operation sequences of macros that contain placeholders. They're
+	/// parsed each time the macro is run.
+	std::map<std::string, std::string> parameterized_macros_;
 	std::vector<MacroCmd> startup_operations_sequence;
 };
 
@@ -18,11 +18,30 @@ namespace newsboat {
 // This has to be in sync with logger::Level in rust/libnewsboat/src/logger.rs
 enum class Level { USERERROR = 1, CRITICAL, ERROR, WARN, INFO, DEBUG };
 
+// This has to be in sync with logger::LogFormat in rust/libnewsboat/src/logger.rs
+enum class LogFormat { TEXT = 1, JSON };
+
+/// Limits on the size of logs. See logger::LogRotation in
+/// rust/libnewsboat/src/logger.rs for details.
+struct LogRotation {
+	/// Maximum size of a log file, in bytes.
+	std::uint64_t max_size;
+	/// Maximum number of files to keep, including the current one.
+	std::uint32_t max_files;
+};
+
 namespace Logger {
 void set_logfile(const std::string& logfile);
 void set_user_error_logfile(const std::string& logfile);
 void set_loglevel(Level l);
 void unset_loglevel();
+/// Sets log levels for specific modules. `filter` has to be valid, e.g. one
+/// returned by CliArgsParser::log_filter().
+void set_log_filter(const std::string& filter);
+void set_log_format(LogFormat format);
+/// Rotates the logs once they get bigger than `rotation` allows. The general
+/// log and the user error log are rotated independently.
+void set_log_rotation(const LogRotation& rotation);
 
 template<typename... Args>
 void log(Level l, const std::string& format, Args... args)
@@ -44,6 +44,7 @@ private:
 	void set_rtl(std::shared_ptr<RssFeed> feed, const std::string& lang);
 
 	void retrieve_uri(const std::string& uri);
+	void retrieve_feed_source(const std::string& uri);
 	void download_http(const std::string& uri);
 	void get_execplugin(const std::string& plugin);
 	void download_filterplugin(const std::string& filter,
@@ -13,6 +13,16 @@ public:
 	~ScopeMeasure() = default;
 	void stopover(const std::string& son = "");
 
+	/// Writes the count, total, minimum, maximum and 95th percentile of
+	/// durations of each measured scope to the log. Scopes are only
+	/// measured while debug messages are logged.
+	static void log_summary();
+	/// Starts recording scopes and stopovers for write_trace().
+	static void enable_trace();
+	/// Writes the scopes and stopovers recorded since enable_trace() to
+	/// `path`, in Chrome's trace event format.
+	static bool write_trace(const std::string& path, std::string& error_message);
+
 private:
 	rust::Box<scopemeasure::bridged::ScopeMeasure> rs_object;
 };
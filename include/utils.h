@@ -1,6 +1,7 @@
 #ifndef NEWSBOAT_UTIL_H_
 #define NEWSBOAT_UTIL_H_
 
+#include <chrono>
 #include <cstdint>
 #include <curl/curl.h>
 #include <libxml/parser.h>
@@ -69,6 +70,78 @@ std::string retrieve_url(const std::string& url,
 	CURL* easyhandle = nullptr);
 std::string run_program(const char* argv[], const std::string& input);
 
+/// Parts of a "query:Title:expression" URL.
+struct QueryUrlParts {
+	std::string title;
+	std::string expression;
+};
+
+/// Splits a query URL into title and filter expression. Returns an error
+/// message if the URL is malformed; the expression is not checked, though.
+nonstd::expected<QueryUrlParts, std::string> parse_query_url(
+	const std::string& url);
+
+/// Where a feed comes from. See FeedSource in
+/// rust/libnewsboat/src/feedsource.rs.
+enum class FeedSourceKind : std::uint8_t {
+	/// Downloaded from `location`
+	URL = 0,
+	/// Read from the file at `location`
+	LOCAL_FILE,
+	/// Collects articles matching `expression` from other feeds
+	QUERY,
+	/// Downloaded from `location` and passed through the script in `command`
+	FILTER,
+	/// Output of `command`
+	EXEC
+};
+
+/// Parts of a URL from the urls file. Fields that don't apply to the `kind`
+/// are empty.
+struct FeedSource {
+	FeedSourceKind kind;
+	std::string location;
+	std::string command;
+	std::string title;
+	std::string expression;
+};
+
+/// Finds out where the feed with the given URL comes from. Returns an error
+/// message if the URL is malformed or unsupported.
+nonstd::expected<FeedSource, std::string> parse_feed_source(
+	const std::string& url);
+
+/// What happened to a command run by one of the functions that take
+/// a timeout. See utils::CommandOutput in rust/libnewsboat/src/utils.rs.
+struct CommandOutput {
+	/// Exit code of the command, or nullopt if it was killed by a signal.
+	nonstd::optional<int> exit_code;
+	/// Captured stdout.
+	std::string output;
+	/// Captured stderr.
+	std::string error_output;
+	/// True if the command was killed because it ran out of time.
+	bool timed_out = false;
+	/// True if stdout or stderr were too long, and got cut.
+	bool truncated = false;
+};
+
+/// Like get_command_output(), but kills the command and all the processes it
+/// started if it runs longer than `timeout` (zero means no timeout), and
+/// limits the size of the captured output. Stderr is captured as well, and
+/// the command can't read from the terminal. Returns an error message if the
+/// command couldn't be started.
+nonstd::expected<CommandOutput, std::string> get_command_output_with_limits(
+	const std::string& cmd,
+	std::chrono::seconds timeout);
+
+/// Like run_program(), but with the same limits as
+/// get_command_output_with_limits().
+nonstd::expected<CommandOutput, std::string> run_program_with_limits(
+	const char* argv[],
+	const std::string& input,
+	std::chrono::seconds timeout);
+
 std::string resolve_tilde(const std::string&);
 std::string resolve_relative(const std::string&, const std::string&);
 std::string replace_all(std::string str,
@@ -1,6 +1,7 @@
 #ifndef NEWSBOAT_VIEW_H_
 #define NEWSBOAT_VIEW_H_
 
+#include <atomic>
 #include <cstdint>
 #include <list>
 #include <mutex>
@@ -138,6 +139,22 @@ public:
 
 	static void ctrl_c_action(int sig);
 
+	/// \brief Asks `run()` to return.
+	///
+	/// Safe to call from any thread; the main loop is woken up right away.
+	void request_quit();
+
+	/// \brief Makes the main loop handle control requests and redraw the
+	/// screen without waiting for user input.
+	///
+	/// Safe to call from any thread.
+	void wake_up();
+
+	/// Runs the macro bound to `key`, filling its `$1`, `$2` etc. with
+	/// `args`, and its `${name}` with the values of configuration settings.
+	void run_macro(const std::string& key,
+		const std::vector<std::string>& args);
+
 protected:
 	bool run_commands(const std::vector<MacroCmd>& commands);
 
@@ -150,6 +167,8 @@ protected:
 	void delete_word(std::shared_ptr<FormAction> fa);
 	bool handle_qna_event(const std::string& event, std::shared_ptr<FormAction> fa);
 	void handle_resize();
+	void show_read_only_banner(std::shared_ptr<FormAction> fa);
+	void wait_for_input_or_wake_up(int timeout_ms);
 
 	Controller* ctrl;
 
@@ -176,6 +195,11 @@ protected:
 	FilterContainer& filters;
 	const ColorManager& colorman;
 	std::vector<std::string> suggestions;
+	std::atomic<bool> quit_requested;
+
+	/// Self-pipe used by `wake_up()`: writing a byte to the second descriptor
+	/// interrupts `wait_for_input_or_wake_up()`, which waits on the first one.
+	int wakeup_pipe[2];
 };
 
 } // namespace newsboat
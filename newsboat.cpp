@@ -1,5 +1,6 @@
 #include <cstring>
 #include <errno.h>
+#include <fstream>
 #include <iostream>
 #include <ncurses.h>
 #include <sstream>
@@ -8,18 +9,25 @@
 #include "cache.h"
 #include "cliargsparser.h"
 #include "config.h"
+#include "configcheck.h"
+#include "configcontainer.h"
 #include "configpaths.h"
 #include "controller.h"
 #include "dbexception.h"
 #include "exception.h"
+#include "keymap.h"
 #include "matcherexception.h"
 #include "rss/parser.h"
+#include "ruststring.h"
+#include "scopemeasure.h"
 #include "utils.h"
 #include "view.h"
 #include "xlicense.h"
 
 extern "C" {
 	void rs_setup_human_panic(void);
+	void rs_set_crash_reports_dir(const char* dir);
+	char* rs_last_crash_report(const char* dir);
 }
 
 using namespace newsboat;
@@ -29,9 +37,11 @@ void print_usage(const std::string& argv0, const std::string& config_path,
 {
 	auto msg = strprintf::fmt(
 			_("%s %s\nusage: %s [-i <file>|-e] [-u <urlfile>] "
-				"[-c <cachefile>] [-x <command> ...] [-h]\n"),
+				"[-c <cachefile>] [-x <command> ...] [-h]\n"
+				"       %s [options] <subcommand> [arguments]\n"),
 			PROGRAM_NAME,
 			utils::program_version(),
+			argv0,
 			argv0);
 	std::cout << msg;
 
@@ -100,7 +110,93 @@ void print_usage(const std::string& argv0, const std::string& config_path,
 			_s("import list of read articles from <file>")
 		},
 		{'h', "help", "", _s("this help")},
-		{'-', "cleanup", "", _s("remove unreferenced items from cache")}
+		{'-', "cleanup", "", _s("remove unreferenced items from cache")},
+		{
+			'-',
+			"log-format",
+			_s("<format>"),
+			_s("write the log as plain text or JSON Lines (valid formats: text, json)")
+		},
+		{
+			'-',
+			"log-max-size",
+			_s("<MiB>"),
+			_s("rotate the logs when they grow bigger than <MiB> mebibytes")
+		},
+		{
+			'-',
+			"log-max-files",
+			_s("<count>"),
+			_s("keep at most <count> files of each log when rotating (default: 5)")
+		},
+		{
+			'-',
+			"trace-file",
+			_s("<file>"),
+			_s("write timings of internal operations to <file> as a Chrome trace")
+		},
+		{
+			'-',
+			"dump-keymap",
+			_s("<format>"),
+			_s("print effective key bindings and macros (valid formats: config, json)")
+		},
+		{
+			'-',
+			"wait-for-lock",
+			_s("<seconds>"),
+			_s("if another instance is running, wait up to <seconds> for it to quit")
+		},
+		{
+			'-',
+			"read-only",
+			"",
+			_s("browse the cache without changing it, even if another instance is running")
+		},
+		{
+			'-',
+			"take-over-lock",
+			"",
+			_s("take over the lock if the process holding it is gone")
+		},
+		{
+			'-',
+			"profile",
+			_s("<name>"),
+			_s("use configs and data of the profile <name>")
+		},
+		{'-', "list-profiles", "", _s("list existing profiles")},
+		{'-', "last-crash-report", "", _s("print the most recent crash report")},
+		{
+			'-',
+			"migration-dry-run",
+			"",
+			_s("show what migration from other feed readers would do, without doing it")
+		},
+		{
+			'-',
+			"migrate-from-opml",
+			_s("<file>"),
+			_s("on first start, import subscriptions from OPML <file> instead of Newsbeuter")
+		},
+		{
+			'-',
+			"migrate-read-guids",
+			_s("<file>"),
+			_s("with --migrate-from-opml, also mark articles with GUIDs listed in <file> as read")
+		},
+		{
+			'-',
+			"check-config",
+			"",
+			_s("check the config file for mistakes and list them all")
+		},
+		{
+			'-',
+			"completions",
+			_s("<shell>"),
+			_s("print a script that sets up completions for <shell> (bash, zsh or fish)")
+		}
 	};
 
 	std::stringstream ss;
@@ -125,6 +221,51 @@ void print_usage(const std::string& argv0, const std::string& config_path,
 
 	std::cout << '\n';
 
+	struct subcommand {
+		const std::string name;
+		const std::string desc;
+	};
+
+	static const std::vector<subcommand> subcommands = {
+		{"reload", _s("reload all feeds and quit (same as -x reload)")},
+		{"export opml", _s("export OPML feed to stdout (same as -e)")},
+		{
+			_s("import opml <file>"),
+			_s("import OPML file (same as -i)")
+		},
+		{
+			_s("readinfo export <file>"),
+			_s("export list of read articles to <file> (same as -E)")
+		},
+		{
+			_s("readinfo import <file>"),
+			_s("import list of read articles from <file> (same as -I)")
+		},
+		{"cache vacuum", _s("compact the cache (same as -X)")},
+		{
+			"cache cleanup",
+			_s("remove unreferenced items from cache (same as --cleanup)")
+		},
+		{"query feeds", _s("print unread and total article counts for each feed")},
+		{"query tags", _s("print unread and total article counts for each tag")},
+		{
+			_s("query items [--filter=<expr>]"),
+			_s("print unread articles, optionally only those matching <expr>")
+		},
+		{_s("query item <id>"), _s("print the article with ID <id>")},
+	};
+
+	std::cout << _("Subcommands:") << '\n';
+	for (const auto& sc : subcommands) {
+		std::cout << "\t" << sc.name;
+		for (unsigned int j = 0; j < utils::gentabs(sc.name); j++) {
+			std::cout << "\t";
+		}
+		std::cout << sc.desc << '\n';
+	}
+
+	std::cout << '\n';
+
 	std::cout << _("Files:") << '\n';
 	/// This is printed out by --help before the path to the config file
 	const std::string tr_config = _("configuration");
@@ -237,6 +378,7 @@ int main(int argc, char* argv[])
 	CliArgsParser args(argc, argv);
 
 	configpaths.process_args(args);
+	rs_set_crash_reports_dir(configpaths.crash_reports_dir().c_str());
 
 	if (args.should_print_usage()) {
 		print_usage(args.program_name(), configpaths.config_file(),
@@ -247,6 +389,61 @@ int main(int argc, char* argv[])
 	} else if (args.show_version()) {
 		print_version(args.program_name(), args.show_version());
 		return EXIT_SUCCESS;
+	} else if (args.list_profiles()) {
+		for (const auto& profile : configpaths.list_profiles()) {
+			std::cout << profile << std::endl;
+		}
+		return EXIT_SUCCESS;
+	} else if (args.last_crash_report()) {
+		const std::string dir = configpaths.crash_reports_dir();
+		const std::string report = RustString(rs_last_crash_report(dir.c_str()));
+		if (report.empty()) {
+			std::cerr << strprintf::fmt(_("No crash reports found in %s"), dir)
+				<< std::endl;
+			return EXIT_FAILURE;
+		}
+		std::ifstream file(report);
+		if (!file.is_open()) {
+			std::cerr << strprintf::fmt(
+					_("Error: couldn't open crash report `%s': %s"),
+					report, strerror(errno))
+				<< std::endl;
+			return EXIT_FAILURE;
+		}
+		std::cout << file.rdbuf();
+		return EXIT_SUCCESS;
+	} else if (args.migration_dry_run()) {
+		std::cout << configpaths.describe_migration_plan();
+		return EXIT_SUCCESS;
+	} else if (args.completions_script().has_value()) {
+		std::cout << args.completions_script().value();
+		return EXIT_SUCCESS;
+	} else if (args.check_config()) {
+		ConfigChecker checker;
+		c.register_config_checks(checker);
+
+		const auto problems = checker.check(configpaths.config_file());
+		if (!problems.has_value()) {
+			std::cerr << problems.error() << std::endl;
+			return EXIT_FAILURE;
+		}
+		for (const auto& problem : problems.value()) {
+			std::cout << problem << std::endl;
+		}
+		return problems.value().empty() ? EXIT_SUCCESS : EXIT_FAILURE;
+	} else if (args.has_query()) {
+		const auto answer = args.run_query(configpaths.cache_file(),
+				configpaths.url_file());
+		if (!answer.has_value()) {
+			std::cerr << answer.error() << std::endl;
+			return EXIT_FAILURE;
+		}
+		std::cout << answer.value();
+		return EXIT_SUCCESS;
+	}
+
+	if (args.trace_file().has_value()) {
+		ScopeMeasure::enable_trace();
 	}
 
 	int ret;
@@ -274,6 +471,17 @@ int main(int argc, char* argv[])
 		::exit(EXIT_FAILURE);
 	}
 
+	ScopeMeasure::log_summary();
+	if (args.trace_file().has_value()) {
+		std::string error_message;
+		if (!ScopeMeasure::write_trace(args.trace_file().value(), error_message)) {
+			std::cerr << strprintf::fmt(
+					_("Error: couldn't write trace to `%s': %s"),
+					args.trace_file().value(), error_message)
+				<< std::endl;
+		}
+	}
+
 	rsspp::Parser::global_cleanup();
 
 	return ret;
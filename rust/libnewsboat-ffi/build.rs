@@ -11,6 +11,8 @@ fn add_cxxbridge(module: &str) {
 
 fn main() {
     add_cxxbridge("cliargsparser");
+    add_cxxbridge("configcheck");
+    add_cxxbridge("controlsocket");
     add_cxxbridge("fslock");
     add_cxxbridge("keymap");
     add_cxxbridge("scopemeasure");
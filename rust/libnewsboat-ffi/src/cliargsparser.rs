@@ -1,4 +1,6 @@
-use libnewsboat::cliargsparser::CliArgsParser;
+use libnewsboat::cachequery;
+use libnewsboat::cliargsparser::{self, CliArgsParser};
+use std::path::Path;
 
 #[cxx::bridge(namespace = "newsboat::cliargsparser::bridged")]
 mod bridged {
@@ -30,10 +32,45 @@ mod bridged {
         fn cache_file(cliargsparser: &CliArgsParser, path: &mut String) -> bool;
         fn config_file(cliargsparser: &CliArgsParser, path: &mut String) -> bool;
         fn log_file(cliargsparser: &CliArgsParser, path: &mut String) -> bool;
+        fn trace_file(cliargsparser: &CliArgsParser, path: &mut String) -> bool;
 
         fn cmds_to_execute(cliargsparser: &CliArgsParser) -> Vec<String>;
 
         fn log_level(cliargsparser: &CliArgsParser, level: &mut i8) -> bool;
+        fn log_filter(cliargsparser: &CliArgsParser, filter: &mut String) -> bool;
+
+        fn log_format(cliargsparser: &CliArgsParser, format: &mut u8) -> bool;
+
+        fn log_rotation(
+            cliargsparser: &CliArgsParser,
+            max_size: &mut u64,
+            max_files: &mut u32,
+        ) -> bool;
+
+        fn keymap_dump_format(cliargsparser: &CliArgsParser, format: &mut u8) -> bool;
+
+        fn wait_for_lock(cliargsparser: &CliArgsParser, seconds: &mut u64) -> bool;
+
+        fn read_only(cliargsparser: &CliArgsParser) -> bool;
+
+        fn take_over_lock(cliargsparser: &CliArgsParser) -> bool;
+
+        fn profile(cliargsparser: &CliArgsParser, name: &mut String) -> bool;
+        fn list_profiles(cliargsparser: &CliArgsParser) -> bool;
+        fn last_crash_report(cliargsparser: &CliArgsParser) -> bool;
+
+        fn migration_dry_run(cliargsparser: &CliArgsParser) -> bool;
+        fn check_config(cliargsparser: &CliArgsParser) -> bool;
+
+        fn completions_script(cliargsparser: &CliArgsParser, script: &mut String) -> bool;
+
+        fn has_query(cliargsparser: &CliArgsParser) -> bool;
+        fn run_query(
+            cliargsparser: &CliArgsParser,
+            cache_file: &str,
+            url_file: &str,
+            output: &mut String,
+        ) -> bool;
     }
 
     extern "C++" {
@@ -181,6 +218,16 @@ fn log_file(cliargsparser: &CliArgsParser, path: &mut String) -> bool {
     }
 }
 
+fn trace_file(cliargsparser: &CliArgsParser, path: &mut String) -> bool {
+    match &cliargsparser.trace_file {
+        Some(p) => {
+            *path = p.to_string_lossy().to_string();
+            true
+        }
+        None => false,
+    }
+}
+
 fn cmds_to_execute(cliargsparser: &CliArgsParser) -> Vec<String> {
     cliargsparser.cmds_to_execute.to_owned()
 }
@@ -194,3 +241,131 @@ fn log_level(cliargsparser: &CliArgsParser, level: &mut i8) -> bool {
         None => false,
     }
 }
+
+fn log_filter(cliargsparser: &CliArgsParser, filter: &mut String) -> bool {
+    match &cliargsparser.log_filter {
+        Some(f) => {
+            *filter = f.to_string();
+            true
+        }
+        None => false,
+    }
+}
+
+fn log_format(cliargsparser: &CliArgsParser, format: &mut u8) -> bool {
+    match cliargsparser.log_format {
+        Some(f) => {
+            *format = f as u8;
+            true
+        }
+        None => false,
+    }
+}
+
+fn log_rotation(cliargsparser: &CliArgsParser, max_size: &mut u64, max_files: &mut u32) -> bool {
+    match cliargsparser.log_rotation {
+        Some(rotation) => {
+            *max_size = rotation.max_size;
+            *max_files = rotation.max_files;
+            true
+        }
+        None => false,
+    }
+}
+
+fn keymap_dump_format(cliargsparser: &CliArgsParser, format: &mut u8) -> bool {
+    match cliargsparser.keymap_dump_format {
+        Some(f) => {
+            *format = f as u8;
+            true
+        }
+        None => false,
+    }
+}
+
+fn wait_for_lock(cliargsparser: &CliArgsParser, seconds: &mut u64) -> bool {
+    match cliargsparser.wait_for_lock {
+        Some(timeout) => {
+            *seconds = timeout.as_secs();
+            true
+        }
+        None => false,
+    }
+}
+
+fn read_only(cliargsparser: &CliArgsParser) -> bool {
+    cliargsparser.read_only
+}
+
+fn take_over_lock(cliargsparser: &CliArgsParser) -> bool {
+    cliargsparser.take_over_lock
+}
+
+fn profile(cliargsparser: &CliArgsParser, name: &mut String) -> bool {
+    match &cliargsparser.profile {
+        Some(profile) => {
+            *name = profile.clone();
+            true
+        }
+        None => false,
+    }
+}
+
+fn list_profiles(cliargsparser: &CliArgsParser) -> bool {
+    cliargsparser.list_profiles
+}
+
+fn last_crash_report(cliargsparser: &CliArgsParser) -> bool {
+    cliargsparser.last_crash_report
+}
+
+fn migration_dry_run(cliargsparser: &CliArgsParser) -> bool {
+    cliargsparser.migration_dry_run
+}
+
+fn check_config(cliargsparser: &CliArgsParser) -> bool {
+    cliargsparser.check_config
+}
+
+fn completions_script(cliargsparser: &CliArgsParser, script: &mut String) -> bool {
+    match cliargsparser.completions_shell {
+        Some(shell) => {
+            *script = cliargsparser::completions_script(shell);
+            true
+        }
+        None => false,
+    }
+}
+
+fn has_query(cliargsparser: &CliArgsParser) -> bool {
+    cliargsparser.query.is_some()
+}
+
+/// Runs the query, putting the answer into `output`. If the query can't be answered, puts an
+/// explanation there instead and returns `false`.
+fn run_query(
+    cliargsparser: &CliArgsParser,
+    cache_file: &str,
+    url_file: &str,
+    output: &mut String,
+) -> bool {
+    let query = match cliargsparser.query {
+        Some(ref query) => query,
+        None => return false,
+    };
+    match cachequery::run(
+        query,
+        cliargsparser.query_format,
+        Path::new(cache_file),
+        Path::new(url_file),
+    ) {
+        Ok(answer) => {
+            *output = answer;
+            true
+        }
+        Err(error) => {
+            *output = error;
+            false
+        }
+    }
+}
@@ -0,0 +1,91 @@
+use libnewsboat::configcheck::{ConfigChecker, SettingKind};
+
+use std::path::Path;
+
+#[cxx::bridge(namespace = "newsboat::configcheck::bridged")]
+mod bridged {
+    extern "Rust" {
+        type ConfigChecker;
+
+        fn create() -> Box<ConfigChecker>;
+        fn add_command(checker: &mut ConfigChecker, name: &str);
+        fn add_bool_setting(checker: &mut ConfigChecker, name: &str);
+        fn add_int_setting(checker: &mut ConfigChecker, name: &str);
+        fn add_text_setting(checker: &mut ConfigChecker, name: &str);
+        fn add_enum_setting(checker: &mut ConfigChecker, name: &str, values: &Vec<String>);
+        fn add_operation(checker: &mut ConfigChecker, name: &str);
+        fn add_context(checker: &mut ConfigChecker, name: &str);
+        fn add_color_element(checker: &mut ConfigChecker, name: &str);
+        fn check(
+            checker: &ConfigChecker,
+            config_file: &str,
+            problems: &mut Vec<String>,
+            error_message: &mut String,
+        ) -> bool;
+    }
+
+    extern "C++" {
+        // cxx uses `std::out_of_range`, but doesn't include the header that defines that
+        // exception. So we do it for them.
+        include!("stdexcept");
+        // Also inject a header that defines ptrdiff_t. Note this is *not* a C++ header, because
+        // cxx uses a non-C++ name of the type.
+        include!("stddef.h");
+    }
+}
+
+fn create() -> Box<ConfigChecker> {
+    Box::new(ConfigChecker::new())
+}
+
+fn add_command(checker: &mut ConfigChecker, name: &str) {
+    checker.add_command(name);
+}
+
+fn add_bool_setting(checker: &mut ConfigChecker, name: &str) {
+    checker.add_setting(name, SettingKind::Bool);
+}
+
+fn add_int_setting(checker: &mut ConfigChecker, name: &str) {
+    checker.add_setting(name, SettingKind::Int);
+}
+
+fn add_text_setting(checker: &mut ConfigChecker, name: &str) {
+    checker.add_setting(name, SettingKind::Text);
+}
+
+// cxx can't pass &[String] over FFI, so we have to take &Vec<String> despite what Clippy says.
+#[allow(clippy::ptr_arg)]
+fn add_enum_setting(checker: &mut ConfigChecker, name: &str, values: &Vec<String>) {
+    checker.add_setting(name, SettingKind::Enum(values.clone()));
+}
+
+fn add_operation(checker: &mut ConfigChecker, name: &str) {
+    checker.add_operation(name);
+}
+
+fn add_context(checker: &mut ConfigChecker, name: &str) {
+    checker.add_context(name);
+}
+
+fn add_color_element(checker: &mut ConfigChecker, name: &str) {
+    checker.add_color_element(name);
+}
+
+fn check(
+    checker: &ConfigChecker,
+    config_file: &str,
+    problems: &mut Vec<String>,
+    error_message: &mut String,
+) -> bool {
+    match checker.check(Path::new(config_file)) {
+        Ok(found) => {
+            *problems = found.iter().map(|problem| problem.to_string()).collect();
+            true
+        }
+        Err(message) => {
+            *error_message = message;
+            false
+        }
+    }
+}
@@ -90,6 +90,18 @@ pub unsafe extern "C" fn rs_configpaths_process_args(
     })
 }
 
+#[no_mangle]
+pub unsafe extern "C" fn rs_configpaths_try_migrate(object: *mut c_void) -> bool {
+    with_configpaths(object, |o| o.try_migrate(), false)
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn rs_configpaths_describe_migration_plan(
+    object: *mut c_void,
+) -> *mut c_char {
+    with_configpaths_string(object, |o| o.describe_migration_plan())
+}
+
 #[no_mangle]
 pub unsafe extern "C" fn rs_configpaths_try_migrate_from_newsbeuter(object: *mut c_void) -> bool {
     with_configpaths(object, |o| o.try_migrate_from_newsbeuter(), false)
@@ -140,6 +152,11 @@ pub unsafe extern "C" fn rs_configpaths_lock_file(object: *mut c_void) -> *mut c
     with_configpaths_path(object, |o| o.lock_file())
 }
 
+#[no_mangle]
+pub unsafe extern "C" fn rs_configpaths_control_socket_file(object: *mut c_void) -> *mut c_char {
+    with_configpaths_path(object, |o| o.control_socket_file())
+}
+
 #[no_mangle]
 pub unsafe extern "C" fn rs_configpaths_queue_file(object: *mut c_void) -> *mut c_char {
     with_configpaths_path(object, |o| o.queue_file())
@@ -154,3 +171,46 @@ pub unsafe extern "C" fn rs_configpaths_search_file(object: *mut c_void) -> *mut
 pub unsafe extern "C" fn rs_configpaths_cmdline_file(object: *mut c_void) -> *mut c_char {
     with_configpaths_path(object, |o| o.cmdline_file())
 }
+
+#[no_mangle]
+pub unsafe extern "C" fn rs_configpaths_article_search_file(object: *mut c_void) -> *mut c_char {
+    with_configpaths_path(object, |o| o.article_search_file())
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn rs_configpaths_feed_search_file(object: *mut c_void) -> *mut c_char {
+    with_configpaths_path(object, |o| o.feed_search_file())
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn rs_configpaths_filter_file(object: *mut c_void) -> *mut c_char {
+    with_configpaths_path(object, |o| o.filter_file())
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn rs_configpaths_crash_reports_dir(object: *mut c_void) -> *mut c_char {
+    with_configpaths_path(object, |o| o.crash_reports_dir())
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn rs_configpaths_imported_read_guids_file(
+    object: *mut c_void,
+) -> *mut c_char {
+    with_configpaths_path(object, |o| o.imported_read_guids_file())
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn rs_configpaths_has_explicit_cache_file(object: *mut c_void) -> bool {
+    with_configpaths(object, |o| o.has_explicit_cache_file(), false)
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn rs_configpaths_profile(object: *mut c_void) -> *mut c_char {
+    with_configpaths_string(object, |o| o.profile().unwrap_or_default().to_owned())
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn rs_configpaths_list_profiles(object: *mut c_void) -> *mut c_char {
+    // Profile names can't contain newlines, so they're safe to use as a separator
+    with_configpaths_string(object, |o| o.list_profiles().join("\n"))
+}
@@ -0,0 +1,128 @@
+use libnewsboat::controlsocket::{self, ControlConnection, Request, Response};
+
+use std::path::Path;
+use std::time::Duration;
+
+#[cxx::bridge(namespace = "newsboat::controlsocket::bridged")]
+mod bridged {
+    extern "Rust" {
+        // Wraps `libnewsboat::controlsocket::ControlServer` along with the client that is currently
+        // connected, so that C++ only has to deal with requests and responses.
+        type ControlServer;
+
+        fn create_server(path: &str, error_message: &mut String) -> Box<ControlServer>;
+        fn is_listening(server: &ControlServer) -> bool;
+        // `kind` is a `ControlRequestType` cast to `u8`. `url` is empty if the request applies to
+        // all feeds.
+        fn next_request(
+            server: &mut ControlServer,
+            timeout_ms: u64,
+            kind: &mut u8,
+            url: &mut String,
+        ) -> bool;
+        fn respond(server: &mut ControlServer, success: bool, message: &str);
+
+        fn forward_commands(
+            path: &str,
+            commands: Vec<String>,
+            error_message: &mut String,
+        ) -> Vec<String>;
+    }
+
+    extern "C++" {
+        // cxx uses `std::out_of_range`, but doesn't include the header that defines that
+        // exception. So we do it for them.
+        include!("stdexcept");
+        // Also inject a header that defines ptrdiff_t. Note this is *not* a C++ header, because
+        // cxx uses a non-C++ name of the type.
+        include!("stddef.h");
+    }
+}
+
+// This has to be in sync with `ControlRequestType` in include/controlsocket.h
+const REQUEST_RELOAD: u8 = 1;
+const REQUEST_MARK_READ: u8 = 2;
+const REQUEST_QUIT: u8 = 3;
+const REQUEST_STATUS: u8 = 4;
+
+struct ControlServer {
+    server: Option<controlsocket::ControlServer>,
+    connection: Option<ControlConnection>,
+}
+
+fn create_server(path: &str, error_message: &mut String) -> Box<ControlServer> {
+    let server = match controlsocket::ControlServer::bind(Path::new(path)) {
+        Ok(server) => Some(server),
+        Err(e) => {
+            *error_message = e.to_string();
+            None
+        }
+    };
+    Box::new(ControlServer {
+        server,
+        connection: None,
+    })
+}
+
+fn is_listening(server: &ControlServer) -> bool {
+    server.server.is_some()
+}
+
+fn next_request(
+    server: &mut ControlServer,
+    timeout_ms: u64,
+    kind: &mut u8,
+    url: &mut String,
+) -> bool {
+    let request = match server.connection.as_mut().and_then(|c| c.next_request()) {
+        Some(request) => request,
+        None => {
+            server.connection = None;
+            let listener = match &server.server {
+                Some(listener) => listener,
+                None => return false,
+            };
+            match listener.accept(Duration::from_millis(timeout_ms)) {
+                Ok(Some(connection)) => server.connection = Some(connection),
+                _ => return false,
+            }
+            match server.connection.as_mut().and_then(|c| c.next_request()) {
+                Some(request) => request,
+                None => return false,
+            }
+        }
+    };
+
+    let (request_kind, request_url) = match request {
+        Request::Reload(url) => (REQUEST_RELOAD, url),
+        Request::MarkRead(url) => (REQUEST_MARK_READ, url),
+        Request::Quit => (REQUEST_QUIT, None),
+        Request::Status => (REQUEST_STATUS, None),
+    };
+    *kind = request_kind;
+    *url = request_url.unwrap_or_default();
+    true
+}
+
+fn respond(server: &mut ControlServer, success: bool, message: &str) {
+    let response = if success {
+        Response::Ok(message.to_owned())
+    } else {
+        Response::Error(message.to_owned())
+    };
+    if let Some(connection) = server.connection.as_mut() {
+        if connection.respond(&response).is_err() {
+            server.connection = None;
+        }
+    }
+}
+
+fn forward_commands(path: &str, commands: Vec<String>, error_message: &mut String) -> Vec<String> {
+    match controlsocket::forward_commands(Path::new(path), &commands) {
+        Ok(messages) => messages,
+        Err(message) => {
+            *error_message = message;
+            vec![]
+        }
+    }
+}
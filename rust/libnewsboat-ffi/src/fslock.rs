@@ -1,6 +1,7 @@
-use libnewsboat::fslock::FsLock;
+use libnewsboat::fslock::{FsLock, LockOwner, LockOwnerStatus};
 
 use std::path::Path;
+use std::time::Duration;
 
 #[cxx::bridge(namespace = "newsboat::fslock::bridged")]
 mod bridged {
@@ -14,6 +15,27 @@ mod bridged {
             pid: &mut i64,
             error_message: &mut String,
         ) -> bool;
+        fn lock_with_timeout(
+            fslock: &mut FsLock,
+            new_lock_path: &str,
+            timeout_seconds: u64,
+            pid: &mut i64,
+            error_message: &mut String,
+        ) -> bool;
+        fn try_lock_shared(
+            fslock: &mut FsLock,
+            lock_path: &str,
+            error_message: &mut String,
+        ) -> bool;
+        fn has_readers(lock_path: &str) -> bool;
+        fn take_over_stale_lock(
+            fslock: &mut FsLock,
+            lock_path: &str,
+            timeout_seconds: u64,
+            force: bool,
+            error_message: &mut String,
+        ) -> bool;
+        fn owner_might_be_gone(lock_path: &str) -> bool;
     }
 }
 
@@ -36,3 +58,67 @@ fn try_lock(
     }
     false
 }
+
+fn lock_with_timeout(
+    fslock: &mut FsLock,
+    new_lock_path: &str,
+    timeout_seconds: u64,
+    pid: &mut i64,
+    error_message: &mut String,
+) -> bool {
+    let p: &mut libc::pid_t = &mut 0;
+    let result = fslock.lock_with_timeout(
+        Path::new(new_lock_path),
+        Duration::from_secs(timeout_seconds),
+        p,
+    );
+    *pid = i64::from(*p);
+    match result {
+        Ok(_) => true,
+        Err(message) => {
+            *error_message = message;
+            false
+        }
+    }
+}
+
+fn try_lock_shared(fslock: &mut FsLock, lock_path: &str, error_message: &mut String) -> bool {
+    match fslock.try_lock_shared(Path::new(lock_path)) {
+        Ok(_) => true,
+        Err(message) => {
+            *error_message = message;
+            false
+        }
+    }
+}
+
+fn has_readers(lock_path: &str) -> bool {
+    FsLock::has_readers(Path::new(lock_path))
+}
+
+fn take_over_stale_lock(
+    fslock: &mut FsLock,
+    lock_path: &str,
+    timeout_seconds: u64,
+    force: bool,
+    error_message: &mut String,
+) -> bool {
+    match fslock.take_over_stale_lock(
+        Path::new(lock_path),
+        Duration::from_secs(timeout_seconds),
+        force,
+    ) {
+        Ok(_) => true,
+        Err(message) => {
+            *error_message = message;
+            false
+        }
+    }
+}
+
+fn owner_might_be_gone(lock_path: &str) -> bool {
+    match LockOwner::read(Path::new(lock_path)) {
+        Some(owner) => owner.status() != LockOwnerStatus::Alive,
+        None => false,
+    }
+}
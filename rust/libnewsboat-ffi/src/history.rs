@@ -19,6 +19,21 @@ pub unsafe extern "C" fn rs_history_free(hst: *mut c_void) {
     })
 }
 
+#[no_mangle]
+pub unsafe extern "C" fn rs_history_set_timestamps(hst: *mut c_void, timestamps: bool) {
+    abort_on_panic(|| {
+        let mut hst = {
+            assert!(!hst.is_null());
+            Box::from_raw(hst as *mut History)
+        };
+
+        hst.set_timestamps(timestamps);
+
+        // Do not deallocate the object - C still has a pointer to it
+        mem::forget(hst);
+    })
+}
+
 #[no_mangle]
 pub unsafe extern "C" fn rs_history_add_line(hst: *mut c_void, line: *const c_char) {
     abort_on_panic(|| {
@@ -116,7 +131,7 @@ pub unsafe extern "C" fn rs_history_save_to_file(
     limit: usize,
 ) {
     abort_on_panic(|| {
-        let hst = {
+        let mut hst = {
             assert!(!hst.is_null());
             Box::from_raw(hst as *mut History)
         };
@@ -1,5 +1,8 @@
 use crate::abort_on_panic;
+use libc::c_char;
 use libnewsboat::human_panic;
+use std::ffi::{CStr, CString};
+use std::path::Path;
 
 #[no_mangle]
 pub extern "C" fn rs_setup_human_panic() {
@@ -7,3 +10,30 @@ pub extern "C" fn rs_setup_human_panic() {
         human_panic::setup();
     })
 }
+
+#[no_mangle]
+pub unsafe extern "C" fn rs_set_crash_reports_dir(dir: *const c_char) {
+    abort_on_panic(|| {
+        let dir = CStr::from_ptr(dir);
+        let dir = dir
+            .to_str()
+            .expect("crash reports dir path contained invalid UTF-8");
+        human_panic::set_crash_reports_dir(Path::new(dir));
+    })
+}
+
+/// Returns the path to the newest crash report in `dir`, or an empty string if there are none.
+#[no_mangle]
+pub unsafe extern "C" fn rs_last_crash_report(dir: *const c_char) -> *mut c_char {
+    abort_on_panic(|| {
+        let dir = CStr::from_ptr(dir);
+        let dir = dir
+            .to_str()
+            .expect("crash reports dir path contained invalid UTF-8");
+        let path = human_panic::last_crash_report(Path::new(dir))
+            .map(|path| path.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        // Paths can't contain NUL bytes
+        CString::new(path).unwrap().into_raw()
+    })
+}
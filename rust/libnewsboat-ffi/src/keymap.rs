@@ -17,6 +17,32 @@ mod ffi {
         type Operation;
         fn tokenize_operation_sequence(input: &str) -> Vec<Operation>;
         fn operation_tokens(operation: &Operation) -> &Vec<String>;
+
+        // Parameterized macros, i.e. the ones with placeholders like `$1` or `${browser}`. C++
+        // keeps the operation sequence as a string, and expands it each time the macro is run.
+        fn macro_takes_arguments(input: &str) -> bool;
+        fn macro_variable_names(input: &str) -> Vec<String>;
+        // Names of the operations, or an empty string if the name contains a placeholder.
+        fn macro_operation_names(input: &str) -> Vec<String>;
+        // Returns an empty vector and sets `error_message` if some argument is missing.
+        // `variable_names` and `variable_values` are pairs of names and values of the variables
+        // `${name}` refers to.
+        fn expand_macro(
+            input: &str,
+            arguments: Vec<String>,
+            variable_names: Vec<String>,
+            variable_values: Vec<String>,
+            error_message: &mut String,
+        ) -> Vec<Operation>;
+
+        // Collects the effective bindings from C++ `KeyMap` so that Rust can serialize them.
+        // `format` is a `KeymapDumpFormat` cast to `u8`.
+        type KeymapDump;
+        fn create_keymap_dump() -> Box<KeymapDump>;
+        fn add_binding(dump: &mut KeymapDump, context: &str, key: &str, operation: &str);
+        fn add_macro_operation(dump: &mut KeymapDump, key: &str, tokens: Vec<String>);
+        fn add_macro_definition(dump: &mut KeymapDump, key: &str, definition: &str);
+        fn format_keymap_dump(dump: &KeymapDump, format: u8) -> String;
     }
 
     extern "C++" {
@@ -29,6 +55,10 @@ mod ffi {
     }
 }
 
+use libnewsboat::keymap::{
+    KeymapDump, KeymapDumpFormat, MacroArguments, MacroToken, PlaceholderName,
+};
+
 struct Operation {
     tokens: Vec<String>,
 }
@@ -46,3 +76,96 @@ fn tokenize_operation_sequence(input: &str) -> Vec<Operation> {
 fn operation_tokens(input: &Operation) -> &Vec<String> {
     &input.tokens
 }
+
+fn macro_placeholder_names(input: &str) -> Vec<PlaceholderName> {
+    libnewsboat::keymap::tokenize_macro_operation_sequence(input)
+        .unwrap_or_default()
+        .iter()
+        .flatten()
+        .flat_map(MacroToken::placeholder_names)
+        .collect()
+}
+
+fn macro_takes_arguments(input: &str) -> bool {
+    macro_placeholder_names(input)
+        .iter()
+        .any(|name| matches!(name, PlaceholderName::Positional(_)))
+}
+
+fn macro_variable_names(input: &str) -> Vec<String> {
+    let mut result: Vec<String> = vec![];
+    for name in macro_placeholder_names(input) {
+        if let PlaceholderName::Named(name) = name {
+            if !result.contains(&name) {
+                result.push(name);
+            }
+        }
+    }
+    result
+}
+
+fn macro_operation_names(input: &str) -> Vec<String> {
+    libnewsboat::keymap::tokenize_macro_operation_sequence(input)
+        .unwrap_or_default()
+        .iter()
+        .filter_map(|tokens| tokens.first())
+        .map(|name| {
+            if name.is_literal() {
+                name.expand(&MacroArguments::default()).unwrap_or_default()
+            } else {
+                String::new()
+            }
+        })
+        .collect()
+}
+
+fn expand_macro(
+    input: &str,
+    arguments: Vec<String>,
+    variable_names: Vec<String>,
+    variable_values: Vec<String>,
+    error_message: &mut String,
+) -> Vec<Operation> {
+    let operations =
+        libnewsboat::keymap::tokenize_macro_operation_sequence(input).unwrap_or_default();
+
+    let mut args = MacroArguments::new(arguments);
+    for (name, value) in variable_names.iter().zip(variable_values.iter()) {
+        args.set_named(name, value);
+    }
+
+    match libnewsboat::keymap::expand_operation_sequence(&operations, &args) {
+        Ok(operations) => operations
+            .into_iter()
+            .map(|tokens| Operation { tokens })
+            .collect(),
+        Err(error) => {
+            *error_message = error.to_string();
+            vec![]
+        }
+    }
+}
+
+fn create_keymap_dump() -> Box<KeymapDump> {
+    Box::new(KeymapDump::new())
+}
+
+fn add_binding(dump: &mut KeymapDump, context: &str, key: &str, operation: &str) {
+    dump.add_binding(context, key, operation);
+}
+
+fn add_macro_operation(dump: &mut KeymapDump, key: &str, tokens: Vec<String>) {
+    dump.add_macro_operation(key, tokens);
+}
+
+fn add_macro_definition(dump: &mut KeymapDump, key: &str, definition: &str) {
+    dump.add_macro_definition(key, definition);
+}
+
+fn format_keymap_dump(dump: &KeymapDump, format: u8) -> String {
+    if format == KeymapDumpFormat::Json as u8 {
+        dump.format(KeymapDumpFormat::Json)
+    } else {
+        dump.format(KeymapDumpFormat::Config)
+    }
+}
@@ -11,7 +11,9 @@ use std::panic::{catch_unwind, UnwindSafe};
 use std::process::abort;
 
 pub mod cliargsparser;
+pub mod configcheck;
 pub mod configpaths;
+pub mod controlsocket;
 pub mod fmtstrformatter;
 pub mod fslock;
 pub mod history;
@@ -18,6 +18,36 @@ pub unsafe extern "C" fn rs_set_loglevel(level: logger::Level) {
     })
 }
 
+#[no_mangle]
+pub unsafe extern "C" fn rs_set_log_filter(filter: *const c_char) {
+    abort_on_panic(|| {
+        let filter = CStr::from_ptr(filter);
+        let filter = filter
+            .to_str()
+            .expect("log filter contained invalid UTF-8")
+            .parse::<logger::LogFilter>()
+            .expect("log filter is invalid");
+        logger::get_instance().set_filter(filter);
+    })
+}
+
+#[no_mangle]
+pub extern "C" fn rs_set_log_rotation(max_size: u64, max_files: u32) {
+    abort_on_panic(|| {
+        logger::get_instance().set_rotation(Some(logger::LogRotation {
+            max_size,
+            max_files,
+        }));
+    })
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn rs_set_log_format(format: logger::LogFormat) {
+    abort_on_panic(|| {
+        logger::get_instance().set_log_format(format);
+    })
+}
+
 #[no_mangle]
 pub unsafe extern "C" fn rs_unset_loglevel() {
     abort_on_panic(|| {
@@ -49,5 +79,7 @@ pub unsafe extern "C" fn rs_set_user_error_logfile(user_error_logfile: *const c_
 
 #[no_mangle]
 pub extern "C" fn rs_get_loglevel() -> i64 {
-    abort_on_panic(|| logger::get_instance().get_loglevel() as i64)
+    // C++ uses this to skip formatting messages that won't be recorded, so this has to include
+    // the messages that are only kept in memory.
+    abort_on_panic(|| logger::get_instance().get_recorded_level() as i64)
 }
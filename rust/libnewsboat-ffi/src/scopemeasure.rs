@@ -1,4 +1,6 @@
-use libnewsboat::scopemeasure::ScopeMeasure;
+use libnewsboat::scopemeasure::{self, ScopeMeasure};
+
+use std::path::Path;
 
 #[cxx::bridge(namespace = "newsboat::scopemeasure::bridged")]
 mod bridged {
@@ -7,9 +9,31 @@ mod bridged {
 
         fn create(scope_name: String) -> Box<ScopeMeasure>;
         fn stopover(self: &ScopeMeasure, stopover_name: &str);
+
+        fn log_summary();
+        fn enable_trace();
+        fn write_trace(path: &str, error_message: &mut String) -> bool;
     }
 }
 
 fn create(scope_name: String) -> Box<ScopeMeasure> {
     Box::new(ScopeMeasure::new(scope_name))
 }
+
+fn log_summary() {
+    scopemeasure::log_summary();
+}
+
+fn enable_trace() {
+    scopemeasure::enable_trace();
+}
+
+fn write_trace(path: &str, error_message: &mut String) -> bool {
+    match scopemeasure::write_trace(Path::new(path)) {
+        Ok(()) => true,
+        Err(e) => {
+            *error_message = e.to_string();
+            false
+        }
+    }
+}
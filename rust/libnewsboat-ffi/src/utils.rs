@@ -1,7 +1,10 @@
 use libc::c_char;
+use libnewsboat::feedsource::FeedSource;
 use libnewsboat::utils::{self, *};
 use std::ffi::{CStr, CString};
+use std::io;
 use std::path::{Path, PathBuf};
+use std::time::Duration;
 
 #[cxx::bridge(namespace = "newsboat::utils")]
 mod ffi {
@@ -38,6 +41,26 @@ mod ffi {
 // Functions that should be wrapped on the C++ side for ease of use.
 #[cxx::bridge(namespace = "newsboat::utils::bridged")]
 mod bridged {
+    /// See `utils::CommandOutput`. `exit_code` is only meaningful if `exited` is true.
+    struct CommandOutput {
+        exited: bool,
+        exit_code: i32,
+        output: String,
+        error_output: String,
+        timed_out: bool,
+        truncated: bool,
+    }
+
+    /// See `feedsource::FeedSource`. `kind` is the index of the variant in C++'s
+    /// `utils::FeedSourceKind`; the meaning of the other fields depends on it.
+    struct FeedSourceParts {
+        kind: u8,
+        location: String,
+        command: String,
+        title: String,
+        expression: String,
+    }
+
     extern "Rust" {
         fn to_u(input: String, default_value: u32) -> u32;
 
@@ -82,6 +105,30 @@ mod bridged {
         fn podcast_mime_to_link_type(mime_type: &str, result: &mut i64) -> bool;
 
         fn run_program(argv: &Vec<String>, input: &str) -> String;
+        fn parse_query_url(
+            url: &str,
+            title: &mut String,
+            expression: &mut String,
+            error_message: &mut String,
+        ) -> bool;
+        fn parse_feed_source(
+            url: &str,
+            parts: &mut FeedSourceParts,
+            error_message: &mut String,
+        ) -> bool;
+        fn get_command_output_with_limits(
+            cmd: &str,
+            timeout_seconds: u64,
+            result: &mut CommandOutput,
+            error_message: &mut String,
+        ) -> bool;
+        fn run_program_with_limits(
+            argv: &Vec<String>,
+            input: &str,
+            timeout_seconds: u64,
+            result: &mut CommandOutput,
+            error_message: &mut String,
+        ) -> bool;
 
         fn translit(tocode: &str, fromcode: &str) -> String;
         fn convert_text(text: &[u8], tocode: &str, fromcode: &str) -> Vec<u8>;
@@ -240,6 +287,136 @@ fn run_program(argv: &Vec<String>, input: &str) -> String {
     utils::run_program(&argv_of_str, input)
 }
 
+fn parse_query_url(
+    url: &str,
+    title: &mut String,
+    expression: &mut String,
+    error_message: &mut String,
+) -> bool {
+    match url.parse::<FeedSource>() {
+        Ok(FeedSource::Query {
+            title: t,
+            expression: e,
+        }) => {
+            *title = t;
+            *expression = e;
+            true
+        }
+        Ok(_) => {
+            *error_message = format!("`{}' is not a query", url);
+            false
+        }
+        Err(e) => {
+            *error_message = e.to_string();
+            false
+        }
+    }
+}
+
+// Keep in sync with `utils::FeedSourceKind` in include/utils.h.
+const FEED_SOURCE_URL: u8 = 0;
+const FEED_SOURCE_LOCAL_FILE: u8 = 1;
+const FEED_SOURCE_QUERY: u8 = 2;
+const FEED_SOURCE_FILTER: u8 = 3;
+const FEED_SOURCE_EXEC: u8 = 4;
+
+fn parse_feed_source(
+    url: &str,
+    parts: &mut bridged::FeedSourceParts,
+    error_message: &mut String,
+) -> bool {
+    let source = match url.parse::<FeedSource>() {
+        Ok(source) => source,
+        Err(e) => {
+            *error_message = e.to_string();
+            return false;
+        }
+    };
+    match source {
+        FeedSource::Http { url } | FeedSource::Remote { url } => {
+            parts.kind = FEED_SOURCE_URL;
+            parts.location = url;
+        }
+        FeedSource::File { path } => {
+            parts.kind = FEED_SOURCE_LOCAL_FILE;
+            parts.location = path;
+        }
+        FeedSource::Query { title, expression } => {
+            parts.kind = FEED_SOURCE_QUERY;
+            parts.title = title;
+            parts.expression = expression;
+        }
+        FeedSource::Filter { script, inner } => {
+            parts.kind = FEED_SOURCE_FILTER;
+            parts.command = script;
+            parts.location = inner.to_string();
+        }
+        FeedSource::Exec { command } => {
+            parts.kind = FEED_SOURCE_EXEC;
+            parts.command = command;
+        }
+    }
+    true
+}
+
+/// Zero `timeout_seconds` means no timeout.
+fn command_limits(timeout_seconds: u64) -> CommandLimits {
+    CommandLimits {
+        timeout: match timeout_seconds {
+            0 => None,
+            seconds => Some(Duration::from_secs(seconds)),
+        },
+        ..CommandLimits::default()
+    }
+}
+
+fn store_command_output(
+    output: io::Result<utils::CommandOutput>,
+    result: &mut bridged::CommandOutput,
+    error_message: &mut String,
+) -> bool {
+    match output {
+        Ok(output) => {
+            result.exited = output.exit_code.is_some();
+            result.exit_code = output.exit_code.unwrap_or(0);
+            result.output = output.stdout;
+            result.error_output = output.stderr;
+            result.timed_out = output.timed_out;
+            result.truncated = output.truncated;
+            true
+        }
+        Err(error) => {
+            *error_message = error.to_string();
+            false
+        }
+    }
+}
+
+fn get_command_output_with_limits(
+    cmd: &str,
+    timeout_seconds: u64,
+    result: &mut bridged::CommandOutput,
+    error_message: &mut String,
+) -> bool {
+    let output = utils::get_command_output_with_limits(cmd, command_limits(timeout_seconds));
+    store_command_output(output, result, error_message)
+}
+
+// See the comment on `run_program()` about `&Vec<_>`.
+#[allow(clippy::ptr_arg)]
+fn run_program_with_limits(
+    argv: &Vec<String>,
+    input: &str,
+    timeout_seconds: u64,
+    result: &mut bridged::CommandOutput,
+    error_message: &mut String,
+) -> bool {
+    let argv_of_str = argv.iter().map(String::as_str).collect::<Vec<_>>();
+    let output =
+        utils::run_program_with_limits(&argv_of_str, input, command_limits(timeout_seconds));
+    store_command_output(output, result, error_message)
+}
+
 #[no_mangle]
 pub unsafe extern "C" fn rs_get_string(line: *const c_char) -> *mut c_char {
     let line = CStr::from_ptr(line);
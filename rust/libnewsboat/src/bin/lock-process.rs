@@ -1,11 +1,40 @@
 //! Helper binary to help lock process for testing.
+//!
+//! Usage: lock-process [--shared] LOCKFILE [OWNER]
+//!
+//! With `--shared`, a shared lock (as used by read-only instances) is taken instead of the
+//! exclusive one.
+//!
+//! If OWNER is given, it's written into the lock file in place of the real owner information, so
+//! that tests can simulate locks left behind by other processes and hosts.
 use libnewsboat::fslock::FsLock;
+use std::io::Write;
 
 fn main() {
-    let lock_location = std::env::args().nth(1).unwrap();
+    let mut args: Vec<String> = std::env::args().skip(1).collect();
+    let shared = args.first().map(String::as_str) == Some("--shared");
+    if shared {
+        args.remove(0);
+    }
+    let lock_location = args[0].clone();
 
     let mut lock = FsLock::default();
-    assert!(lock.try_lock(lock_location.as_ref(), &mut 0).is_ok());
+    if shared {
+        assert!(lock.try_lock_shared(lock_location.as_ref()).is_ok());
+    } else {
+        assert!(lock.try_lock(lock_location.as_ref(), &mut 0).is_ok());
+    }
+
+    if let Some(owner) = args.get(1) {
+        let mut file = std::fs::OpenOptions::new()
+            .write(true)
+            .truncate(true)
+            .open(&lock_location)
+            .unwrap();
+        file.write_all(owner.as_bytes()).unwrap();
+        // Closing any descriptor of the file releases our lockf() lock, so keep this one open
+        std::mem::forget(file);
+    }
 
     // signal that we already lock file
     println!();
@@ -0,0 +1,680 @@
+//! Answers questions about the cache without starting the user interface.
+//!
+//! This is what `newsboat query ...` runs. The cache is opened read-only, so queries can be run
+//! while another instance of Newsboat is using it, e.g. from a status bar widget.
+
+mod output;
+mod queries;
+mod sqlite;
+
+use crate::matchable::Matchable;
+use crate::matcher::Matcher;
+use crate::utils::{self, ReadTextFileError};
+use chrono::{Local, TimeZone};
+use gettextrs::gettext;
+use sqlite::{Connection, Param, Row};
+use std::collections::{BTreeMap, HashMap};
+use std::path::Path;
+use std::str::FromStr;
+use strprintf::fmt;
+
+/// A question that can be asked about the cache.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Query {
+    /// Unread and total numbers of articles in each feed.
+    FeedCounts,
+
+    /// Unread and total numbers of articles in feeds with each tag.
+    TagCounts,
+
+    /// Unread articles, optionally only those that match a filter expression.
+    UnreadItems { filter: Option<String> },
+
+    /// Everything about a single article, identified by its ID in the cache.
+    Item { id: i64 },
+}
+
+/// How the answer to a query is printed.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum OutputFormat {
+    /// One record per line, fields separated by tabs.
+    Text,
+    Json,
+}
+
+// Deriving `Default` for an enum needs `#[default]`, which is newer than the Rust we support.
+#[allow(unknown_lints, clippy::derivable_impls)]
+impl Default for OutputFormat {
+    fn default() -> Self {
+        OutputFormat::Text
+    }
+}
+
+impl FromStr for OutputFormat {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "text" => Ok(OutputFormat::Text),
+            "json" => Ok(OutputFormat::Json),
+            _ => Err(()),
+        }
+    }
+}
+
+/// Numbers of articles in a feed.
+#[derive(Debug, PartialEq)]
+pub struct FeedCount {
+    pub rssurl: String,
+    pub title: String,
+    pub unread: u64,
+    pub total: u64,
+}
+
+/// Numbers of articles in all the feeds with a given tag.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TagCount {
+    pub tag: String,
+    pub unread: u64,
+    pub total: u64,
+}
+
+/// An article, as stored in the cache.
+#[derive(Debug, PartialEq)]
+pub struct Item {
+    pub id: i64,
+    pub guid: String,
+    pub title: String,
+    pub author: String,
+    pub link: String,
+    pub feedurl: String,
+    pub feedtitle: String,
+    /// Tags of the feed, as listed in the urls file.
+    pub tags: Vec<String>,
+    /// Publication date, in seconds since the Unix epoch.
+    pub pubdate: i64,
+    pub unread: bool,
+    pub flags: String,
+    pub enclosure_url: String,
+    pub enclosure_type: String,
+    pub content: String,
+    pub content_mime_type: String,
+}
+
+impl Matchable for Item {
+    fn attribute_value(&self, attr: &str) -> Option<String> {
+        match attr {
+            "title" => Some(self.title.clone()),
+            "link" => Some(self.link.clone()),
+            "author" => Some(self.author.clone()),
+            "content" => Some(self.content.clone()),
+            "date" => Some(
+                Local
+                    .timestamp_opt(self.pubdate, 0)
+                    .single()
+                    .map(|date| date.format("%a, %d %b %Y %T %z").to_string())
+                    .unwrap_or_default(),
+            ),
+            "guid" => Some(self.guid.clone()),
+            "unread" => Some(if self.unread { "yes" } else { "no" }.to_string()),
+            "enclosure_url" => Some(self.enclosure_url.clone()),
+            "enclosure_type" => Some(self.enclosure_type.clone()),
+            "flags" => Some(self.flags.clone()),
+            "age" => Some(((Local::now().timestamp() - self.pubdate) / 86400).to_string()),
+            "feedtitle" => Some(self.feedtitle.clone()),
+            "rssurl" => Some(self.feedurl.clone()),
+            "tags" => Some(self.tags.join(" ")),
+            _ => None,
+        }
+    }
+}
+
+/// A feed listed in the urls file.
+#[derive(Debug, PartialEq)]
+struct Subscription {
+    url: String,
+    /// Tags, not including the special ones that start with `~` and `!`.
+    tags: Vec<String>,
+    /// Title set with a `~` tag.
+    title: Option<String>,
+    /// True if the feed has a `!` tag, which hides it from the feed list.
+    hidden: bool,
+}
+
+/// Read-only view of the cache, limited to the feeds the user is subscribed to.
+pub struct Cache {
+    connection: Connection,
+    subscriptions: Vec<Subscription>,
+}
+
+impl Cache {
+    /// Opens the cache, and reads the list of subscriptions from the urls file.
+    ///
+    /// Returns an explanation if either of the files can't be read.
+    pub fn open(cache_file: &Path, url_file: &Path) -> Result<Cache, String> {
+        let subscriptions = read_subscriptions(url_file)?;
+        let connection = Connection::open_read_only(cache_file).map_err(|error| {
+            fmt!(
+                &gettext("Error: failed to open cache file `%s': %s"),
+                cache_file.to_string_lossy().into_owned(),
+                error
+            )
+        })?;
+        Ok(Cache {
+            connection,
+            subscriptions,
+        })
+    }
+
+    /// Returns the numbers of articles in each visible feed, in the order of the urls file.
+    ///
+    /// Query feeds are skipped, because their articles are only known to a running Newsboat.
+    pub fn feed_counts(&self) -> Result<Vec<FeedCount>, String> {
+        let rows = self.query(queries::FEED_COUNTS, &[])?;
+        let mut counts = BTreeMap::new();
+        for row in rows {
+            counts.insert(
+                row[0].clone(),
+                (row[1].clone(), to_u64(&row[2]), to_u64(&row[3])),
+            );
+        }
+
+        Ok(self
+            .visible_subscriptions()
+            .map(|subscription| {
+                let (title, total, unread) = counts
+                    .get(&subscription.url)
+                    .cloned()
+                    .unwrap_or_else(|| (String::new(), 0, 0));
+                FeedCount {
+                    rssurl: subscription.url.clone(),
+                    title: display_title(subscription, &title),
+                    unread,
+                    total,
+                }
+            })
+            .collect())
+    }
+
+    /// Returns the numbers of articles in visible feeds with each tag, sorted by tag.
+    pub fn tag_counts(&self) -> Result<Vec<TagCount>, String> {
+        let feed_counts = self.feed_counts()?;
+        let mut counts: BTreeMap<&str, TagCount> = BTreeMap::new();
+        for (subscription, feed) in self.visible_subscriptions().zip(&feed_counts) {
+            for tag in &subscription.tags {
+                let count = counts.entry(tag).or_insert_with(|| TagCount {
+                    tag: tag.clone(),
+                    unread: 0,
+                    total: 0,
+                });
+                count.unread += feed.unread;
+                count.total += feed.total;
+            }
+        }
+        Ok(counts.values().cloned().collect())
+    }
+
+    /// Returns unread articles from visible feeds, newest first. If `filter` is given, only the
+    /// articles that match it are returned.
+    pub fn unread_items(&self, filter: Option<&Matcher>) -> Result<Vec<Item>, String> {
+        // If a feed is listed more than once, the first entry wins, as it does in the feed list
+        let mut subscriptions = HashMap::new();
+        for subscription in self.visible_subscriptions() {
+            subscriptions
+                .entry(subscription.url.as_str())
+                .or_insert(subscription);
+        }
+        let urls = subscriptions.keys().copied().collect::<Vec<_>>();
+
+        let mut items = Vec::new();
+        for urls in urls.chunks(MAX_PARAMS) {
+            let params = urls.iter().map(|url| Param::Text(url)).collect::<Vec<_>>();
+            let rows = self.query(&queries::unread_items(urls.len()), &params)?;
+
+            for row in rows {
+                let subscription = match subscriptions.get(row[5].as_str()) {
+                    Some(subscription) => subscription,
+                    None => continue,
+                };
+                let item = item_from_row(row, subscription);
+                let matches = match filter {
+                    Some(matcher) => matcher.matches(&item).map_err(|e| e.to_string())?,
+                    None => true,
+                };
+                if matches {
+                    items.push(item);
+                }
+            }
+        }
+        items.sort_by(|a, b| b.pubdate.cmp(&a.pubdate).then(b.id.cmp(&a.id)));
+        Ok(items)
+    }
+
+    /// Returns the article with ID `id`, or `None` if there is no such article.
+    pub fn item(&self, id: i64) -> Result<Option<Item>, String> {
+        let rows = self.query(queries::ITEM, &[Param::Integer(id)])?;
+        Ok(rows.into_iter().next().map(|row| {
+            let subscription = self
+                .subscriptions
+                .iter()
+                .find(|subscription| subscription.url == row[5]);
+            match subscription {
+                Some(subscription) => item_from_row(row, subscription),
+                None => {
+                    let unsubscribed = Subscription {
+                        url: row[5].clone(),
+                        tags: Vec::new(),
+                        title: None,
+                        hidden: false,
+                    };
+                    item_from_row(row, &unsubscribed)
+                }
+            }
+        }))
+    }
+
+    fn visible_subscriptions(&self) -> impl Iterator<Item = &Subscription> {
+        self.subscriptions
+            .iter()
+            .filter(|subscription| !subscription.hidden && !utils::is_query_url(&subscription.url))
+    }
+
+    fn query(&self, sql: &str, params: &[Param]) -> Result<Vec<Row>, String> {
+        self.connection
+            .query(sql, params)
+            .map_err(|error| fmt!(&gettext("Error: failed to query the cache: %s"), error))
+    }
+}
+
+/// How many `?` placeholders a query may have. Older versions of SQLite don't allow more than 999.
+const MAX_PARAMS: usize = 500;
+
+/// Turns a row returned by `queries::ITEM` or `queries::unread_items()` into an `Item`.
+fn item_from_row(mut row: Row, subscription: &Subscription) -> Item {
+    let mut take = |column: usize| std::mem::take(&mut row[column]);
+    let feedtitle = take(13);
+    Item {
+        id: take(0).parse().unwrap_or(0),
+        guid: take(1),
+        title: take(2),
+        author: take(3),
+        link: take(4),
+        feedurl: take(5),
+        pubdate: take(6).parse().unwrap_or(0),
+        unread: take(7) == "1",
+        flags: take(8),
+        enclosure_url: take(9),
+        enclosure_type: take(10),
+        content: take(11),
+        content_mime_type: take(12),
+        feedtitle: display_title(subscription, &feedtitle),
+        tags: subscription.tags.clone(),
+    }
+}
+
+/// Title of the feed as shown in the feed list: the one set in the urls file, or the one from the
+/// feed itself, or the URL if the feed has no title.
+fn display_title(subscription: &Subscription, cached_title: &str) -> String {
+    match subscription.title {
+        Some(ref title) => title.clone(),
+        None if !cached_title.is_empty() => cached_title.to_string(),
+        None => subscription.url.clone(),
+    }
+}
+
+fn to_u64(value: &str) -> u64 {
+    value.parse().unwrap_or(0)
+}
+
+/// Runs `query` against the cache and formats the answer.
+///
+/// Returns an explanation if the query couldn't be answered.
+pub fn run(
+    query: &Query,
+    format: OutputFormat,
+    cache_file: &Path,
+    url_file: &Path,
+) -> Result<String, String> {
+    let cache = Cache::open(cache_file, url_file)?;
+    match query {
+        Query::FeedCounts => Ok(output::feed_counts(&cache.feed_counts()?, format)),
+        Query::TagCounts => Ok(output::tag_counts(&cache.tag_counts()?, format)),
+        Query::UnreadItems { filter } => {
+            let matcher = match filter {
+                Some(filter) => Some(Matcher::parse(filter)?),
+                None => None,
+            };
+            Ok(output::items(
+                &cache.unread_items(matcher.as_ref())?,
+                format,
+            ))
+        }
+        Query::Item { id } => match cache.item(*id)? {
+            Some(item) => Ok(output::item_details(&item, format)),
+            None => Err(fmt!(
+                &gettext("Error: there is no article with ID %s"),
+                id.to_string()
+            )),
+        },
+    }
+}
+
+fn read_subscriptions(url_file: &Path) -> Result<Vec<Subscription>, String> {
+    let lines = utils::read_text_file(url_file).map_err(|error| {
+        let reason = match error {
+            ReadTextFileError::CantOpen { reason } => {
+                fmt!(&gettext("Failed to open file (%s)"), reason.to_string())
+            }
+            ReadTextFileError::LineError {
+                line_number,
+                reason,
+            } => fmt!(
+                &gettext("Failed to read line %u (%s)"),
+                line_number as u32,
+                reason.to_string()
+            ),
+        };
+        fmt!(
+            &gettext("Error: Failed to read URLs from file \"%s\" (%s)"),
+            url_file.to_string_lossy().into_owned(),
+            reason
+        )
+    })?;
+
+    Ok(lines
+        .iter()
+        .filter(|line| !line.starts_with('#'))
+        .filter_map(|line| {
+            let mut tokens = utils::tokenize_quoted(line).into_iter();
+            let url = tokens.next()?;
+            let mut subscription = Subscription {
+                url,
+                tags: Vec::new(),
+                title: None,
+                hidden: false,
+            };
+            for token in tokens {
+                if let Some(title) = token.strip_prefix('~') {
+                    subscription.title = Some(title.to_string());
+                } else if token.starts_with('!') {
+                    subscription.hidden = true;
+                } else {
+                    subscription.tags.push(token);
+                }
+            }
+            Some(subscription)
+        })
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use tempfile::TempDir;
+
+    /// The parts of the cache schema that queries rely on.
+    const SCHEMA: &str = "CREATE TABLE rss_feed ( \
+            rssurl VARCHAR(1024) PRIMARY KEY NOT NULL, \
+            url VARCHAR(1024) NOT NULL, \
+            title VARCHAR(1024) NOT NULL ); \
+        CREATE TABLE rss_item ( \
+            id INTEGER PRIMARY KEY AUTOINCREMENT NOT NULL, \
+            guid VARCHAR(64) NOT NULL, \
+            title VARCHAR(1024) NOT NULL, \
+            author VARCHAR(1024) NOT NULL, \
+            url VARCHAR(1024) NOT NULL, \
+            feedurl VARCHAR(1024) NOT NULL, \
+            pubDate INTEGER NOT NULL, \
+            content VARCHAR(65535) NOT NULL, \
+            unread INTEGER(1) NOT NULL, \
+            enclosure_url VARCHAR(1024), \
+            enclosure_type VARCHAR(1024), \
+            flags VARCHAR(52), \
+            deleted INTEGER(1) NOT NULL DEFAULT 0, \
+            content_mime_type VARCHAR(255) NOT NULL DEFAULT '');";
+
+    const URLS: &str = "# comment\n\
+        https://example.com/news.xml news \"tech stuff\"\n\
+        https://example.com/blog.xml \"tech stuff\" \"~My blog\"\n\
+        https://example.com/hidden.xml news !\n\
+        \"query:Unread:unread = \\\"yes\\\"\"\n\
+        https://example.com/empty.xml news\n";
+
+    const DATA: &str = "INSERT INTO rss_feed VALUES \
+            ('https://example.com/news.xml', 'https://example.com/', 'News'), \
+            ('https://example.com/blog.xml', 'https://example.com/blog', 'Blog'), \
+            ('https://example.com/hidden.xml', 'https://example.com/h', 'Hidden'), \
+            ('https://example.com/old.xml', 'https://example.com/old', 'Unsubscribed'); \
+        INSERT INTO rss_item \
+            (id, guid, title, author, url, feedurl, pubDate, content, unread, enclosure_url, \
+             enclosure_type, flags, deleted) \
+        VALUES \
+            (1, 'n1', 'First news', 'Alice', 'https://example.com/1', \
+             'https://example.com/news.xml', 100, '<p>One</p>', 1, '', '', 'a', 0), \
+            (2, 'n2', 'Second news', 'Bob', 'https://example.com/2', \
+             'https://example.com/news.xml', 300, '<p>Two</p>', 1, '', '', '', 0), \
+            (3, 'n3', 'Read news', 'Alice', 'https://example.com/3', \
+             'https://example.com/news.xml', 200, '<p>Three</p>', 0, '', '', '', 0), \
+            (4, 'n4', 'Deleted news', 'Alice', 'https://example.com/4', \
+             'https://example.com/news.xml', 400, '', 1, '', '', '', 1), \
+            (5, 'b1', 'Blog\tpost', 'Carol', 'https://example.com/blog/1', \
+             'https://example.com/blog.xml', 200, 'Text', 1, 'https://example.com/1.mp3', \
+             'audio/mpeg', NULL, 0), \
+            (6, 'h1', 'Hidden post', 'Dave', 'https://example.com/h/1', \
+             'https://example.com/hidden.xml', 500, '', 1, '', '', '', 0), \
+            (7, 'o1', 'Old post', 'Eve', 'https://example.com/old/1', \
+             'https://example.com/old.xml', 600, '', 1, '', '', '', 0);";
+
+    fn setup() -> (TempDir, Cache) {
+        let tmp = TempDir::new().unwrap();
+        let cache_file = tmp.path().join("cache.db");
+        let url_file = tmp.path().join("urls");
+        let connection = Connection::create(&cache_file).unwrap();
+        connection.execute(SCHEMA).unwrap();
+        connection.execute(DATA).unwrap();
+        fs::write(&url_file, URLS).unwrap();
+        let cache = Cache::open(&cache_file, &url_file).unwrap();
+        (tmp, cache)
+    }
+
+    #[test]
+    fn t_feed_counts_lists_visible_feeds_in_the_order_of_the_urls_file() {
+        let (_tmp, cache) = setup();
+
+        assert_eq!(
+            cache.feed_counts().unwrap(),
+            vec![
+                FeedCount {
+                    rssurl: "https://example.com/news.xml".to_string(),
+                    title: "News".to_string(),
+                    unread: 2,
+                    total: 3,
+                },
+                FeedCount {
+                    rssurl: "https://example.com/blog.xml".to_string(),
+                    title: "My blog".to_string(),
+                    unread: 1,
+                    total: 1,
+                },
+                FeedCount {
+                    rssurl: "https://example.com/empty.xml".to_string(),
+                    title: "https://example.com/empty.xml".to_string(),
+                    unread: 0,
+                    total: 0,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn t_tag_counts_sums_counts_of_visible_feeds_with_each_tag() {
+        let (_tmp, cache) = setup();
+
+        assert_eq!(
+            cache.tag_counts().unwrap(),
+            vec![
+                TagCount {
+                    tag: "news".to_string(),
+                    unread: 2,
+                    total: 3,
+                },
+                TagCount {
+                    tag: "tech stuff".to_string(),
+                    unread: 3,
+                    total: 4,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn t_unread_items_returns_unread_articles_of_visible_feeds_newest_first() {
+        let (_tmp, cache) = setup();
+
+        let ids = |items: Vec<Item>| items.iter().map(|item| item.id).collect::<Vec<_>>();
+
+        assert_eq!(ids(cache.unread_items(None).unwrap()), vec![2, 5, 1]);
+
+        let matcher = Matcher::parse("author = \"Alice\" or feedtitle = \"My blog\"").unwrap();
+        assert_eq!(ids(cache.unread_items(Some(&matcher)).unwrap()), vec![5, 1]);
+
+        let matcher = Matcher::parse("tags # \"news\"").unwrap();
+        assert_eq!(ids(cache.unread_items(Some(&matcher)).unwrap()), vec![2, 1]);
+    }
+
+    #[test]
+    fn t_unread_items_works_with_more_feeds_than_fit_in_a_single_query() {
+        let tmp = TempDir::new().unwrap();
+        let cache_file = tmp.path().join("cache.db");
+        let url_file = tmp.path().join("urls");
+        let connection = Connection::create(&cache_file).unwrap();
+        connection.execute(SCHEMA).unwrap();
+
+        let feeds = MAX_PARAMS * 2 + 1;
+        let mut urls = String::new();
+        let mut data = String::new();
+        for i in 0..feeds {
+            let url = format!("https://example.com/{}.xml", i);
+            urls.push_str(&format!("{}\n", url));
+            data.push_str(&format!(
+                "INSERT INTO rss_item (id, guid, title, author, url, feedurl, pubDate, content, \
+                    unread) VALUES ({}, 'g{}', 'Post', '', '', '{}', {}, '', 1);",
+                i + 1,
+                i,
+                url,
+                i
+            ));
+        }
+        connection.execute(&data).unwrap();
+        fs::write(&url_file, urls).unwrap();
+
+        let cache = Cache::open(&cache_file, &url_file).unwrap();
+        let items = cache.unread_items(None).unwrap();
+        assert_eq!(items.len(), feeds);
+        assert_eq!(items[0].id, feeds as i64);
+        assert_eq!(items[feeds - 1].id, 1);
+    }
+
+    #[test]
+    fn t_unread_items_returns_an_error_if_filter_uses_unknown_attribute() {
+        let (_tmp, cache) = setup();
+
+        let matcher = Matcher::parse("nonexistent = \"yes\"").unwrap();
+        let error = cache.unread_items(Some(&matcher)).unwrap_err();
+        assert!(error.contains("nonexistent"));
+    }
+
+    #[test]
+    fn t_item_returns_details_of_any_article_that_isnt_deleted() {
+        let (_tmp, cache) = setup();
+
+        let item = cache.item(5).unwrap().unwrap();
+        assert_eq!(
+            item,
+            Item {
+                id: 5,
+                guid: "b1".to_string(),
+                title: "Blog\tpost".to_string(),
+                author: "Carol".to_string(),
+                link: "https://example.com/blog/1".to_string(),
+                feedurl: "https://example.com/blog.xml".to_string(),
+                feedtitle: "My blog".to_string(),
+                tags: vec!["tech stuff".to_string()],
+                pubdate: 200,
+                unread: true,
+                flags: String::new(),
+                enclosure_url: "https://example.com/1.mp3".to_string(),
+                enclosure_type: "audio/mpeg".to_string(),
+                content: "Text".to_string(),
+                content_mime_type: String::new(),
+            }
+        );
+
+        assert!(!cache.item(3).unwrap().unwrap().unread);
+        assert_eq!(cache.item(7).unwrap().unwrap().feedtitle, "Unsubscribed");
+        assert_eq!(cache.item(4).unwrap(), None);
+        assert_eq!(cache.item(42).unwrap(), None);
+    }
+
+    #[test]
+    fn t_run_formats_answers_as_text_or_json() {
+        let (tmp, _cache) = setup();
+        let cache_file = tmp.path().join("cache.db");
+        let url_file = tmp.path().join("urls");
+        let run = |query, format| run(&query, format, &cache_file, &url_file).unwrap();
+
+        assert_eq!(
+            run(Query::TagCounts, OutputFormat::Text),
+            "2\t3\tnews\n3\t4\ttech stuff\n"
+        );
+        assert_eq!(
+            run(Query::FeedCounts, OutputFormat::Json),
+            concat!(
+                r#"[{"rssurl":"https://example.com/news.xml","title":"News","unread":2,"total":3},"#,
+                r#"{"rssurl":"https://example.com/blog.xml","title":"My blog","unread":1,"total":1},"#,
+                r#"{"rssurl":"https://example.com/empty.xml","title":"https://example.com/empty.xml","unread":0,"total":0}]"#,
+                "\n"
+            )
+        );
+
+        let items = run(
+            Query::UnreadItems {
+                filter: Some("feedtitle = \"My blog\"".to_string()),
+            },
+            OutputFormat::Text,
+        );
+        // Tabs inside fields are replaced, so that they don't break the columns
+        assert!(items.starts_with("5\t"));
+        assert!(items.ends_with("\tMy blog\tBlog post\thttps://example.com/blog/1\n"));
+
+        let item = run(Query::Item { id: 1 }, OutputFormat::Json);
+        assert!(item.starts_with(r#"{"id":1,"title":"First news","author":"Alice","#));
+        assert!(item.contains(r#""tags":["news","tech stuff"],"date":100,"unread":true,"#));
+        assert!(item.ends_with("\"content\":\"<p>One</p>\",\"content_mime_type\":\"\"}\n"));
+    }
+
+    #[test]
+    fn t_run_returns_an_error_if_query_cant_be_answered() {
+        let (tmp, _cache) = setup();
+        let cache_file = tmp.path().join("cache.db");
+        let url_file = tmp.path().join("urls");
+        let missing = tmp.path().join("missing");
+
+        let run = |query, cache_file: &Path, url_file: &Path| {
+            run(&query, OutputFormat::Text, cache_file, url_file)
+        };
+
+        assert!(run(Query::FeedCounts, &missing, &url_file).is_err());
+        assert!(run(Query::FeedCounts, &cache_file, &missing).is_err());
+        assert!(run(Query::Item { id: 42 }, &cache_file, &url_file).is_err());
+        assert!(run(
+            Query::UnreadItems {
+                filter: Some("title =".to_string())
+            },
+            &cache_file,
+            &url_file
+        )
+        .is_err());
+    }
+}
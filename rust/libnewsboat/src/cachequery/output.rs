@@ -0,0 +1,158 @@
+//! Formatting of query answers as text or JSON.
+
+use super::{FeedCount, Item, OutputFormat, TagCount};
+use crate::utils;
+use chrono::{Local, TimeZone};
+use gettextrs::gettext;
+
+pub fn feed_counts(counts: &[FeedCount], format: OutputFormat) -> String {
+    match format {
+        OutputFormat::Text => lines(counts.iter().map(|count| {
+            fields(&[
+                &count.unread.to_string(),
+                &count.total.to_string(),
+                &count.title,
+                &count.rssurl,
+            ])
+        })),
+        OutputFormat::Json => json_list(counts.iter().map(|count| {
+            json_object(&[
+                ("rssurl", utils::quote_for_json(&count.rssurl)),
+                ("title", utils::quote_for_json(&count.title)),
+                ("unread", count.unread.to_string()),
+                ("total", count.total.to_string()),
+            ])
+        })),
+    }
+}
+
+pub fn tag_counts(counts: &[TagCount], format: OutputFormat) -> String {
+    match format {
+        OutputFormat::Text => lines(counts.iter().map(|count| {
+            fields(&[
+                &count.unread.to_string(),
+                &count.total.to_string(),
+                &count.tag,
+            ])
+        })),
+        OutputFormat::Json => json_list(counts.iter().map(|count| {
+            json_object(&[
+                ("tag", utils::quote_for_json(&count.tag)),
+                ("unread", count.unread.to_string()),
+                ("total", count.total.to_string()),
+            ])
+        })),
+    }
+}
+
+pub fn items(items: &[Item], format: OutputFormat) -> String {
+    match format {
+        OutputFormat::Text => lines(items.iter().map(|item| {
+            fields(&[
+                &item.id.to_string(),
+                &format_date(item.pubdate, "%Y-%m-%d %H:%M"),
+                &item.feedtitle,
+                &item.title,
+                &item.link,
+            ])
+        })),
+        OutputFormat::Json => json_list(items.iter().map(|item| json_object(&item_fields(item)))),
+    }
+}
+
+pub fn item_details(item: &Item, format: OutputFormat) -> String {
+    match format {
+        OutputFormat::Text => {
+            let mut result = String::new();
+            // Same headers as in the article view
+            let mut header = |name: String, value: &str| {
+                if !value.is_empty() {
+                    result.push_str(&format!("{}{}\n", name, value));
+                }
+            };
+            header(gettext("Feed: "), &item.feedtitle);
+            header(gettext("Title: "), &item.title);
+            header(gettext("Author: "), &item.author);
+            header(
+                gettext("Date: "),
+                &format_date(item.pubdate, "%a, %d %b %Y %T %z"),
+            );
+            header(gettext("Link: "), &item.link);
+            header(gettext("Flags: "), &item.flags);
+            header(gettext("Podcast Download URL: "), &item.enclosure_url);
+            result.push('\n');
+            result.push_str(&item.content);
+            result.push('\n');
+            result
+        }
+        OutputFormat::Json => {
+            let mut fields = item_fields(item);
+            fields.push(("guid", utils::quote_for_json(&item.guid)));
+            fields.push(("content", utils::quote_for_json(&item.content)));
+            fields.push((
+                "content_mime_type",
+                utils::quote_for_json(&item.content_mime_type),
+            ));
+            json_object(&fields) + "\n"
+        }
+    }
+}
+
+fn item_fields(item: &Item) -> Vec<(&'static str, String)> {
+    let tags = item
+        .tags
+        .iter()
+        .map(|tag| utils::quote_for_json(tag))
+        .collect::<Vec<_>>();
+    vec![
+        ("id", item.id.to_string()),
+        ("title", utils::quote_for_json(&item.title)),
+        ("author", utils::quote_for_json(&item.author)),
+        ("link", utils::quote_for_json(&item.link)),
+        ("rssurl", utils::quote_for_json(&item.feedurl)),
+        ("feedtitle", utils::quote_for_json(&item.feedtitle)),
+        ("tags", format!("[{}]", tags.join(","))),
+        ("date", item.pubdate.to_string()),
+        ("unread", item.unread.to_string()),
+        ("flags", utils::quote_for_json(&item.flags)),
+        ("enclosure_url", utils::quote_for_json(&item.enclosure_url)),
+        (
+            "enclosure_type",
+            utils::quote_for_json(&item.enclosure_type),
+        ),
+    ]
+}
+
+fn format_date(timestamp: i64, format: &str) -> String {
+    Local
+        .timestamp_opt(timestamp, 0)
+        .single()
+        .map(|date| date.format(format).to_string())
+        .unwrap_or_default()
+}
+
+/// Joins the fields with tabs. Tabs and newlines inside the fields are replaced with spaces, so
+/// that each record stays on its own line.
+fn fields(values: &[&str]) -> String {
+    values
+        .iter()
+        .map(|value| value.replace(&['\t', '\n', '\r'][..], " "))
+        .collect::<Vec<_>>()
+        .join("\t")
+}
+
+fn lines(records: impl Iterator<Item = String>) -> String {
+    records.map(|record| record + "\n").collect()
+}
+
+fn json_object(fields: &[(&str, String)]) -> String {
+    let fields = fields
+        .iter()
+        .map(|(name, value)| format!("{}:{}", utils::quote_for_json(name), value))
+        .collect::<Vec<_>>();
+    format!("{{{}}}", fields.join(","))
+}
+
+fn json_list(values: impl Iterator<Item = String>) -> String {
+    format!("[{}]\n", values.collect::<Vec<_>>().join(","))
+}
@@ -0,0 +1,44 @@
+//! All the SQL that `newsboat query` runs against the cache.
+//!
+//! The cache is created and upgraded by the C++ `Cache` class, so these queries depend on the
+//! schema in `schemaPatches` in src/cache.cpp. Keep the two in sync: the "Queries run by
+//! `newsboat query` work on caches created by Cache" test in test/cache.cpp runs every query here
+//! against a cache written by the C++ code.
+
+/// Title, number of articles and number of unread articles of each feed in the cache.
+pub const FEED_COUNTS: &str = "SELECT rss_feed.rssurl, rss_feed.title, \
+        COUNT(rss_item.id), COALESCE(SUM(rss_item.unread), 0) \
+    FROM rss_feed \
+    LEFT JOIN rss_item \
+        ON rss_item.feedurl = rss_feed.rssurl AND rss_item.deleted = 0 \
+    GROUP BY rss_feed.rssurl";
+
+/// Columns of `rss_item` (and the title of its feed) that `item_from_row()` expects, in order.
+/// This is a macro so that it can be used in `concat!`.
+macro_rules! select_items {
+    () => {
+        "SELECT rss_item.id, rss_item.guid, rss_item.title, rss_item.author, \
+            rss_item.url, rss_item.feedurl, rss_item.pubDate, rss_item.unread, rss_item.flags, \
+            rss_item.enclosure_url, rss_item.enclosure_type, rss_item.content, \
+            rss_item.content_mime_type, COALESCE(rss_feed.title, '') \
+        FROM rss_item \
+        LEFT JOIN rss_feed ON rss_feed.rssurl = rss_item.feedurl "
+    };
+}
+
+/// The article whose ID is the only parameter.
+pub const ITEM: &str = concat!(
+    select_items!(),
+    "WHERE rss_item.id = ? AND rss_item.deleted = 0"
+);
+
+/// Unread articles of the feeds whose URLs are the parameters. There are `feeds` of them.
+pub fn unread_items(feeds: usize) -> String {
+    format!(
+        concat!(
+            select_items!(),
+            "WHERE rss_item.unread = 1 AND rss_item.deleted = 0 AND rss_item.feedurl IN ({})"
+        ),
+        vec!["?"; feeds].join(", ")
+    )
+}
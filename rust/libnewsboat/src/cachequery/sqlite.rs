@@ -0,0 +1,228 @@
+//! Just enough of SQLite's C API to run queries against the cache.
+//!
+//! The C++ code does all the writing; this side only ever reads, so there is no need for a full
+//! binding.
+
+use libc::{c_char, c_int, c_uchar};
+use std::ffi::{CStr, CString};
+use std::os::unix::ffi::OsStrExt;
+use std::path::Path;
+use std::ptr;
+
+#[allow(non_camel_case_types)]
+enum sqlite3 {}
+#[allow(non_camel_case_types)]
+enum sqlite3_stmt {}
+
+const SQLITE_OK: c_int = 0;
+const SQLITE_ROW: c_int = 100;
+const SQLITE_DONE: c_int = 101;
+const SQLITE_OPEN_READONLY: c_int = 0x0000_0001;
+/// Tells `sqlite3_bind_text` to make its own copy of the string.
+const SQLITE_TRANSIENT: isize = -1;
+#[cfg(test)]
+const SQLITE_OPEN_READWRITE: c_int = 0x0000_0002;
+#[cfg(test)]
+const SQLITE_OPEN_CREATE: c_int = 0x0000_0004;
+
+#[link(name = "sqlite3")]
+extern "C" {
+    fn sqlite3_open_v2(
+        filename: *const c_char,
+        db: *mut *mut sqlite3,
+        flags: c_int,
+        vfs: *const c_char,
+    ) -> c_int;
+    fn sqlite3_close(db: *mut sqlite3) -> c_int;
+    fn sqlite3_errmsg(db: *mut sqlite3) -> *const c_char;
+    fn sqlite3_busy_timeout(db: *mut sqlite3, ms: c_int) -> c_int;
+    fn sqlite3_prepare_v2(
+        db: *mut sqlite3,
+        sql: *const c_char,
+        nbyte: c_int,
+        stmt: *mut *mut sqlite3_stmt,
+        tail: *mut *const c_char,
+    ) -> c_int;
+    fn sqlite3_bind_int64(stmt: *mut sqlite3_stmt, index: c_int, value: i64) -> c_int;
+    fn sqlite3_bind_text(
+        stmt: *mut sqlite3_stmt,
+        index: c_int,
+        value: *const c_char,
+        nbyte: c_int,
+        destructor: isize,
+    ) -> c_int;
+    fn sqlite3_step(stmt: *mut sqlite3_stmt) -> c_int;
+    fn sqlite3_column_count(stmt: *mut sqlite3_stmt) -> c_int;
+    fn sqlite3_column_text(stmt: *mut sqlite3_stmt, column: c_int) -> *const c_uchar;
+    fn sqlite3_column_bytes(stmt: *mut sqlite3_stmt, column: c_int) -> c_int;
+    fn sqlite3_finalize(stmt: *mut sqlite3_stmt) -> c_int;
+    #[cfg(test)]
+    fn sqlite3_exec(
+        db: *mut sqlite3,
+        sql: *const c_char,
+        callback: *const u8,
+        arg: *mut u8,
+        errmsg: *mut *mut c_char,
+    ) -> c_int;
+}
+
+/// A value for a `?` placeholder.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Param<'a> {
+    Integer(i64),
+    Text(&'a str),
+}
+
+/// A row returned by a query, with all the columns converted to text. NULLs become empty strings.
+pub type Row = Vec<String>;
+
+/// An open SQLite database.
+pub struct Connection {
+    db: *mut sqlite3,
+}
+
+impl Connection {
+    /// Opens an existing database for reading.
+    ///
+    /// Returns an explanation if the database can't be opened.
+    pub fn open_read_only(path: &Path) -> Result<Connection, String> {
+        Connection::open(path, SQLITE_OPEN_READONLY)
+    }
+
+    /// Opens the database, creating it if it doesn't exist.
+    #[cfg(test)]
+    pub fn create(path: &Path) -> Result<Connection, String> {
+        Connection::open(path, SQLITE_OPEN_READWRITE | SQLITE_OPEN_CREATE)
+    }
+
+    fn open(path: &Path, flags: c_int) -> Result<Connection, String> {
+        let filename = CString::new(path.as_os_str().as_bytes()).map_err(|e| e.to_string())?;
+        let mut db = ptr::null_mut();
+        let result = unsafe { sqlite3_open_v2(filename.as_ptr(), &mut db, flags, ptr::null()) };
+        // SQLite allocates a handle even if it fails to open the database, so that the error
+        // message can be retrieved from it. The handle still has to be closed.
+        let connection = Connection { db };
+        if result != SQLITE_OK {
+            return Err(connection.error_message());
+        }
+        // The cache might be busy being updated by a running instance of Newsboat
+        unsafe { sqlite3_busy_timeout(db, 5000) };
+        Ok(connection)
+    }
+
+    /// Runs `sql`, with `?` placeholders replaced by `params`, and returns all the rows.
+    pub fn query(&self, sql: &str, params: &[Param]) -> Result<Vec<Row>, String> {
+        let statement = Statement::prepare(self, sql)?;
+        statement.bind(params)?;
+        statement.rows()
+    }
+
+    /// Runs one or more statements that don't return anything.
+    #[cfg(test)]
+    pub fn execute(&self, sql: &str) -> Result<(), String> {
+        let sql = CString::new(sql).map_err(|e| e.to_string())?;
+        let result = unsafe {
+            sqlite3_exec(
+                self.db,
+                sql.as_ptr(),
+                ptr::null(),
+                ptr::null_mut(),
+                ptr::null_mut(),
+            )
+        };
+        self.check(result)
+    }
+
+    fn check(&self, result: c_int) -> Result<(), String> {
+        if result == SQLITE_OK {
+            Ok(())
+        } else {
+            Err(self.error_message())
+        }
+    }
+
+    fn error_message(&self) -> String {
+        if self.db.is_null() {
+            // Only happens if SQLite fails to allocate memory for the handle
+            return String::from("out of memory");
+        }
+        unsafe { CStr::from_ptr(sqlite3_errmsg(self.db)) }
+            .to_string_lossy()
+            .into_owned()
+    }
+}
+
+impl Drop for Connection {
+    fn drop(&mut self) {
+        unsafe { sqlite3_close(self.db) };
+    }
+}
+
+struct Statement<'a> {
+    connection: &'a Connection,
+    stmt: *mut sqlite3_stmt,
+}
+
+impl<'a> Statement<'a> {
+    fn prepare(connection: &'a Connection, sql: &str) -> Result<Statement<'a>, String> {
+        let sql = CString::new(sql).map_err(|e| e.to_string())?;
+        let mut stmt = ptr::null_mut();
+        let result = unsafe {
+            sqlite3_prepare_v2(connection.db, sql.as_ptr(), -1, &mut stmt, ptr::null_mut())
+        };
+        connection.check(result)?;
+        Ok(Statement { connection, stmt })
+    }
+
+    fn bind(&self, params: &[Param]) -> Result<(), String> {
+        for (i, param) in params.iter().enumerate() {
+            // Placeholders are numbered from 1
+            let index = (i + 1) as c_int;
+            let result = match *param {
+                Param::Integer(value) => unsafe { sqlite3_bind_int64(self.stmt, index, value) },
+                Param::Text(value) => unsafe {
+                    sqlite3_bind_text(
+                        self.stmt,
+                        index,
+                        value.as_ptr() as *const c_char,
+                        value.len() as c_int,
+                        SQLITE_TRANSIENT,
+                    )
+                },
+            };
+            self.connection.check(result)?;
+        }
+        Ok(())
+    }
+
+    fn rows(&self) -> Result<Vec<Row>, String> {
+        let mut rows = Vec::new();
+        loop {
+            match unsafe { sqlite3_step(self.stmt) } {
+                SQLITE_ROW => rows.push(self.row()),
+                SQLITE_DONE => return Ok(rows),
+                _ => return Err(self.connection.error_message()),
+            }
+        }
+    }
+
+    fn row(&self) -> Row {
+        let count = unsafe { sqlite3_column_count(self.stmt) };
+        (0..count)
+            .map(|column| unsafe {
+                let text = sqlite3_column_text(self.stmt, column);
+                if text.is_null() {
+                    return String::new();
+                }
+                let length = sqlite3_column_bytes(self.stmt, column) as usize;
+                String::from_utf8_lossy(std::slice::from_raw_parts(text, length)).into_owned()
+            })
+            .collect()
+    }
+}
+
+impl<'a> Drop for Statement<'a> {
+    fn drop(&mut self) {
+        unsafe { sqlite3_finalize(self.stmt) };
+    }
+}
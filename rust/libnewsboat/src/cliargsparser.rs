@@ -1,9 +1,15 @@
-use clap::{App, Arg};
+use clap::{App, AppSettings, Arg, ArgMatches, ErrorKind, Shell, SubCommand};
 use gettextrs::gettext;
 use libc::{EXIT_FAILURE, EXIT_SUCCESS};
+use std::env;
 use std::path::PathBuf;
+use std::time::Duration;
 
-use crate::logger::Level;
+use crate::cachequery::{OutputFormat, Query};
+use crate::configpaths;
+use crate::keymap::KeymapDumpFormat;
+use crate::logger::{Level, LogFilter, LogFormat, LogRotation};
+use crate::matcher::Matcher;
 use crate::utils;
 use strprintf::fmt;
 
@@ -67,118 +73,416 @@ pub struct CliArgsParser {
     /// If this contains some value, it's the path to the log file specified by the user.
     pub log_file: Option<PathBuf>,
 
-    /// If this contains some value, it's the log level specified by the user.
+    /// If this contains some value, it's the log level specified by the user. Same as
+    /// `log_filter.default_level`.
     pub log_level: Option<Level>,
+
+    /// If this contains some value, it's the log filter specified by the user, either with
+    /// `--log-level` or in the `NEWSBOAT_LOG` environment variable.
+    pub log_filter: Option<LogFilter>,
+
+    /// If this contains some value, it's the format of the log file specified by the user.
+    pub log_format: Option<LogFormat>,
+
+    /// If this contains some value, the logs should be rotated to stay within these limits.
+    pub log_rotation: Option<LogRotation>,
+
+    /// If this contains some value, Newsboat should record how long the measured scopes took,
+    /// and write that to this file in Chrome's trace event format before quitting.
+    pub trace_file: Option<PathBuf>,
+
+    /// If this contains some value, Newsboat should load the config, print the effective key
+    /// bindings and macros in this format, and quit.
+    pub keymap_dump_format: Option<KeymapDumpFormat>,
+
+    /// If this contains some value, Newsboat should wait up to this long for the lock held by
+    /// another instance to be released, instead of quitting right away.
+    pub wait_for_lock: Option<Duration>,
+
+    /// If true, Newsboat should open the cache read-only, leaving it to the instance that holds
+    /// the lock.
+    pub read_only: bool,
+
+    /// If true and the lock is held by a process that is gone (or runs on another host, where
+    /// it can't be checked), Newsboat should take the lock over.
+    pub take_over_lock: bool,
+
+    /// If this contains some value, it's the name of the profile whose configs and data Newsboat
+    /// should use.
+    pub profile: Option<String>,
+
+    /// If true, Newsboat should print the names of existing profiles and quit.
+    pub list_profiles: bool,
+
+    /// If true, Newsboat should print the newest saved crash report and quit.
+    pub last_crash_report: bool,
+
+    /// If true, Newsboat should describe what migration from other feed readers would do, and
+    /// quit without touching any files.
+    pub migration_dry_run: bool,
+
+    /// If this contains some value, Newsboat should migrate subscriptions from this OPML file
+    /// instead of looking for Newsbeuter's files.
+    pub migrate_from_opml: Option<PathBuf>,
+
+    /// If this contains some value, it's a file with GUIDs of read articles (one per line) that
+    /// should be migrated along with `migrate_from_opml`.
+    pub migrate_read_guids: Option<PathBuf>,
+
+    /// If true, Newsboat should check the config file for mistakes, report them, and quit.
+    pub check_config: bool,
+
+    /// If this contains some value, Newsboat should print a script that sets up completions for
+    /// this shell, and quit.
+    pub completions_shell: Option<Shell>,
+
+    /// If this contains some value, Newsboat should print the answer to this query about the
+    /// cache, and quit.
+    pub query: Option<Query>,
+
+    /// Format in which the answer to `query` should be printed.
+    pub query_format: OutputFormat,
 }
 
 const LOCK_SUFFIX: &str = ".lock";
 
+const CACHE: &str = "cache";
+const CACHE_FILE: &str = "cache-file";
+const CHECK_CONFIG: &str = "check-config";
+const CLEANUP: &str = "cleanup";
+const COMPLETIONS: &str = "completions";
+const CONFIG_FILE: &str = "config-file";
+const DUMP_KEYMAP: &str = "dump-keymap";
+const EXECUTE: &str = "execute";
+const EXPORT: &str = "export";
+const EXPORT_TO_FILE: &str = "export-to-file";
+const EXPORT_TO_OPML: &str = "export-to-opml";
+const FEEDS: &str = "feeds";
+const FILE: &str = "file";
+const FILTER: &str = "filter";
+const FORMAT: &str = "format";
+const HELP: &str = "help";
+const ID: &str = "id";
+const IMPORT: &str = "import";
+const IMPORT_FROM_FILE: &str = "import-from-file";
+const IMPORT_FROM_OPML: &str = "import-from-opml";
+const ITEM: &str = "item";
+const ITEMS: &str = "items";
+const LAST_CRASH_REPORT: &str = "last-crash-report";
+const LIST_PROFILES: &str = "list-profiles";
+const LOG_FILE: &str = "log-file";
+const LOG_FORMAT: &str = "log-format";
+const LOG_LEVEL: &str = "log-level";
+const LOG_MAX_FILES: &str = "log-max-files";
+const LOG_MAX_SIZE: &str = "log-max-size";
+const MIGRATE_FROM_OPML: &str = "migrate-from-opml";
+const MIGRATE_READ_GUIDS: &str = "migrate-read-guids";
+const MIGRATION_DRY_RUN: &str = "migration-dry-run";
+const OPML: &str = "opml";
+const PROFILE: &str = "profile";
+const QUERY: &str = "query";
+const QUIET: &str = "quiet";
+const READ_ONLY: &str = "read-only";
+const TAKE_OVER_LOCK: &str = "take-over-lock";
+const READINFO: &str = "readinfo";
+const REFRESH_ON_START: &str = "refresh-on-start";
+const RELOAD: &str = "reload";
+const TAGS: &str = "tags";
+const TRACE_FILE: &str = "trace-file";
+const URL_FILE: &str = "url-file";
+const VACUUM: &str = "vacuum";
+const VERSION: &str = "version";
+const VERSION_V: &str = "-V";
+const WAIT_FOR_LOCK: &str = "wait-for-lock";
+
+/// Environment variable that holds the log filter if `--log-level` isn't provided.
+const LOG_FILTER_ENV_VAR: &str = "NEWSBOAT_LOG";
+
+/// Values accepted by `--log-level`. Filters, like `info,libnewsboat::fslock=debug`, are also
+/// accepted; see `LogFilter`.
+const LOG_LEVELS: &[&str] = &["1", "2", "3", "4", "5", "6"];
+
+/// Number of log files kept if `--log-max-size` is provided without `--log-max-files`.
+const DEFAULT_LOG_MAX_FILES: u32 = 5;
+
+/// Formats accepted by `--log-format`.
+const LOG_FORMATS: &[&str] = &["text", "json"];
+
+/// Formats accepted by `--dump-keymap`.
+const KEYMAP_DUMP_FORMATS: &[&str] = &["config", "json"];
+
+/// Commands accepted by `--execute`. The parser doesn't check them, the Controller does.
+const EXECUTE_COMMANDS: &[&str] = &["reload", "print-unread"];
+
+/// Formats accepted by `--format` of the `query` subcommands.
+const QUERY_FORMATS: &[&str] = &["text", "json"];
+
+/// Shells for which `--completions` can generate a script.
+const COMPLETION_SHELLS: &[&str] = &["bash", "zsh", "fish"];
+
+/// Placeholder "value" of options that take a path to a file.
+///
+/// Clap can't tell completion scripts that an option takes a file, so the placeholder is put into
+/// the definition instead, and `completions_script` replaces it with the shell's own way of
+/// completing filenames.
+const FILE_PLACEHOLDER: &str = "__newsboat_file__";
+const FILE_HINT: &[&str] = &[FILE_PLACEHOLDER];
+
+/// Placeholder "value" of options that take a value which can't be completed, e.g. a number.
+///
+/// Without it, Clap's zsh and fish scripts treat such options as flags.
+const VALUE_PLACEHOLDER: &str = "__newsboat_value__";
+const VALUE_HINT: &[&str] = &[VALUE_PLACEHOLDER];
+
+/// Builds the definition of Newsboat's command line.
+///
+/// With `value_hints`, options also list the values they accept, which is what completion scripts
+/// need. The parser doesn't use the hints because it checks the values itself, explaining what's
+/// wrong with them.
+fn build_app(value_hints: bool) -> App<'static, 'static> {
+    let hint = |arg: Arg<'static, 'static>, values: &'static [&'static str]| {
+        if value_hints {
+            arg.possible_values(values)
+        } else {
+            arg
+        }
+    };
+
+    // Settings are spelled out for each subcommand rather than set globally, because Clap only
+    // propagates global settings when parsing, not when generating completions.
+    let subcommand = |name| {
+        SubCommand::with_name(name)
+            .setting(AppSettings::DisableHelpSubcommand)
+            .setting(AppSettings::DisableVersion)
+    };
+
+    // Subcommands that take a file, e.g. `import opml FILE`
+    let with_file = |name| subcommand(name).arg(Arg::with_name(FILE).required(true).index(1));
+
+    // Subcommands of `query`, which can all print the answer in different formats
+    let query = |name| {
+        subcommand(name).arg(hint(
+            Arg::with_name(FORMAT).long(FORMAT).takes_value(true),
+            QUERY_FORMATS,
+        ))
+    };
+
+    App::new("Newsboat")
+        .setting(AppSettings::DisableHelpSubcommand)
+        .arg(hint(
+            Arg::with_name(IMPORT_FROM_OPML)
+                .short("i")
+                .long(IMPORT_FROM_OPML)
+                .takes_value(true),
+            FILE_HINT,
+        ))
+        .arg(
+            Arg::with_name(EXPORT_TO_OPML)
+                .short("e")
+                .long(EXPORT_TO_OPML),
+        )
+        .arg(
+            Arg::with_name(REFRESH_ON_START)
+                .short("r")
+                .long(REFRESH_ON_START),
+        )
+        .arg(Arg::with_name(HELP).short("h").long(HELP))
+        .arg(hint(
+            Arg::with_name(URL_FILE)
+                .short("u")
+                .long(URL_FILE)
+                .takes_value(true),
+            FILE_HINT,
+        ))
+        .arg(hint(
+            Arg::with_name(CACHE_FILE)
+                .short("c")
+                .long(CACHE_FILE)
+                .takes_value(true),
+            FILE_HINT,
+        ))
+        .arg(hint(
+            Arg::with_name(CONFIG_FILE)
+                .short("C")
+                .long(CONFIG_FILE)
+                .takes_value(true),
+            FILE_HINT,
+        ))
+        .arg(Arg::with_name(VACUUM).short("X").long(VACUUM))
+        .arg(Arg::with_name(CLEANUP).long(CLEANUP))
+        .arg(
+            Arg::with_name(VERSION)
+                .short("v")
+                .long(VERSION)
+                .multiple(true),
+        )
+        .arg(Arg::with_name(VERSION_V).short("V").multiple(true))
+        .arg(hint(
+            Arg::with_name(EXECUTE)
+                .short("x")
+                .long(EXECUTE)
+                .takes_value(true)
+                .multiple(true),
+            EXECUTE_COMMANDS,
+        ))
+        .arg(Arg::with_name(QUIET).short("q").long(QUIET))
+        .arg(hint(
+            Arg::with_name(IMPORT_FROM_FILE)
+                .short("I")
+                .long(IMPORT_FROM_FILE)
+                .takes_value(true),
+            FILE_HINT,
+        ))
+        .arg(hint(
+            Arg::with_name(EXPORT_TO_FILE)
+                .short("E")
+                .long(EXPORT_TO_FILE)
+                .takes_value(true),
+            FILE_HINT,
+        ))
+        .arg(hint(
+            Arg::with_name(LOG_FILE)
+                .short("d")
+                .long(LOG_FILE)
+                .takes_value(true),
+            FILE_HINT,
+        ))
+        .arg(hint(
+            Arg::with_name(LOG_LEVEL)
+                .short("l")
+                .long(LOG_LEVEL)
+                .takes_value(true),
+            LOG_LEVELS,
+        ))
+        .arg(hint(
+            Arg::with_name(LOG_FORMAT)
+                .long(LOG_FORMAT)
+                .takes_value(true),
+            LOG_FORMATS,
+        ))
+        .arg(hint(
+            Arg::with_name(LOG_MAX_SIZE)
+                .long(LOG_MAX_SIZE)
+                .takes_value(true),
+            VALUE_HINT,
+        ))
+        .arg(hint(
+            Arg::with_name(LOG_MAX_FILES)
+                .long(LOG_MAX_FILES)
+                .takes_value(true)
+                .requires(LOG_MAX_SIZE),
+            VALUE_HINT,
+        ))
+        .arg(hint(
+            Arg::with_name(TRACE_FILE)
+                .long(TRACE_FILE)
+                .takes_value(true),
+            FILE_HINT,
+        ))
+        .arg(hint(
+            Arg::with_name(DUMP_KEYMAP)
+                .long(DUMP_KEYMAP)
+                .takes_value(true),
+            KEYMAP_DUMP_FORMATS,
+        ))
+        .arg(hint(
+            Arg::with_name(WAIT_FOR_LOCK)
+                .long(WAIT_FOR_LOCK)
+                .takes_value(true),
+            VALUE_HINT,
+        ))
+        .arg(Arg::with_name(READ_ONLY).long(READ_ONLY))
+        .arg(
+            Arg::with_name(TAKE_OVER_LOCK)
+                .long(TAKE_OVER_LOCK)
+                .conflicts_with(READ_ONLY),
+        )
+        .arg(hint(
+            Arg::with_name(PROFILE).long(PROFILE).takes_value(true),
+            VALUE_HINT,
+        ))
+        .arg(Arg::with_name(LIST_PROFILES).long(LIST_PROFILES))
+        .arg(Arg::with_name(LAST_CRASH_REPORT).long(LAST_CRASH_REPORT))
+        .arg(Arg::with_name(MIGRATION_DRY_RUN).long(MIGRATION_DRY_RUN))
+        .arg(hint(
+            Arg::with_name(MIGRATE_FROM_OPML)
+                .long(MIGRATE_FROM_OPML)
+                .takes_value(true),
+            FILE_HINT,
+        ))
+        .arg(hint(
+            Arg::with_name(MIGRATE_READ_GUIDS)
+                .long(MIGRATE_READ_GUIDS)
+                .takes_value(true)
+                .requires(MIGRATE_FROM_OPML),
+            FILE_HINT,
+        ))
+        .arg(Arg::with_name(CHECK_CONFIG).long(CHECK_CONFIG))
+        .arg(hint(
+            Arg::with_name(COMPLETIONS)
+                .long(COMPLETIONS)
+                .takes_value(true),
+            COMPLETION_SHELLS,
+        ))
+        .subcommand(subcommand(RELOAD))
+        .subcommand(
+            subcommand(EXPORT)
+                .setting(AppSettings::SubcommandRequired)
+                .subcommand(subcommand(OPML)),
+        )
+        .subcommand(
+            subcommand(IMPORT)
+                .setting(AppSettings::SubcommandRequired)
+                .subcommand(with_file(OPML)),
+        )
+        .subcommand(
+            subcommand(READINFO)
+                .setting(AppSettings::SubcommandRequired)
+                .subcommand(with_file(EXPORT))
+                .subcommand(with_file(IMPORT)),
+        )
+        .subcommand(
+            subcommand(CACHE)
+                .setting(AppSettings::SubcommandRequired)
+                .subcommand(subcommand(VACUUM))
+                .subcommand(subcommand(CLEANUP)),
+        )
+        .subcommand(
+            subcommand(QUERY)
+                .setting(AppSettings::SubcommandRequired)
+                .subcommand(query(FEEDS))
+                .subcommand(query(TAGS))
+                .subcommand(query(ITEMS).arg(hint(
+                    Arg::with_name(FILTER).long(FILTER).takes_value(true),
+                    VALUE_HINT,
+                )))
+                .subcommand(
+                    query(ITEM).arg(hint(Arg::with_name(ID).required(true).index(1), VALUE_HINT)),
+                ),
+        )
+}
+
 impl CliArgsParser {
     pub fn new(opts: Vec<String>) -> CliArgsParser {
-        const CACHE_FILE: &str = "cache-file";
-        const CONFIG_FILE: &str = "config-file";
-        const EXECUTE: &str = "execute";
-        const EXPORT_TO_FILE: &str = "export-to-file";
-        const EXPORT_TO_OPML: &str = "export-to-opml";
-        const HELP: &str = "help";
-        const IMPORT_FROM_FILE: &str = "import-from-file";
-        const IMPORT_FROM_OPML: &str = "import-from-opml";
-        const LOG_FILE: &str = "log-file";
-        const LOG_LEVEL: &str = "log-level";
-        const QUIET: &str = "quiet";
-        const REFRESH_ON_START: &str = "refresh-on-start";
-        const URL_FILE: &str = "url-file";
-        const VACUUM: &str = "vacuum";
-        const CLEANUP: &str = "cleanup";
-        const VERSION: &str = "version";
-        const VERSION_V: &str = "-V";
-
-        let app = App::new("Newsboat")
-            .arg(
-                Arg::with_name(IMPORT_FROM_OPML)
-                    .short("i")
-                    .long(IMPORT_FROM_OPML)
-                    .takes_value(true),
-            )
-            .arg(
-                Arg::with_name(EXPORT_TO_OPML)
-                    .short("e")
-                    .long(EXPORT_TO_OPML),
-            )
-            .arg(
-                Arg::with_name(REFRESH_ON_START)
-                    .short("r")
-                    .long(REFRESH_ON_START),
-            )
-            .arg(Arg::with_name(HELP).short("h").long(HELP))
-            .arg(
-                Arg::with_name(URL_FILE)
-                    .short("u")
-                    .long(URL_FILE)
-                    .takes_value(true),
-            )
-            .arg(
-                Arg::with_name(CACHE_FILE)
-                    .short("c")
-                    .long(CACHE_FILE)
-                    .takes_value(true),
-            )
-            .arg(
-                Arg::with_name(CONFIG_FILE)
-                    .short("C")
-                    .long(CONFIG_FILE)
-                    .takes_value(true),
-            )
-            .arg(Arg::with_name(VACUUM).short("X").long(VACUUM))
-            .arg(Arg::with_name(CLEANUP).long(CLEANUP))
-            .arg(
-                Arg::with_name(VERSION)
-                    .short("v")
-                    .long(VERSION)
-                    .multiple(true),
-            )
-            .arg(Arg::with_name(VERSION_V).short("V").multiple(true))
-            .arg(
-                Arg::with_name(EXECUTE)
-                    .short("x")
-                    .long(EXECUTE)
-                    .takes_value(true)
-                    .multiple(true),
-            )
-            .arg(Arg::with_name(QUIET).short("q").long(QUIET))
-            .arg(
-                Arg::with_name(IMPORT_FROM_FILE)
-                    .short("I")
-                    .long(IMPORT_FROM_FILE)
-                    .takes_value(true),
-            )
-            .arg(
-                Arg::with_name(EXPORT_TO_FILE)
-                    .short("E")
-                    .long(EXPORT_TO_FILE)
-                    .takes_value(true),
-            )
-            .arg(
-                Arg::with_name(LOG_FILE)
-                    .short("d")
-                    .long(LOG_FILE)
-                    .takes_value(true),
-            )
-            .arg(
-                Arg::with_name(LOG_LEVEL)
-                    .short("l")
-                    .long(LOG_LEVEL)
-                    .takes_value(true),
-            );
+        CliArgsParser::with_log_filter_env(opts, env::var(LOG_FILTER_ENV_VAR).ok())
+    }
 
+    /// Like `new()`, but takes the value of `NEWSBOAT_LOG` environment variable as a parameter.
+    fn with_log_filter_env(opts: Vec<String>, log_filter_env: Option<String>) -> CliArgsParser {
         let mut args = CliArgsParser::default();
 
-        if let Some(program_name) = opts.get(0).cloned() {
+        if let Some(program_name) = opts.first().cloned() {
             args.program_name = program_name;
         }
 
-        let matches = match app.get_matches_from_safe(&opts) {
+        let matches = match build_app(false).get_matches_from_safe(&opts) {
             Ok(matches) => matches,
+            Err(ref e) if e.kind == ErrorKind::HelpDisplayed => {
+                // Asked for help on a subcommand, e.g. `newsboat reload --help`
+                args.should_print_usage = true;
+                args.return_code = Some(EXIT_SUCCESS);
+                return args;
+            }
             Err(_) => {
                 args.should_print_usage = true;
                 args.return_code = Some(EXIT_FAILURE);
@@ -186,6 +490,40 @@ impl CliArgsParser {
             }
         };
 
+        // Subcommands are processed first, so that the checks below catch the flags that conflict
+        // with them.
+        let file = |matches: &ArgMatches| {
+            matches
+                .value_of(FILE)
+                .map(|file| utils::resolve_tilde(PathBuf::from(file)))
+        };
+        match matches.subcommand() {
+            (RELOAD, Some(_)) => {
+                args.cmds_to_execute.push(RELOAD.to_string());
+                args.silent = true;
+            }
+            (EXPORT, Some(_)) => {
+                // `opml` is the only thing that can be exported
+                args.do_export = true;
+                args.silent = true;
+            }
+            (IMPORT, Some(import)) => {
+                args.importfile = import.subcommand_matches(OPML).and_then(file);
+            }
+            (READINFO, Some(readinfo)) => match readinfo.subcommand() {
+                (EXPORT, Some(export)) => args.readinfo_export_file = file(export),
+                (IMPORT, Some(import)) => args.readinfo_import_file = file(import),
+                _ => {}
+            },
+            (CACHE, Some(cache)) => match cache.subcommand_name() {
+                Some(VACUUM) => args.do_vacuum = true,
+                Some(CLEANUP) => args.do_cleanup = true,
+                _ => {}
+            },
+            (QUERY, Some(query)) => args.set_query(query),
+            _ => {}
+        }
+
         if matches.is_present(EXPORT_TO_OPML) {
             if args.importfile.is_some() {
                 args.should_print_usage = true;
@@ -203,9 +541,27 @@ impl CliArgsParser {
             args.return_code = Some(EXIT_SUCCESS);
         }
 
-        args.do_vacuum = matches.is_present(VACUUM);
+        args.do_vacuum = args.do_vacuum || matches.is_present(VACUUM);
+
+        args.do_cleanup = args.do_cleanup || matches.is_present(CLEANUP);
+
+        args.read_only = matches.is_present(READ_ONLY);
+        args.take_over_lock = matches.is_present(TAKE_OVER_LOCK);
+
+        args.list_profiles = matches.is_present(LIST_PROFILES);
+        args.last_crash_report = matches.is_present(LAST_CRASH_REPORT);
+
+        args.migration_dry_run = matches.is_present(MIGRATION_DRY_RUN);
+
+        if let Some(opml_file) = matches.value_of(MIGRATE_FROM_OPML) {
+            args.migrate_from_opml = Some(utils::resolve_tilde(PathBuf::from(opml_file)));
+        }
+
+        if let Some(read_guids_file) = matches.value_of(MIGRATE_READ_GUIDS) {
+            args.migrate_read_guids = Some(utils::resolve_tilde(PathBuf::from(read_guids_file)));
+        }
 
-        args.do_cleanup = matches.is_present(CLEANUP);
+        args.check_config = matches.is_present(CHECK_CONFIG);
 
         args.silent = args.silent || matches.is_present(QUIET);
 
@@ -269,43 +625,267 @@ impl CliArgsParser {
             args.log_file = Some(utils::resolve_tilde(PathBuf::from(log_file)));
         }
 
+        if let Some(trace_file) = matches.value_of(TRACE_FILE) {
+            args.trace_file = Some(utils::resolve_tilde(PathBuf::from(trace_file)));
+        }
+
         if let Some(log_level_str) = matches.value_of(LOG_LEVEL) {
-            match log_level_str.parse::<u8>() {
-                Ok(1) => {
-                    args.log_level = Some(Level::UserError);
+            match log_level_str.parse::<LogFilter>() {
+                Ok(filter) => {
+                    args.log_level = filter.default_level;
+                    args.log_filter = Some(filter);
+                }
+                Err(_) => {
+                    args.display_msg = fmt!(
+                        &gettext("%s: %s: invalid loglevel value"),
+                        &opts[0],
+                        log_level_str
+                    );
+                    args.return_code = Some(EXIT_FAILURE);
+                }
+            };
+        } else if let Some(log_filter_str) = log_filter_env {
+            match log_filter_str.parse::<LogFilter>() {
+                Ok(filter) => {
+                    args.log_level = filter.default_level;
+                    args.log_filter = Some(filter);
+                }
+                Err(_) => {
+                    args.display_msg = fmt!(
+                        &gettext("%s: %s: invalid loglevel value in %s"),
+                        &opts[0],
+                        &log_filter_str,
+                        LOG_FILTER_ENV_VAR
+                    );
+                    args.return_code = Some(EXIT_FAILURE);
+                }
+            };
+        }
+
+        if let Some(format) = matches.value_of(LOG_FORMAT) {
+            match format.parse::<LogFormat>() {
+                Ok(format) => {
+                    args.log_format = Some(format);
                 }
-                Ok(2) => {
-                    args.log_level = Some(Level::Critical);
+                Err(_) => {
+                    args.display_msg = fmt!(
+                        &gettext("%s: %s: invalid log format (valid values: text, json)"),
+                        &opts[0],
+                        format
+                    );
+                    args.return_code = Some(EXIT_FAILURE);
                 }
-                Ok(3) => {
-                    args.log_level = Some(Level::Error);
+            }
+        }
+
+        if let Some(size) = matches.value_of(LOG_MAX_SIZE) {
+            // The size is in mebibytes.
+            let max_size = match size.parse::<u64>() {
+                Ok(size) if size > 0 && size <= u64::MAX >> 20 => Some(size << 20),
+                _ => {
+                    args.display_msg =
+                        fmt!(&gettext("%s: %s: invalid log size limit"), &opts[0], size);
+                    args.return_code = Some(EXIT_FAILURE);
+                    None
                 }
-                Ok(4) => {
-                    args.log_level = Some(Level::Warn);
+            };
+            let max_files = match matches.value_of(LOG_MAX_FILES) {
+                Some(count) => match count.parse::<u32>() {
+                    Ok(count) if count > 0 => Some(count),
+                    _ => {
+                        args.display_msg = fmt!(
+                            &gettext("%s: %s: invalid number of log files to keep"),
+                            &opts[0],
+                            count
+                        );
+                        args.return_code = Some(EXIT_FAILURE);
+                        None
+                    }
+                },
+                None => Some(DEFAULT_LOG_MAX_FILES),
+            };
+            if let (Some(max_size), Some(max_files)) = (max_size, max_files) {
+                args.log_rotation = Some(LogRotation {
+                    max_size,
+                    max_files,
+                });
+            }
+        }
+
+        if let Some(format) = matches.value_of(DUMP_KEYMAP) {
+            match format.parse::<KeymapDumpFormat>() {
+                Ok(format) => {
+                    args.keymap_dump_format = Some(format);
+                    args.silent = true;
                 }
-                Ok(5) => {
-                    args.log_level = Some(Level::Info);
+                Err(_) => {
+                    args.display_msg = fmt!(
+                        &gettext("%s: %s: invalid keymap dump format (valid values: config, json)"),
+                        &opts[0],
+                        format
+                    );
+                    args.return_code = Some(EXIT_FAILURE);
                 }
-                Ok(6) => {
-                    args.log_level = Some(Level::Debug);
+            }
+        }
+
+        if let Some(profile) = matches.value_of(PROFILE) {
+            if configpaths::is_valid_profile_name(profile) {
+                args.profile = Some(profile.to_string());
+            } else {
+                args.display_msg = fmt!(
+                    &gettext(
+                        "%s: %s: invalid profile name (use letters, digits, `-', `_' and `.')"
+                    ),
+                    &opts[0],
+                    profile
+                );
+                args.return_code = Some(EXIT_FAILURE);
+            }
+        }
+
+        if let Some(seconds) = matches.value_of(WAIT_FOR_LOCK) {
+            match seconds.parse::<u64>() {
+                Ok(seconds) => args.wait_for_lock = Some(Duration::from_secs(seconds)),
+                Err(_) => {
+                    args.display_msg = fmt!(
+                        &gettext("%s: %s: invalid number of seconds to wait for the lock"),
+                        &opts[0],
+                        seconds
+                    );
+                    args.return_code = Some(EXIT_FAILURE);
                 }
+            }
+        }
+
+        if let Some(shell) = matches.value_of(COMPLETIONS) {
+            match shell {
+                "bash" => args.completions_shell = Some(Shell::Bash),
+                "zsh" => args.completions_shell = Some(Shell::Zsh),
+                "fish" => args.completions_shell = Some(Shell::Fish),
                 _ => {
                     args.display_msg = fmt!(
-                        &gettext("%s: %s: invalid loglevel value"),
+                        &gettext("%s: %s: unsupported shell (valid values: bash, zsh, fish)"),
                         &opts[0],
-                        log_level_str
+                        shell
                     );
                     args.return_code = Some(EXIT_FAILURE);
                 }
-            };
+            }
         }
 
         args
     }
 
+    /// Fills in `query` and `query_format` from the matches of the `query` subcommand.
+    fn set_query(&mut self, matches: &ArgMatches) {
+        self.silent = true;
+
+        let (name, matches) = match matches.subcommand() {
+            (name, Some(matches)) => (name, matches),
+            _ => return,
+        };
+
+        if let Some(format) = matches.value_of(FORMAT) {
+            match format.parse::<OutputFormat>() {
+                Ok(format) => self.query_format = format,
+                Err(_) => {
+                    self.display_msg = fmt!(
+                        &gettext("%s: %s: invalid output format (valid values: text, json)"),
+                        &self.program_name,
+                        format
+                    );
+                    self.return_code = Some(EXIT_FAILURE);
+                    return;
+                }
+            }
+        }
+
+        self.query = match name {
+            FEEDS => Some(Query::FeedCounts),
+            TAGS => Some(Query::TagCounts),
+            ITEMS => {
+                let filter = matches.value_of(FILTER).map(String::from);
+                if let Some(ref filter) = filter {
+                    if let Err(error) = Matcher::parse(filter) {
+                        self.display_msg = fmt!("%s: %s", &self.program_name, error);
+                        self.return_code = Some(EXIT_FAILURE);
+                        return;
+                    }
+                }
+                Some(Query::UnreadItems { filter })
+            }
+            ITEM => {
+                let id = matches.value_of(ID).unwrap_or_default();
+                match id.parse::<i64>() {
+                    Ok(id) => Some(Query::Item { id }),
+                    Err(_) => {
+                        self.display_msg = fmt!(
+                            &gettext("%s: %s: invalid article ID"),
+                            &self.program_name,
+                            id
+                        );
+                        self.return_code = Some(EXIT_FAILURE);
+                        None
+                    }
+                }
+            }
+            _ => None,
+        };
+    }
+
     pub fn using_nonstandard_configs(&self) -> bool {
-        self.url_file.is_some() || self.cache_file.is_some() || self.config_file.is_some()
+        self.url_file.is_some()
+            || self.cache_file.is_some()
+            || self.config_file.is_some()
+            || self.profile.is_some()
+    }
+}
+
+/// Generates a script that sets up completions of Newsboat's command line in `shell`.
+///
+/// Clap 2 can't tell completion scripts what kind of value an option takes, so this generates
+/// the script with placeholders among the possible values (see `FILE_PLACEHOLDER` and
+/// `VALUE_PLACEHOLDER`), and then replaces them with the shell's own way of completing such
+/// values. If any placeholder remains, e.g. because Clap changed the way it lists the values, the
+/// script is generated without the hints instead.
+pub fn completions_script(shell: Shell) -> String {
+    // How each shell's script lists the possible values of an option, and what the placeholders
+    // should be replaced with.
+    let (list_of, file, value, positionals) = match shell {
+        Shell::Bash => (
+            r#"compgen -W "%s""#,
+            "compgen -f",
+            r#"compgen -W """#,
+            // Clap offers names of positional arguments, like `<file>`, as completions
+            &[
+                (" <file> ", r#" $(compgen -f -- "${cur}") "#),
+                (" <id> ", " "),
+            ][..],
+        ),
+        Shell::Zsh => (":(%s)", ":_files", ": ", &[][..]),
+        Shell::Fish => (r#"-f -a "%s""#, "-F", "-f", &[][..]),
+        // `--completions` doesn't offer other shells, so we don't know how to translate the hints
+        _ => return generate_completions(shell, false),
+    };
+
+    let mut script = generate_completions(shell, true)
+        .replace(&list_of.replace("%s", FILE_PLACEHOLDER), file)
+        .replace(&list_of.replace("%s", VALUE_PLACEHOLDER), value);
+    for (from, to) in positionals {
+        script = script.replace(from, to);
+    }
+
+    if script.contains(FILE_PLACEHOLDER) || script.contains(VALUE_PLACEHOLDER) {
+        return generate_completions(shell, false);
     }
+    script
+}
+
+fn generate_completions(shell: Shell, value_hints: bool) -> String {
+    let mut script = Vec::new();
+    build_app(value_hints).gen_completions_to("newsboat", shell, &mut script);
+    String::from_utf8_lossy(&script).into_owned()
 }
 
 #[cfg(test)]
@@ -735,6 +1315,21 @@ mod tests {
         ]);
     }
 
+    #[test]
+    fn t_sets_trace_file_if_dash_dash_trace_file_is_provided() {
+        assert_eq!(
+            CliArgsParser::new(vec!["newsboat".to_string()]).trace_file,
+            None
+        );
+
+        let filename = "trace file.json";
+        let args = CliArgsParser::new(vec![
+            "newsboat".to_string(),
+            "--trace-file=".to_string() + filename,
+        ]);
+        assert_eq!(args.trace_file, Some(PathBuf::from(filename)));
+    }
+
     #[test]
     fn t_sets_set_log_level_and_log_level_if_argument_to_dash_l_is_1_to_6() {
         let check = |opts, expected_level| {
@@ -793,6 +1388,679 @@ mod tests {
             "newsboat".to_string(),
             "--log-level=90001".to_string(),
         ]);
+        check(vec![
+            "newsboat".to_string(),
+            "--log-level=info,libnewsboat::fslock=loud".to_string(),
+        ]);
+    }
+
+    #[test]
+    fn t_sets_log_filter_and_log_level_if_argument_to_dash_l_is_a_filter() {
+        let args = CliArgsParser::new(vec![
+            "newsboat".to_string(),
+            "--log-level=info,libnewsboat::fslock=debug".to_string(),
+        ]);
+
+        assert_eq!(args.log_level, Some(Level::Info));
+        let filter = args.log_filter.unwrap();
+        assert_eq!(filter.level_for("libnewsboat::fslock"), Some(Level::Debug));
+        assert_eq!(filter.level_for("libnewsboat::utils"), Some(Level::Info));
+
+        let args = CliArgsParser::new(vec![
+            "newsboat".to_string(),
+            "-l".to_string(),
+            "libnewsboat::reloader=6".to_string(),
+        ]);
+
+        assert_eq!(args.log_level, None);
+        let filter = args.log_filter.unwrap();
+        assert_eq!(
+            filter.level_for("libnewsboat::reloader"),
+            Some(Level::Debug)
+        );
+        assert_eq!(filter.level_for("libnewsboat::utils"), None);
+    }
+
+    #[test]
+    fn t_takes_log_filter_from_environment_if_dash_l_is_not_provided() {
+        let args = CliArgsParser::with_log_filter_env(
+            vec!["newsboat".to_string()],
+            Some("warn,libnewsboat::fslock=debug".to_string()),
+        );
+
+        assert_eq!(args.log_level, Some(Level::Warn));
+        let filter = args.log_filter.unwrap();
+        assert_eq!(filter.level_for("libnewsboat::fslock"), Some(Level::Debug));
+        assert_eq!(args.return_code, None);
+    }
+
+    #[test]
+    fn t_dash_l_takes_precedence_over_log_filter_from_environment() {
+        let args = CliArgsParser::with_log_filter_env(
+            vec!["newsboat".to_string(), "-l3".to_string()],
+            Some("debug".to_string()),
+        );
+
+        assert_eq!(args.log_level, Some(Level::Error));
+        assert_eq!(
+            args.log_filter.unwrap().level_for("libnewsboat"),
+            Some(Level::Error)
+        );
+
+        // Invalid environment variable is ignored, too
+        let args = CliArgsParser::with_log_filter_env(
+            vec!["newsboat".to_string(), "-l3".to_string()],
+            Some("loud".to_string()),
+        );
+
+        assert_eq!(args.log_level, Some(Level::Error));
+        assert_eq!(args.return_code, None);
+    }
+
+    #[test]
+    fn t_sets_display_msg_and_asks_to_exit_with_failure_if_log_filter_from_environment_is_invalid()
+    {
+        let args = CliArgsParser::with_log_filter_env(
+            vec!["newsboat".to_string()],
+            Some("libnewsboat=loud".to_string()),
+        );
+
+        assert_eq!(args.log_level, None);
+        assert_eq!(args.log_filter, None);
+        assert!(args.display_msg.contains("NEWSBOAT_LOG"));
+        assert_eq!(args.return_code, Some(EXIT_FAILURE));
+    }
+
+    #[test]
+    fn t_sets_log_format_if_dash_dash_log_format_is_provided() {
+        let check = |opts, expected_format| {
+            let args = CliArgsParser::new(opts);
+
+            assert_eq!(args.log_format, Some(expected_format));
+            assert_eq!(args.return_code, None);
+        };
+
+        check(
+            vec!["newsboat".to_string(), "--log-format=text".to_string()],
+            LogFormat::Text,
+        );
+        check(
+            vec![
+                "newsboat".to_string(),
+                "--log-format".to_string(),
+                "json".to_string(),
+            ],
+            LogFormat::Json,
+        );
+
+        let args = CliArgsParser::new(vec!["newsboat".to_string()]);
+        assert_eq!(args.log_format, None);
+    }
+
+    #[test]
+    fn t_sets_display_msg_and_asks_to_exit_with_failure_if_argument_to_dash_dash_log_format_is_unknown(
+    ) {
+        let args = CliArgsParser::new(vec!["newsboat".to_string(), "--log-format=xml".to_string()]);
+
+        assert_eq!(args.log_format, None);
+        assert!(!args.display_msg.is_empty());
+        assert_eq!(args.return_code, Some(EXIT_FAILURE));
+    }
+
+    #[test]
+    fn t_sets_log_rotation_if_dash_dash_log_max_size_is_provided() {
+        let args = CliArgsParser::new(vec!["newsboat".to_string()]);
+        assert_eq!(args.log_rotation, None);
+
+        let args = CliArgsParser::new(vec![
+            "newsboat".to_string(),
+            "--log-max-size=10".to_string(),
+        ]);
+        assert_eq!(
+            args.log_rotation,
+            Some(LogRotation {
+                max_size: 10 * 1024 * 1024,
+                max_files: DEFAULT_LOG_MAX_FILES,
+            })
+        );
+        assert_eq!(args.return_code, None);
+
+        let args = CliArgsParser::new(vec![
+            "newsboat".to_string(),
+            "--log-max-size".to_string(),
+            "1".to_string(),
+            "--log-max-files=2".to_string(),
+        ]);
+        assert_eq!(
+            args.log_rotation,
+            Some(LogRotation {
+                max_size: 1024 * 1024,
+                max_files: 2,
+            })
+        );
+        assert_eq!(args.return_code, None);
+    }
+
+    #[test]
+    fn t_sets_display_msg_and_asks_to_exit_with_failure_if_log_rotation_limits_are_invalid() {
+        let check = |opts| {
+            let args = CliArgsParser::new(opts);
+
+            assert_eq!(args.log_rotation, None);
+            assert!(!args.display_msg.is_empty());
+            assert_eq!(args.return_code, Some(EXIT_FAILURE));
+        };
+
+        check(vec!["newsboat".to_string(), "--log-max-size=0".to_string()]);
+        check(vec![
+            "newsboat".to_string(),
+            "--log-max-size=1.5".to_string(),
+        ]);
+        check(vec![
+            "newsboat".to_string(),
+            "--log-max-size=99999999999999999".to_string(),
+        ]);
+        check(vec![
+            "newsboat".to_string(),
+            "--log-max-size=1".to_string(),
+            "--log-max-files=0".to_string(),
+        ]);
+        check(vec![
+            "newsboat".to_string(),
+            "--log-max-size=1".to_string(),
+            "--log-max-files=many".to_string(),
+        ]);
+    }
+
+    #[test]
+    fn t_asks_to_print_usage_and_exit_with_failure_if_log_max_files_is_provided_without_log_max_size(
+    ) {
+        let args = CliArgsParser::new(vec![
+            "newsboat".to_string(),
+            "--log-max-files=3".to_string(),
+        ]);
+
+        assert!(args.should_print_usage);
+        assert_eq!(args.return_code, Some(EXIT_FAILURE));
+    }
+
+    #[test]
+    fn t_sets_keymap_dump_format_and_silent_if_dash_dash_dump_keymap_is_provided() {
+        let check = |opts, expected_format| {
+            let args = CliArgsParser::new(opts);
+
+            assert_eq!(args.keymap_dump_format, Some(expected_format));
+            assert!(args.silent);
+            assert_eq!(args.return_code, None);
+        };
+
+        check(
+            vec!["newsboat".to_string(), "--dump-keymap=config".to_string()],
+            KeymapDumpFormat::Config,
+        );
+        check(
+            vec![
+                "newsboat".to_string(),
+                "--dump-keymap".to_string(),
+                "json".to_string(),
+            ],
+            KeymapDumpFormat::Json,
+        );
+    }
+
+    #[test]
+    fn t_sets_display_msg_and_asks_to_exit_with_failure_if_argument_to_dash_dash_dump_keymap_is_unknown(
+    ) {
+        let check = |opts| {
+            let args = CliArgsParser::new(opts);
+
+            assert!(!args.display_msg.is_empty());
+            assert_eq!(args.return_code, Some(EXIT_FAILURE));
+            assert_eq!(args.keymap_dump_format, None);
+        };
+
+        check(vec![
+            "newsboat".to_string(),
+            "--dump-keymap=yaml".to_string(),
+        ]);
+        check(vec!["newsboat".to_string(), "--dump-keymap=".to_string()]);
+    }
+
+    #[test]
+    fn t_sets_wait_for_lock_if_dash_dash_wait_for_lock_is_provided() {
+        let check = |opts, expected| {
+            let args = CliArgsParser::new(opts);
+
+            assert_eq!(args.wait_for_lock, Some(expected));
+            assert_eq!(args.return_code, None);
+        };
+
+        check(
+            vec!["newsboat".to_string(), "--wait-for-lock=30".to_string()],
+            Duration::from_secs(30),
+        );
+        check(
+            vec![
+                "newsboat".to_string(),
+                "--wait-for-lock".to_string(),
+                "0".to_string(),
+            ],
+            Duration::from_secs(0),
+        );
+    }
+
+    #[test]
+    fn t_sets_display_msg_and_asks_to_exit_with_failure_if_argument_to_dash_dash_wait_for_lock_is_not_a_number(
+    ) {
+        let check = |opts| {
+            let args = CliArgsParser::new(opts);
+
+            assert!(!args.display_msg.is_empty());
+            assert_eq!(args.return_code, Some(EXIT_FAILURE));
+            assert_eq!(args.wait_for_lock, None);
+        };
+
+        check(vec![
+            "newsboat".to_string(),
+            "--wait-for-lock=soon".to_string(),
+        ]);
+        check(vec![
+            "newsboat".to_string(),
+            "--wait-for-lock=-5".to_string(),
+        ]);
+        check(vec!["newsboat".to_string(), "--wait-for-lock=".to_string()]);
+    }
+
+    #[test]
+    fn t_sets_read_only_if_dash_dash_read_only_is_provided() {
+        assert!(!CliArgsParser::new(vec!["newsboat".to_string()]).read_only);
+
+        let args = CliArgsParser::new(vec!["newsboat".to_string(), "--read-only".to_string()]);
+        assert!(args.read_only);
+        assert_eq!(args.return_code, None);
+    }
+
+    #[test]
+    fn t_sets_take_over_lock_if_dash_dash_take_over_lock_is_provided() {
+        assert!(!CliArgsParser::new(vec!["newsboat".to_string()]).take_over_lock);
+
+        let args = CliArgsParser::new(vec!["newsboat".to_string(), "--take-over-lock".to_string()]);
+        assert!(args.take_over_lock);
+        assert_eq!(args.return_code, None);
+
+        let args = CliArgsParser::new(vec![
+            "newsboat".to_string(),
+            "--take-over-lock".to_string(),
+            "--read-only".to_string(),
+        ]);
+        assert!(args.should_print_usage);
+        assert_eq!(args.return_code, Some(EXIT_FAILURE));
+    }
+
+    #[test]
+    fn t_sets_profile_and_using_nonstandard_configs_if_dash_dash_profile_is_provided() {
+        let check = |opts| {
+            let args = CliArgsParser::new(opts);
+
+            assert_eq!(args.profile, Some("work".to_string()));
+            assert!(args.using_nonstandard_configs());
+            assert_eq!(args.return_code, None);
+        };
+
+        check(vec!["newsboat".to_string(), "--profile=work".to_string()]);
+        check(vec![
+            "newsboat".to_string(),
+            "--profile".to_string(),
+            "work".to_string(),
+        ]);
+    }
+
+    #[test]
+    fn t_sets_display_msg_and_asks_to_exit_with_failure_if_profile_name_is_invalid() {
+        let check = |opts| {
+            let args = CliArgsParser::new(opts);
+
+            assert!(!args.display_msg.is_empty());
+            assert_eq!(args.return_code, Some(EXIT_FAILURE));
+            assert_eq!(args.profile, None);
+        };
+
+        check(vec!["newsboat".to_string(), "--profile=".to_string()]);
+        check(vec!["newsboat".to_string(), "--profile=..".to_string()]);
+        check(vec![
+            "newsboat".to_string(),
+            "--profile=.hidden".to_string(),
+        ]);
+        check(vec!["newsboat".to_string(), "--profile=a/b".to_string()]);
+        check(vec![
+            "newsboat".to_string(),
+            "--profile=two words".to_string(),
+        ]);
+    }
+
+    #[test]
+    fn t_sets_list_profiles_if_dash_dash_list_profiles_is_provided() {
+        assert!(!CliArgsParser::new(vec!["newsboat".to_string()]).list_profiles);
+
+        let args = CliArgsParser::new(vec!["newsboat".to_string(), "--list-profiles".to_string()]);
+        assert!(args.list_profiles);
+    }
+
+    #[test]
+    fn t_sets_last_crash_report_if_dash_dash_last_crash_report_is_provided() {
+        assert!(!CliArgsParser::new(vec!["newsboat".to_string()]).last_crash_report);
+
+        let args = CliArgsParser::new(vec![
+            "newsboat".to_string(),
+            "--last-crash-report".to_string(),
+        ]);
+        assert!(args.last_crash_report);
+    }
+
+    #[test]
+    fn t_sets_check_config_if_dash_dash_check_config_is_provided() {
+        assert!(!CliArgsParser::new(vec!["newsboat".to_string()]).check_config);
+
+        let args = CliArgsParser::new(vec!["newsboat".to_string(), "--check-config".to_string()]);
+        assert!(args.check_config);
+    }
+
+    #[test]
+    fn t_sets_migration_dry_run_if_dash_dash_migration_dry_run_is_provided() {
+        assert!(!CliArgsParser::new(vec!["newsboat".to_string()]).migration_dry_run);
+
+        let args = CliArgsParser::new(vec![
+            "newsboat".to_string(),
+            "--migration-dry-run".to_string(),
+        ]);
+        assert!(args.migration_dry_run);
+    }
+
+    #[test]
+    fn t_sets_migrate_from_opml_and_migrate_read_guids_if_provided() {
+        let args = CliArgsParser::new(vec!["newsboat".to_string()]);
+        assert_eq!(args.migrate_from_opml, None);
+        assert_eq!(args.migrate_read_guids, None);
+
+        let args = CliArgsParser::new(vec![
+            "newsboat".to_string(),
+            "--migrate-from-opml=feeds.opml".to_string(),
+            "--migrate-read-guids=read.txt".to_string(),
+        ]);
+        assert_eq!(args.migrate_from_opml, Some(PathBuf::from("feeds.opml")));
+        assert_eq!(args.migrate_read_guids, Some(PathBuf::from("read.txt")));
+    }
+
+    #[test]
+    fn t_requires_dash_dash_migrate_from_opml_for_dash_dash_migrate_read_guids() {
+        let args = CliArgsParser::new(vec![
+            "newsboat".to_string(),
+            "--migrate-read-guids=read.txt".to_string(),
+        ]);
+        assert!(args.should_print_usage);
+        assert_eq!(args.return_code, Some(EXIT_FAILURE));
+    }
+
+    #[test]
+    fn t_sets_completions_shell_if_dash_dash_completions_is_provided() {
+        let check = |shell: &str| {
+            let args = CliArgsParser::new(vec![
+                "newsboat".to_string(),
+                "--completions".to_string(),
+                shell.to_string(),
+            ]);
+            assert_eq!(args.return_code, None);
+            args.completions_shell
+        };
+
+        // clap::Shell doesn't implement PartialEq
+        assert!(matches!(check("bash"), Some(Shell::Bash)));
+        assert!(matches!(check("zsh"), Some(Shell::Zsh)));
+        assert!(matches!(check("fish"), Some(Shell::Fish)));
+    }
+
+    #[test]
+    fn t_sets_display_msg_and_asks_to_exit_with_failure_if_argument_to_dash_dash_completions_is_unsupported(
+    ) {
+        let check = |shell: &str| {
+            let args = CliArgsParser::new(vec![
+                "newsboat".to_string(),
+                "--completions".to_string(),
+                shell.to_string(),
+            ]);
+            assert!(args.completions_shell.is_none());
+            assert!(!args.display_msg.is_empty());
+            assert_eq!(args.return_code, Some(EXIT_FAILURE));
+        };
+
+        check("powershell");
+        check("tcsh");
+        check("");
+    }
+
+    #[test]
+    fn t_completions_script_offers_files_log_levels_and_commands() {
+        let bash = completions_script(Shell::Bash);
+        assert!(bash.contains("complete -F _newsboat"));
+        assert!(bash.contains(
+            r#"--url-file)
+                    COMPREPLY=($(compgen -f -- "${cur}"))"#
+        ));
+        assert!(bash.contains(
+            r#"--log-level)
+                    COMPREPLY=($(compgen -W "1 2 3 4 5 6" -- "${cur}"))"#
+        ));
+        assert!(bash.contains(r#"compgen -W "reload print-unread""#));
+        assert!(!bash.contains("<file>"));
+        assert!(!bash.contains("<id>"));
+
+        let zsh = completions_script(Shell::Zsh);
+        assert!(zsh.starts_with("#compdef newsboat"));
+        assert!(zsh.contains("'--cache-file=[]: :_files'"));
+        assert!(zsh.contains("'--import-from-opml=[]: :_files'"));
+        assert!(zsh.contains("'-l+[]: :(1 2 3 4 5 6)'"));
+        assert!(zsh.contains("'*--execute=[]: :(reload print-unread)'"));
+        assert!(zsh.contains("'--profile=[]: : '"));
+        assert!(zsh.contains("'--format=[]: :(text json)'"));
+        assert!(zsh.contains("':id: '"));
+
+        let fish = completions_script(Shell::Fish);
+        assert!(fish.contains("-s u -l url-file -r -F\n"));
+        assert!(fish.contains(r#"-s l -l log-level -r -f -a "1 2 3 4 5 6""#));
+        assert!(fish.contains(r#"-s x -l execute -r -f -a "reload print-unread""#));
+        assert!(fish.contains("-l wait-for-lock -r -f\n"));
+    }
+
+    #[test]
+    fn t_completions_script_never_contains_placeholders() {
+        for name in &Shell::variants() {
+            let shell = name.parse::<Shell>().unwrap();
+            let script = completions_script(shell);
+            assert!(!script.is_empty(), "empty script for {}", name);
+            assert!(
+                !script.contains(FILE_PLACEHOLDER),
+                "{} script offers {}",
+                name,
+                FILE_PLACEHOLDER
+            );
+            assert!(
+                !script.contains(VALUE_PLACEHOLDER),
+                "{} script offers {}",
+                name,
+                VALUE_PLACEHOLDER
+            );
+        }
+    }
+
+    fn parse(opts: &[&str]) -> CliArgsParser {
+        CliArgsParser::new(opts.iter().map(|s| s.to_string()).collect())
+    }
+
+    #[test]
+    fn t_reload_subcommand_is_equivalent_to_dash_x_reload() {
+        let args = parse(&["newsboat", "reload"]);
+        assert_eq!(args.cmds_to_execute, vec!["reload".to_string()]);
+        assert!(args.silent);
+        assert_eq!(args.return_code, None);
+
+        let args = parse(&["newsboat", "-x", "print-unread", "reload"]);
+        assert_eq!(
+            args.cmds_to_execute,
+            vec!["print-unread".to_string(), "reload".to_string()]
+        );
+    }
+
+    #[test]
+    fn t_export_opml_subcommand_is_equivalent_to_dash_e() {
+        let args = parse(&["newsboat", "export", "opml"]);
+        assert!(args.do_export);
+        assert!(args.silent);
+        assert_eq!(args.return_code, None);
+    }
+
+    #[test]
+    fn t_import_opml_subcommand_is_equivalent_to_dash_i() {
+        let args = parse(&["newsboat", "import", "opml", "feeds.opml"]);
+        assert_eq!(args.importfile, Some(PathBuf::from("feeds.opml")));
+        assert_eq!(args.return_code, None);
+    }
+
+    #[test]
+    fn t_readinfo_subcommands_are_equivalent_to_dash_capital_e_and_dash_capital_i() {
+        let args = parse(&["newsboat", "readinfo", "export", "read.txt"]);
+        assert_eq!(args.readinfo_export_file, Some(PathBuf::from("read.txt")));
+        assert_eq!(args.return_code, None);
+
+        let args = parse(&["newsboat", "readinfo", "import", "read.txt"]);
+        assert_eq!(args.readinfo_import_file, Some(PathBuf::from("read.txt")));
+        assert_eq!(args.return_code, None);
+    }
+
+    #[test]
+    fn t_cache_subcommands_are_equivalent_to_dash_capital_x_and_dash_dash_cleanup() {
+        let args = parse(&["newsboat", "cache", "vacuum"]);
+        assert!(args.do_vacuum);
+        assert!(!args.do_cleanup);
+
+        let args = parse(&["newsboat", "cache", "cleanup"]);
+        assert!(args.do_cleanup);
+        assert!(!args.do_vacuum);
+    }
+
+    #[test]
+    fn t_subcommands_can_be_combined_with_flags() {
+        let args = parse(&[
+            "newsboat", "-u", "my-urls", "-c", "cache.db", "cache", "vacuum",
+        ]);
+        assert!(args.do_vacuum);
+        assert_eq!(args.url_file, Some(PathBuf::from("my-urls")));
+        assert_eq!(args.cache_file, Some(PathBuf::from("cache.db")));
+        assert_eq!(args.lock_file, Some(PathBuf::from("cache.db.lock")));
+    }
+
+    #[test]
+    fn t_asks_to_print_usage_and_exit_with_failure_if_subcommand_conflicts_with_a_flag() {
+        let check = |opts: &[&str]| {
+            let args = parse(opts);
+            assert!(args.should_print_usage);
+            assert_eq!(args.return_code, Some(EXIT_FAILURE));
+        };
+
+        check(&["newsboat", "-e", "import", "opml", "feeds.opml"]);
+        check(&["newsboat", "-i", "feeds.opml", "export", "opml"]);
+        check(&[
+            "newsboat", "-I", "read.txt", "readinfo", "export", "out.txt",
+        ]);
+        check(&[
+            "newsboat", "-E", "out.txt", "readinfo", "import", "read.txt",
+        ]);
+    }
+
+    #[test]
+    fn t_asks_to_print_usage_and_exit_with_failure_if_subcommand_is_incomplete_or_unknown() {
+        let check = |opts: &[&str]| {
+            let args = parse(opts);
+            assert!(args.should_print_usage);
+            assert_eq!(args.return_code, Some(EXIT_FAILURE));
+        };
+
+        check(&["newsboat", "export"]);
+        check(&["newsboat", "export", "json"]);
+        check(&["newsboat", "import", "opml"]);
+        check(&["newsboat", "readinfo"]);
+        check(&["newsboat", "cache"]);
+        check(&["newsboat", "cache", "compact"]);
+        check(&["newsboat", "refresh"]);
+        check(&["newsboat", "help"]);
+    }
+
+    #[test]
+    fn t_query_subcommands_set_query_and_silent() {
+        let check = |opts: &[&str], expected| {
+            let args = parse(opts);
+            assert_eq!(args.query, Some(expected));
+            assert_eq!(args.query_format, OutputFormat::Text);
+            assert!(args.silent);
+            assert_eq!(args.return_code, None);
+        };
+
+        check(&["newsboat", "query", "feeds"], Query::FeedCounts);
+        check(&["newsboat", "query", "tags"], Query::TagCounts);
+        check(
+            &["newsboat", "query", "items"],
+            Query::UnreadItems { filter: None },
+        );
+        check(
+            &["newsboat", "query", "items", "--filter", "age < 2"],
+            Query::UnreadItems {
+                filter: Some("age < 2".to_string()),
+            },
+        );
+        check(&["newsboat", "query", "item", "42"], Query::Item { id: 42 });
+    }
+
+    #[test]
+    fn t_query_subcommands_set_query_format_if_dash_dash_format_is_provided() {
+        let args = parse(&["newsboat", "query", "feeds", "--format", "json"]);
+        assert_eq!(args.query_format, OutputFormat::Json);
+
+        let args = parse(&["newsboat", "query", "item", "--format=text", "1"]);
+        assert_eq!(args.query_format, OutputFormat::Text);
+        assert_eq!(args.query, Some(Query::Item { id: 1 }));
+    }
+
+    #[test]
+    fn t_sets_display_msg_and_asks_to_exit_with_failure_if_query_arguments_are_invalid() {
+        let check = |opts: &[&str]| {
+            let args = parse(opts);
+            assert_eq!(args.query, None);
+            assert!(!args.display_msg.is_empty());
+            assert_eq!(args.return_code, Some(EXIT_FAILURE));
+        };
+
+        check(&["newsboat", "query", "feeds", "--format", "xml"]);
+        check(&["newsboat", "query", "items", "--filter", "title ="]);
+        check(&["newsboat", "query", "item", "first"]);
+    }
+
+    #[test]
+    fn t_asks_to_print_usage_and_exit_with_failure_if_query_subcommand_is_incomplete() {
+        let check = |opts: &[&str]| {
+            let args = parse(opts);
+            assert!(args.should_print_usage);
+            assert_eq!(args.return_code, Some(EXIT_FAILURE));
+        };
+
+        check(&["newsboat", "query"]);
+        check(&["newsboat", "query", "item"]);
+        check(&["newsboat", "query", "everything"]);
+    }
+
+    #[test]
+    fn t_asks_to_print_usage_and_exit_with_success_if_help_is_requested_for_a_subcommand() {
+        let args = parse(&["newsboat", "cache", "--help"]);
+        assert!(args.should_print_usage);
+        assert_eq!(args.return_code, Some(EXIT_SUCCESS));
     }
 
     #[test]
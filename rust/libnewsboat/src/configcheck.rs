@@ -0,0 +1,671 @@
+//! Checks config files for mistakes without applying them.
+//!
+//! On startup, Newsboat stops at the first bad line of the config. The checker goes through the
+//! whole config instead, following `include`s, and reports every problem it finds along with the
+//! file and line it's on.
+//!
+//! Commands, settings, operations, key binding contexts and color elements are defined on the
+//! C++ side, so the caller has to describe them with `add_command()`, `add_setting()`,
+//! `add_operation()`, `add_context()` and `add_color_element()` before calling `check()`.
+
+use crate::filterparser;
+use crate::fmtstrformatter;
+use crate::keymap;
+use crate::utils::{self, ReadTextFileError};
+use gettextrs::gettext;
+use regex_rs::{CompFlags, Regex};
+use std::collections::{BTreeSet, HashMap};
+use std::fmt;
+use std::path::{Path, PathBuf};
+use strprintf::fmt;
+
+/// Dialogs that the `highlight` command applies to.
+const HIGHLIGHT_LOCATIONS: [&str; 4] = ["all", "article", "articlelist", "feedlist"];
+
+/// Settings whose names end in "-format" but which take strftime(3) formats rather than
+/// Newsboat's own format strings.
+const STRFTIME_SETTINGS: [&str; 2] = ["datetime-format", "download-filename-format"];
+
+/// The values a setting accepts.
+#[derive(Debug, Clone, PartialEq)]
+pub enum SettingKind {
+    /// "yes", "no", "true" or "false".
+    Bool,
+    /// A non-negative integer.
+    Int,
+    /// Any string, including paths.
+    Text,
+    /// One of the listed values.
+    Enum(Vec<String>),
+}
+
+/// A mistake in a config file.
+#[derive(Debug, PartialEq)]
+pub struct Problem {
+    /// The file that contains the mistake.
+    pub file: PathBuf,
+    /// The number of the offending line, starting from 1.
+    pub line: usize,
+    /// Internationalized description of the mistake.
+    pub message: String,
+}
+
+impl fmt::Display for Problem {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let location = fmt!(
+            &gettext("%s line %u"),
+            self.file.to_string_lossy().into_owned(),
+            self.line as u32
+        );
+        write!(f, "{}: {}", location, self.message)
+    }
+}
+
+/// Knows about all the commands that can appear in the config, and checks config files against
+/// them.
+#[derive(Default)]
+pub struct ConfigChecker {
+    commands: BTreeSet<String>,
+    settings: HashMap<String, SettingKind>,
+    operations: BTreeSet<String>,
+    contexts: BTreeSet<String>,
+    color_elements: BTreeSet<String>,
+}
+
+impl ConfigChecker {
+    /// Creates a checker that doesn't know about any commands yet.
+    pub fn new() -> ConfigChecker {
+        ConfigChecker::default()
+    }
+
+    /// Makes `name` a valid command. The parameters of the commands that the checker knows, like
+    /// `color` or `bind-key`, are checked as well; other commands accept any parameters.
+    pub fn add_command(&mut self, name: &str) {
+        self.commands.insert(name.to_string());
+    }
+
+    /// Makes `name` a valid command that takes values of the given `kind`.
+    pub fn add_setting(&mut self, name: &str, kind: SettingKind) {
+        self.settings.insert(name.to_string(), kind);
+    }
+
+    /// Makes `name` a valid operation for `bind-key`, `macro` and `run-on-startup`.
+    pub fn add_operation(&mut self, name: &str) {
+        self.operations.insert(name.to_string());
+    }
+
+    /// Makes `name` a valid context for `bind-key` and `unbind-key`. "all" is always valid.
+    pub fn add_context(&mut self, name: &str) {
+        self.contexts.insert(name.to_string());
+    }
+
+    /// Makes `name` a valid element for the `color` command.
+    pub fn add_color_element(&mut self, name: &str) {
+        self.color_elements.insert(name.to_string());
+    }
+
+    /// Checks `config_file` and all the files it includes, and returns the problems in the order
+    /// they appear.
+    ///
+    /// Returns an internationalized error message if `config_file` itself couldn't be read.
+    pub fn check(&self, config_file: &Path) -> Result<Vec<Problem>, String> {
+        let mut run = CheckRun {
+            checker: self,
+            included: Vec::new(),
+            problems: Vec::new(),
+        };
+        let config_file = match utils::getcwd() {
+            Ok(cwd) => cwd.join(config_file),
+            Err(_) => config_file.to_path_buf(),
+        };
+        if !run.check_file(&config_file) {
+            return Err(fmt!(
+                &gettext("Error: couldn't open configuration file `%s'!"),
+                config_file.to_string_lossy().into_owned()
+            ));
+        }
+        Ok(run.problems)
+    }
+
+    fn is_known_command(&self, command: &str) -> bool {
+        self.commands.contains(command) || self.settings.contains_key(command)
+    }
+
+    fn check_command(&self, command: &str, line: &str) -> Result<(), String> {
+        if !self.is_known_command(command) {
+            return Err(fmt!(&gettext("unknown command `%s'"), command));
+        }
+
+        let tokens = utils::tokenize_quoted(line);
+        let params = &tokens[1..];
+        match command {
+            "include" => at_least(params, 1),
+            "color" => {
+                at_least(params, 3)?;
+                check_colors(&params[1..3])?;
+                check_attributes(&params[3..])?;
+                if !self.color_elements.contains(&params[0]) {
+                    return Err(fmt!(
+                        &gettext("`%s' is not a valid configuration element"),
+                        params[0].clone()
+                    ));
+                }
+                Ok(())
+            }
+            "highlight" => {
+                at_least(params, 3)?;
+                if !HIGHLIGHT_LOCATIONS.contains(&params[0].as_str()) {
+                    return Err(fmt!(
+                        &gettext("`%s' is an invalid dialog type"),
+                        params[0].clone()
+                    ));
+                }
+                if let Err(error) =
+                    Regex::new(&params[1], CompFlags::EXTENDED | CompFlags::IGNORE_CASE)
+                {
+                    return Err(fmt!(
+                        &gettext("`%s' is not a valid regular expression: %s"),
+                        params[1].clone(),
+                        error
+                    ));
+                }
+                check_colors(&params[2..params.len().min(4)])?;
+                check_attributes(params.get(4..).unwrap_or(&[]))
+            }
+            "highlight-article" => {
+                at_least(params, 3)?;
+                check_filter(&params[0])?;
+                check_colors(&params[1..3])?;
+                check_attributes(&params[3..])
+            }
+            "ignore-article" | "define-filter" => {
+                at_least(params, 2)?;
+                check_filter(&params[1])
+            }
+            "always-download" | "reset-unread-on-update" => at_least(params, 1),
+            "bind-key" => {
+                at_least(params, 2)?;
+                if let Some(context) = params.get(2) {
+                    self.check_context(context)?;
+                }
+                if !self.operations.contains(&params[1]) {
+                    return Err(fmt!(
+                        &gettext("`%s' is not a valid key command"),
+                        params[1].clone()
+                    ));
+                }
+                Ok(())
+            }
+            "unbind-key" => {
+                at_least(params, 1)?;
+                match params.get(1) {
+                    Some(context) => self.check_context(context),
+                    None => Ok(()),
+                }
+            }
+            // The operations are everything after the key
+            "macro" => self.check_operations(line, 2, true, true),
+            "run-on-startup" => self.check_operations(line, 1, false, false),
+            _ => match self.settings.get(command) {
+                Some(kind) => {
+                    at_least(params, 1)?;
+                    check_setting(command, kind, &params[0])
+                }
+                None => Ok(()),
+            },
+        }
+    }
+
+    fn check_context(&self, context: &str) -> Result<(), String> {
+        if context == "all" || self.contexts.contains(context) {
+            Ok(())
+        } else {
+            Err(fmt!(&gettext("`%s' is not a valid context"), context))
+        }
+    }
+
+    /// Checks the operation sequence that follows the first `skip` tokens of the line. If
+    /// `placeholders` is `true`, operation names that contain macro placeholders are only known
+    /// when the macro is invoked, so they're not checked.
+    fn check_operations(
+        &self,
+        line: &str,
+        skip: usize,
+        required: bool,
+        placeholders: bool,
+    ) -> Result<(), String> {
+        let literal = |token: &keymap::MacroToken| {
+            if token.is_literal() {
+                token.expand(&keymap::MacroArguments::default()).ok()
+            } else {
+                None
+            }
+        };
+        let mut operations: Vec<Vec<Option<String>>> = if placeholders {
+            keymap::tokenize_macro_operation_sequence(line).map(|operations| {
+                operations
+                    .iter()
+                    .map(|tokens| tokens.iter().map(literal).collect())
+                    .collect()
+            })
+        } else {
+            keymap::tokenize_operation_sequence(line).map(|operations| {
+                operations
+                    .into_iter()
+                    .map(|tokens| tokens.into_iter().map(Some).collect())
+                    .collect()
+            })
+        }
+        .ok_or_else(|| gettext("invalid parameters."))?;
+        if let Some(first) = operations.first_mut() {
+            let skip = skip.min(first.len());
+            first.drain(..skip);
+        }
+        let operations = operations
+            .into_iter()
+            .filter(|operation| !operation.is_empty())
+            .collect::<Vec<_>>();
+        if required && operations.is_empty() {
+            return Err(gettext("too few parameters."));
+        }
+        for operation in &operations {
+            if let Some(name) = &operation[0] {
+                if !self.operations.contains(name) {
+                    return Err(fmt!(
+                        &gettext("`%s' is not a valid operation"),
+                        name.clone()
+                    ));
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// State of a single `ConfigChecker::check()` call.
+struct CheckRun<'a> {
+    checker: &'a ConfigChecker,
+    /// Files that are being checked, from the outermost to the innermost.
+    included: Vec<PathBuf>,
+    problems: Vec<Problem>,
+}
+
+impl<'a> CheckRun<'a> {
+    /// Checks the file, recording problems as it goes. Returns `false` if the file couldn't be
+    /// opened.
+    fn check_file(&mut self, path: &Path) -> bool {
+        // Newsboat ignores recursive includes, and so do we
+        if self.included.iter().any(|included| included == path) {
+            return true;
+        }
+
+        let lines = match utils::read_text_file(path) {
+            Ok(lines) => lines,
+            Err(ReadTextFileError::CantOpen { .. }) => return false,
+            Err(ReadTextFileError::LineError {
+                line_number,
+                reason,
+            }) => {
+                self.problems.push(Problem {
+                    file: path.to_path_buf(),
+                    line: line_number,
+                    message: reason.to_string(),
+                });
+                return true;
+            }
+        };
+
+        self.included.push(path.to_path_buf());
+        for (index, line) in lines.iter().enumerate() {
+            if let Err(message) = self.check_line(path, line) {
+                self.problems.push(Problem {
+                    file: path.to_path_buf(),
+                    line: index + 1,
+                    message,
+                });
+            }
+        }
+        self.included.pop();
+
+        true
+    }
+
+    fn check_line(&mut self, path: &Path, line: &str) -> Result<(), String> {
+        let line = utils::strip_comments(line);
+        let command = match utils::tokenize_quoted(line).into_iter().next() {
+            Some(command) => command,
+            None => return Ok(()),
+        };
+
+        // Backticks are replaced by the output of the commands inside them, which we don't want
+        // to run just to check the config. All we can check is a command that is spelled out.
+        if line.contains('`') {
+            if command.contains('`') || self.checker.is_known_command(&command) {
+                return Ok(());
+            }
+            return Err(fmt!(&gettext("unknown command `%s'"), command));
+        }
+
+        self.checker.check_command(&command, line)?;
+
+        if command == "include" {
+            let included = utils::tokenize_quoted(line)[1].clone();
+            let included = utils::resolve_tilde(PathBuf::from(included));
+            let included = utils::resolve_relative(path, &included);
+            if !self.check_file(&included) {
+                return Err(gettext("file couldn't be opened."));
+            }
+        }
+
+        Ok(())
+    }
+}
+
+fn at_least(params: &[String], count: usize) -> Result<(), String> {
+    if params.len() < count {
+        Err(gettext("too few parameters."))
+    } else {
+        Ok(())
+    }
+}
+
+fn check_colors(colors: &[String]) -> Result<(), String> {
+    match colors.iter().find(|color| !utils::is_valid_color(color)) {
+        Some(color) => Err(fmt!(&gettext("`%s' is not a valid color"), color.clone())),
+        None => Ok(()),
+    }
+}
+
+fn check_attributes(attributes: &[String]) -> Result<(), String> {
+    match attributes
+        .iter()
+        .find(|attribute| !utils::is_valid_attribute(attribute))
+    {
+        Some(attribute) => Err(fmt!(
+            &gettext("`%s' is not a valid attribute"),
+            attribute.clone()
+        )),
+        None => Ok(()),
+    }
+}
+
+fn check_filter(expr: &str) -> Result<(), String> {
+    filterparser::parse(expr).map(|_| ()).map_err(|error| {
+        fmt!(
+            &gettext("couldn't parse filter expression `%s': %s"),
+            expr,
+            error
+        )
+    })
+}
+
+fn check_setting(name: &str, kind: &SettingKind, value: &str) -> Result<(), String> {
+    match kind {
+        SettingKind::Bool => {
+            if !["yes", "no", "true", "false"].contains(&value) {
+                return Err(fmt!(
+                    &gettext("expected boolean value, found `%s' instead"),
+                    value
+                ));
+            }
+        }
+        SettingKind::Int => {
+            if !value.chars().all(|c| c.is_ascii_digit()) {
+                return Err(fmt!(
+                    &gettext("expected integer value, found `%s' instead"),
+                    value
+                ));
+            }
+        }
+        SettingKind::Enum(values) => {
+            if !values.iter().any(|v| v == value) {
+                return Err(fmt!(&gettext("invalid configuration value `%s'"), value));
+            }
+        }
+        SettingKind::Text => {
+            if name.ends_with("-format") && !STRFTIME_SETTINGS.contains(&name) {
+                fmtstrformatter::validate(value)?;
+            }
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use tempfile::TempDir;
+
+    fn checker() -> ConfigChecker {
+        let mut checker = ConfigChecker::new();
+        for command in &[
+            "always-download",
+            "bind-key",
+            "color",
+            "define-filter",
+            "highlight",
+            "highlight-article",
+            "ignore-article",
+            "include",
+            "macro",
+            "reset-unread-on-update",
+            "run-on-startup",
+            "unbind-key",
+        ] {
+            checker.add_command(command);
+        }
+        checker.add_setting("auto-reload", SettingKind::Bool);
+        checker.add_setting("reload-time", SettingKind::Int);
+        checker.add_setting("browser", SettingKind::Text);
+        checker.add_setting("datetime-format", SettingKind::Text);
+        checker.add_setting("feedlist-format", SettingKind::Text);
+        checker.add_setting(
+            "proxy-type",
+            SettingKind::Enum(vec!["http".to_string(), "socks5".to_string()]),
+        );
+        checker.add_operation("open");
+        checker.add_operation("next-unread");
+        checker.add_operation("set");
+        checker.add_context("feedlist");
+        checker.add_color_element("listfocus");
+        checker.add_color_element("article");
+        checker
+    }
+
+    fn check(config: &str) -> Vec<(usize, String)> {
+        let tmp = TempDir::new().unwrap();
+        let config_file = tmp.path().join("config");
+        fs::write(&config_file, config).unwrap();
+        checker()
+            .check(&config_file)
+            .unwrap()
+            .into_iter()
+            .map(|problem| {
+                assert_eq!(problem.file, config_file);
+                (problem.line, problem.message)
+            })
+            .collect()
+    }
+
+    fn lines_with_problems(config: &str) -> Vec<usize> {
+        check(config).into_iter().map(|(line, _)| line).collect()
+    }
+
+    #[test]
+    fn t_accepts_a_valid_config() {
+        let config = r#"
+# A comment
+auto-reload yes # another comment
+reload-time 30
+browser "firefox %u"
+datetime-format "%b %d"
+feedlist-format "%4i %n %11u %t"
+proxy-type socks5
+color listfocus black yellow bold
+highlight article "^Title:.*" red default bold
+highlight-article "title =~ \"Rust\"" green default
+ignore-article "*" "title =~ \"Sponsored\""
+define-filter "Unread" "unread = \"yes\""
+always-download "https://example.com/feed.xml"
+reset-unread-on-update "https://example.com/feed.xml"
+bind-key o open feedlist
+unbind-key o
+macro m set browser "mpv %u" ; open ; next-unread
+run-on-startup next-unread
+"#;
+        assert_eq!(check(config), vec![]);
+    }
+
+    #[test]
+    fn t_reports_every_problem_with_its_line() {
+        let config = "auto-reload maybe\n\
+                      reload-time soon\n\
+                      proxy-type carrier-pigeon\n\
+                      unknown-setting 42\n\
+                      auto-reload\n\
+                      reload-time 10\n";
+        let problems = check(config);
+        assert_eq!(
+            problems.iter().map(|(line, _)| *line).collect::<Vec<_>>(),
+            vec![1, 2, 3, 4, 5]
+        );
+        assert!(problems[0].1.contains("maybe"));
+        assert!(problems[1].1.contains("soon"));
+        assert!(problems[2].1.contains("carrier-pigeon"));
+        assert!(problems[3].1.contains("unknown-setting"));
+    }
+
+    #[test]
+    fn t_checks_colors_and_attributes() {
+        let config = "color listfocus black purple\n\
+                      color listfocus black yellow sparkly\n\
+                      color sidebar black yellow\n\
+                      color listfocus black\n\
+                      highlight-article \"title = \\\"x\\\"\" color300 default\n";
+        assert_eq!(lines_with_problems(config), vec![1, 2, 3, 4, 5]);
+    }
+
+    #[test]
+    fn t_only_accepts_commands_and_color_elements_that_were_added() {
+        let tmp = TempDir::new().unwrap();
+        let config_file = tmp.path().join("config");
+        fs::write(
+            &config_file,
+            "color listfocus black yellow
+             bind-key o open
+             some-new-command with any parameters
+",
+        )
+        .unwrap();
+
+        let mut checker = ConfigChecker::new();
+        checker.add_operation("open");
+        let problems = checker.check(&config_file).unwrap();
+        assert_eq!(problems.len(), 3);
+        assert!(problems[0].message.contains("`color'"));
+        assert!(problems[1].message.contains("`bind-key'"));
+        assert!(problems[2].message.contains("`some-new-command'"));
+
+        checker.add_command("color");
+        checker.add_command("bind-key");
+        checker.add_command("some-new-command");
+        let problems = checker.check(&config_file).unwrap();
+        assert_eq!(problems.len(), 1);
+        assert!(problems[0].message.contains("`listfocus'"));
+
+        checker.add_color_element("listfocus");
+        assert_eq!(checker.check(&config_file).unwrap(), vec![]);
+    }
+
+    #[test]
+    fn t_checks_filter_expressions_and_regular_expressions() {
+        let config = "ignore-article \"*\" \"title =~\"\n\
+                      define-filter \"Broken\" \"unread = \"\n\
+                      highlight-article \"title ==\" red default\n\
+                      highlight article \"(unclosed\" red default\n\
+                      highlight sidebar \"foo\" red default\n";
+        let problems = check(config);
+        assert_eq!(
+            problems.iter().map(|(line, _)| *line).collect::<Vec<_>>(),
+            vec![1, 2, 3, 4, 5]
+        );
+        assert!(problems[0].1.contains("title =~"));
+    }
+
+    #[test]
+    fn t_checks_format_strings() {
+        let config = "feedlist-format \"%4i %n %?T?%T\"\n\
+                      feedlist-format \"%4i %\"\n\
+                      datetime-format \"%\"\n";
+        assert_eq!(lines_with_problems(config), vec![1, 2]);
+    }
+
+    #[test]
+    fn t_checks_operations_and_contexts() {
+        let config = "bind-key o open-everything\n\
+                      bind-key o open articlelist\n\
+                      unbind-key o nowhere\n\
+                      macro m open ; fly-away\n\
+                      macro m\n\
+                      run-on-startup fly-away\n";
+        assert_eq!(lines_with_problems(config), vec![1, 2, 3, 4, 5, 6]);
+    }
+
+    #[test]
+    fn t_only_checks_literal_operation_names_of_parameterized_macros() {
+        let config = "macro m ${1:+set} browser $1 ; ${flag:+toggle-article-read} ; open\n\
+                      macro m set browser \"${1:-lynx}\" ; fly-${1}\n\
+                      macro m set browser $1 ; fly-away\n";
+        assert_eq!(lines_with_problems(config), vec![3]);
+    }
+
+    #[test]
+    fn t_only_checks_command_names_on_lines_with_backticks() {
+        let config = "browser `which firefox`\n\
+                      reload-time `echo soon`\n\
+                      `echo auto-reload` yes\n\
+                      unknown-setting `echo 42`\n";
+        assert_eq!(lines_with_problems(config), vec![4]);
+    }
+
+    #[test]
+    fn t_follows_includes_relative_to_the_including_file() {
+        let tmp = TempDir::new().unwrap();
+        let config_file = tmp.path().join("config");
+        let colors_file = tmp.path().join("colors");
+        fs::write(
+            &config_file,
+            "auto-reload yes\n\
+             include colors\n\
+             include missing\n\
+             include config\n\
+             reload-time soon\n",
+        )
+        .unwrap();
+        fs::write(&colors_file, "color listfocus black purple\n").unwrap();
+
+        let problems = checker().check(&config_file).unwrap();
+        let locations = problems
+            .iter()
+            .map(|problem| (problem.file.clone(), problem.line))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            locations,
+            vec![
+                (colors_file, 1),
+                (config_file.clone(), 3),
+                (config_file.clone(), 5)
+            ]
+        );
+        assert!(problems[0]
+            .to_string()
+            .starts_with(&format!("{} line 1: ", tmp.path().join("colors").display())));
+    }
+
+    #[test]
+    fn t_returns_error_if_config_file_does_not_exist() {
+        let tmp = TempDir::new().unwrap();
+        assert!(checker().check(&tmp.path().join("config")).is_err());
+    }
+}
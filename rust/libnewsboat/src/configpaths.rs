@@ -1,10 +1,12 @@
 use crate::cliargsparser::CliArgsParser;
 use crate::logger::{self, Level};
+use crate::migration::{
+    Importer, MigrationPlan, NewsbeuterDotdirImporter, NewsbeuterXdgImporter, OpmlImporter,
+};
 use crate::utils;
 use gettextrs::gettext;
-use std::fs::{self, DirBuilder};
-use std::io;
-use std::os::unix::fs::DirBuilderExt;
+use std::env;
+use std::fs;
 use std::path::{Path, PathBuf};
 use strprintf::fmt;
 
@@ -13,13 +15,35 @@ pub const NEWSBOAT_CONFIG_SUBDIR: &str = ".newsboat";
 pub const NEWSBEUTER_SUBDIR_XDG: &str = "newsbeuter";
 pub const NEWSBEUTER_CONFIG_SUBDIR: &str = ".newsbeuter";
 pub const LOCK_SUFFIX: &str = ".lock";
-
-const URLS_FILENAME: &str = "urls";
-const CACHE_FILENAME: &str = "cache.db";
-const CONFIG_FILENAME: &str = "config";
-const QUEUE_FILENAME: &str = "queue";
-const SEARCH_HISTORY_FILENAME: &str = "history.search";
-const CMDLINE_HISTORY_FILENAME: &str = "history.cmdline";
+pub const CONTROL_SOCKET_SUFFIX: &str = ".sock";
+/// Subdirectory of the config and data dirs that holds one directory per profile.
+pub const PROFILES_SUBDIR: &str = "profiles";
+
+/// Environment variable that overrides Newsboat's config dir.
+pub const CONFIG_DIR_ENV_VAR: &str = "NEWSBOAT_CONFIG_DIR";
+/// Environment variable that overrides Newsboat's data dir.
+pub const DATA_DIR_ENV_VAR: &str = "NEWSBOAT_DATA_DIR";
+/// Environment variable that overrides the path to the URLs file.
+pub const URL_FILE_ENV_VAR: &str = "NEWSBOAT_URL_FILE";
+/// Environment variable that overrides the path to the config file.
+pub const CONFIG_FILE_ENV_VAR: &str = "NEWSBOAT_CONFIG_FILE";
+/// Environment variable that overrides the path to the cache file (and thus the lock file and
+/// the control socket, which are placed next to it).
+pub const CACHE_FILE_ENV_VAR: &str = "NEWSBOAT_CACHE_FILE";
+/// Environment variable that overrides the path to the queue file.
+pub const QUEUE_FILE_ENV_VAR: &str = "NEWSBOAT_QUEUE_FILE";
+
+pub(crate) const URLS_FILENAME: &str = "urls";
+pub(crate) const CACHE_FILENAME: &str = "cache.db";
+pub(crate) const CONFIG_FILENAME: &str = "config";
+pub(crate) const QUEUE_FILENAME: &str = "queue";
+pub(crate) const SEARCH_HISTORY_FILENAME: &str = "history.search";
+pub(crate) const CMDLINE_HISTORY_FILENAME: &str = "history.cmdline";
+const ARTICLE_SEARCH_HISTORY_FILENAME: &str = "history.search.articles";
+const FEED_SEARCH_HISTORY_FILENAME: &str = "history.search.feeds";
+const FILTER_HISTORY_FILENAME: &str = "history.filter";
+const IMPORTED_READ_GUIDS_FILENAME: &str = "imported-read-guids";
+const CRASH_REPORTS_SUBDIR: &str = "crash-reports";
 
 #[derive(Debug, Default)]
 pub struct ConfigPaths {
@@ -43,16 +67,37 @@ pub struct ConfigPaths {
     /// it through the command line parameter.
     config_dir: PathBuf,
 
+    /// Name of the profile chosen on the command line, if any.
+    ///
+    /// When set, `config_dir` and `data_dir` point into the profile's subdirectories.
+    profile: Option<String>,
+
     url_file: PathBuf,
     cache_file: PathBuf,
     config_file: PathBuf,
     lock_file: PathBuf,
+    control_socket_file: PathBuf,
     queue_file: PathBuf,
     search_file: PathBuf,
     cmdline_file: PathBuf,
+    article_search_file: PathBuf,
+    feed_search_file: PathBuf,
+    filter_file: PathBuf,
+    imported_read_guids_file: PathBuf,
+    crash_reports_dir: PathBuf,
+
+    /// OPML file that the user asked to migrate subscriptions from, instead of looking for
+    /// Newsbeuter's directories.
+    migration_opml_file: Option<PathBuf>,
+    /// File with GUIDs of read articles to migrate along with `migration_opml_file`.
+    migration_read_guids_file: Option<PathBuf>,
 
     silent: bool,
     using_nonstandard_configs: bool,
+
+    /// Whether the path to the cache file was given explicitly, on the command line or through
+    /// the environment.
+    explicit_cache_file: bool,
 }
 
 impl ConfigPaths {
@@ -64,16 +109,28 @@ impl ConfigPaths {
             data_dir: PathBuf::new(),
             config_dir: PathBuf::new(),
 
+            profile: None,
+
             url_file: PathBuf::new(),
             cache_file: PathBuf::new(),
             config_file: PathBuf::new(),
             lock_file: PathBuf::new(),
+            control_socket_file: PathBuf::new(),
             queue_file: PathBuf::new(),
             search_file: PathBuf::new(),
             cmdline_file: PathBuf::new(),
+            article_search_file: PathBuf::new(),
+            feed_search_file: PathBuf::new(),
+            filter_file: PathBuf::new(),
+            imported_read_guids_file: PathBuf::new(),
+            crash_reports_dir: PathBuf::new(),
+
+            migration_opml_file: None,
+            migration_read_guids_file: None,
 
             silent: false,
             using_nonstandard_configs: false,
+            explicit_cache_file: false,
         };
 
         let env_home = utils::home_dir();
@@ -100,163 +157,26 @@ impl ConfigPaths {
         config_paths
     }
 
-    fn migrate_data_from_newsbeuter_xdg(&mut self) -> bool {
-        // This can't panic because we've tested we can find the home directory in ConfigPaths::new
-        // This should be replaced with proper error handling after this is not used by c++ anymore
-        let xdg_dirs = xdg::BaseDirectories::new().unwrap();
-        let xdg_config_dir = xdg_dirs.get_config_home();
-        let xdg_data_dir = xdg_dirs.get_data_home();
-
-        let newsbeuter_config_dir = xdg_config_dir.join(NEWSBEUTER_SUBDIR_XDG);
-        let newsbeuter_data_dir = xdg_data_dir.join(NEWSBEUTER_SUBDIR_XDG);
-
-        let newsboat_config_dir = xdg_config_dir.join(NEWSBOAT_SUBDIR_XDG);
-        let newsboat_data_dir = xdg_data_dir.join(NEWSBOAT_SUBDIR_XDG);
-
-        if !newsbeuter_config_dir.is_dir() {
-            return false;
-        }
-
-        fn exists(path: &Path) -> bool {
-            let exists = path.exists();
-            if exists {
-                log!(
-                    Level::Debug,
-                    "{:?} already exists, aborting XDG migration.",
-                    path
-                );
-            }
-            exists
-        }
-
-        if exists(&newsboat_config_dir) {
-            return false;
-        }
-
-        if exists(&newsboat_data_dir) {
-            return false;
-        }
-
-        self.config_dir = newsboat_config_dir;
-        self.data_dir = newsboat_data_dir;
-
-        if !self.silent {
-            eprintln!(
-                "{}",
-                &gettext("Migrating configs and data from Newsbeuter's XDG dirs...")
-            );
-        }
-
-        if !try_mkdir(&self.config_dir) {
-            return false;
-        }
-
-        if !try_mkdir(&self.data_dir) {
-            return false;
-        }
-
-        // We ignore the return codes because it's okay if some files are missing.
-
-        // in config
-        let _ = migrate_file(&newsbeuter_config_dir, &self.config_dir, URLS_FILENAME);
-        let _ = migrate_file(&newsbeuter_config_dir, &self.config_dir, CONFIG_FILENAME);
-
-        // in data
-        let _ = migrate_file(&newsbeuter_data_dir, &self.data_dir, CACHE_FILENAME);
-        let _ = migrate_file(&newsbeuter_data_dir, &self.data_dir, QUEUE_FILENAME);
-        let _ = migrate_file(
-            &newsbeuter_data_dir,
-            &self.data_dir,
-            SEARCH_HISTORY_FILENAME,
-        );
-        let _ = migrate_file(
-            &newsbeuter_data_dir,
-            &self.data_dir,
-            CMDLINE_HISTORY_FILENAME,
-        );
-
-        true
-    }
-
-    fn migrate_data_from_newsbeuter_simple(&self) -> bool {
-        let newsbeuter_dir = self.env_home.join(NEWSBEUTER_CONFIG_SUBDIR);
-
-        if !newsbeuter_dir.is_dir() {
-            return false;
-        }
-
-        let newsboat_dir = self.env_home.join(NEWSBOAT_CONFIG_SUBDIR);
-
-        if newsboat_dir.exists() {
-            log!(
-                Level::Debug,
-                "{:?} already exists, aborting migration.",
-                newsboat_dir
-            );
-            return false;
-        }
-
-        if !self.silent {
-            eprintln!(
-                "{}",
-                &gettext("Migrating configs and data from ~/.newsbeuter/...")
-            );
-        }
-
-        match mkdir(&newsboat_dir, 0o700) {
-            Ok(_) => (),
-            Err(ref e) if e.kind() == io::ErrorKind::AlreadyExists => (),
-            Err(err) => {
-                if !self.silent {
-                    eprintln!(
-                        "{}",
-                        &fmt!(
-                            &gettext("Aborting migration because mkdir on `%s' failed: %s"),
-                            &newsboat_dir.to_string_lossy().into_owned(),
-                            err.to_string()
-                        )
-                    );
-                }
-                return false;
-            }
-        };
-
-        // We ignore the return codes because it's okay if some files are missing.
-        let _ = migrate_file(&newsbeuter_dir, &newsboat_dir, URLS_FILENAME);
-        let _ = migrate_file(&newsbeuter_dir, &newsboat_dir, CACHE_FILENAME);
-        let _ = migrate_file(&newsbeuter_dir, &newsboat_dir, CONFIG_FILENAME);
-        let _ = migrate_file(&newsbeuter_dir, &newsboat_dir, QUEUE_FILENAME);
-        let _ = migrate_file(&newsbeuter_dir, &newsboat_dir, SEARCH_HISTORY_FILENAME);
-        let _ = migrate_file(&newsbeuter_dir, &newsboat_dir, CMDLINE_HISTORY_FILENAME);
-
-        true
-    }
-
-    fn migrate_data_from_newsbeuter(&mut self) -> bool {
-        let mut migrated = self.migrate_data_from_newsbeuter_xdg();
-
-        if migrated {
-            // Re-running to pick up XDG dirs
-            self.find_dirs();
-        } else {
-            migrated = self.migrate_data_from_newsbeuter_simple();
-        }
-
-        migrated
-    }
-
     pub fn create_dirs(&self) -> bool {
         try_mkdir(&self.config_dir) && try_mkdir(&self.data_dir)
     }
 
     fn find_dirs(&mut self) {
-        self.config_dir = self.env_home.join(NEWSBOAT_CONFIG_SUBDIR);
+        let (config_dir, data_dir) = self.base_dirs();
 
-        self.data_dir = self.config_dir.clone();
+        // Podboat doesn't know about profiles, so the queue is shared by all of them
+        self.queue_file = data_dir.join(QUEUE_FILENAME);
 
-        // Will change config_dir and data_dir to point to XDG if XDG
-        // directories are available.
-        self.find_dirs_xdg();
+        match self.profile {
+            Some(ref profile) => {
+                self.config_dir = config_dir.join(PROFILES_SUBDIR).join(profile);
+                self.data_dir = data_dir.join(PROFILES_SUBDIR).join(profile);
+            }
+            None => {
+                self.config_dir = config_dir;
+                self.data_dir = data_dir;
+            }
+        }
 
         // in config
         self.url_file = self.config_dir.join(URLS_FILENAME);
@@ -265,29 +185,69 @@ impl ConfigPaths {
         // in data
         self.cache_file = self.data_dir.join(CACHE_FILENAME);
         self.lock_file = self.data_dir.join(CACHE_FILENAME.to_owned() + LOCK_SUFFIX);
-        self.queue_file = self.data_dir.join(QUEUE_FILENAME);
+        self.control_socket_file = self
+            .data_dir
+            .join(CACHE_FILENAME.to_owned() + CONTROL_SOCKET_SUFFIX);
         self.search_file = self.data_dir.join(SEARCH_HISTORY_FILENAME);
         self.cmdline_file = self.data_dir.join(CMDLINE_HISTORY_FILENAME);
+        self.article_search_file = self.data_dir.join(ARTICLE_SEARCH_HISTORY_FILENAME);
+        self.feed_search_file = self.data_dir.join(FEED_SEARCH_HISTORY_FILENAME);
+        self.filter_file = self.data_dir.join(FILTER_HISTORY_FILENAME);
+        self.imported_read_guids_file = self.data_dir.join(IMPORTED_READ_GUIDS_FILENAME);
+        self.crash_reports_dir = self.data_dir.join(CRASH_REPORTS_SUBDIR);
+
+        self.apply_env_overrides();
     }
 
-    fn find_dirs_xdg(&mut self) {
-        // This can't panic because we've tested we can find the home directory in ConfigPaths::new
-        // This should be replaced with proper error handling after this is not used by c++ anymore
-        let xdg_dirs = xdg::BaseDirectories::new().unwrap();
-        let config_dir = xdg_dirs.get_config_home().join(NEWSBOAT_SUBDIR_XDG);
-        let data_dir = xdg_dirs.get_data_home().join(NEWSBOAT_SUBDIR_XDG);
+    /// Replaces paths to individual files with the ones given through the environment.
+    fn apply_env_overrides(&mut self) {
+        if let Some(url_file) = env_path(URL_FILE_ENV_VAR) {
+            self.url_file = url_file;
+        }
+
+        if let Some(config_file) = env_path(CONFIG_FILE_ENV_VAR) {
+            self.config_file = config_file;
+        }
+
+        if let Some(cache_file) = env_path(CACHE_FILE_ENV_VAR) {
+            self.set_cache_file(cache_file);
+            self.explicit_cache_file = true;
+        }
+
+        if let Some(queue_file) = env_path(QUEUE_FILE_ENV_VAR) {
+            self.queue_file = queue_file;
+        }
 
-        if !config_dir.is_dir() {
-            return;
+        let env_vars = [
+            CONFIG_DIR_ENV_VAR,
+            DATA_DIR_ENV_VAR,
+            URL_FILE_ENV_VAR,
+            CONFIG_FILE_ENV_VAR,
+            CACHE_FILE_ENV_VAR,
+            QUEUE_FILE_ENV_VAR,
+        ];
+        if env_vars.iter().any(|var| env_path(var).is_some()) {
+            self.using_nonstandard_configs = true;
         }
+    }
 
-        /* Invariant: config dir exists.
-         *
-         * At this point, we're confident we'll be using XDG. We don't check if
-         * data dir exists, because if it doesn't we'll create it. */
+    /// Returns Newsboat's config and data dirs, not taking the profile into account.
+    ///
+    /// These are XDG dirs if they're available, and the dotdir otherwise. Each of them can be
+    /// overridden through the environment; a dir that isn't overridden keeps its default.
+    fn base_dirs(&self) -> (PathBuf, PathBuf) {
+        let (config_dir, data_dir) = match find_dirs_xdg() {
+            Some(dirs) => dirs,
+            None => {
+                let dotdir = self.env_home.join(NEWSBOAT_CONFIG_SUBDIR);
+                (dotdir.clone(), dotdir)
+            }
+        };
 
-        self.config_dir = config_dir;
-        self.data_dir = data_dir;
+        (
+            env_path(CONFIG_DIR_ENV_VAR).unwrap_or(config_dir),
+            env_path(DATA_DIR_ENV_VAR).unwrap_or(data_dir),
+        )
     }
 
     /// Indicates if the object can be used.
@@ -306,13 +266,23 @@ impl ConfigPaths {
     }
 
     /// Initializes paths to config, cache etc. from CLI arguments.
+    ///
+    /// If a profile is chosen, all paths point into the profile's directories, except for the
+    /// ones that are specified explicitly, and the queue file, which Podboat has to find.
     pub fn process_args(&mut self, args: &CliArgsParser) {
+        if args.profile.is_some() && self.initialized() {
+            self.profile = args.profile.clone();
+            self.find_dirs();
+        }
+
         if let Some(ref url_file) = args.url_file {
             self.url_file = url_file.to_owned();
         }
 
         if let Some(ref cache_file) = args.cache_file {
             self.cache_file = cache_file.to_owned();
+            self.control_socket_file = append_extension(cache_file, CONTROL_SOCKET_SUFFIX);
+            self.explicit_cache_file = true;
         }
 
         if let Some(ref lock_file) = args.lock_file {
@@ -323,19 +293,216 @@ impl ConfigPaths {
             self.config_file = config_file.to_owned();
         }
 
+        self.migration_opml_file = args.migrate_from_opml.clone();
+        self.migration_read_guids_file = args.migrate_read_guids.clone();
+
         self.silent = args.silent;
-        self.using_nonstandard_configs = args.using_nonstandard_configs();
+        self.using_nonstandard_configs =
+            self.using_nonstandard_configs || args.using_nonstandard_configs();
+    }
+
+    /// Migrate configs and data from another feed reader, if one is found. Return `true` if
+    /// migrated something, `false` otherwise.
+    ///
+    /// If the user pointed Newsboat at an OPML file (and possibly a list of read GUIDs) on the
+    /// command line, that's the only source. Otherwise, the sources are tried in order:
+    /// Newsbeuter's XDG dirs, then ~/.newsbeuter.
+    pub fn try_migrate(&mut self) -> bool {
+        let importers = self.importers();
+        self.run_importers(importers)
     }
 
     /// Migrate configs and data from Newsbeuter if they exist. Return `true` if migrated
     /// something, `false` otherwise.
+    ///
+    /// Unlike `try_migrate`, this ignores the OPML file given on the command line.
     pub fn try_migrate_from_newsbeuter(&mut self) -> bool {
-        if !self.using_nonstandard_configs && !&self.url_file.exists() {
-            return self.migrate_data_from_newsbeuter();
+        let importers = self.newsbeuter_importers();
+        self.run_importers(importers)
+    }
+
+    /// Describes what `try_migrate` would do, without touching any files.
+    ///
+    /// Returns an explanation if no migration would be attempted at all. Otherwise, returns plans
+    /// for each source that is present, in the order in which they're tried; the first one that
+    /// has no conflicts is the one that would be migrated.
+    pub fn plan_migration(&self) -> Result<Vec<MigrationPlan>, String> {
+        self.plan_importers(&self.importers())
+    }
+
+    /// Describes what `try_migrate_from_newsbeuter` would do, without touching any files.
+    ///
+    /// See `plan_migration` for the meaning of the return value.
+    pub fn plan_migration_from_newsbeuter(&self) -> Result<Vec<MigrationPlan>, String> {
+        self.plan_importers(&self.newsbeuter_importers())
+    }
+
+    /// Describes what `try_migrate` would do in a human-readable form.
+    pub fn describe_migration_plan(&self) -> String {
+        let plans = match self.plan_migration() {
+            Ok(plans) => plans,
+            Err(reason) => return reason + "\n",
+        };
+
+        if plans.is_empty() {
+            return gettext("Found nothing to migrate.") + "\n";
+        }
+
+        let mut result = String::new();
+        for plan in &plans {
+            let chosen = plan.conflicts.is_empty();
+            let header = if chosen {
+                gettext("Would migrate configs and data from %s:")
+            } else {
+                gettext("Would not migrate configs and data from %s:")
+            };
+            result.push_str(&fmt!(&header, plan.source_name.as_str()));
+            result.push('\n');
+            for line in plan.describe() {
+                result.push_str("    ");
+                result.push_str(&line);
+                result.push('\n');
+            }
+
+            if chosen {
+                // Later sources are not even looked at
+                break;
+            }
+        }
+        result
+    }
+
+    fn plan_importers(
+        &self,
+        importers: &[Box<dyn Importer>],
+    ) -> Result<Vec<MigrationPlan>, String> {
+        if self.profile.is_some() {
+            return Err(gettext(
+                "Nothing would be migrated, because a profile is in use. Migration only fills \
+                 the default configs and data.",
+            ));
+        }
+
+        if self.using_nonstandard_configs {
+            return Err(gettext(
+                "Nothing would be migrated, because paths were specified on the command line or \
+                 through the environment.",
+            ));
+        }
+
+        if self.url_file.exists() {
+            return Err(fmt!(
+                &gettext("Nothing would be migrated, because `%s' already exists."),
+                self.url_file.to_string_lossy().into_owned()
+            ));
+        }
+
+        Ok(importers
+            .iter()
+            .filter(|importer| importer.source_exists())
+            .map(|importer| importer.plan())
+            .collect())
+    }
+
+    fn importers(&self) -> Vec<Box<dyn Importer>> {
+        match self.migration_opml_file {
+            Some(ref opml_file) => vec![Box::new(OpmlImporter::new(
+                opml_file,
+                self.migration_read_guids_file.as_deref(),
+                &self.url_file,
+                &self.imported_read_guids_file,
+            ))],
+            None => self.newsbeuter_importers(),
+        }
+    }
+
+    fn newsbeuter_importers(&self) -> Vec<Box<dyn Importer>> {
+        vec![
+            Box::new(NewsbeuterXdgImporter::new()),
+            Box::new(NewsbeuterDotdirImporter::new(&self.env_home)),
+        ]
+    }
+
+    /// Runs the first importer whose source is present.
+    fn run_importers(&mut self, importers: Vec<Box<dyn Importer>>) -> bool {
+        // Profiles start out empty; migrated data goes into the default configs only
+        if self.profile.is_some() || self.using_nonstandard_configs || self.url_file.exists() {
+            // No migration occurred.
+            return false;
+        }
+
+        let importer = match importers.iter().find(|importer| importer.detect()) {
+            Some(importer) => importer,
+            None => return false,
+        };
+
+        if !self.silent {
+            eprintln!(
+                "{}",
+                &fmt!(
+                    &gettext("Migrating configs and data from %s..."),
+                    importer.source_name()
+                )
+            );
+        }
+
+        match importer.import() {
+            Ok(report) => {
+                for action in &report.actions {
+                    log!(Level::Info, "Migration: {}", action);
+                    if !self.silent {
+                        eprintln!("{}", action);
+                    }
+                }
+
+                // Re-running to pick up the dirs the importer might have created
+                self.find_dirs();
+                true
+            }
+            Err(message) => {
+                log!(Level::Error, "Migration failed: {}", message);
+                if !self.silent {
+                    eprintln!("{}", message);
+                }
+                false
+            }
+        }
+    }
+
+    /// Name of the profile in use, if any.
+    pub fn profile(&self) -> Option<&str> {
+        self.profile.as_deref()
+    }
+
+    /// Names of the profiles that exist, in alphabetical order.
+    ///
+    /// A profile exists if it has a directory in the config or the data dir.
+    pub fn list_profiles(&self) -> Vec<String> {
+        if !self.initialized() {
+            return vec![];
         }
 
-        // No migration occurred.
-        false
+        let (config_dir, data_dir) = self.base_dirs();
+        let mut profiles = vec![];
+        for dir in &[config_dir, data_dir] {
+            let entries = match fs::read_dir(dir.join(PROFILES_SUBDIR)) {
+                Ok(entries) => entries,
+                Err(_) => continue,
+            };
+            for entry in entries.filter_map(Result::ok) {
+                if !entry.path().is_dir() {
+                    continue;
+                }
+                if let Ok(name) = entry.file_name().into_string() {
+                    if is_valid_profile_name(&name) {
+                        profiles.push(name);
+                    }
+                }
+            }
+        }
+        profiles.sort();
+        profiles.dedup();
+        profiles
     }
 
     /// Path to the URLs file.
@@ -348,20 +515,26 @@ impl ConfigPaths {
         &self.cache_file
     }
 
+    /// Whether the path to the cache file was given on the command line or through the
+    /// environment, in which case it takes precedence over the `cache-file` setting.
+    pub fn has_explicit_cache_file(&self) -> bool {
+        self.explicit_cache_file
+    }
+
+    /// Path to the file with GUIDs of articles that were imported as read before they were in the
+    /// cache, and should be marked read once they are fetched.
+    pub fn imported_read_guids_file(&self) -> &Path {
+        &self.imported_read_guids_file
+    }
+
     /// Sets path to the cache file.
     // FIXME: this is actually a kludge that lets Controller change the path
     // midway. That logic should be moved into ConfigPaths, and this method
     // removed.
-    pub fn set_cache_file(&mut self, mut path: PathBuf) {
-        self.cache_file = path.clone();
-        self.lock_file = {
-            let current_extension = path
-                .extension()
-                .map(|p| p.to_string_lossy().into_owned())
-                .unwrap_or_else(String::new);
-            path.set_extension(current_extension + LOCK_SUFFIX);
-            path
-        };
+    pub fn set_cache_file(&mut self, path: PathBuf) {
+        self.lock_file = append_extension(&path, LOCK_SUFFIX);
+        self.control_socket_file = append_extension(&path, CONTROL_SOCKET_SUFFIX);
+        self.cache_file = path;
     }
 
     /// Path to the config file.
@@ -376,6 +549,13 @@ impl ConfigPaths {
         &self.lock_file
     }
 
+    /// Path to the socket through which other processes can control this instance.
+    ///
+    /// \note This changes when path to cache file changes.
+    pub fn control_socket_file(&self) -> &Path {
+        &self.control_socket_file
+    }
+
     /// \brief Path to the queue file.
     ///
     /// Queue file stores enqueued podcasts. It's written by Newsboat, and read by Podboat.
@@ -392,19 +572,77 @@ impl ConfigPaths {
     pub fn cmdline_file(&self) -> &Path {
         &self.cmdline_file
     }
+
+    /// Path to the file with previous queries entered at the article search prompt.
+    pub fn article_search_file(&self) -> &Path {
+        &self.article_search_file
+    }
+
+    /// Path to the file with previous queries entered at the feed search prompt.
+    pub fn feed_search_file(&self) -> &Path {
+        &self.feed_search_file
+    }
+
+    /// Path to the file with previous expressions entered at the filter prompt.
+    pub fn filter_file(&self) -> &Path {
+        &self.filter_file
+    }
+
+    /// Path to the directory where crash reports are saved. See `human_panic`.
+    pub fn crash_reports_dir(&self) -> &Path {
+        &self.crash_reports_dir
+    }
 }
 
-fn try_mkdir<R: AsRef<Path>>(path: R) -> bool {
-    utils::mkdir_parents(&path.as_ref(), 0o700).is_ok()
+/// Returns the path stored in the environment variable `name`, or `None` if the variable is unset
+/// or empty.
+fn env_path(name: &str) -> Option<PathBuf> {
+    env::var_os(name)
+        .filter(|value| !value.is_empty())
+        .map(PathBuf::from)
 }
 
-fn mkdir<R: AsRef<Path>>(path: R, mode: u32) -> io::Result<()> {
-    DirBuilder::new().mode(mode).create(path.as_ref())
+/// Returns Newsboat's XDG config and data dirs, or `None` if the config dir doesn't exist.
+fn find_dirs_xdg() -> Option<(PathBuf, PathBuf)> {
+    // This can't panic because we've tested we can find the home directory in ConfigPaths::new
+    // This should be replaced with proper error handling after this is not used by c++ anymore
+    let xdg_dirs = xdg::BaseDirectories::new().unwrap();
+    let config_dir = xdg_dirs.get_config_home().join(NEWSBOAT_SUBDIR_XDG);
+    let data_dir = xdg_dirs.get_data_home().join(NEWSBOAT_SUBDIR_XDG);
+
+    if !config_dir.is_dir() {
+        return None;
+    }
+
+    /* Invariant: config dir exists.
+     *
+     * At this point, we're confident we'll be using XDG. We don't check if
+     * data dir exists, because if it doesn't we'll create it. */
+
+    Some((config_dir, data_dir))
 }
 
-fn migrate_file<R: AsRef<Path>>(newsbeuter_dir: R, newsboat_dir: R, file: &str) -> io::Result<()> {
-    let input_filepath = newsbeuter_dir.as_ref().join(file);
-    let output_filepath = newsboat_dir.as_ref().join(file);
-    eprintln!("{:?} -> {:?}", input_filepath, output_filepath);
-    fs::copy(input_filepath, output_filepath).map(|_| ())
+/// Checks if `name` can be used as a profile name.
+///
+/// Profile names become directory names, so they're limited to ASCII letters, digits, dashes,
+/// underscores and dots, and can't start with a dot.
+pub fn is_valid_profile_name(name: &str) -> bool {
+    !name.is_empty()
+        && !name.starts_with('.')
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_' || c == '.')
+}
+
+/// Appends `suffix` (which should start with a dot) to the extension of `path`.
+fn append_extension(path: &Path, suffix: &str) -> PathBuf {
+    let current_extension = path
+        .extension()
+        .map(|p| p.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    path.with_extension(current_extension + suffix)
+}
+
+fn try_mkdir<R: AsRef<Path>>(path: R) -> bool {
+    utils::mkdir_parents(&path.as_ref(), 0o700).is_ok()
 }
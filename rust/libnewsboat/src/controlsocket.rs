@@ -0,0 +1,550 @@
+//! Lets other processes control a running instance of Newsboat.
+//!
+//! The instance that holds the lock listens on a Unix domain socket next to the lock file (see
+//! `ConfigPaths::control_socket_file()`). Clients connect, send one request per line, and get one
+//! response line per request, in the same order. Requests are:
+//!
+//! - `reload` -- reload all feeds;
+//! - `reload URL` -- reload the feed with given URL;
+//! - `mark-read` -- mark all feeds read;
+//! - `mark-read URL` -- mark the feed with given URL read;
+//! - `quit` -- quit the instance;
+//! - `status` -- describe the state of the instance.
+//!
+//! Responses are `ok` or `error`, optionally followed by a space and a human-readable message.
+//!
+//! `reload` is answered as soon as the reload starts, so `ok` only means that the request was
+//! accepted; whether the feeds could be fetched isn't reported.
+
+use crate::logger::{self, Level};
+use gettextrs::gettext;
+use std::fmt;
+use std::fs;
+use std::io::{self, BufRead, BufReader, Write};
+use std::os::unix::io::AsRawFd;
+use std::os::unix::net::{UnixListener, UnixStream};
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::time::Duration;
+use strprintf::fmt;
+
+/// How long the server waits for a connected client to send the next request, and the client
+/// waits for a response.
+const IO_TIMEOUT: Duration = Duration::from_secs(30);
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Request {
+    /// Reload the feed with given URL, or all feeds if there is none.
+    Reload(Option<String>),
+    /// Mark the feed with given URL read, or all feeds if there is none.
+    MarkRead(Option<String>),
+    Quit,
+    Status,
+}
+
+impl Request {
+    /// Converts a command passed to `newsboat -x` into a request, so that it can be forwarded to
+    /// the running instance. Returns `None` for commands that can't be forwarded.
+    pub fn from_command(command: &str) -> Option<Request> {
+        match command {
+            "reload" => Some(Request::Reload(None)),
+            "print-unread" => Some(Request::Status),
+            _ => None,
+        }
+    }
+}
+
+impl FromStr for Request {
+    type Err = String;
+
+    fn from_str(line: &str) -> Result<Request, String> {
+        let line = line.trim();
+        let (command, argument) = match line.find(' ') {
+            Some(pos) => (&line[..pos], Some(line[pos + 1..].trim().to_owned())),
+            None => (line, None),
+        };
+        let argument = argument.filter(|arg| !arg.is_empty());
+
+        match (command, argument) {
+            ("reload", url) => Ok(Request::Reload(url)),
+            ("mark-read", url) => Ok(Request::MarkRead(url)),
+            ("quit", None) => Ok(Request::Quit),
+            ("status", None) => Ok(Request::Status),
+            ("quit", Some(_)) | ("status", Some(_)) => {
+                Err(fmt!(&gettext("`%s' doesn't take arguments"), command))
+            }
+            _ => Err(fmt!(&gettext("unknown request `%s'"), command)),
+        }
+    }
+}
+
+impl fmt::Display for Request {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Request::Reload(None) => write!(f, "reload"),
+            Request::Reload(Some(url)) => write!(f, "reload {}", url),
+            Request::MarkRead(None) => write!(f, "mark-read"),
+            Request::MarkRead(Some(url)) => write!(f, "mark-read {}", url),
+            Request::Quit => write!(f, "quit"),
+            Request::Status => write!(f, "status"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Response {
+    Ok(String),
+    Error(String),
+}
+
+impl Response {
+    pub fn message(&self) -> &str {
+        match self {
+            Response::Ok(message) | Response::Error(message) => message,
+        }
+    }
+}
+
+impl FromStr for Response {
+    type Err = String;
+
+    fn from_str(line: &str) -> Result<Response, String> {
+        let line = line.trim_end_matches(&['\r', '\n'][..]);
+        let (status, message) = match line.find(' ') {
+            Some(pos) => (&line[..pos], line[pos + 1..].to_owned()),
+            None => (line, String::new()),
+        };
+        match status {
+            "ok" => Ok(Response::Ok(message)),
+            "error" => Ok(Response::Error(message)),
+            _ => Err(fmt!(&gettext("malformed response `%s'"), line)),
+        }
+    }
+}
+
+impl fmt::Display for Response {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let (status, message) = match self {
+            Response::Ok(message) => ("ok", message),
+            Response::Error(message) => ("error", message),
+        };
+        // Responses are line-based, so the message can't span multiple lines
+        let message = message.replace('\n', " ");
+        if message.is_empty() {
+            write!(f, "{}", status)
+        } else {
+            write!(f, "{} {}", status, message)
+        }
+    }
+}
+
+/// Listening end of the control socket.
+///
+/// The socket file is removed when the server is dropped.
+pub struct ControlServer {
+    listener: UnixListener,
+    path: PathBuf,
+}
+
+impl ControlServer {
+    /// Starts listening at `path`.
+    ///
+    /// A socket file left behind by an instance that crashed is replaced. If another instance is
+    /// still listening at `path`, an error of kind `AddrInUse` is returned.
+    pub fn bind(path: &Path) -> io::Result<ControlServer> {
+        if fs::symlink_metadata(path).is_ok() {
+            if UnixStream::connect(path).is_ok() {
+                return Err(io::Error::new(
+                    io::ErrorKind::AddrInUse,
+                    "another instance is listening on the control socket",
+                ));
+            }
+            log!(
+                Level::Debug,
+                "ControlServer: removing stale socket `{}'",
+                path.display()
+            );
+            fs::remove_file(path)?;
+        }
+
+        // The socket has to be created with the right permissions: changing them after `bind()`
+        // would let other users connect in between. The umask is shared by all threads, but
+        // files they create meanwhile only end up with stricter permissions.
+        let old_umask = unsafe { libc::umask(0o177) };
+        let listener = UnixListener::bind(path);
+        unsafe { libc::umask(old_umask) };
+        let listener = listener?;
+        log!(
+            Level::Debug,
+            "ControlServer: listening on `{}'",
+            path.display()
+        );
+        Ok(ControlServer {
+            listener,
+            path: path.to_owned(),
+        })
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Waits up to `timeout` for a client to connect.
+    ///
+    /// Returns `Ok(None)` if no client connected in time, which lets the caller check if it
+    /// should stop serving.
+    pub fn accept(&self, timeout: Duration) -> io::Result<Option<ControlConnection>> {
+        let mut pollfd = libc::pollfd {
+            fd: self.listener.as_raw_fd(),
+            events: libc::POLLIN,
+            revents: 0,
+        };
+        let timeout_ms = timeout.as_millis().min(libc::c_int::MAX as u128) as libc::c_int;
+        let ready = unsafe { libc::poll(&mut pollfd, 1, timeout_ms) };
+        if ready < 0 {
+            let error = io::Error::last_os_error();
+            if error.kind() == io::ErrorKind::Interrupted {
+                return Ok(None);
+            }
+            return Err(error);
+        }
+        if ready == 0 {
+            return Ok(None);
+        }
+
+        let (stream, _) = self.listener.accept()?;
+        ControlConnection::new(stream).map(Some)
+    }
+}
+
+impl Drop for ControlServer {
+    fn drop(&mut self) {
+        fs::remove_file(&self.path).ok();
+        log!(
+            Level::Debug,
+            "ControlServer: removed socket `{}'",
+            self.path.display()
+        );
+    }
+}
+
+/// A client connected to `ControlServer`.
+pub struct ControlConnection {
+    reader: BufReader<UnixStream>,
+    writer: UnixStream,
+}
+
+impl ControlConnection {
+    fn new(stream: UnixStream) -> io::Result<ControlConnection> {
+        stream.set_read_timeout(Some(IO_TIMEOUT))?;
+        stream.set_write_timeout(Some(IO_TIMEOUT))?;
+        let writer = stream.try_clone()?;
+        Ok(ControlConnection {
+            reader: BufReader::new(stream),
+            writer,
+        })
+    }
+
+    /// Reads the next request.
+    ///
+    /// Returns `None` when the client has no more requests. Malformed requests are answered with
+    /// an error right away, and skipped.
+    pub fn next_request(&mut self) -> Option<Request> {
+        loop {
+            let mut line = String::new();
+            match self.reader.read_line(&mut line) {
+                Ok(0) => return None,
+                Ok(_) => {}
+                Err(e) => {
+                    log!(
+                        Level::Debug,
+                        "ControlConnection: failed to read a request: {}",
+                        e
+                    );
+                    return None;
+                }
+            }
+
+            if line.trim().is_empty() {
+                continue;
+            }
+
+            match line.parse::<Request>() {
+                Ok(request) => {
+                    log!(Level::Debug, "ControlConnection: got request `{}'", request);
+                    return Some(request);
+                }
+                Err(message) => {
+                    if self.respond(&Response::Error(message)).is_err() {
+                        return None;
+                    }
+                }
+            }
+        }
+    }
+
+    pub fn respond(&mut self, response: &Response) -> io::Result<()> {
+        writeln!(self.writer, "{}", response)?;
+        self.writer.flush()
+    }
+}
+
+/// Sends `requests` to the instance listening at `path`, and returns its responses in the same
+/// order.
+pub fn send_requests(path: &Path, requests: &[Request]) -> io::Result<Vec<Response>> {
+    let mut stream = UnixStream::connect(path)?;
+    stream.set_read_timeout(Some(IO_TIMEOUT))?;
+    stream.set_write_timeout(Some(IO_TIMEOUT))?;
+
+    for request in requests {
+        writeln!(stream, "{}", request)?;
+    }
+    stream.flush()?;
+    stream.shutdown(std::net::Shutdown::Write)?;
+
+    let mut responses = Vec::with_capacity(requests.len());
+    for line in BufReader::new(stream).lines() {
+        let response = line?
+            .parse::<Response>()
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+        responses.push(response);
+    }
+
+    if responses.len() != requests.len() {
+        return Err(io::Error::new(
+            io::ErrorKind::UnexpectedEof,
+            "the instance closed the connection before answering all requests",
+        ));
+    }
+
+    Ok(responses)
+}
+
+/// Forwards `newsboat -x` commands to the instance listening at `path`.
+///
+/// Returns the messages from the responses, or an explanation why the commands couldn't be
+/// forwarded or failed.
+pub fn forward_commands(path: &Path, commands: &[String]) -> Result<Vec<String>, String> {
+    let mut requests = Vec::with_capacity(commands.len());
+    for command in commands {
+        match Request::from_command(command) {
+            Some(request) => requests.push(request),
+            None => {
+                return Err(fmt!(
+                    &gettext("%s: can't be sent to the running instance"),
+                    command
+                ))
+            }
+        }
+    }
+
+    let responses = send_requests(path, &requests).map_err(|e| {
+        fmt!(
+            &gettext("Failed to contact the running instance: %s"),
+            e.to_string()
+        )
+    })?;
+
+    let mut messages = Vec::with_capacity(responses.len());
+    for response in responses {
+        match response {
+            Response::Ok(message) => messages.push(message),
+            Response::Error(message) => return Err(message),
+        }
+    }
+    Ok(messages)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::thread;
+    use tempfile::TempDir;
+
+    #[test]
+    fn t_requests_round_trip_through_their_wire_format() {
+        let requests = vec![
+            Request::Reload(None),
+            Request::Reload(Some("https://example.com/feed.xml".to_string())),
+            Request::MarkRead(None),
+            Request::MarkRead(Some("https://example.com/feed.xml".to_string())),
+            Request::Quit,
+            Request::Status,
+        ];
+        for request in requests {
+            assert_eq!(request.to_string().parse::<Request>(), Ok(request));
+        }
+    }
+
+    #[test]
+    fn t_parsing_requests() {
+        assert_eq!("reload\n".parse::<Request>(), Ok(Request::Reload(None)));
+        assert_eq!("reload \n".parse::<Request>(), Ok(Request::Reload(None)));
+        assert_eq!(
+            "mark-read   http://example.com  ".parse::<Request>(),
+            Ok(Request::MarkRead(Some("http://example.com".to_string())))
+        );
+        assert!("quit now".parse::<Request>().is_err());
+        assert!("status please".parse::<Request>().is_err());
+        assert!("reboot".parse::<Request>().is_err());
+        assert!("".parse::<Request>().is_err());
+    }
+
+    #[test]
+    fn t_responses_round_trip_through_their_wire_format() {
+        let responses = vec![
+            Response::Ok(String::new()),
+            Response::Ok("5 unread articles".to_string()),
+            Response::Error("no such feed".to_string()),
+        ];
+        for response in responses {
+            assert_eq!(response.to_string().parse::<Response>(), Ok(response));
+        }
+
+        assert_eq!(
+            Response::Error("two\nlines".to_string()).to_string(),
+            "error two lines"
+        );
+        assert!("maybe".parse::<Response>().is_err());
+    }
+
+    #[test]
+    fn t_from_command_maps_commands_that_can_be_forwarded() {
+        assert_eq!(Request::from_command("reload"), Some(Request::Reload(None)));
+        assert_eq!(Request::from_command("print-unread"), Some(Request::Status));
+        assert_eq!(Request::from_command("quit"), None);
+    }
+
+    fn serve_one_client(server: ControlServer) -> thread::JoinHandle<Vec<Request>> {
+        thread::spawn(move || {
+            let mut connection = server
+                .accept(Duration::from_secs(30))
+                .unwrap()
+                .expect("client didn't connect");
+            let mut received = Vec::new();
+            while let Some(request) = connection.next_request() {
+                let response = match &request {
+                    Request::Status => Response::Ok("3 unread articles".to_string()),
+                    Request::Reload(Some(url)) => Response::Error(format!("no feed {}", url)),
+                    _ => Response::Ok(String::new()),
+                };
+                connection.respond(&response).unwrap();
+                received.push(request);
+            }
+            received
+        })
+    }
+
+    #[test]
+    fn t_client_gets_responses_to_its_requests_in_order() {
+        let tmp = TempDir::new().unwrap();
+        let path = tmp.path().join("cache.db.sock");
+        let server = ControlServer::bind(&path).unwrap();
+        let handle = serve_one_client(server);
+
+        let requests = vec![
+            Request::Status,
+            Request::Reload(Some("http://example.com".to_string())),
+            Request::Quit,
+        ];
+        let responses = send_requests(&path, &requests).unwrap();
+        assert_eq!(
+            responses,
+            vec![
+                Response::Ok("3 unread articles".to_string()),
+                Response::Error("no feed http://example.com".to_string()),
+                Response::Ok(String::new()),
+            ]
+        );
+        assert_eq!(handle.join().unwrap(), requests);
+    }
+
+    #[test]
+    fn t_server_answers_malformed_requests_with_an_error() {
+        let tmp = TempDir::new().unwrap();
+        let path = tmp.path().join("cache.db.sock");
+        let server = ControlServer::bind(&path).unwrap();
+        let handle = serve_one_client(server);
+
+        let mut stream = UnixStream::connect(&path).unwrap();
+        stream.write_all(b"frobnicate\nstatus\n").unwrap();
+        stream.shutdown(std::net::Shutdown::Write).unwrap();
+        let lines: Vec<String> = BufReader::new(stream)
+            .lines()
+            .map(|line| line.unwrap())
+            .collect();
+        assert_eq!(lines.len(), 2);
+        assert!(lines[0].starts_with("error "));
+        assert_eq!(lines[1], "ok 3 unread articles");
+
+        assert_eq!(handle.join().unwrap(), vec![Request::Status]);
+    }
+
+    #[test]
+    fn t_forward_commands_returns_messages_or_first_error() {
+        let tmp = TempDir::new().unwrap();
+        let path = tmp.path().join("cache.db.sock");
+
+        let server = ControlServer::bind(&path).unwrap();
+        let handle = serve_one_client(server);
+        let commands = vec!["reload".to_string(), "print-unread".to_string()];
+        assert_eq!(
+            forward_commands(&path, &commands),
+            Ok(vec![String::new(), "3 unread articles".to_string()])
+        );
+        handle.join().unwrap();
+
+        // Unknown commands are rejected before connecting, so no server is needed
+        let commands = vec!["reload".to_string(), "frobnicate".to_string()];
+        assert!(forward_commands(&path, &commands).is_err());
+    }
+
+    #[test]
+    fn t_forward_commands_fails_if_nobody_listens() {
+        let tmp = TempDir::new().unwrap();
+        let path = tmp.path().join("cache.db.sock");
+        assert!(forward_commands(&path, &["reload".to_string()]).is_err());
+    }
+
+    #[test]
+    fn t_accept_returns_none_if_nobody_connects_in_time() {
+        let tmp = TempDir::new().unwrap();
+        let path = tmp.path().join("cache.db.sock");
+        let server = ControlServer::bind(&path).unwrap();
+        assert!(server.accept(Duration::from_millis(10)).unwrap().is_none());
+    }
+
+    #[test]
+    fn t_bind_replaces_stale_socket_but_not_a_live_one() {
+        let tmp = TempDir::new().unwrap();
+        let path = tmp.path().join("cache.db.sock");
+
+        let server = ControlServer::bind(&path).unwrap();
+        let error = ControlServer::bind(&path).err().unwrap();
+        assert_eq!(error.kind(), io::ErrorKind::AddrInUse);
+        drop(server);
+        assert!(!path.exists());
+
+        // Simulate a socket left behind by a crashed instance
+        let listener = UnixListener::bind(&path).unwrap();
+        drop(listener);
+        assert!(path.exists());
+        assert!(ControlServer::bind(&path).is_ok());
+    }
+
+    #[test]
+    fn t_socket_is_only_accessible_by_its_owner_and_umask_is_restored() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let tmp = TempDir::new().unwrap();
+        let path = tmp.path().join("cache.db.sock");
+
+        let umask = unsafe { libc::umask(0o022) };
+        let server = ControlServer::bind(&path).unwrap();
+        let restored_umask = unsafe { libc::umask(umask) };
+
+        assert_eq!(restored_umask, 0o022);
+        let mode = fs::metadata(server.path()).unwrap().permissions().mode();
+        assert_eq!(mode & 0o777, 0o600);
+    }
+}
@@ -0,0 +1,502 @@
+//! Classifies the URLs from the urls file by where the feed comes from.
+//!
+//! Besides regular URLs, the urls file can contain "special" ones:
+//!
+//! - `query:Title:expression` is a query feed, which collects articles matching the filter
+//!   expression from all other feeds;
+//! - `filter:script:url` downloads the `url` and passes it through the `script`. Unlike the
+//!   feeds themselves, filters can download from anywhere curl can, e.g. `ftp://`;
+//! - `exec:command` runs the `command` and uses its output as the feed.
+//!
+//! `FeedSource` can be parsed from such a URL, and turned back into it with `to_string()`.
+
+use gettextrs::gettext;
+use std::fmt;
+use std::str::FromStr;
+use strprintf::fmt;
+use url::Url;
+
+const HTTP_PREFIX: &str = "http://";
+const HTTPS_PREFIX: &str = "https://";
+const FILE_PREFIX: &str = "file://";
+const QUERY_PREFIX: &str = "query:";
+const FILTER_PREFIX: &str = "filter:";
+const EXEC_PREFIX: &str = "exec:";
+
+/// Where a feed comes from, as described by its URL in the urls file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FeedSource {
+    /// `http://` or `https://` URL, downloaded as is.
+    Http { url: String },
+
+    /// `file://` URL. `path` is everything after the `file://` prefix.
+    File { path: String },
+
+    /// URL with some other scheme, e.g. `ftp://example.com/feed.xml`. Only filters download
+    /// these, so on its own such URL is reported as `FeedSourceError::UnsupportedUrl`.
+    Remote { url: String },
+
+    /// Query feed, e.g. `query:Unread:unread = "yes"`.
+    ///
+    /// \note The parser does not check if the expression is valid.
+    Query { title: String, expression: String },
+
+    /// Feed downloaded from `inner` and passed through `script`, e.g.
+    /// `filter:~/bin/script.sh:https://example.com/feed.xml`. `inner` is `Http`, `File` or `Remote`.
+    Filter {
+        script: String,
+        inner: Box<FeedSource>,
+    },
+
+    /// Output of a command, e.g. `exec:~/bin/make-feed.sh`.
+    Exec { command: String },
+}
+
+impl FeedSource {
+    /// True for the sources that are not plain URLs: queries, filters and commands.
+    pub fn is_special(&self) -> bool {
+        match self {
+            FeedSource::Http { .. } | FeedSource::File { .. } | FeedSource::Remote { .. } => false,
+            FeedSource::Query { .. } | FeedSource::Filter { .. } | FeedSource::Exec { .. } => true,
+        }
+    }
+}
+
+/// Reasons why a URL from the urls file couldn't be turned into a `FeedSource`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FeedSourceError {
+    /// The URL doesn't start with any of the supported prefixes.
+    UnsupportedUrl { url: String },
+
+    /// `http://` or `https://` URL that the `url` crate couldn't parse.
+    InvalidUrl { url: String, reason: String },
+
+    /// `file://` URL without a path.
+    FileWithoutPath,
+
+    /// There's no title between `query:` and the next colon.
+    QueryWithoutTitle { url: String },
+
+    /// There's no filter expression after the title.
+    QueryWithoutExpression { url: String },
+
+    /// There's no script between `filter:` and the next colon.
+    FilterWithoutScript { url: String },
+
+    /// There's no URL after the script.
+    FilterWithoutUrl { url: String },
+
+    /// The URL after the script is not something that filters can download, e.g. it's another
+    /// special URL, or it has no host.
+    UnsupportedFilterUrl { url: String },
+
+    /// There's nothing after `exec:`.
+    ExecWithoutCommand,
+}
+
+impl fmt::Display for FeedSourceError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let message = match self {
+            FeedSourceError::UnsupportedUrl { url } => fmt!(
+                &gettext("`%s' is not a supported URL (expected http://, https://, file://, query:, filter: or exec:)"),
+                url.as_str()
+            ),
+            FeedSourceError::InvalidUrl { url, reason } => fmt!(
+                &gettext("`%s' is not a valid URL: %s"),
+                url.as_str(),
+                reason.as_str()
+            ),
+            FeedSourceError::FileWithoutPath => gettext("file:// URL has no path"),
+            FeedSourceError::QueryWithoutTitle { url } => {
+                fmt!(&gettext("query `%s' has no title"), url.as_str())
+            }
+            FeedSourceError::QueryWithoutExpression { url } => {
+                fmt!(&gettext("query `%s' has no filter expression"), url.as_str())
+            }
+            FeedSourceError::FilterWithoutScript { url } => {
+                fmt!(&gettext("filter `%s' has no script"), url.as_str())
+            }
+            FeedSourceError::FilterWithoutUrl { url } => {
+                fmt!(&gettext("filter `%s' has no URL to download"), url.as_str())
+            }
+            FeedSourceError::UnsupportedFilterUrl { url } => fmt!(
+                &gettext("filters can only download file:// URLs and URLs with a host, not `%s'"),
+                url.as_str()
+            ),
+            FeedSourceError::ExecWithoutCommand => gettext("exec: URL has no command"),
+        };
+        write!(f, "{}", message)
+    }
+}
+
+impl FromStr for FeedSource {
+    type Err = FeedSourceError;
+
+    fn from_str(url: &str) -> Result<FeedSource, FeedSourceError> {
+        if url.starts_with(HTTP_PREFIX) || url.starts_with(HTTPS_PREFIX) {
+            match Url::parse(url) {
+                Ok(_) => Ok(FeedSource::Http {
+                    url: url.to_owned(),
+                }),
+                Err(e) => Err(FeedSourceError::InvalidUrl {
+                    url: url.to_owned(),
+                    reason: e.to_string(),
+                }),
+            }
+        } else if let Some(path) = url.strip_prefix(FILE_PREFIX) {
+            if path.is_empty() {
+                return Err(FeedSourceError::FileWithoutPath);
+            }
+            Ok(FeedSource::File {
+                path: path.to_owned(),
+            })
+        } else if let Some(rest) = url.strip_prefix(QUERY_PREFIX) {
+            parse_query(url, rest)
+        } else if let Some(rest) = url.strip_prefix(FILTER_PREFIX) {
+            parse_filter(url, rest)
+        } else if let Some(command) = url.strip_prefix(EXEC_PREFIX) {
+            if command.trim().is_empty() {
+                return Err(FeedSourceError::ExecWithoutCommand);
+            }
+            Ok(FeedSource::Exec {
+                command: command.to_owned(),
+            })
+        } else {
+            Err(FeedSourceError::UnsupportedUrl {
+                url: url.to_owned(),
+            })
+        }
+    }
+}
+
+impl fmt::Display for FeedSource {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            FeedSource::Http { url } => write!(f, "{}", url),
+            FeedSource::File { path } => write!(f, "{}{}", FILE_PREFIX, path),
+            FeedSource::Remote { url } => write!(f, "{}", url),
+            FeedSource::Query { title, expression } => {
+                write!(f, "{}{}:{}", QUERY_PREFIX, title, expression)
+            }
+            FeedSource::Filter { script, inner } => {
+                write!(f, "{}{}:{}", FILTER_PREFIX, script, inner)
+            }
+            FeedSource::Exec { command } => write!(f, "{}{}", EXEC_PREFIX, command),
+        }
+    }
+}
+
+/// Parses `rest` of a query `url`, i.e. everything after `query:`. The title ends at the first
+/// colon; the expression might contain colons, e.g. in `age between 0:7`.
+fn parse_query(url: &str, rest: &str) -> Result<FeedSource, FeedSourceError> {
+    let (title, expression) = match rest.find(':') {
+        Some(colon) => (&rest[..colon], &rest[colon + 1..]),
+        None => (rest, ""),
+    };
+    if title.trim().is_empty() {
+        return Err(FeedSourceError::QueryWithoutTitle {
+            url: url.to_owned(),
+        });
+    }
+    if expression.trim().is_empty() {
+        return Err(FeedSourceError::QueryWithoutExpression {
+            url: url.to_owned(),
+        });
+    }
+    Ok(FeedSource::Query {
+        title: title.to_owned(),
+        expression: expression.to_owned(),
+    })
+}
+
+/// Parses `rest` of a filter `url`, i.e. everything after `filter:`. The script ends at the first
+/// colon; the URL it downloads usually contains colons.
+///
+/// The URL is passed to curl, so anything with a scheme and a host is accepted, not just
+/// `http://` and `https://`. Whether curl actually supports the scheme is only found out when
+/// the feed is reloaded.
+fn parse_filter(url: &str, rest: &str) -> Result<FeedSource, FeedSourceError> {
+    let (script, inner) = match rest.find(':') {
+        Some(colon) => (&rest[..colon], &rest[colon + 1..]),
+        None => (rest, ""),
+    };
+    if script.trim().is_empty() {
+        return Err(FeedSourceError::FilterWithoutScript {
+            url: url.to_owned(),
+        });
+    }
+    if inner.is_empty() {
+        return Err(FeedSourceError::FilterWithoutUrl {
+            url: url.to_owned(),
+        });
+    }
+    let inner = match inner.parse::<FeedSource>() {
+        Ok(inner @ FeedSource::Http { .. }) | Ok(inner @ FeedSource::File { .. }) => inner,
+        Err(FeedSourceError::UnsupportedUrl { .. }) if has_host(inner) => FeedSource::Remote {
+            url: inner.to_owned(),
+        },
+        Ok(_) | Err(FeedSourceError::UnsupportedUrl { .. }) => {
+            return Err(FeedSourceError::UnsupportedFilterUrl {
+                url: inner.to_owned(),
+            })
+        }
+        Err(e) => return Err(e),
+    };
+    Ok(FeedSource::Filter {
+        script: script.to_owned(),
+        inner: Box::new(inner),
+    })
+}
+
+fn has_host(url: &str) -> bool {
+    Url::parse(url).map(|url| url.has_host()).unwrap_or(false)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proptest::prelude::*;
+
+    fn parse(url: &str) -> Result<FeedSource, FeedSourceError> {
+        url.parse::<FeedSource>()
+    }
+
+    #[test]
+    fn t_parses_http_and_https_urls() {
+        assert_eq!(
+            parse("https://example.com/feed.xml"),
+            Ok(FeedSource::Http {
+                url: "https://example.com/feed.xml".to_owned()
+            })
+        );
+        assert_eq!(
+            parse("http://example.com"),
+            Ok(FeedSource::Http {
+                url: "http://example.com".to_owned()
+            })
+        );
+    }
+
+    #[test]
+    fn t_reports_invalid_http_urls() {
+        match parse("http://") {
+            Err(FeedSourceError::InvalidUrl { url, .. }) => assert_eq!(url, "http://"),
+            other => panic!("unexpected result: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn t_parses_file_urls() {
+        assert_eq!(
+            parse("file:///home/user/feed.xml"),
+            Ok(FeedSource::File {
+                path: "/home/user/feed.xml".to_owned()
+            })
+        );
+        assert_eq!(parse("file://"), Err(FeedSourceError::FileWithoutPath));
+    }
+
+    #[test]
+    fn t_parses_queries_whose_expressions_contain_colons() {
+        assert_eq!(
+            parse(r#"query:Recent:unread = "yes" and age between 0:7"#),
+            Ok(FeedSource::Query {
+                title: "Recent".to_owned(),
+                expression: r#"unread = "yes" and age between 0:7"#.to_owned()
+            })
+        );
+    }
+
+    #[test]
+    fn t_reports_queries_without_title_or_expression() {
+        let error = |url: &str| FeedSourceError::QueryWithoutTitle {
+            url: url.to_owned(),
+        };
+        assert_eq!(parse("query:"), Err(error("query:")));
+        assert_eq!(
+            parse(r#"query::unread = "yes""#),
+            Err(error(r#"query::unread = "yes""#))
+        );
+
+        let error = |url: &str| FeedSourceError::QueryWithoutExpression {
+            url: url.to_owned(),
+        };
+        assert_eq!(parse("query:Title"), Err(error("query:Title")));
+        assert_eq!(parse("query:Title: "), Err(error("query:Title: ")));
+    }
+
+    #[test]
+    fn t_parses_filters() {
+        assert_eq!(
+            parse("filter:~/bin/script.sh:https://example.com/feed.xml"),
+            Ok(FeedSource::Filter {
+                script: "~/bin/script.sh".to_owned(),
+                inner: Box::new(FeedSource::Http {
+                    url: "https://example.com/feed.xml".to_owned()
+                })
+            })
+        );
+        assert_eq!(
+            parse("filter:script.sh:file:///tmp/feed.xml"),
+            Ok(FeedSource::Filter {
+                script: "script.sh".to_owned(),
+                inner: Box::new(FeedSource::File {
+                    path: "/tmp/feed.xml".to_owned()
+                })
+            })
+        );
+    }
+
+    #[test]
+    fn t_parses_filters_that_download_from_other_schemes() {
+        assert_eq!(
+            parse("filter:script.sh:ftp://example.com/feed.xml"),
+            Ok(FeedSource::Filter {
+                script: "script.sh".to_owned(),
+                inner: Box::new(FeedSource::Remote {
+                    url: "ftp://example.com/feed.xml".to_owned()
+                })
+            })
+        );
+        assert_eq!(
+            parse("filter:script.sh:HTTP://example.com"),
+            Ok(FeedSource::Filter {
+                script: "script.sh".to_owned(),
+                inner: Box::new(FeedSource::Remote {
+                    url: "HTTP://example.com".to_owned()
+                })
+            })
+        );
+    }
+
+    #[test]
+    fn t_reports_malformed_filters() {
+        // `extract_filter()` used to treat this as a script named "https" downloading
+        // "//example.com"
+        assert_eq!(
+            parse("filter:https://example.com"),
+            Err(FeedSourceError::UnsupportedFilterUrl {
+                url: "//example.com".to_owned()
+            })
+        );
+        assert_eq!(
+            parse("filter::https://example.com"),
+            Err(FeedSourceError::FilterWithoutScript {
+                url: "filter::https://example.com".to_owned()
+            })
+        );
+        assert_eq!(
+            parse("filter:script.sh"),
+            Err(FeedSourceError::FilterWithoutUrl {
+                url: "filter:script.sh".to_owned()
+            })
+        );
+        assert_eq!(
+            parse("filter:script.sh:exec:ls"),
+            Err(FeedSourceError::UnsupportedFilterUrl {
+                url: "exec:ls".to_owned()
+            })
+        );
+        assert_eq!(
+            parse("filter:script.sh:mailto:user@example.com"),
+            Err(FeedSourceError::UnsupportedFilterUrl {
+                url: "mailto:user@example.com".to_owned()
+            })
+        );
+        assert_eq!(
+            parse("filter:script.sh:file://"),
+            Err(FeedSourceError::FileWithoutPath)
+        );
+    }
+
+    #[test]
+    fn t_parses_exec_urls() {
+        assert_eq!(
+            parse("exec:~/bin/feed.sh --all"),
+            Ok(FeedSource::Exec {
+                command: "~/bin/feed.sh --all".to_owned()
+            })
+        );
+        assert_eq!(parse("exec:"), Err(FeedSourceError::ExecWithoutCommand));
+        assert_eq!(parse("exec:  "), Err(FeedSourceError::ExecWithoutCommand));
+    }
+
+    #[test]
+    fn t_reports_unsupported_urls() {
+        for url in &["", "ftp://example.com", "example.com", "HTTP://example.com"] {
+            assert_eq!(
+                parse(url),
+                Err(FeedSourceError::UnsupportedUrl {
+                    url: url.to_string()
+                })
+            );
+        }
+    }
+
+    #[test]
+    fn t_is_special_is_true_for_queries_filters_and_commands() {
+        assert!(!parse("https://example.com").unwrap().is_special());
+        assert!(!parse("file:///feed.xml").unwrap().is_special());
+        assert!(!FeedSource::Remote {
+            url: "ftp://example.com".to_owned()
+        }
+        .is_special());
+        assert!(parse("query:a:b").unwrap().is_special());
+        assert!(parse("filter:a:https://example.com").unwrap().is_special());
+        assert!(parse("exec:a").unwrap().is_special());
+    }
+
+    fn http_source() -> impl Strategy<Value = FeedSource> {
+        "https?://[a-z]{1,10}(\\.[a-z]{1,5})?(/[a-z0-9_.-]{0,8}){0,3}(\\?[a-z]=[a-z0-9]{0,5})?"
+            .prop_map(|url| FeedSource::Http { url })
+    }
+
+    fn file_source() -> impl Strategy<Value = FeedSource> {
+        "\\PC+".prop_map(|path| FeedSource::File { path })
+    }
+
+    fn remote_source() -> impl Strategy<Value = FeedSource> {
+        "(ftps?|sftp|gopher)://[a-z]{1,10}(\\.[a-z]{1,5})?(/[a-z0-9_.-]{0,8}){0,3}"
+            .prop_map(|url| FeedSource::Remote { url })
+    }
+
+    fn feed_source() -> impl Strategy<Value = FeedSource> {
+        let filter = (
+            "[^:]*[^:\\s][^:]*",
+            prop_oneof![http_source(), file_source(), remote_source()],
+        )
+            .prop_map(|(script, inner)| FeedSource::Filter {
+                script,
+                inner: Box::new(inner),
+            });
+        prop_oneof![
+            http_source(),
+            file_source(),
+            ("[^:]*[^:\\s][^:]*", "\\PC*\\S\\PC*")
+                .prop_map(|(title, expression)| FeedSource::Query { title, expression }),
+            filter,
+            "\\PC*\\S\\PC*".prop_map(|command| FeedSource::Exec { command }),
+        ]
+    }
+
+    proptest::proptest! {
+        #[test]
+        fn does_not_crash_on_any_input(ref input in "\\PC*") {
+            // Result explicitly ignored because we just want to make sure this call doesn't panic.
+            let _ = parse(input);
+        }
+
+        #[test]
+        fn round_trips_through_the_urls_file_representation(source in feed_source()) {
+            let url = source.to_string();
+            assert_eq!(parse(&url), Ok(source));
+        }
+
+        #[test]
+        fn parsed_sources_print_as_the_original_url(ref input in "(https?://|file://|query:|filter:|exec:)\\PC*") {
+            if let Ok(source) = parse(input) {
+                assert_eq!(&source.to_string(), input);
+            }
+        }
+    }
+}
@@ -11,7 +11,7 @@ use nom::{
     IResult, Offset, Parser,
 };
 use std::vec::Vec;
-use strprintf::fmt;
+use strprintf::{fmt, PRIu64};
 
 /// Operators that can be used in comparisons.
 #[derive(Debug, Clone, PartialEq)]
@@ -350,18 +350,21 @@ pub fn parse(expr: &str) -> Result<Expression, String> {
     match internal_parse(expr) {
         Ok(expression) => Ok(expression),
         Err(error) => {
+            // The translated messages contain "%{}" where the specifier for a u64 should go.
+            let with_u64_specifier =
+                |message: String| message.replace("%{}", &format!("%{}", PRIu64));
             let err = match error {
                 Error::TrailingCharacters(pos, tail) => fmt!(
                     // The "%{}" thing is a number, a zero-based offset into a string.
-                    &gettext("Parse error: trailing characters after position %{}: %s"),
-                    PRIu64,
+                    &with_u64_specifier(gettext(
+                        "Parse error: trailing characters after position %{}: %s"
+                    )),
                     pos as u64,
                     tail
                 ),
                 Error::AtPos(pos, expected) => fmt!(
                     // The "%{}" thing is a number, a zero-based offset into a string.
-                    &gettext("Parse error at position %{}: expected %s"),
-                    PRIu64,
+                    &with_u64_specifier(gettext("Parse error at position %{}: expected %s")),
                     pos as u64,
                     &translate_expected(expected)
                 ),
@@ -376,6 +379,17 @@ pub fn parse(expr: &str) -> Result<Expression, String> {
 mod tests {
     use super::{Expression::*, *};
 
+    #[test]
+    fn t_parse_explains_errors() {
+        assert_eq!(
+            parse("title ="),
+            Err(
+                "Parse error at position 7: expected one of: quoted string, range, number"
+                    .to_string()
+            )
+        );
+    }
+
     #[test]
     fn t_error_on_invalid_queries() {
         // Invalid character in operator
@@ -8,6 +8,12 @@ use limited_string::LimitedString;
 use parser::{parse, Padding, Specifier};
 use std::collections::BTreeMap;
 
+/// Checks that `format` is a well-formed format string. Returns a description of the first
+/// problem found, e.g. an incomplete specifier at the end or a conditional that isn't closed.
+pub fn validate(format: &str) -> Result<(), String> {
+    parser::check(format)
+}
+
 /// Produces strings of values in a specified format, strftime(3)-like.
 ///
 /// Newsboat lets users customize its appearance using "format strings". These strings consist of
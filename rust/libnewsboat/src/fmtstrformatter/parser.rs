@@ -1,9 +1,11 @@
+use gettextrs::gettext;
 use nom::branch::alt;
 use nom::bytes::complete::{tag, take, take_till1, take_while};
 use nom::multi::many0;
 use nom::IResult;
 use std::cmp::Ordering;
 use std::str;
+use strprintf::fmt;
 
 /// Describes how formats should be padded: on the left, on the right, or not at all.
 #[derive(PartialEq, Eq, Debug)]
@@ -166,6 +168,55 @@ pub fn parse(input: &str) -> Vec<Specifier> {
     }
 }
 
+/// Describes the first problem with the format string, if any. `parse()` silently drops or
+/// misinterprets malformed specifiers, so this is the only way to notice them.
+pub fn check(input: &str) -> Result<(), String> {
+    fn check_specifiers(specifiers: &[Specifier]) -> Result<(), String> {
+        for specifier in specifiers {
+            match *specifier {
+                // `conditional` failed to match, and `padded_format` picked up the `%?` instead
+                Specifier::Format('?', _) => {
+                    return Err(gettext(
+                        "conditional format isn't complete (expected `%?x?then&else?')",
+                    ))
+                }
+                // `spacing` or `center_format` ran out of input, and `padded_format` picked up
+                // the rest instead
+                Specifier::Format(c, _) if c == '>' || c == '=' => {
+                    return Err(fmt!(
+                        &gettext("format ends with an incomplete specifier `%s'"),
+                        format!("%{}", c)
+                    ))
+                }
+                Specifier::Format(c, _) if !c.is_ascii() => {
+                    return Err(fmt!(
+                        &gettext("`%s' is not a valid format specifier"),
+                        format!("%{}", c)
+                    ))
+                }
+                Specifier::Conditional(_, ref then, ref els) => {
+                    check_specifiers(then)?;
+                    if let Some(els) = els {
+                        check_specifiers(els)?;
+                    }
+                }
+                _ => {}
+            }
+        }
+        Ok(())
+    }
+
+    let (leftovers, ast) = parser(input).map_err(|e| e.to_string())?;
+    check_specifiers(&ast)?;
+    if !leftovers.is_empty() {
+        return Err(fmt!(
+            &gettext("format ends with an incomplete specifier `%s'"),
+            leftovers
+        ));
+    }
+    Ok(())
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -329,4 +380,31 @@ mod tests {
         let expected = vec![Specifier::Conditional('x', vec![], Some(vec![]))];
         assert_eq!(result, expected);
     }
+
+    #[test]
+    fn t_check_accepts_well_formed_formats() {
+        assert!(check("").is_ok());
+        assert!(check("%4i %f %D %6L %?T?;%-17T; &?%t").is_ok());
+        assert!(check("%N %V - %u unread %>- %=10t 100%%").is_ok());
+    }
+
+    #[test]
+    fn t_check_rejects_incomplete_specifiers_at_the_end() {
+        assert!(check("%t %").is_err());
+        assert!(check("%t %-").is_err());
+        assert!(check("%t %>").is_err());
+        assert!(check("%t %=").is_err());
+    }
+
+    #[test]
+    fn t_check_rejects_unclosed_conditionals() {
+        assert!(check("%?T?%T").is_err());
+        assert!(check("%?T?%T&%t").is_err());
+        assert!(check("%?T").is_err());
+    }
+
+    #[test]
+    fn t_check_rejects_non_ascii_specifiers() {
+        assert!(check("%t %ж").is_err());
+    }
 }
@@ -1,15 +1,53 @@
 use crate::logger::{self, Level};
 use gettextrs::gettext;
+use std::ffi::CStr;
 use std::fs::{self, File, OpenOptions};
-use std::io::{Error, Read, Write};
-use std::os::unix::fs::OpenOptionsExt;
+use std::io::{self, Error, Read, Seek, SeekFrom, Write};
+use std::mem;
+use std::os::unix::fs::{MetadataExt, OpenOptionsExt};
 use std::os::unix::io::AsRawFd;
 use std::path::{Path, PathBuf};
 use std::process;
+use std::thread;
+use std::time::{Duration, Instant};
 
 use strprintf::fmt;
 
-fn remove_lock(lock_path: &Path) {
+/// Removes the lock file at `lock_path`, which we locked through `file`.
+///
+/// Everything is done through `file` while it still holds the lock: closing any other descriptor
+/// of the lock file would release our `lockf()` lock, letting another process take the lock and
+/// then lose its file to us.
+fn remove_lock(lock_path: &Path, file: &mut File) {
+    // Lock files are only replaced by their owners, but don't remove a file that isn't ours
+    // just in case.
+    if !is_same_file(lock_path, file) {
+        log!(
+            Level::Debug,
+            "FsLock: lockfile {} was replaced, leaving it alone",
+            lock_path.display()
+        );
+        return;
+    }
+
+    let mut contents = String::new();
+    let owner = file
+        .seek(SeekFrom::Start(0))
+        .and_then(|_| file.read_to_string(&mut contents))
+        .ok()
+        .and_then(|_| LockOwner::parse(&contents));
+    if let Some(owner) = owner {
+        if owner != LockOwner::current() {
+            log!(
+                Level::Debug,
+                "FsLock: lockfile {} is now owned by {:?}, leaving it alone",
+                lock_path.display(),
+                owner
+            );
+            return;
+        }
+    }
+
     fs::remove_file(lock_path).ok();
     log!(
         Level::Debug,
@@ -18,22 +56,284 @@ fn remove_lock(lock_path: &Path) {
     );
 }
 
+/// Returns `true` if `path` refers to the same file as `file`, i.e. the file wasn't removed or
+/// replaced since it was opened.
+fn is_same_file(path: &Path, file: &File) -> bool {
+    match (fs::metadata(path), file.metadata()) {
+        (Ok(on_disk), Ok(opened)) => on_disk.dev() == opened.dev() && on_disk.ino() == opened.ino(),
+        _ => false,
+    }
+}
+
+/// Returns the name of the host we're running on, or an empty string if it can't be determined.
+pub fn hostname() -> String {
+    let mut buf = [0 as libc::c_char; 256];
+    if unsafe { libc::gethostname(buf.as_mut_ptr(), buf.len()) } != 0 {
+        return String::new();
+    }
+    // POSIX doesn't guarantee null termination if the name was truncated
+    buf[buf.len() - 1] = 0;
+    unsafe { CStr::from_ptr(buf.as_ptr()) }
+        .to_string_lossy()
+        .into_owned()
+}
+
+/// Returns the time the process was started, in clock ticks since the system booted.
+///
+/// The value is only good for telling processes with the same PID apart: it's the raw
+/// `starttime` field of _/proc/<pid>/stat_, which, unlike the wall-clock time it could be
+/// converted to, doesn't change when the system clock is set.
+///
+/// Only implemented on Linux, where it's read from procfs; returns `None` everywhere else, or if
+/// the process doesn't exist.
+pub fn process_start_time(pid: libc::pid_t) -> Option<u64> {
+    if cfg!(target_os = "linux") {
+        let stat = fs::read_to_string(format!("/proc/{}/stat", pid)).ok()?;
+        // The second field is the executable name in parentheses, and it can contain spaces and
+        // parentheses itself. Thus we skip to the last closing paren; the field after it is the
+        // third one.
+        let fields = &stat[stat.rfind(')')? + 1..];
+        fields.split_whitespace().nth(19)?.parse().ok()
+    } else {
+        None
+    }
+}
+
+/// Whether the process that holds a lock is still there.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LockOwnerStatus {
+    /// The process is running on this host.
+    Alive,
+    /// The process is gone, or its PID now belongs to a different process.
+    Stale,
+    /// The lock was taken on another host, so we can't check.
+    Unknown,
+}
+
+impl LockOwnerStatus {
+    /// Human-readable description, to be shown after the owner's PID and hostname.
+    fn describe(self) -> String {
+        match self {
+            LockOwnerStatus::Alive => gettext("which is still running"),
+            LockOwnerStatus::Stale => gettext("which is no longer running"),
+            LockOwnerStatus::Unknown => gettext("which can't be checked from this host"),
+        }
+    }
+}
+
+/// Information about the process holding a lock, as recorded in the lock file.
+///
+/// The lock file contains the PID, the hostname and the process start time on separate lines.
+/// Lock files written by older versions of Newsboat contain nothing but the PID.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LockOwner {
+    pub pid: libc::pid_t,
+    /// Empty if the lock file doesn't say.
+    pub hostname: String,
+    /// Clock ticks since boot; see `process_start_time()`.
+    pub start_time: Option<u64>,
+}
+
+impl LockOwner {
+    /// Describes the current process.
+    pub fn current() -> LockOwner {
+        let pid = process::id() as libc::pid_t;
+        LockOwner {
+            pid,
+            hostname: hostname(),
+            start_time: process_start_time(pid),
+        }
+    }
+
+    /// Reads the lock file at `path`.
+    ///
+    /// Returns `None` if the file can't be read or doesn't contain a PID.
+    ///
+    /// \note Don't call this in the process that holds the lock: closing the file releases all of
+    /// the process's `lockf()` locks on it.
+    pub fn read(path: &Path) -> Option<LockOwner> {
+        LockOwner::parse(&fs::read_to_string(path).ok()?)
+    }
+
+    fn parse(contents: &str) -> Option<LockOwner> {
+        let mut lines = contents.lines();
+        let pid = lines.next()?.trim().parse().ok()?;
+        let hostname = lines.next().unwrap_or("").trim().to_owned();
+        let start_time = lines.next().and_then(|line| line.trim().parse().ok());
+        Some(LockOwner {
+            pid,
+            hostname,
+            start_time,
+        })
+    }
+
+    fn serialize(&self) -> String {
+        let mut result = format!("{}\n{}\n", self.pid, self.hostname);
+        if let Some(start_time) = self.start_time {
+            result.push_str(&format!("{}\n", start_time));
+        }
+        result
+    }
+
+    /// Describes the owner and its status for the user, e.g. "process with PID 42 on host
+    /// `laptop', which is still running".
+    pub fn describe(&self) -> String {
+        let status = self.status().describe();
+        if self.hostname.is_empty() {
+            fmt!(
+                &gettext("process with PID %s, %s"),
+                self.pid.to_string(),
+                &status
+            )
+        } else {
+            fmt!(
+                &gettext("process with PID %s on host `%s', %s"),
+                self.pid.to_string(),
+                &self.hostname,
+                &status
+            )
+        }
+    }
+
+    /// Checks if the owner is still running.
+    ///
+    /// A lock without a hostname is assumed to come from this host.
+    pub fn status(&self) -> LockOwnerStatus {
+        if !self.hostname.is_empty() && self.hostname != hostname() {
+            return LockOwnerStatus::Unknown;
+        }
+
+        if self.pid <= 0 {
+            return LockOwnerStatus::Unknown;
+        }
+
+        let exists = unsafe { libc::kill(self.pid, 0) } == 0
+            || Error::last_os_error().raw_os_error() == Some(libc::EPERM);
+        if !exists {
+            return LockOwnerStatus::Stale;
+        }
+
+        match (self.start_time, process_start_time(self.pid)) {
+            (Some(recorded), Some(actual)) if recorded != actual => LockOwnerStatus::Stale,
+            _ => LockOwnerStatus::Alive,
+        }
+    }
+}
+
+/// The longest pause between attempts in `FsLock::lock_with_timeout()`.
+const MAX_RETRY_DELAY: Duration = Duration::from_secs(1);
+
+enum LockError {
+    /// The lock is held by another process.
+    Contended(String),
+    /// Locking is not possible at all, e.g. because the lock file can't be opened.
+    Failed(String),
+}
+
+impl LockError {
+    fn into_message(self) -> String {
+        match self {
+            LockError::Contended(message) | LockError::Failed(message) => message,
+        }
+    }
+}
+
+/// Suffix appended to the lock file path to get the path of the file that read-only instances
+/// lock.
+const READERS_LOCK_SUFFIX: &str = ".readers";
+
+/// Returns the path of the file that read-only instances take shared locks on.
+///
+/// The exclusive lock file can't double as this one: it's removed when the main instance exits,
+/// while read-only instances may keep running.
+pub fn readers_lock_path(lock_path: &Path) -> PathBuf {
+    let mut path = lock_path.as_os_str().to_owned();
+    path.push(READERS_LOCK_SUFFIX);
+    PathBuf::from(path)
+}
+
+/// Places (or, with `libc::F_GETLK`, queries) an `fcntl()` lock of type `lock_type` over the whole
+/// file.
+fn fcntl_lock(file: &File, cmd: libc::c_int, lock_type: libc::c_int) -> io::Result<libc::flock> {
+    let mut lock: libc::flock = unsafe { mem::zeroed() };
+    lock.l_type = lock_type as libc::c_short;
+    lock.l_whence = libc::SEEK_SET as libc::c_short;
+    lock.l_start = 0;
+    lock.l_len = 0;
+    if unsafe { libc::fcntl(file.as_raw_fd(), cmd, &mut lock) } == 0 {
+        Ok(lock)
+    } else {
+        Err(Error::last_os_error())
+    }
+}
+
 #[derive(Default)]
 pub struct FsLock {
     lock_path: PathBuf,
     lock_file: Option<File>,
+    readers_lock_file: Option<File>,
 }
 
 impl Drop for FsLock {
     fn drop(&mut self) {
-        if self.lock_file.is_some() {
-            remove_lock(&self.lock_path);
+        if let Some(mut file) = self.lock_file.take() {
+            remove_lock(&self.lock_path, &mut file);
         }
     }
 }
 
 impl FsLock {
     pub fn try_lock(&mut self, new_lock_path: &Path, pid: &mut libc::pid_t) -> Result<(), String> {
+        self.try_lock_impl(new_lock_path, pid)
+            .map_err(LockError::into_message)
+    }
+
+    /// Like `try_lock()`, but if the lock is held by another process, keeps retrying until it's
+    /// released or `timeout` passes. The pause between attempts starts small and doubles after
+    /// each one, up to a second.
+    ///
+    /// Errors that retrying won't fix, like an unwritable lock file, are returned right away.
+    pub fn lock_with_timeout(
+        &mut self,
+        new_lock_path: &Path,
+        timeout: Duration,
+        pid: &mut libc::pid_t,
+    ) -> Result<(), String> {
+        let deadline = Instant::now() + timeout;
+        let mut delay = Duration::from_millis(50);
+        loop {
+            match self.try_lock_impl(new_lock_path, pid) {
+                Ok(()) => return Ok(()),
+                Err(LockError::Failed(message)) => return Err(message),
+                Err(LockError::Contended(message)) => {
+                    let now = Instant::now();
+                    if now >= deadline {
+                        log!(
+                            Level::Debug,
+                            "FsLock: gave up waiting for `{}'",
+                            new_lock_path.display()
+                        );
+                        return Err(message);
+                    }
+                    let pause = delay.min(deadline - now);
+                    log!(
+                        Level::Debug,
+                        "FsLock: `{}' is locked, retrying in {:?}",
+                        new_lock_path.display(),
+                        pause
+                    );
+                    thread::sleep(pause);
+                    delay = (delay * 2).min(MAX_RETRY_DELAY);
+                }
+            }
+        }
+    }
+
+    fn try_lock_impl(
+        &mut self,
+        new_lock_path: &Path,
+        pid: &mut libc::pid_t,
+    ) -> Result<(), LockError> {
         if self.lock_file.is_some() && self.lock_path == new_lock_path {
             return Ok(());
         }
@@ -53,69 +353,232 @@ impl FsLock {
         let mut file = match options.open(&new_lock_path) {
             Ok(file) => file,
             Err(reason) => {
-                return Err(fmt!(
+                return Err(LockError::Failed(fmt!(
                     &gettext("Failed to open lock file: '%s' (%s)"),
                     new_lock_path
                         .to_str()
                         .unwrap_or(&gettext("<filename containing invalid UTF-8 codepoint>")),
                     reason.to_string()
-                ))
+                )))
             }
         };
 
         // then we lock it (returns immediately if locking is not possible)
         if unsafe { libc::lockf(file.as_raw_fd(), libc::F_TLOCK, 0) } == 0 {
+            if !is_same_file(new_lock_path, &file) {
+                // The previous owner removed the file after we opened it, and then released the
+                // lock. Locking the removed file is pointless, so start over with a new one.
+                log!(
+                    Level::Debug,
+                    "FsLock: `{}' was removed while we were locking it, retrying",
+                    new_lock_path.display()
+                );
+                drop(file);
+                return self.try_lock_impl(new_lock_path, pid);
+            }
+
             log!(
                 Level::Debug,
                 "FsLock: locked `{}', writing PID...",
                 new_lock_path.display()
             );
-            let pid = process::id().to_string();
+            let owner = LockOwner::current().serialize();
             if let Err(reason) = file
                 .set_len(0)
-                .and_then(|_| file.write_all(&pid.as_bytes()))
+                .and_then(|_| file.write_all(&owner.as_bytes()))
             {
                 log!(Level::Debug, "FsLock: Failed to write PID");
-                return Err(fmt!(
+                return Err(LockError::Failed(fmt!(
                     &gettext("Failed to write PID to lock file: '%s' (%s)"),
                     new_lock_path
                         .to_str()
                         .unwrap_or(&gettext("<filename containing invalid UTF-8 codepoint>")),
                     reason.to_string()
-                ));
+                )));
             }
             log!(Level::Debug, "FsLock: PID written successfully");
-            if self.lock_file.take().is_some() {
-                remove_lock(&self.lock_path);
+            if let Some(mut old_file) = self.lock_file.take() {
+                remove_lock(&self.lock_path, &mut old_file);
             }
             self.lock_file = Some(file);
             self.lock_path = new_lock_path.to_owned();
             Ok(())
         } else {
+            let error = Error::last_os_error();
             log!(
                 Level::Error,
                 "FsLock: something went wrong during locking: {}",
-                Error::last_os_error()
+                error
+            );
+            let contended = matches!(
+                error.raw_os_error(),
+                Some(libc::EACCES) | Some(libc::EAGAIN)
             );
 
             // locking was not successful -> read PID of locking process from the file
             let mut buf = String::new();
-            if file.read_to_string(&mut buf).is_ok() {
-                *pid = buf.parse().unwrap_or(0);
-            }
-            log!(
-                Level::Debug,
-                "FsLock: locking failed, already locked by {}",
-                pid
-            );
-            Err(fmt!(
-                &gettext("Failed to lock '%s', already locked by process with PID %{}"),
+            let owner = file
+                .read_to_string(&mut buf)
+                .ok()
+                .and_then(|_| LockOwner::parse(&buf));
+            let owner_description = match owner {
+                Some(owner) => {
+                    *pid = owner.pid;
+                    log!(
+                        Level::Debug,
+                        "FsLock: locking failed, already locked by {:?} ({:?})",
+                        owner,
+                        owner.status()
+                    );
+                    owner.describe()
+                }
+                None => fmt!(&gettext("process with PID %s"), pid.to_string()),
+            };
+            let message = fmt!(
+                &gettext("Failed to lock '%s', already locked by %s"),
                 new_lock_path
                     .to_str()
                     .unwrap_or(&gettext("<filename containing invalid UTF-8 codepoint>")),
-                PRIi64,
-                *pid as i64
-            ))
+                &owner_description
+            );
+            if contended {
+                Err(LockError::Contended(message))
+            } else {
+                Err(LockError::Failed(message))
+            }
         }
     }
+
+    /// Takes a shared lock for a read-only instance.
+    ///
+    /// Any number of processes can hold the shared lock at the same time, and holding it doesn't
+    /// stop another process from taking the exclusive lock at `lock_path` with `try_lock()`. The
+    /// shared lock is placed on a separate file (see `readers_lock_path()`) which the main
+    /// instance can check with `has_readers()`, e.g. to postpone maintenance that would make
+    /// readers wait.
+    ///
+    /// `try_lock()` never looks at the readers lock file, so the main instance doesn't wait for
+    /// readers, nor they for it. Readers rely on SQLite's own locking to get a consistent view of
+    /// the cache while the main instance writes to it.
+    ///
+    /// The readers lock file is left in place when the lock is released, because another reader
+    /// might be about to lock it.
+    pub fn try_lock_shared(&mut self, lock_path: &Path) -> Result<(), String> {
+        let path = readers_lock_path(lock_path);
+        let path_str = path
+            .to_str()
+            .unwrap_or(&gettext("<filename containing invalid UTF-8 codepoint>"))
+            .to_owned();
+
+        log!(
+            Level::Debug,
+            "FsLock: trying to take a shared lock on `{}'",
+            path.display()
+        );
+
+        let mut options = OpenOptions::new();
+        options.create(true).read(true).write(true).mode(0o600);
+        let file = options.open(&path).map_err(|reason| {
+            fmt!(
+                &gettext("Failed to open lock file: '%s' (%s)"),
+                &path_str,
+                reason.to_string()
+            )
+        })?;
+
+        fcntl_lock(&file, libc::F_SETLK, libc::F_RDLCK).map_err(|reason| {
+            log!(
+                Level::Error,
+                "FsLock: failed to take a shared lock on `{}': {}",
+                path.display(),
+                reason
+            );
+            fmt!(
+                &gettext("Failed to take a shared lock on '%s' (%s)"),
+                &path_str,
+                reason.to_string()
+            )
+        })?;
+
+        log!(
+            Level::Debug,
+            "FsLock: took a shared lock on `{}'",
+            path.display()
+        );
+        self.readers_lock_file = Some(file);
+        Ok(())
+    }
+
+    /// Returns `true` if some other process holds a shared lock taken with `try_lock_shared()`
+    /// for `lock_path`.
+    pub fn has_readers(lock_path: &Path) -> bool {
+        let file = match File::open(readers_lock_path(lock_path)) {
+            Ok(file) => file,
+            Err(_) => return false,
+        };
+        match fcntl_lock(&file, libc::F_GETLK, libc::F_WRLCK) {
+            Ok(lock) => lock.l_type != libc::F_UNLCK as libc::c_short,
+            Err(_) => false,
+        }
+    }
+
+    /// Takes over the lock at `lock_path` if the process holding it is gone.
+    ///
+    /// This is meant for locks left behind on network filesystems, where the lock can outlive the
+    /// process until the server notices it's gone (e.g. if NFS lock recovery is slow). The lock
+    /// file is never removed; instead, locking it is retried until the stale lock is released or
+    /// `timeout` passes, like in `lock_with_timeout()`. Removing the file would let another
+    /// process create a new one and lock it at the same time as us.
+    ///
+    /// Locks held by live processes are never taken over. Locks taken on other hosts are only
+    /// taken over if `force` is true, i.e. the user made sure that the process is gone.
+    pub fn take_over_stale_lock(
+        &mut self,
+        lock_path: &Path,
+        timeout: Duration,
+        force: bool,
+    ) -> Result<(), String> {
+        let path_str = lock_path
+            .to_str()
+            .unwrap_or(&gettext("<filename containing invalid UTF-8 codepoint>"))
+            .to_owned();
+
+        let owner = match LockOwner::read(lock_path) {
+            Some(owner) => owner,
+            None => {
+                return Err(fmt!(
+                    &gettext("Can't take over lock '%s': unable to determine its owner"),
+                    &path_str
+                ))
+            }
+        };
+
+        match owner.status() {
+            LockOwnerStatus::Stale => {}
+            LockOwnerStatus::Unknown if force => {}
+            LockOwnerStatus::Alive => {
+                return Err(fmt!(
+                    &gettext("Can't take over lock '%s': process with PID %s is still running"),
+                    &path_str,
+                    owner.pid.to_string()
+                ))
+            }
+            LockOwnerStatus::Unknown => {
+                return Err(fmt!(
+                    &gettext("Can't take over lock '%s': it is held by a process on host '%s'"),
+                    &path_str,
+                    &owner.hostname
+                ))
+            }
+        }
+
+        log!(
+            Level::UserError,
+            "FsLock: taking over stale lock `{}' left by PID {} on host `{}'",
+            lock_path.display(),
+            owner.pid,
+            owner.hostname
+        );
+        self.lock_with_timeout(lock_path, timeout, &mut 0)
+    }
 }
@@ -1,73 +1,411 @@
-use std::fs::OpenOptions;
+use chrono::{DateTime, TimeZone, Utc};
+use std::collections::HashSet;
+use std::ffi::OsString;
+use std::fs::{self, File, OpenOptions};
 use std::io::{self, BufRead, BufReader, BufWriter, Write};
-use std::path::Path;
+use std::os::unix::fs::OpenOptionsExt;
+use std::os::unix::io::AsRawFd;
+use std::path::{Path, PathBuf};
+use std::process;
 
-#[derive(Default)]
+/// Which duplicates `History::add_line()` drops.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Deduplication {
+    /// Every line is stored, even if it was just added.
+    None,
+    /// A line is not stored if it's the same as the most recent one.
+    Consecutive,
+    /// Older copies of the line are removed, so each line is stored only once.
+    Global,
+}
+
+/// How `History::search()` matches the entries against the query.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SearchMode {
+    /// Entry starts with the query.
+    Prefix,
+    /// Entry contains the query anywhere.
+    Substring,
+}
+
+/// A single line of history.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HistoryEntry {
+    pub line: String,
+    /// When the line was entered. Entries loaded from files written by older versions of Newsboat
+    /// don't have a timestamp.
+    pub timestamp: Option<DateTime<Utc>>,
+}
+
+impl HistoryEntry {
+    pub fn new(line: String, timestamp: Option<DateTime<Utc>>) -> HistoryEntry {
+        HistoryEntry { line, timestamp }
+    }
+}
+
+/// Command-line or search history.
+///
+/// History files contain one entry per line, oldest first. By default, that's all there is to
+/// them, which is the format every version of Newsboat reads.
+///
+/// With `set_timestamps(true)`, files are saved in a format that also keeps timestamps. Its first
+/// line is `TIMESTAMPS_HEADER`, and an entry can be preceded by a line with a `#` followed by a
+/// Unix timestamp, e.g. `#1612345678`, like bash does when `HISTTIMEFORMAT` is set. Entries
+/// starting with `#` or `\` are written with an extra `\` in front, so they can't be mistaken for
+/// timestamps. Older versions of Newsboat read such files as plain ones, i.e. they show the
+/// header, the timestamps and the backslashes as parts of the history.
+///
+/// Both formats are read regardless of the setting.
 pub struct History {
     idx: usize,
-    lines: Vec<String>,
+    entries: Vec<HistoryEntry>,
+    deduplication: Deduplication,
+    max_lines: Option<usize>,
+    /// Entries added via `add_line()` since the last successful `save_to_file()`, oldest first.
+    unsaved: Vec<HistoryEntry>,
+    timestamps: bool,
 }
 
 impl History {
     pub fn new() -> History {
         History {
             idx: 0,
-            lines: Vec::new(),
+            entries: Vec::new(),
+            deduplication: Deduplication::None,
+            max_lines: None,
+            unsaved: Vec::new(),
+            timestamps: false,
         }
     }
+
+    /// Set whether `save_to_file()` should write timestamps. See `History` for the details of the
+    /// file format.
+    pub fn set_timestamps(&mut self, timestamps: bool) {
+        self.timestamps = timestamps;
+    }
+
+    /// Set which duplicates should be dropped by subsequent `add_line()` calls. Lines that are
+    /// already in the history are not affected.
+    pub fn set_deduplication(&mut self, deduplication: Deduplication) {
+        self.deduplication = deduplication;
+    }
+
+    /// Keep at most `max_lines` most recent lines in memory; `None` means there is no limit.
+    ///
+    /// This is independent from the limit passed to `save_to_file()`.
+    pub fn set_max_lines(&mut self, max_lines: Option<usize>) {
+        self.max_lines = max_lines;
+        self.enforce_max_lines();
+    }
+
+    /// Add a line entered just now.
     pub fn add_line(&mut self, line: String) {
+        self.add_entry(HistoryEntry::new(line, Some(Utc::now())));
+    }
+
+    pub fn add_entry(&mut self, entry: HistoryEntry) {
         // When a line is added, we need to do so and reset the index so that the next
         // previous_line()/next_line() operations start from the beginning again.
-        if !line.is_empty() {
-            self.lines.insert(0, line)
-        }
         self.idx = 0;
+
+        if self.store_entry(entry.clone()) {
+            self.unsaved.push(entry);
+            if let Some(max_lines) = self.max_lines {
+                let excess = self.unsaved.len().saturating_sub(max_lines);
+                self.unsaved.drain(..excess);
+            }
+        }
+    }
+
+    /// Put the entry at the front of the history, applying de-duplication and the in-memory cap.
+    /// Returns `false` if the entry was dropped.
+    fn store_entry(&mut self, entry: HistoryEntry) -> bool {
+        if entry.line.is_empty() {
+            return false;
+        }
+
+        match self.deduplication {
+            Deduplication::None => {}
+            Deduplication::Consecutive => {
+                if self.entries.first().map(|e| &e.line) == Some(&entry.line) {
+                    return false;
+                }
+            }
+            Deduplication::Global => self.entries.retain(|existing| existing.line != entry.line),
+        }
+
+        self.entries.insert(0, entry);
+        self.enforce_max_lines();
+        true
+    }
+
+    /// Iterate over the entries that match `query`, from the most recent one to the oldest, like
+    /// bash's reverse-i-search (Ctrl-R) does. An empty query matches every entry.
+    ///
+    /// This doesn't affect the position used by `previous_line()` and `next_line()`.
+    pub fn search<'a>(
+        &'a self,
+        query: &'a str,
+        mode: SearchMode,
+    ) -> impl Iterator<Item = &'a str> + 'a {
+        self.entries
+            .iter()
+            .map(|entry| entry.line.as_str())
+            .filter(move |line| match mode {
+                SearchMode::Prefix => line.starts_with(query),
+                SearchMode::Substring => line.contains(query),
+            })
     }
+
+    /// Iterate over all entries, from the most recent one to the oldest.
+    pub fn entries(&self) -> impl Iterator<Item = &HistoryEntry> {
+        self.entries.iter()
+    }
+
+    fn enforce_max_lines(&mut self) {
+        if let Some(max_lines) = self.max_lines {
+            self.entries.truncate(max_lines);
+            if self.idx > self.entries.len() {
+                self.idx = self.entries.len();
+            }
+        }
+    }
+
     pub fn next_line(&mut self) -> String {
         match self.idx {
             0 => String::new(),
             _ => {
                 self.idx -= 1;
-                self.lines[self.idx].clone()
+                self.entries[self.idx].line.clone()
             }
         }
     }
     pub fn previous_line(&mut self) -> String {
-        match self.lines.len() {
+        match self.entries.len() {
             0 => String::new(),
             len if self.idx < len => {
-                let line = self.lines[self.idx].clone();
+                let line = self.entries[self.idx].line.clone();
                 self.idx += 1;
                 line
             }
-            _ => self.lines[self.idx - 1].clone(),
+            _ => self.entries[self.idx - 1].line.clone(),
         }
     }
     pub fn load_from_file<P: AsRef<Path>>(&mut self, path: P) -> io::Result<()> {
-        let file = OpenOptions::new().read(true).open(path)?;
-        let lines: Vec<String> = BufReader::new(file)
-            .lines()
-            .collect::<Result<Vec<_>, _>>()?;
-        lines.into_iter().for_each(|ln| self.add_line(ln));
+        for entry in read_entries(path.as_ref())? {
+            self.store_entry(entry);
+        }
+        self.idx = 0;
         Ok(())
     }
-    pub fn save_to_file<P: AsRef<Path>>(&self, path: P, limit: usize) -> io::Result<()> {
-        if limit == 0 || self.lines.is_empty() {
+
+    /// Append entries added since the previous save to the file, keeping at most `limit` most recent
+    /// lines in it. Nothing is written if `limit` is zero, or if there are no new lines and the
+    /// file is already within the limit.
+    ///
+    /// The file is re-read before writing, so lines saved by other Newsboat instances in the
+    /// meantime are preserved. While that happens, an exclusive lock is held on `<file>.lock`, so
+    /// instances that save at the same time take turns instead of overwriting each other's lines.
+    /// The new contents are written to a temporary file which is then renamed over the old one, so
+    /// a crash or a concurrent reader never sees a half-written file.
+    pub fn save_to_file<P: AsRef<Path>>(&mut self, path: P, limit: usize) -> io::Result<()> {
+        if limit == 0 {
+            return Ok(());
+        }
+
+        let path = resolve_symlink(path.as_ref());
+        if self.unsaved.is_empty() && !path.exists() {
             return Ok(());
         }
+        let _lock = lock_for_saving(&path)?;
+
+        let mut merged = match read_entries(&path) {
+            Ok(entries) => entries,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Vec::new(),
+            Err(e) => return Err(e),
+        };
+        if self.unsaved.is_empty() && merged.len() <= limit {
+            return Ok(());
+        }
+        merged.extend(self.unsaved.iter().cloned());
+        let merged = deduplicate(merged, self.deduplication);
+
+        let skip = merged.len().saturating_sub(limit);
+        write_atomically(&path, &merged[skip..], self.timestamps)?;
+
+        self.unsaved.clear();
+        Ok(())
+    }
+}
+
+/// Suffix appended to the history file path to get the path of the file that is locked while
+/// saving.
+const LOCK_SUFFIX: &str = ".lock";
+
+/// Waits until this process is the only one saving the history file at `path`. The returned file
+/// holds the lock until it is dropped.
+///
+/// The lock file is never removed: another instance might have opened it and be waiting for the
+/// lock, and removing the file would let a third one lock a new file at the same time.
+fn lock_for_saving(path: &Path) -> io::Result<File> {
+    let mut lock_path = path.as_os_str().to_owned();
+    lock_path.push(LOCK_SUFFIX);
+    let file = OpenOptions::new()
+        .create(true)
+        .truncate(false)
+        .write(true)
+        .mode(0o600)
+        .open(lock_path)?;
+    // flock() locks are tied to the open file rather than the process, so this also works for
+    // several `History` objects in one process.
+    loop {
+        if unsafe { libc::flock(file.as_raw_fd(), libc::LOCK_EX) } == 0 {
+            return Ok(file);
+        }
+        let error = io::Error::last_os_error();
+        if error.kind() != io::ErrorKind::Interrupted {
+            return Err(error);
+        }
+    }
+}
+
+/// The first line of history files that contain timestamps.
+const TIMESTAMPS_HEADER: &str = "#newsboat-history-timestamps";
+
+/// Prepended to entries that would otherwise be mistaken for timestamps or other special lines in
+/// files that contain timestamps.
+const ESCAPE: char = '\\';
+
+/// Parse a `#<unix timestamp>` line that precedes an entry.
+fn parse_timestamp_line(line: &str) -> Option<DateTime<Utc>> {
+    let digits = line.strip_prefix('#')?;
+    if digits.is_empty() || !digits.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    let seconds = digits.parse::<i64>().ok()?;
+    Utc.timestamp_opt(seconds, 0).single()
+}
+
+/// Read entries from a history file, oldest first.
+fn read_entries(path: &Path) -> io::Result<Vec<HistoryEntry>> {
+    let file = OpenOptions::new().read(true).open(path)?;
+    let mut lines = BufReader::new(file).lines().peekable();
+    let has_timestamps = match lines.peek() {
+        Some(Ok(first)) => first == TIMESTAMPS_HEADER,
+        _ => false,
+    };
+    if has_timestamps {
+        lines.next();
+    }
+
+    let mut entries = Vec::new();
+    let mut timestamp = None;
+    for line in lines {
+        let line = line?;
+        if !has_timestamps {
+            entries.push(HistoryEntry::new(line, None));
+        } else if let Some(escaped) = line.strip_prefix(ESCAPE) {
+            entries.push(HistoryEntry::new(escaped.to_owned(), timestamp.take()));
+        } else if line.starts_with('#') {
+            // Lines starting with `#` that aren't timestamps are reserved for future use
+            if let Some(ts) = parse_timestamp_line(&line) {
+                timestamp = Some(ts);
+            }
+        } else {
+            entries.push(HistoryEntry::new(line, timestamp.take()));
+        }
+    }
+    Ok(entries)
+}
 
+/// Remove duplicates from the entries (ordered oldest to newest) the way `add_line()` would have.
+fn deduplicate(mut entries: Vec<HistoryEntry>, deduplication: Deduplication) -> Vec<HistoryEntry> {
+    match deduplication {
+        Deduplication::None => entries,
+        Deduplication::Consecutive => {
+            entries.dedup_by(|a, b| a.line == b.line);
+            entries
+        }
+        Deduplication::Global => {
+            let mut seen = HashSet::new();
+            let mut result: Vec<HistoryEntry> = entries
+                .into_iter()
+                .rev()
+                .filter(|entry| seen.insert(entry.line.clone()))
+                .collect();
+            result.reverse();
+            result
+        }
+    }
+}
+
+/// If `path` is a symlink, return the path of the file it points to, so that replacing the file
+/// doesn't replace the link.
+fn resolve_symlink(path: &Path) -> PathBuf {
+    match fs::symlink_metadata(path) {
+        Ok(metadata) if metadata.file_type().is_symlink() => {
+            fs::canonicalize(path).unwrap_or_else(|_| path.to_owned())
+        }
+        _ => path.to_owned(),
+    }
+}
+
+fn write_atomically(path: &Path, entries: &[HistoryEntry], timestamps: bool) -> io::Result<()> {
+    // A plain file starting with the header would be read back as one with timestamps, so use
+    // that format, in which the entry gets escaped.
+    let timestamps =
+        timestamps || entries.first().map(|entry| entry.line.as_str()) == Some(TIMESTAMPS_HEADER);
+
+    let file_name = path.file_name().ok_or_else(|| {
+        io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "history file path doesn't have a file name",
+        )
+    })?;
+    let mut tmp_name = OsString::from(".");
+    tmp_name.push(file_name);
+    tmp_name.push(format!(".{}.tmp", process::id()));
+    let tmp_path = path.with_file_name(tmp_name);
+
+    let write = || -> io::Result<()> {
         let file = OpenOptions::new()
             .write(true)
             .create(true)
             .truncate(true)
-            .open(path)?;
-        let mut f = BufWriter::new(file);
+            .open(&tmp_path)?;
+        let mut f = BufWriter::new(&file);
+        if timestamps {
+            writeln!(f, "{}", TIMESTAMPS_HEADER)?;
+        }
+        for entry in entries {
+            if !timestamps {
+                writeln!(f, "{}", entry.line)?;
+                continue;
+            }
+            if let Some(timestamp) = entry.timestamp {
+                writeln!(f, "#{}", timestamp.timestamp())?;
+            }
+            if entry.line.starts_with('#') || entry.line.starts_with(ESCAPE) {
+                write!(f, "{}", ESCAPE)?;
+            }
+            writeln!(f, "{}", entry.line)?;
+        }
+        f.flush()?;
+        drop(f);
+        file.sync_all()?;
+        fs::rename(&tmp_path, path)
+    };
 
-        self.lines
-            .iter()
-            .take(limit)
-            .rev()
-            .try_for_each(|ln| writeln!(f, "{}", ln))
+    let result = write();
+    if result.is_err() {
+        let _ = fs::remove_file(&tmp_path);
+    }
+    result
+}
+
+impl Default for History {
+    fn default() -> Self {
+        History::new()
     }
 }
 
@@ -161,4 +499,482 @@ mod tests {
         assert_eq!(loaded_h.previous_line(), "2");
         assert_eq!(loaded_h.previous_line(), "2");
     }
+
+    #[test]
+    fn t_add_line_keeps_duplicates_by_default() {
+        let mut h = History::new();
+        h.add_line("foo".to_string());
+        h.add_line("foo".to_string());
+        assert_eq!(h.previous_line(), "foo");
+        assert_eq!(h.previous_line(), "foo");
+        assert_eq!(h.search("", SearchMode::Prefix).count(), 2);
+    }
+
+    #[test]
+    fn t_consecutive_deduplication_drops_line_equal_to_the_most_recent_one() {
+        let mut h = History::new();
+        h.set_deduplication(Deduplication::Consecutive);
+        h.add_line("foo".to_string());
+        h.add_line("foo".to_string());
+        h.add_line("bar".to_string());
+        h.add_line("foo".to_string());
+        h.add_line("foo".to_string());
+
+        let lines: Vec<&str> = h.search("", SearchMode::Substring).collect();
+        assert_eq!(lines, vec!["foo", "bar", "foo"]);
+    }
+
+    #[test]
+    fn t_global_deduplication_moves_repeated_line_to_the_front() {
+        let mut h = History::new();
+        h.set_deduplication(Deduplication::Global);
+        h.add_line("foo".to_string());
+        h.add_line("bar".to_string());
+        h.add_line("baz".to_string());
+        h.add_line("foo".to_string());
+
+        let lines: Vec<&str> = h.search("", SearchMode::Substring).collect();
+        assert_eq!(lines, vec!["foo", "baz", "bar"]);
+        assert_eq!(h.previous_line(), "foo");
+        assert_eq!(h.previous_line(), "baz");
+    }
+
+    #[test]
+    fn t_max_lines_drops_oldest_lines() {
+        let mut h = History::new();
+        h.set_max_lines(Some(2));
+        h.add_line("1".to_string());
+        h.add_line("2".to_string());
+        h.add_line("3".to_string());
+        assert_eq!(h.previous_line(), "3");
+        assert_eq!(h.previous_line(), "2");
+        assert_eq!(h.previous_line(), "2");
+
+        // Lowering the limit applies to lines that are already in the history
+        h.set_max_lines(Some(1));
+        assert_eq!(h.next_line(), "3");
+        assert_eq!(h.previous_line(), "3");
+        assert_eq!(h.previous_line(), "3");
+
+        h.set_max_lines(Some(0));
+        assert_eq!(h.previous_line(), "");
+        assert_eq!(h.next_line(), "");
+        h.add_line("4".to_string());
+        assert_eq!(h.previous_line(), "");
+    }
+
+    #[test]
+    fn t_max_lines_applies_to_lines_loaded_from_file() {
+        let tmp_dir = TempDir::new().unwrap();
+        let file_path = tmp_dir.path().join("history.search");
+
+        let mut h = History::new();
+        h.add_line("1".to_string());
+        h.add_line("2".to_string());
+        h.add_line("3".to_string());
+        h.save_to_file(file_path.clone(), 10).unwrap();
+
+        let mut loaded_h = History::new();
+        loaded_h.set_max_lines(Some(2));
+        loaded_h.load_from_file(file_path).unwrap();
+        let lines: Vec<&str> = loaded_h.search("", SearchMode::Prefix).collect();
+        assert_eq!(lines, vec!["3", "2"]);
+    }
+
+    #[test]
+    fn t_search_iterates_over_matching_lines_from_most_recent() {
+        let mut h = History::new();
+        h.add_line("set browser lynx".to_string());
+        h.add_line("quit".to_string());
+        h.add_line("set browser firefox".to_string());
+        h.add_line("reset browser".to_string());
+
+        let prefix: Vec<&str> = h.search("set", SearchMode::Prefix).collect();
+        assert_eq!(prefix, vec!["set browser firefox", "set browser lynx"]);
+
+        let substring: Vec<&str> = h.search("set", SearchMode::Substring).collect();
+        assert_eq!(
+            substring,
+            vec!["reset browser", "set browser firefox", "set browser lynx"]
+        );
+
+        let mut matches = h.search("browser", SearchMode::Substring).skip(1);
+        assert_eq!(matches.next(), Some("set browser firefox"));
+        assert_eq!(matches.next(), Some("set browser lynx"));
+        assert_eq!(matches.next(), None);
+
+        assert_eq!(h.search("nothing", SearchMode::Substring).next(), None);
+    }
+
+    #[test]
+    fn t_search_does_not_affect_navigation() {
+        let mut h = History::new();
+        h.add_line("foo".to_string());
+        h.add_line("bar".to_string());
+        assert_eq!(h.previous_line(), "bar");
+        assert_eq!(h.search("foo", SearchMode::Prefix).next(), Some("foo"));
+        assert_eq!(h.previous_line(), "foo");
+    }
+
+    fn file_lines(path: &Path) -> Vec<String> {
+        read_entries(path)
+            .unwrap()
+            .into_iter()
+            .map(|entry| entry.line)
+            .collect()
+    }
+
+    #[test]
+    fn t_save_merges_lines_saved_by_another_instance() {
+        let tmp_dir = TempDir::new().unwrap();
+        let file_path = tmp_dir.path().join("history.cmdline");
+        std::fs::write(&file_path, "old\n").unwrap();
+
+        let mut first = History::new();
+        first.load_from_file(&file_path).unwrap();
+        let mut second = History::new();
+        second.load_from_file(&file_path).unwrap();
+
+        first.add_line("from first".to_string());
+        second.add_line("from second".to_string());
+
+        second.save_to_file(&file_path, 10).unwrap();
+        first.save_to_file(&file_path, 10).unwrap();
+
+        assert_eq!(
+            file_lines(&file_path),
+            vec!["old", "from second", "from first"]
+        );
+    }
+
+    #[test]
+    fn t_save_writes_each_line_only_once() {
+        let tmp_dir = TempDir::new().unwrap();
+        let file_path = tmp_dir.path().join("history.search");
+
+        let mut h = History::new();
+        h.add_line("1".to_string());
+        h.save_to_file(&file_path, 10).unwrap();
+        h.save_to_file(&file_path, 10).unwrap();
+        h.add_line("2".to_string());
+        h.save_to_file(&file_path, 10).unwrap();
+
+        assert_eq!(file_lines(&file_path), vec!["1", "2"]);
+    }
+
+    #[test]
+    fn t_save_keeps_limit_most_recent_lines_of_merged_history() {
+        let tmp_dir = TempDir::new().unwrap();
+        let file_path = tmp_dir.path().join("history.search");
+        std::fs::write(&file_path, "1\n2\n3\n").unwrap();
+
+        let mut h = History::new();
+        h.add_line("4".to_string());
+        h.add_line("5".to_string());
+        h.save_to_file(&file_path, 3).unwrap();
+
+        assert_eq!(file_lines(&file_path), vec!["3", "4", "5"]);
+    }
+
+    #[test]
+    fn t_save_applies_deduplication_to_merged_history() {
+        let tmp_dir = TempDir::new().unwrap();
+        let file_path = tmp_dir.path().join("history.search");
+        std::fs::write(&file_path, "a\nb\nb\nc\n").unwrap();
+
+        let mut h = History::new();
+        h.set_deduplication(Deduplication::Consecutive);
+        h.add_line("c".to_string());
+        h.add_line("d".to_string());
+        h.save_to_file(&file_path, 10).unwrap();
+        assert_eq!(file_lines(&file_path), vec!["a", "b", "c", "d"]);
+
+        let mut h = History::new();
+        h.set_deduplication(Deduplication::Global);
+        h.add_line("a".to_string());
+        h.save_to_file(&file_path, 10).unwrap();
+        assert_eq!(file_lines(&file_path), vec!["b", "c", "d", "a"]);
+    }
+
+    #[test]
+    fn t_save_does_not_leave_temporary_files_behind() {
+        let tmp_dir = TempDir::new().unwrap();
+        let file_path = tmp_dir.path().join("history.cmdline");
+
+        let mut h = History::new();
+        h.add_line("line".to_string());
+        h.save_to_file(&file_path, 10).unwrap();
+
+        let mut entries: Vec<_> = std::fs::read_dir(tmp_dir.path())
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name())
+            .collect();
+        entries.sort();
+        assert_eq!(
+            entries,
+            vec![
+                OsString::from("history.cmdline"),
+                OsString::from("history.cmdline.lock")
+            ]
+        );
+    }
+
+    #[test]
+    fn t_save_applies_lowered_limit_even_without_new_lines() {
+        let tmp_dir = TempDir::new().unwrap();
+        let file_path = tmp_dir.path().join("history.search");
+        std::fs::write(&file_path, "1\n2\n3\n4\n5\n").unwrap();
+
+        let mut h = History::new();
+        h.load_from_file(&file_path).unwrap();
+        h.save_to_file(&file_path, 10).unwrap();
+        assert_eq!(file_lines(&file_path), vec!["1", "2", "3", "4", "5"]);
+
+        h.save_to_file(&file_path, 2).unwrap();
+        assert_eq!(file_lines(&file_path), vec!["4", "5"]);
+    }
+
+    #[test]
+    fn t_save_does_not_create_file_without_new_lines() {
+        let tmp_dir = TempDir::new().unwrap();
+        let file_path = tmp_dir.path().join("history.search");
+
+        let mut h = History::new();
+        h.save_to_file(&file_path, 10).unwrap();
+        assert!(!file_path.exists());
+        assert_eq!(std::fs::read_dir(tmp_dir.path()).unwrap().count(), 0);
+    }
+
+    #[test]
+    fn t_concurrent_saves_do_not_lose_lines() {
+        let tmp_dir = TempDir::new().unwrap();
+        let file_path = tmp_dir.path().join("history.cmdline");
+
+        let threads: Vec<_> = (0..8)
+            .map(|thread| {
+                let file_path = file_path.clone();
+                std::thread::spawn(move || {
+                    let mut h = History::new();
+                    for i in 0..20 {
+                        h.add_line(format!("{} {}", thread, i));
+                        h.save_to_file(&file_path, 1000).unwrap();
+                    }
+                })
+            })
+            .collect();
+        for thread in threads {
+            thread.join().unwrap();
+        }
+
+        let mut lines = file_lines(&file_path);
+        lines.sort();
+        let mut expected: Vec<String> = (0..8)
+            .flat_map(|thread| (0..20).map(move |i| format!("{} {}", thread, i)))
+            .collect();
+        expected.sort();
+        assert_eq!(lines, expected);
+    }
+
+    #[test]
+    fn t_save_replaces_target_of_symlink_rather_than_symlink_itself() {
+        let tmp_dir = TempDir::new().unwrap();
+        let target_path = tmp_dir.path().join("dotfiles-history");
+        let link_path = tmp_dir.path().join("history.cmdline");
+        std::fs::write(&target_path, "old\n").unwrap();
+        std::os::unix::fs::symlink(&target_path, &link_path).unwrap();
+
+        let mut h = History::new();
+        h.add_line("new".to_string());
+        h.save_to_file(&link_path, 10).unwrap();
+
+        assert!(std::fs::symlink_metadata(&link_path)
+            .unwrap()
+            .file_type()
+            .is_symlink());
+        assert_eq!(file_lines(&target_path), vec!["old", "new"]);
+    }
+
+    #[test]
+    fn t_entries_from_files_without_timestamps_have_none() {
+        let tmp_dir = TempDir::new().unwrap();
+        let file_path = tmp_dir.path().join("history.search");
+        std::fs::write(&file_path, "foo\nbar\n").unwrap();
+
+        let mut h = History::new();
+        h.load_from_file(&file_path).unwrap();
+        let entries: Vec<&HistoryEntry> = h.entries().collect();
+        assert_eq!(
+            entries,
+            vec![
+                &HistoryEntry::new("bar".to_string(), None),
+                &HistoryEntry::new("foo".to_string(), None),
+            ]
+        );
+    }
+
+    #[test]
+    fn t_timestamps_are_not_saved_by_default() {
+        let tmp_dir = TempDir::new().unwrap();
+        let file_path = tmp_dir.path().join("history.search");
+
+        let mut h = History::new();
+        h.add_line("foo".to_string());
+        h.save_to_file(&file_path, 10).unwrap();
+
+        assert_eq!(std::fs::read_to_string(&file_path).unwrap(), "foo\n");
+    }
+
+    #[test]
+    fn t_timestamps_are_saved_and_loaded() {
+        let tmp_dir = TempDir::new().unwrap();
+        let file_path = tmp_dir.path().join("history.search");
+        std::fs::write(&file_path, "old\n").unwrap();
+
+        let timestamp = Utc.timestamp(1_612_345_678, 0);
+        let mut h = History::new();
+        h.set_timestamps(true);
+        h.add_entry(HistoryEntry::new("new".to_string(), Some(timestamp)));
+        h.save_to_file(&file_path, 10).unwrap();
+
+        assert_eq!(
+            std::fs::read_to_string(&file_path).unwrap(),
+            "#newsboat-history-timestamps\nold\n#1612345678\nnew\n"
+        );
+
+        let mut loaded_h = History::new();
+        loaded_h.load_from_file(&file_path).unwrap();
+        let entries: Vec<&HistoryEntry> = loaded_h.entries().collect();
+        assert_eq!(
+            entries,
+            vec![
+                &HistoryEntry::new("new".to_string(), Some(timestamp)),
+                &HistoryEntry::new("old".to_string(), None),
+            ]
+        );
+        assert_eq!(loaded_h.previous_line(), "new");
+        assert_eq!(loaded_h.previous_line(), "old");
+    }
+
+    #[test]
+    fn t_add_line_records_current_time() {
+        let before = Utc::now();
+        let mut h = History::new();
+        h.add_line("foo".to_string());
+        let after = Utc::now();
+
+        let timestamp = h.entries().next().unwrap().timestamp.unwrap();
+        assert!(before <= timestamp && timestamp <= after);
+    }
+
+    #[test]
+    fn t_entries_that_look_like_timestamps_survive_a_round_trip() {
+        let lines = vec![
+            "#123",
+            "#",
+            "\\#123",
+            "\\",
+            "#newsboat-history-timestamps",
+            "plain",
+        ];
+        for &timestamps in &[false, true] {
+            let tmp_dir = TempDir::new().unwrap();
+            let file_path = tmp_dir.path().join("history.cmdline");
+
+            let mut h = History::new();
+            h.set_timestamps(timestamps);
+            for line in &lines {
+                h.add_entry(HistoryEntry::new(line.to_string(), None));
+            }
+            h.save_to_file(&file_path, 10).unwrap();
+            assert_eq!(file_lines(&file_path), lines);
+
+            // Entries are kept when another instance merges its lines into the file
+            let mut other = History::new();
+            other.set_timestamps(timestamps);
+            other.add_entry(HistoryEntry::new("#1".to_string(), None));
+            other.save_to_file(&file_path, 10).unwrap();
+
+            let mut expected = lines.clone();
+            expected.push("#1");
+            assert_eq!(file_lines(&file_path), expected);
+        }
+    }
+
+    #[test]
+    fn t_entry_that_looks_like_the_header_survives_a_round_trip() {
+        let tmp_dir = TempDir::new().unwrap();
+        let file_path = tmp_dir.path().join("history.cmdline");
+
+        let mut h = History::new();
+        h.add_line("#newsboat-history-timestamps".to_string());
+        h.add_line("foo".to_string());
+        h.save_to_file(&file_path, 10).unwrap();
+
+        assert_eq!(
+            file_lines(&file_path),
+            vec!["#newsboat-history-timestamps", "foo"]
+        );
+    }
+
+    #[test]
+    fn t_files_without_header_are_read_as_plain_lines() {
+        let tmp_dir = TempDir::new().unwrap();
+        let file_path = tmp_dir.path().join("history.cmdline");
+        std::fs::write(&file_path, "#1\nfoo\n\\bar\n").unwrap();
+
+        let mut h = History::new();
+        h.load_from_file(&file_path).unwrap();
+        let entries: Vec<&HistoryEntry> = h.entries().collect();
+        assert_eq!(
+            entries,
+            vec![
+                &HistoryEntry::new("\\bar".to_string(), None),
+                &HistoryEntry::new("foo".to_string(), None),
+                &HistoryEntry::new("#1".to_string(), None),
+            ]
+        );
+    }
+
+    #[test]
+    fn t_only_last_timestamp_before_an_entry_is_used() {
+        let tmp_dir = TempDir::new().unwrap();
+        let file_path = tmp_dir.path().join("history.cmdline");
+        std::fs::write(
+            &file_path,
+            "#newsboat-history-timestamps\n#1\n#2\n#unknown\nfoo\n#3\n",
+        )
+        .unwrap();
+
+        let mut h = History::new();
+        h.load_from_file(&file_path).unwrap();
+        let entries: Vec<&HistoryEntry> = h.entries().collect();
+        // The timestamp at the end of the file is ignored because there's no entry after it
+        assert_eq!(
+            entries,
+            vec![&HistoryEntry::new(
+                "foo".to_string(),
+                Some(Utc.timestamp(2, 0))
+            )]
+        );
+    }
+
+    #[test]
+    fn t_save_keeps_timestamps_of_entries_saved_by_another_instance() {
+        let tmp_dir = TempDir::new().unwrap();
+        let file_path = tmp_dir.path().join("history.cmdline");
+        std::fs::write(&file_path, "#newsboat-history-timestamps\n#100\nfoo\n").unwrap();
+
+        let mut h = History::new();
+        h.set_timestamps(true);
+        h.add_entry(HistoryEntry::new(
+            "bar".to_string(),
+            Some(Utc.timestamp(200, 0)),
+        ));
+        h.save_to_file(&file_path, 10).unwrap();
+
+        assert_eq!(
+            std::fs::read_to_string(&file_path).unwrap(),
+            "#newsboat-history-timestamps\n#100\nfoo\n#200\nbar\n"
+        );
+    }
 }
@@ -1,12 +1,17 @@
 use nom::{
     branch::alt,
-    bytes::complete::{escaped_transform, is_not, tag, take},
-    character::complete::one_of,
-    combinator::{complete, eof, map, recognize, value},
+    bytes::complete::{escaped_transform, is_not, tag, take, take_while, take_while1},
+    character::complete::{one_of, satisfy},
+    combinator::{complete, eof, map, map_res, opt, recognize, value},
     multi::{many0, many1, separated_list0, separated_list1},
-    sequence::{delimited, preceded},
+    sequence::{delimited, pair, preceded},
     IResult,
 };
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+use std::str::FromStr;
+
+use crate::utils;
 
 fn unquoted_token(input: &str) -> IResult<&str, String> {
     let mut parser = map(recognize(is_not("\t ;")), String::from);
@@ -37,25 +42,340 @@ fn token(input: &str) -> IResult<&str, String> {
     parser(input)
 }
 
-fn operation_with_args(input: &str) -> IResult<&str, Vec<String>> {
-    let mut parser = separated_list1(many1(one_of(" \t")), token);
-    parser(input)
+fn operation_with_args<'a, T, F>(token: F) -> impl FnMut(&'a str) -> IResult<&'a str, Vec<T>>
+where
+    F: FnMut(&'a str) -> IResult<&'a str, T>,
+{
+    separated_list1(many1(one_of(" \t")), token)
 }
 
 fn semicolon(input: &str) -> IResult<&str, &str> {
     delimited(many0(one_of(" \t")), tag(";"), many0(one_of(" \t")))(input)
 }
 
-fn operation_sequence(input: &str) -> IResult<&str, Vec<Vec<String>>> {
-    let parser = separated_list0(many1(semicolon), operation_with_args);
+fn operation_sequence<'a, T, F>(token: F) -> impl FnMut(&'a str) -> IResult<&'a str, Vec<Vec<T>>>
+where
+    F: FnMut(&'a str) -> IResult<&'a str, T>,
+{
+    let parser = separated_list0(many1(semicolon), operation_with_args(token));
     let parser = delimited(many0(semicolon), parser, many0(semicolon));
     let parser = preceded(many0(one_of(" \t")), parser);
 
-    let mut parser = complete(parser);
+    complete(parser)
+}
+
+/// Name of the argument that a macro placeholder refers to.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum PlaceholderName {
+    /// 1-based index of an argument given to the macro when it's invoked, e.g. `$1` or `${2}`.
+    Positional(usize),
+    /// Name of a variable, e.g. `${browser}`.
+    Named(String),
+}
+
+impl fmt::Display for PlaceholderName {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            PlaceholderName::Positional(index) => write!(f, "${}", index),
+            PlaceholderName::Named(name) => write!(f, "${{{}}}", name),
+        }
+    }
+}
+
+/// What a placeholder expands to, depending on whether its argument is set.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PlaceholderModifier {
+    /// `${N:-text}`: the argument if it's non-empty, otherwise the text.
+    Default(MacroToken),
+    /// `${N:+text}`: the text if the argument is non-empty, otherwise nothing.
+    Alternative(MacroToken),
+}
+
+/// A reference to a macro argument, optionally with a text to use instead of the argument.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Placeholder {
+    pub name: PlaceholderName,
+    pub modifier: Option<PlaceholderModifier>,
+}
+
+impl Placeholder {
+    fn expand(&self, args: &MacroArguments) -> Result<String, MacroExpansionError> {
+        let value = args.get(&self.name);
+        let is_set = matches!(value, Some(value) if !value.is_empty());
+        match (&self.modifier, value) {
+            (None, Some(value)) => Ok(value.to_string()),
+            (None, None) => Err(MacroExpansionError::MissingArgument(self.name.clone())),
+            (Some(PlaceholderModifier::Default(text)), _) if !is_set => text.expand(args),
+            (Some(PlaceholderModifier::Default(_)), value) => {
+                Ok(value.unwrap_or_default().to_string())
+            }
+            (Some(PlaceholderModifier::Alternative(text)), _) if is_set => text.expand(args),
+            (Some(PlaceholderModifier::Alternative(_)), _) => Ok(String::new()),
+        }
+    }
+}
+
+/// A piece of a macro token: either a literal text, or a placeholder which is replaced by an
+/// argument when the macro is invoked.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MacroTokenPart {
+    Literal(String),
+    Placeholder(Placeholder),
+}
+
+/// A token of a parameterized operation sequence, i.e. a string that might contain placeholders.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct MacroToken {
+    parts: Vec<MacroTokenPart>,
+}
+
+impl MacroToken {
+    fn new(parts: Vec<MacroTokenPart>) -> MacroToken {
+        // Merge adjacent literals, so that the representation doesn't depend on how the token
+        // was escaped.
+        let mut merged: Vec<MacroTokenPart> = Vec::with_capacity(parts.len());
+        for part in parts {
+            match (merged.last_mut(), part) {
+                (Some(MacroTokenPart::Literal(last)), MacroTokenPart::Literal(text)) => {
+                    last.push_str(&text)
+                }
+                (_, part) => merged.push(part),
+            }
+        }
+        MacroToken { parts: merged }
+    }
+
+    /// Parts that make up this token, in order.
+    pub fn parts(&self) -> &[MacroTokenPart] {
+        &self.parts
+    }
+
+    /// Returns `true` if the token doesn't contain any placeholders.
+    pub fn is_literal(&self) -> bool {
+        self.parts
+            .iter()
+            .all(|part| matches!(part, MacroTokenPart::Literal(_)))
+    }
+
+    /// Names of the arguments this token refers to, including the ones inside default and
+    /// alternative texts.
+    pub fn placeholder_names(&self) -> Vec<PlaceholderName> {
+        let mut names = vec![];
+        for part in &self.parts {
+            if let MacroTokenPart::Placeholder(placeholder) = part {
+                names.push(placeholder.name.clone());
+                match &placeholder.modifier {
+                    Some(PlaceholderModifier::Default(text))
+                    | Some(PlaceholderModifier::Alternative(text)) => {
+                        names.extend(text.placeholder_names())
+                    }
+                    None => {}
+                }
+            }
+        }
+        names
+    }
+
+    /// Replace placeholders with values from `args`. Default and alternative texts are only
+    /// expanded if they're used.
+    pub fn expand(&self, args: &MacroArguments) -> Result<String, MacroExpansionError> {
+        let mut result = String::new();
+        for part in &self.parts {
+            match part {
+                MacroTokenPart::Literal(text) => result.push_str(text),
+                MacroTokenPart::Placeholder(placeholder) => {
+                    result.push_str(&placeholder.expand(args)?)
+                }
+            }
+        }
+        Ok(result)
+    }
+}
+
+/// Values for the placeholders of a parameterized operation sequence.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct MacroArguments {
+    positional: Vec<String>,
+    named: HashMap<String, String>,
+}
+
+impl MacroArguments {
+    /// Create arguments from the values that were passed to the macro when it was invoked. The
+    /// first value is available as `$1`, the second one as `$2`, and so on.
+    pub fn new(positional: Vec<String>) -> MacroArguments {
+        MacroArguments {
+            positional,
+            named: HashMap::new(),
+        }
+    }
+
+    /// Make `value` available as `${name}`.
+    pub fn set_named(&mut self, name: &str, value: &str) {
+        self.named.insert(name.to_string(), value.to_string());
+    }
+
+    /// Value of the argument the placeholder refers to, if any.
+    pub fn get(&self, name: &PlaceholderName) -> Option<&str> {
+        match name {
+            PlaceholderName::Positional(index) => index
+                .checked_sub(1)
+                .and_then(|i| self.positional.get(i))
+                .map(String::as_str),
+            PlaceholderName::Named(name) => self.named.get(name).map(String::as_str),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MacroExpansionError {
+    /// The placeholder has no default value, and no argument was given for it.
+    MissingArgument(PlaceholderName),
+}
+
+impl fmt::Display for MacroExpansionError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            MacroExpansionError::MissingArgument(name) => {
+                write!(f, "no value given for macro argument {}", name)
+            }
+        }
+    }
+}
+
+fn positional_placeholder_name(input: &str) -> IResult<&str, PlaceholderName> {
+    let digits = recognize(pair(
+        satisfy(|c| ('1'..='9').contains(&c)),
+        take_while(|c: char| c.is_ascii_digit()),
+    ));
+    let mut parser = map_res(digits, |digits: &str| {
+        digits.parse::<usize>().map(PlaceholderName::Positional)
+    });
+
+    parser(input)
+}
+
+fn named_placeholder_name(input: &str) -> IResult<&str, PlaceholderName> {
+    let identifier = recognize(pair(
+        satisfy(|c| c.is_ascii_alphabetic() || c == '_'),
+        take_while(|c: char| c.is_ascii_alphanumeric() || c == '_' || c == '-'),
+    ));
+    let mut parser = map(identifier, |name: &str| {
+        PlaceholderName::Named(name.to_string())
+    });
+
+    parser(input)
+}
+
+/// Parses an escape sequence inside double quotes.
+fn escaped_char(input: &str) -> IResult<&str, &str> {
+    preceded(
+        tag("\\"),
+        alt((
+            value(r#"""#, tag(r#"""#)),
+            value(r#"\"#, tag(r#"\"#)),
+            value("\r", tag("r")),
+            value("\n", tag("n")),
+            value("\t", tag("t")),
+            take(1usize), // all other escaped characters are passed through, unmodified
+        )),
+    )(input)
+}
+
+/// Parses the text after `:-` or `:+` in a placeholder, up to the closing brace. The text can
+/// contain placeholders of its own. Inside double quotes, it can also contain escape sequences;
+/// outside of them, it can't contain whitespace and semicolons.
+fn modifier_text(quoted: bool, input: &str) -> IResult<&str, MacroToken> {
+    let literal = |input| {
+        if quoted {
+            alt((take_while1(|c| !"}\"\\$".contains(c)), escaped_char))(input)
+        } else {
+            take_while1(|c| !"}\t ;$".contains(c))(input)
+        }
+    };
+    let literal = map(literal, |text: &str| {
+        MacroTokenPart::Literal(text.to_string())
+    });
+    let mut parser = map(
+        many0(alt((literal, |input| dollar_sign(quoted, input)))),
+        MacroToken::new,
+    );
+
+    parser(input)
+}
+
+/// Parses `$N`, `${N}`, `${name}`, `${N:-default}`, `${name:-default}`, `${N:+alternative}` and
+/// `${name:+alternative}`. `quoted` tells if the placeholder is inside double quotes.
+fn placeholder(quoted: bool, input: &str) -> IResult<&str, Placeholder> {
+    let text = |input| modifier_text(quoted, input);
+    let modifier = alt((
+        map(preceded(tag(":-"), text), PlaceholderModifier::Default),
+        map(preceded(tag(":+"), text), PlaceholderModifier::Alternative),
+    ));
+    let braced = delimited(
+        tag("{"),
+        pair(
+            alt((positional_placeholder_name, named_placeholder_name)),
+            opt(modifier),
+        ),
+        tag("}"),
+    );
+    let bare = map(positional_placeholder_name, |name| (name, None));
+    let mut parser = map(
+        preceded(tag("$"), alt((braced, bare))),
+        |(name, modifier)| Placeholder { name, modifier },
+    );
+
+    parser(input)
+}
+
+/// Parses the parts that are common to quoted and unquoted macro tokens: escaped dollar signs,
+/// placeholders, and dollar signs that don't start a placeholder.
+fn dollar_sign(quoted: bool, input: &str) -> IResult<&str, MacroTokenPart> {
+    alt((
+        value(MacroTokenPart::Literal("$".to_string()), tag("$$")),
+        map(
+            |input| placeholder(quoted, input),
+            MacroTokenPart::Placeholder,
+        ),
+        value(MacroTokenPart::Literal("$".to_string()), tag("$")),
+    ))(input)
+}
+
+fn unquoted_macro_token(input: &str) -> IResult<&str, MacroToken> {
+    let literal = map(is_not("\t ;$"), |text: &str| {
+        MacroTokenPart::Literal(text.to_string())
+    });
+    let mut parser = map(
+        many1(alt((literal, |input| dollar_sign(false, input)))),
+        MacroToken::new,
+    );
+
+    parser(input)
+}
+
+fn quoted_macro_token(input: &str) -> IResult<&str, MacroToken> {
+    let escaped = map(escaped_char, |text: &str| {
+        MacroTokenPart::Literal(text.to_string())
+    });
+    let literal = map(take_while1(|c| !"\"\\$".contains(c)), |text: &str| {
+        MacroTokenPart::Literal(text.to_string())
+    });
+    let parts = many0(alt((literal, escaped, |input| dollar_sign(true, input))));
+
+    let double_quote = tag("\"");
+    let mut parser = map(
+        delimited(&double_quote, parts, alt((&double_quote, eof))),
+        MacroToken::new,
+    );
 
     parser(input)
 }
 
+fn macro_token(input: &str) -> IResult<&str, MacroToken> {
+    let mut parser = alt((quoted_macro_token, unquoted_macro_token));
+    parser(input)
+}
+
 /// Split a semicolon-separated list of operations into a vector. Each operation is represented by
 /// a non-empty sub-vector, where the first element is the name of the operation, and the rest of
 /// the elements are operation's arguments.
@@ -70,15 +390,254 @@ fn operation_sequence(input: &str) -> IResult<&str, Vec<Vec<String>>> {
 ///
 /// Returns `None` if the input could not be parsed.
 pub fn tokenize_operation_sequence(input: &str) -> Option<Vec<Vec<String>>> {
-    match operation_sequence(input) {
+    match operation_sequence(token)(input) {
+        Ok((_leftovers, tokens)) => Some(tokens),
+        Err(_error) => None,
+    }
+}
+
+/// Split a semicolon-separated list of operations of a parameterized macro into a vector. This
+/// works just like `tokenize_operation_sequence()`, except that tokens can contain placeholders:
+///
+/// - `$1`, `$2` etc. refer to the arguments the macro is invoked with;
+/// - `${1}` and `${name}` are the same, but can be followed by other text without a separator; the
+///   latter refers to a named argument;
+/// - `${1:-text}` and `${name:-text}` expand to "text" if the argument is empty or wasn't given;
+/// - `${1:+text}` and `${name:+text}` expand to "text" if the argument is non-empty, and to
+///   nothing otherwise.
+///
+/// The "text" can contain placeholders of its own. Inside double quotes, it can also contain
+/// escape sequences, e.g. `\}` for a closing brace.
+///
+/// Placeholders are recognized both inside and outside of double quotes. To get a literal dollar
+/// sign, write `$$` (or `\$` inside double quotes). A dollar sign that doesn't start
+/// a placeholder, like the one in `$URL`, is kept as is.
+///
+/// Use `expand_operation_sequence()` to replace placeholders with actual values.
+///
+/// Returns `None` if the input could not be parsed.
+pub fn tokenize_macro_operation_sequence(input: &str) -> Option<Vec<Vec<MacroToken>>> {
+    match operation_sequence(macro_token)(input) {
         Ok((_leftovers, tokens)) => Some(tokens),
         Err(_error) => None,
     }
 }
 
+/// Replace placeholders in the operation sequence with values from `args`. The result has the
+/// same shape as the output of `tokenize_operation_sequence()`.
+///
+/// Operations whose name expands to an empty string are dropped, without expanding their
+/// arguments. Together with `${N:+name}`, this lets a macro run an operation only if it was given
+/// an argument.
+pub fn expand_operation_sequence(
+    operations: &[Vec<MacroToken>],
+    args: &MacroArguments,
+) -> Result<Vec<Vec<String>>, MacroExpansionError> {
+    let mut result = Vec::with_capacity(operations.len());
+    for tokens in operations {
+        let name = match tokens.first() {
+            Some(name) => name.expand(args)?,
+            None => continue,
+        };
+        if name.is_empty() {
+            continue;
+        }
+        let mut operation = vec![name];
+        for token in &tokens[1..] {
+            operation.push(token.expand(args)?);
+        }
+        result.push(operation);
+    }
+    Ok(result)
+}
+
+/// Format in which `KeymapDump` can be printed.
+// This has to be in sync with KeymapDumpFormat in include/keymap.h
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum KeymapDumpFormat {
+    /// `bind-key` and `macro` lines that can be pasted into a config file.
+    Config = 1,
+    /// A JSON object with "bindings" and "macros" keys.
+    Json,
+}
+
+impl FromStr for KeymapDumpFormat {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "config" => Ok(KeymapDumpFormat::Config),
+            "json" => Ok(KeymapDumpFormat::Json),
+            _ => Err(()),
+        }
+    }
+}
+
+/// Key bindings and macros that are in effect after all the defaults and the user's config were
+/// applied.
+#[derive(Debug, Default)]
+pub struct KeymapDump {
+    /// Context name -> key -> operation name.
+    bindings: BTreeMap<String, BTreeMap<String, String>>,
+    /// Macro key -> the macro.
+    macros: BTreeMap<String, MacroDump>,
+}
+
+#[derive(Debug)]
+enum MacroDump {
+    /// Operations, each one being the operation name followed by its arguments.
+    Operations(Vec<Vec<String>>),
+    /// Operation sequence of a parameterized macro, as written in the config.
+    Definition(String),
+}
+
+impl KeymapDump {
+    pub fn new() -> KeymapDump {
+        KeymapDump::default()
+    }
+
+    /// Record that pressing `key` in `context` runs `operation`.
+    pub fn add_binding(&mut self, context: &str, key: &str, operation: &str) {
+        self.bindings
+            .entry(context.to_string())
+            .or_default()
+            .insert(key.to_string(), operation.to_string());
+    }
+
+    /// Append an operation to the macro bound to `key`. `tokens` is the name of the operation
+    /// followed by its arguments.
+    pub fn add_macro_operation(&mut self, key: &str, tokens: Vec<String>) {
+        let entry = self
+            .macros
+            .entry(key.to_string())
+            .or_insert_with(|| MacroDump::Operations(vec![]));
+        match entry {
+            MacroDump::Operations(operations) => operations.push(tokens),
+            MacroDump::Definition(_) => *entry = MacroDump::Operations(vec![tokens]),
+        }
+    }
+
+    /// Record the parameterized macro bound to `key`. `definition` is its operation sequence with
+    /// placeholders, as accepted by `tokenize_macro_operation_sequence()`.
+    pub fn add_macro_definition(&mut self, key: &str, definition: &str) {
+        self.macros.insert(
+            key.to_string(),
+            MacroDump::Definition(definition.to_string()),
+        );
+    }
+
+    /// Serialize the bindings in the given format. The output ends with a newline.
+    pub fn format(&self, format: KeymapDumpFormat) -> String {
+        match format {
+            KeymapDumpFormat::Config => self.to_config(),
+            KeymapDumpFormat::Json => self.to_json(),
+        }
+    }
+
+    fn to_config(&self) -> String {
+        let mut result = String::new();
+        for (context, bindings) in &self.bindings {
+            for (key, operation) in bindings {
+                result.push_str(&format!(
+                    "bind-key {} {} {}\n",
+                    quote_token(key),
+                    quote_token(operation),
+                    quote_token(context)
+                ));
+            }
+        }
+        for (key, operations) in &self.macros {
+            let operations = match operations {
+                MacroDump::Operations(operations) => operations
+                    .iter()
+                    .map(|tokens| {
+                        tokens
+                            .iter()
+                            .map(|token| quote_token(token))
+                            .collect::<Vec<_>>()
+                            .join(" ")
+                    })
+                    .collect::<Vec<_>>()
+                    .join(" ; "),
+                MacroDump::Definition(definition) => definition.clone(),
+            };
+            result.push_str(&format!("macro {} {}\n", quote_token(key), operations));
+        }
+        result
+    }
+
+    fn to_json(&self) -> String {
+        let json_list = |items: Vec<String>, open: char, close: char| -> String {
+            format!("{}{}{}", open, items.join(","), close)
+        };
+
+        let bindings = self
+            .bindings
+            .iter()
+            .map(|(context, bindings)| {
+                let bindings = bindings
+                    .iter()
+                    .map(|(key, operation)| {
+                        format!(
+                            "{}:{}",
+                            utils::quote_for_json(key),
+                            utils::quote_for_json(operation)
+                        )
+                    })
+                    .collect();
+                format!(
+                    "{}:{}",
+                    utils::quote_for_json(context),
+                    json_list(bindings, '{', '}')
+                )
+            })
+            .collect();
+
+        let macros = self
+            .macros
+            .iter()
+            .map(|(key, operations)| {
+                let operations = match operations {
+                    MacroDump::Operations(operations) => {
+                        let operations = operations
+                            .iter()
+                            .map(|tokens| {
+                                let tokens =
+                                    tokens.iter().map(|t| utils::quote_for_json(t)).collect();
+                                json_list(tokens, '[', ']')
+                            })
+                            .collect();
+                        json_list(operations, '[', ']')
+                    }
+                    // Placeholders can't be represented as tokens, so the definition is dumped
+                    // as a string
+                    MacroDump::Definition(definition) => utils::quote_for_json(definition),
+                };
+                format!("{}:{}", utils::quote_for_json(key), operations)
+            })
+            .collect();
+
+        format!(
+            "{{\"bindings\":{},\"macros\":{}}}\n",
+            json_list(bindings, '{', '}'),
+            json_list(macros, '{', '}')
+        )
+    }
+}
+
+/// Quote a token so that `tokenize_operation_sequence()` and the config parser read it back
+/// unchanged.
+fn quote_token(token: &str) -> String {
+    if token.is_empty() || token.contains(&['\t', ';', '"'][..]) {
+        utils::quote(token.to_string())
+    } else {
+        utils::quote_if_necessary(token.to_string())
+    }
+}
+
 #[cfg(test)]
 mod tests {
-    use super::tokenize_operation_sequence;
+    use super::*;
 
     #[test]
     fn t_tokenize_operation_sequence_works_for_all_cpp_inputs() {
@@ -309,4 +868,456 @@ mod tests {
             vec![vec!["set", "a", "b"], vec!["open"]]
         );
     }
+
+    fn literal(text: &str) -> MacroTokenPart {
+        MacroTokenPart::Literal(text.to_string())
+    }
+
+    fn positional(index: usize, modifier: Option<PlaceholderModifier>) -> MacroTokenPart {
+        MacroTokenPart::Placeholder(Placeholder {
+            name: PlaceholderName::Positional(index),
+            modifier,
+        })
+    }
+
+    fn named(name: &str, modifier: Option<PlaceholderModifier>) -> MacroTokenPart {
+        MacroTokenPart::Placeholder(Placeholder {
+            name: PlaceholderName::Named(name.to_string()),
+            modifier,
+        })
+    }
+
+    fn default(parts: Vec<MacroTokenPart>) -> Option<PlaceholderModifier> {
+        Some(PlaceholderModifier::Default(MacroToken::new(parts)))
+    }
+
+    fn alternative(parts: Vec<MacroTokenPart>) -> Option<PlaceholderModifier> {
+        Some(PlaceholderModifier::Alternative(MacroToken::new(parts)))
+    }
+
+    fn macro_parts(input: &str) -> Vec<Vec<Vec<MacroTokenPart>>> {
+        tokenize_macro_operation_sequence(input)
+            .unwrap()
+            .into_iter()
+            .map(|tokens| tokens.iter().map(|t| t.parts().to_vec()).collect())
+            .collect()
+    }
+
+    #[test]
+    fn t_tokenize_macro_operation_sequence_matches_tokenize_operation_sequence_without_placeholders(
+    ) {
+        let inputs = [
+            "",
+            "open",
+            "; ; ; ;",
+            "open ; next ; prev",
+            r#"set "arg 1" ; set "arg 2" "arg 3""#,
+            r#"open;set browser "firefox --private-window";quit"#,
+            r#"set browser "sleep 3; do-something ; echo hi"; open-in-browser"#,
+            r#"\t "\t" "\x" set "arg 1"#,
+            " \t set a b \t   ",
+        ];
+        for input in &inputs {
+            let args = MacroArguments::default();
+            let operations = tokenize_macro_operation_sequence(input).unwrap();
+            assert!(operations.iter().flatten().all(MacroToken::is_literal));
+            assert_eq!(
+                expand_operation_sequence(&operations, &args).unwrap(),
+                tokenize_operation_sequence(input).unwrap()
+            );
+        }
+    }
+
+    #[test]
+    fn t_tokenize_macro_operation_sequence_parses_positional_placeholders() {
+        assert_eq!(
+            macro_parts("set browser $1; open-in-browser"),
+            vec![
+                vec![
+                    vec![literal("set")],
+                    vec![literal("browser")],
+                    vec![positional(1, None)]
+                ],
+                vec![vec![literal("open-in-browser")]]
+            ]
+        );
+        assert_eq!(
+            macro_parts("set $2 $12"),
+            vec![vec![
+                vec![literal("set")],
+                vec![positional(2, None)],
+                vec![positional(12, None)]
+            ]]
+        );
+        assert_eq!(
+            macro_parts("set browser ${1}-bin"),
+            vec![vec![
+                vec![literal("set")],
+                vec![literal("browser")],
+                vec![positional(1, None), literal("-bin")]
+            ]]
+        );
+    }
+
+    #[test]
+    fn t_tokenize_macro_operation_sequence_parses_named_placeholders() {
+        assert_eq!(
+            macro_parts("set browser ${browser}"),
+            vec![vec![
+                vec![literal("set")],
+                vec![literal("browser")],
+                vec![named("browser", None)]
+            ]]
+        );
+        assert_eq!(
+            macro_parts(r#"set browser "${my_browser-2} --new-tab""#),
+            vec![vec![
+                vec![literal("set")],
+                vec![literal("browser")],
+                vec![named("my_browser-2", None), literal(" --new-tab")]
+            ]]
+        );
+    }
+
+    #[test]
+    fn t_tokenize_macro_operation_sequence_parses_default_values() {
+        assert_eq!(
+            macro_parts("set browser ${1:-lynx}"),
+            vec![vec![
+                vec![literal("set")],
+                vec![literal("browser")],
+                vec![positional(1, default(vec![literal("lynx")]))]
+            ]]
+        );
+        assert_eq!(
+            macro_parts(r#"set browser "${browser:-firefox --private-window}""#),
+            vec![vec![
+                vec![literal("set")],
+                vec![literal("browser")],
+                vec![named(
+                    "browser",
+                    default(vec![literal("firefox --private-window")])
+                )]
+            ]]
+        );
+        assert_eq!(
+            macro_parts("set browser ${1:-}"),
+            vec![vec![
+                vec![literal("set")],
+                vec![literal("browser")],
+                vec![positional(1, default(vec![]))]
+            ]]
+        );
+    }
+
+    #[test]
+    fn t_tokenize_macro_operation_sequence_does_not_let_unquoted_defaults_span_tokens() {
+        assert_eq!(
+            macro_parts("set ${1:-a b}"),
+            vec![vec![
+                vec![literal("set")],
+                vec![literal("${1:-a")],
+                vec![literal("b}")]
+            ]]
+        );
+        assert_eq!(
+            macro_parts("set ${1:-a;b}"),
+            vec![
+                vec![vec![literal("set")], vec![literal("${1:-a")]],
+                vec![vec![literal("b}")]]
+            ]
+        );
+    }
+
+    #[test]
+    fn t_tokenize_macro_operation_sequence_supports_escaping_dollar_sign() {
+        assert_eq!(
+            macro_parts("set $$1 $$$1"),
+            vec![vec![
+                vec![literal("set")],
+                vec![literal("$1")],
+                vec![literal("$"), positional(1, None)]
+            ]]
+        );
+        assert_eq!(
+            macro_parts(r#"set "\$1" "$${name}""#),
+            vec![vec![
+                vec![literal("set")],
+                vec![literal("$1")],
+                vec![literal("${name}")]
+            ]]
+        );
+    }
+
+    #[test]
+    fn t_tokenize_macro_operation_sequence_keeps_dollar_signs_that_dont_start_placeholders() {
+        assert_eq!(
+            macro_parts(r#"set browser "echo $URL $0 ${} ${1" $"#),
+            vec![vec![
+                vec![literal("set")],
+                vec![literal("browser")],
+                vec![literal("echo $URL $0 ${} ${1")],
+                vec![literal("$")]
+            ]]
+        );
+    }
+
+    #[test]
+    fn t_expand_operation_sequence_replaces_placeholders_with_arguments() {
+        let operations = tokenize_macro_operation_sequence(
+            r#"set browser "${1:-lynx} ${flags:-}"; open-in-browser $2"#,
+        )
+        .unwrap();
+
+        let args = MacroArguments::new(vec!["firefox".to_string(), "x y".to_string()]);
+        assert_eq!(
+            expand_operation_sequence(&operations, &args).unwrap(),
+            vec![
+                vec!["set", "browser", "firefox "],
+                vec!["open-in-browser", "x y"]
+            ]
+        );
+
+        let mut args = MacroArguments::new(vec![]);
+        args.set_named("flags", "--new-tab");
+        assert_eq!(
+            expand_operation_sequence(&operations, &args),
+            Err(MacroExpansionError::MissingArgument(
+                PlaceholderName::Positional(2)
+            ))
+        );
+
+        let args = MacroArguments::new(vec![String::new(), "2".to_string()]);
+        assert_eq!(
+            expand_operation_sequence(&operations, &args).unwrap(),
+            vec![
+                vec!["set", "browser", "lynx "],
+                vec!["open-in-browser", "2"]
+            ]
+        );
+    }
+
+    #[test]
+    fn t_tokenize_macro_operation_sequence_parses_alternative_values() {
+        assert_eq!(
+            macro_parts(r#"set browser "firefox${1:+ --profile $1}""#),
+            vec![vec![
+                vec![literal("set")],
+                vec![literal("browser")],
+                vec![
+                    literal("firefox"),
+                    positional(
+                        1,
+                        alternative(vec![literal(" --profile "), positional(1, None)])
+                    )
+                ]
+            ]]
+        );
+        assert_eq!(
+            macro_parts("${name:+open-in-browser}"),
+            vec![vec![vec![named(
+                "name",
+                alternative(vec![literal("open-in-browser")])
+            )]]]
+        );
+    }
+
+    #[test]
+    fn t_tokenize_macro_operation_sequence_allows_placeholders_in_default_values() {
+        assert_eq!(
+            macro_parts("set browser ${1:-${browser}}"),
+            vec![vec![
+                vec![literal("set")],
+                vec![literal("browser")],
+                vec![positional(1, default(vec![named("browser", None)]))]
+            ]]
+        );
+    }
+
+    #[test]
+    fn t_tokenize_macro_operation_sequence_processes_escapes_in_quoted_default_values() {
+        assert_eq!(
+            macro_parts(r#"set browser "${1:-echo \"a\}b\" \\ \$2\t}""#),
+            vec![vec![
+                vec![literal("set")],
+                vec![literal("browser")],
+                vec![positional(
+                    1,
+                    default(vec![literal("echo \"a}b\" \\ $2\t")])
+                )]
+            ]]
+        );
+    }
+
+    #[test]
+    fn t_tokenize_macro_operation_sequence_keeps_backslashes_in_unquoted_default_values() {
+        assert_eq!(
+            macro_parts(r#"set browser ${1:-a\tb}"#),
+            vec![vec![
+                vec![literal("set")],
+                vec![literal("browser")],
+                vec![positional(1, default(vec![literal(r#"a\tb"#)]))]
+            ]]
+        );
+    }
+
+    #[test]
+    fn t_expand_operation_sequence_expands_alternative_only_for_non_empty_arguments() {
+        let operations =
+            tokenize_macro_operation_sequence(r#"set browser "lynx${1:+ -useragent=$1}""#).unwrap();
+
+        let args = MacroArguments::new(vec!["X".to_string()]);
+        assert_eq!(
+            expand_operation_sequence(&operations, &args).unwrap(),
+            vec![vec!["set", "browser", "lynx -useragent=X"]]
+        );
+
+        for args in &[
+            MacroArguments::new(vec![]),
+            MacroArguments::new(vec![String::new()]),
+        ] {
+            assert_eq!(
+                expand_operation_sequence(&operations, args).unwrap(),
+                vec![vec!["set", "browser", "lynx"]]
+            );
+        }
+    }
+
+    #[test]
+    fn t_expand_operation_sequence_skips_operations_whose_name_expands_to_nothing() {
+        let operations = tokenize_macro_operation_sequence(
+            "${1:+set} browser $1; ${flag:+toggle-article-read}; open-in-browser",
+        )
+        .unwrap();
+
+        assert_eq!(
+            expand_operation_sequence(&operations, &MacroArguments::default()).unwrap(),
+            vec![vec!["open-in-browser"]]
+        );
+
+        let mut args = MacroArguments::new(vec!["w3m".to_string()]);
+        args.set_named("flag", "yes");
+        assert_eq!(
+            expand_operation_sequence(&operations, &args).unwrap(),
+            vec![
+                vec!["set", "browser", "w3m"],
+                vec!["toggle-article-read"],
+                vec!["open-in-browser"]
+            ]
+        );
+    }
+
+    #[test]
+    fn t_macro_token_placeholder_names_include_nested_placeholders() {
+        let operations =
+            tokenize_macro_operation_sequence(r#"set x "$1 ${a:-${2:+${b}}}""#).unwrap();
+        assert_eq!(
+            operations[0][2].placeholder_names(),
+            vec![
+                PlaceholderName::Positional(1),
+                PlaceholderName::Named("a".to_string()),
+                PlaceholderName::Positional(2),
+                PlaceholderName::Named("b".to_string()),
+            ]
+        );
+        assert!(operations[0][0].placeholder_names().is_empty());
+    }
+
+    fn sample_keymap_dump() -> KeymapDump {
+        let mut dump = KeymapDump::new();
+        dump.add_binding("feedlist", "ENTER", "open");
+        dump.add_binding("feedlist", "q", "quit");
+        dump.add_binding("article", "SPACE", "pagedown");
+        dump.add_macro_operation(
+            "o",
+            vec![
+                "set".to_string(),
+                "browser".to_string(),
+                "firefox --new-tab".to_string(),
+            ],
+        );
+        dump.add_macro_operation("o", vec!["open-in-browser".to_string()]);
+        dump.add_macro_operation(
+            "s",
+            vec!["set".to_string(), "x".to_string(), "a;b".to_string()],
+        );
+        dump
+    }
+
+    #[test]
+    fn t_keymap_dump_format_parses_known_names() {
+        assert_eq!("config".parse(), Ok(KeymapDumpFormat::Config));
+        assert_eq!("json".parse(), Ok(KeymapDumpFormat::Json));
+        assert_eq!("JSON".parse::<KeymapDumpFormat>(), Err(()));
+        assert_eq!("".parse::<KeymapDumpFormat>(), Err(()));
+    }
+
+    #[test]
+    fn t_keymap_dump_as_config_produces_bind_key_and_macro_lines() {
+        assert_eq!(
+            sample_keymap_dump().format(KeymapDumpFormat::Config),
+            concat!(
+                "bind-key SPACE pagedown article\n",
+                "bind-key ENTER open feedlist\n",
+                "bind-key q quit feedlist\n",
+                "macro o set browser \"firefox --new-tab\" ; open-in-browser\n",
+                "macro s set x \"a;b\"\n",
+            )
+        );
+    }
+
+    #[test]
+    fn t_keymap_dump_as_config_round_trips_macros_through_tokenizer() {
+        let config = sample_keymap_dump().format(KeymapDumpFormat::Config);
+        let macro_line = config
+            .lines()
+            .find(|line| line.starts_with("macro o "))
+            .unwrap();
+        assert_eq!(
+            tokenize_operation_sequence(&macro_line["macro o ".len()..]).unwrap(),
+            vec![
+                vec!["set", "browser", "firefox --new-tab"],
+                vec!["open-in-browser"]
+            ]
+        );
+    }
+
+    #[test]
+    fn t_keymap_dump_as_json_produces_a_single_object() {
+        assert_eq!(
+            sample_keymap_dump().format(KeymapDumpFormat::Json),
+            concat!(
+                r#"{"bindings":{"article":{"SPACE":"pagedown"},"feedlist":{"ENTER":"open","q":"quit"}},"#,
+                r#""macros":{"o":[["set","browser","firefox --new-tab"],["open-in-browser"]],"#,
+                r#""s":[["set","x","a;b"]]}}"#,
+                "\n"
+            )
+        );
+        assert_eq!(
+            KeymapDump::new().format(KeymapDumpFormat::Json),
+            "{\"bindings\":{},\"macros\":{}}\n"
+        );
+    }
+
+    #[test]
+    fn t_keymap_dump_prints_parameterized_macros_as_written() {
+        let mut dump = KeymapDump::new();
+        dump.add_macro_definition("o", r#"set browser "${1:-lynx}" ; open-in-browser"#);
+        dump.add_macro_operation("q", vec!["quit".to_string()]);
+        assert_eq!(
+            dump.format(KeymapDumpFormat::Config),
+            concat!(
+                "macro o set browser \"${1:-lynx}\" ; open-in-browser\n",
+                "macro q quit\n",
+            )
+        );
+        assert_eq!(
+            dump.format(KeymapDumpFormat::Json),
+            concat!(
+                r#"{"bindings":{},"macros":{"o":"set browser \"${1:-lynx}\" ; open-in-browser","#,
+                r#""q":[["quit"]]}}"#,
+                "\n"
+            )
+        );
+    }
 }
@@ -9,8 +9,12 @@ pub mod logger;
 pub mod human_panic;
 pub mod utils;
 
+pub mod cachequery;
 pub mod cliargsparser;
+pub mod configcheck;
 pub mod configpaths;
+pub mod controlsocket;
+pub mod feedsource;
 pub mod filterparser;
 pub mod fmtstrformatter;
 pub mod fslock;
@@ -20,4 +24,5 @@ pub mod keymap;
 pub mod matchable;
 pub mod matcher;
 pub mod matchererror;
+pub mod migration;
 pub mod scopemeasure;
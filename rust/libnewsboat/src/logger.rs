@@ -1,12 +1,17 @@
 //! Keeps a record of what the program did.
 
+use crate::utils;
 use chrono::{offset::Local, Datelike, Timelike};
 use once_cell::sync::OnceCell;
+use std::collections::VecDeque;
+use std::ffi::OsString;
 use std::fmt;
-use std::fs::{File, OpenOptions};
-use std::io::Write;
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
 use std::sync::atomic::{AtomicIsize, Ordering};
-use std::sync::Mutex;
+use std::sync::{Mutex, TryLockError};
 
 #[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Debug)]
 /// "Importance levels" for log messages.
@@ -62,16 +67,245 @@ impl fmt::Display for Level {
     }
 }
 
+impl FromStr for Level {
+    type Err = ();
+
+    /// Parses a level either from its number (1 for UserError to 6 for Debug), or from its
+    /// lowercase name: "usererror", "critical", "error", "warn", "info", or "debug".
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "1" | "usererror" => Ok(Level::UserError),
+            "2" | "critical" => Ok(Level::Critical),
+            "3" | "error" => Ok(Level::Error),
+            "4" | "warn" => Ok(Level::Warn),
+            "5" | "info" => Ok(Level::Info),
+            "6" | "debug" => Ok(Level::Debug),
+            _ => Err(()),
+        }
+    }
+}
+
+/// Decides which messages make it to the log, based on their level and the module they came from.
+///
+/// A filter is written as a comma-separated list of directives, e.g. `info,libnewsboat::fslock=debug`.
+/// A directive is either a level, which applies to all messages, or `path=level`, which applies to
+/// messages from the module `path` and its submodules. If several paths match, the longest one
+/// wins. See `Level::from_str()` for the accepted levels.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct LogFilter {
+    /// Level for messages that don't match any path. If `None`, only user errors are logged.
+    pub default_level: Option<Level>,
+
+    /// Module paths and their levels, longest paths first.
+    directives: Vec<(String, Level)>,
+}
+
+impl LogFilter {
+    /// Returns the level for messages that came from `target`.
+    pub fn level_for(&self, target: &str) -> Option<Level> {
+        self.directives
+            .iter()
+            .find(|(path, _)| target_matches(target, path))
+            .map(|&(_, level)| level)
+            .or(self.default_level)
+    }
+}
+
+impl FromStr for LogFilter {
+    /// The directive that couldn't be parsed.
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut filter = LogFilter::default();
+        for directive in s.split(',').map(str::trim).filter(|d| !d.is_empty()) {
+            match directive.find('=') {
+                Some(pos) => {
+                    let path = directive[..pos].trim();
+                    let level = directive[pos + 1..].trim().parse::<Level>();
+                    match level {
+                        Ok(level) if !path.is_empty() => {
+                            filter.directives.retain(|(p, _)| p != path);
+                            filter.directives.push((path.to_string(), level));
+                        }
+                        _ => return Err(directive.to_string()),
+                    }
+                }
+                None => {
+                    let level = directive
+                        .parse::<Level>()
+                        .map_err(|_| directive.to_string())?;
+                    filter.default_level = Some(level);
+                }
+            }
+        }
+        if filter.default_level.is_none() && filter.directives.is_empty() {
+            return Err(s.to_string());
+        }
+        // Stable sort, so among equally long paths the one specified earlier wins.
+        filter
+            .directives
+            .sort_by_key(|(path, _)| std::cmp::Reverse(path.len()));
+        Ok(filter)
+    }
+}
+
+impl fmt::Display for LogFilter {
+    /// Writes the filter in the form accepted by `LogFilter::from_str()`.
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let mut directives = self
+            .directives
+            .iter()
+            .map(|(path, level)| format!("{}={}", path, *level as isize))
+            .collect::<Vec<_>>();
+        if let Some(level) = self.default_level {
+            directives.insert(0, (level as isize).to_string());
+        }
+        write!(f, "{}", directives.join(","))
+    }
+}
+
+/// Checks if `target` is the module `path` or one of its submodules.
+fn target_matches(target: &str, path: &str) -> bool {
+    target == path || (target.starts_with(path) && target[path.len()..].starts_with("::"))
+}
+
+/// How records are written to the general log.
+// This has to be in sync with LogFormat in include/logger.h
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+// TODO: remove repr(C) when we finished porting C++ code to Rust.
+#[repr(C)]
+pub enum LogFormat {
+    /// `[timestamp] LEVEL: message` lines. Messages containing newlines span multiple lines.
+    Text = 1,
+
+    /// JSON Lines: one object per record, with "timestamp", "level" and "message" fields, plus
+    /// "target" if the origin of the message is known.
+    Json,
+}
+
+impl FromStr for LogFormat {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "text" => Ok(LogFormat::Text),
+            "json" => Ok(LogFormat::Json),
+            _ => Err(()),
+        }
+    }
+}
+
+/// Limits on the size of a log.
+///
+/// When writing a record would make the log bigger than `max_size` bytes, the log is rotated:
+/// `log` is renamed to `log.1`, `log.1` to `log.2` and so on, and a new, empty `log` is created.
+/// At most `max_files` files are kept, including the current one; the oldest is deleted.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct LogRotation {
+    /// Maximum size of a log file, in bytes. A single record that is bigger than this is still
+    /// written, to a file of its own.
+    pub max_size: u64,
+
+    /// Maximum number of files to keep. If it's 1 (or 0), the log is simply truncated.
+    pub max_files: u32,
+}
+
+/// An open log file, along with what's needed to rotate it.
+struct LogFile {
+    file: File,
+    path: PathBuf,
+
+    /// The number of bytes in the file, including what was there before we opened it.
+    size: u64,
+}
+
+impl LogFile {
+    /// Opens the file in append mode, creating it if it doesn't exist yet.
+    fn open(path: &Path) -> io::Result<LogFile> {
+        let file = OpenOptions::new().create(true).append(true).open(path)?;
+        let size = file.metadata()?.len();
+        Ok(LogFile {
+            file,
+            path: path.to_owned(),
+            size,
+        })
+    }
+
+    /// Writes `parts` one after another, rotating the file first if they wouldn't fit.
+    ///
+    /// Errors are ignored since checking every log() call will be too bothersome.
+    fn write(&mut self, parts: &[&[u8]], rotation: Option<LogRotation>) {
+        let length = parts.iter().map(|part| part.len() as u64).sum::<u64>();
+
+        if let Some(rotation) = rotation {
+            if self.size > 0 && self.size + length > rotation.max_size {
+                // If the rotation failed, we keep writing to the current file, and retry with the
+                // next record.
+                let _ = self.rotate(rotation.max_files);
+            }
+        }
+
+        for part in parts {
+            let _ = self.file.write_all(part);
+        }
+        self.size += length;
+    }
+
+    fn rotate(&mut self, max_files: u32) -> io::Result<()> {
+        if max_files > 1 {
+            // Some of the older files might not exist yet, so we ignore errors here.
+            for i in (1..max_files - 1).rev() {
+                let _ = fs::rename(self.rotated_path(i), self.rotated_path(i + 1));
+            }
+            fs::rename(&self.path, self.rotated_path(1))?;
+        } else {
+            fs::remove_file(&self.path)?;
+        }
+
+        *self = LogFile::open(&self.path)?;
+        Ok(())
+    }
+
+    /// Returns the path of the `n`-th oldest rotated file, e.g. `newsboat.log.2`.
+    fn rotated_path(&self, n: u32) -> PathBuf {
+        let mut path = OsString::from(self.path.as_os_str());
+        path.push(format!(".{}", n));
+        PathBuf::from(path)
+    }
+}
+
+/// How many messages are kept in memory by default. See `Logger::set_recent_messages()`.
+pub const DEFAULT_RECENT_MESSAGES_CAPACITY: usize = 100;
+
+/// Maximum "importance level" of the messages kept in memory by default.
+pub const DEFAULT_RECENT_MESSAGES_LEVEL: Level = Level::Info;
+
+/// The most recent messages, kept in memory so that crash reports can include them.
+struct RecentMessages {
+    /// How many messages to keep. The oldest are dropped to make room for new ones.
+    capacity: usize,
+
+    /// The messages, oldest first, formatted like in `LogFormat::Text`.
+    messages: VecDeque<String>,
+}
+
 /// Stores the handles for logfiles.
 ///
 /// This is part of `Logger` struct. This struct is not thread-safe, but in `Logger`, it will be
 /// behind a `Mutex`.
 struct LogFiles {
     /// The file to which all messages at and above `loglevel` will be written.
-    logfile: Option<File>,
+    logfile: Option<LogFile>,
 
     /// The file to which all Level::UserError messages will be written.
-    user_error_logfile: Option<File>,
+    user_error_logfile: Option<LogFile>,
+
+    /// The format of records in `logfile`. `user_error_logfile` is meant for people to read, so
+    /// it's always plain text.
+    format: LogFormat,
+
+    /// If set, both logs are rotated independently when they get bigger than this allows.
+    rotation: Option<LogRotation>,
 }
 
 /// Keeps a record of what the program did.
@@ -84,7 +318,12 @@ struct LogFiles {
 /// Another, user-specific log, is created after the call to set_user_error_logfile(). Only
 /// Level::UserLevel messages are written to that one.
 ///
-/// Each message in the log is time-stamped, and marked with its importance level.
+/// Regardless of logfiles, a few recent messages are kept in memory, so that crash reports can
+/// include them. See set_recent_messages().
+///
+/// Each message in the log is time-stamped, and marked with its importance level. By default, the
+/// general log is plain text; set_log_format() switches it to JSON Lines, which stays
+/// machine-readable even if messages contain newlines.
 ///
 /// This is meant to be a long-lived, shared object that exists for the duration of the program.
 /// Users would call its `log` method to add messages to the log file, like this:
@@ -112,6 +351,15 @@ pub struct Logger {
 
     /// Maximum "importance level" of the messages that will be written to the log.
     loglevel: AtomicIsize,
+
+    /// Per-module overrides of `loglevel`, longest module paths first. See `LogFilter`.
+    directives: Mutex<Vec<(String, Level)>>,
+
+    /// Maximum "importance level" of the messages that will be kept in `recent`.
+    recent_level: AtomicIsize,
+
+    /// Recent messages, kept in memory even if there is no logfile.
+    recent: Mutex<RecentMessages>,
 }
 
 impl Logger {
@@ -123,8 +371,16 @@ impl Logger {
             files: Mutex::new(LogFiles {
                 logfile: None,
                 user_error_logfile: None,
+                format: LogFormat::Text,
+                rotation: None,
             }),
             loglevel: AtomicIsize::new(-1_isize),
+            directives: Mutex::new(Vec::new()),
+            recent_level: AtomicIsize::new(DEFAULT_RECENT_MESSAGES_LEVEL as isize),
+            recent: Mutex::new(RecentMessages {
+                capacity: DEFAULT_RECENT_MESSAGES_CAPACITY,
+                messages: VecDeque::with_capacity(DEFAULT_RECENT_MESSAGES_CAPACITY),
+            }),
         }
     }
 
@@ -140,7 +396,7 @@ impl Logger {
     /// This can't fail, but if the file couldn't be created or opened, an error message will be
     /// printed to stderr.
     pub fn set_logfile(&self, filename: &str) {
-        let file = OpenOptions::new().create(true).append(true).open(filename);
+        let file = LogFile::open(Path::new(filename));
 
         match file {
             Ok(file) => {
@@ -163,7 +419,7 @@ impl Logger {
     /// This can't fail, but if the file couldn't be created or opened, an error message will be
     /// printed to stderr.
     pub fn set_user_error_logfile(&self, filename: &str) {
-        let file = OpenOptions::new().create(true).append(true).open(filename);
+        let file = LogFile::open(Path::new(filename));
 
         match file {
             Ok(file) => {
@@ -177,15 +433,86 @@ impl Logger {
         }
     }
 
+    /// Sets the format of records in the general log. Records that were already written are left
+    /// as they are.
+    pub fn set_log_format(&self, format: LogFormat) {
+        let mut files = self.files.lock().expect("Someone poisoned logger's mutex");
+        files.format = format;
+    }
+
+    /// Limits the size of the logs, see `LogRotation`. Both logs are rotated independently of each
+    /// other. `None` lets the logs grow without bound, which is the default.
+    ///
+    /// The limit applies to the records written from now on: if a log is already too big, it's
+    /// rotated before the next record is written to it.
+    pub fn set_rotation(&self, rotation: Option<LogRotation>) {
+        let mut files = self.files.lock().expect("Someone poisoned logger's mutex");
+        files.rotation = rotation;
+    }
+
+    /// Sets how many of the most recent messages are kept in memory, and the maximum "importance
+    /// level" of such messages. `None` disables keeping messages in memory.
+    ///
+    /// These messages are kept whether or not there is a logfile, and regardless of the loglevel.
+    /// By default, `DEFAULT_RECENT_MESSAGES_CAPACITY` messages at `DEFAULT_RECENT_MESSAGES_LEVEL`
+    /// are kept.
+    pub fn set_recent_messages(&self, capacity: usize, level: Option<Level>) {
+        let mut recent = self.recent.lock().expect("Someone poisoned logger's mutex");
+        recent.capacity = capacity;
+        while recent.messages.len() > capacity {
+            recent.messages.pop_front();
+        }
+        let level = level.map(|l| l as isize).unwrap_or(-1_isize);
+        self.recent_level.store(level, Ordering::SeqCst);
+    }
+
+    /// Returns the most recent messages, oldest first. See `set_recent_messages()`.
+    ///
+    /// This is meant to be used from a panic hook, so it never blocks: if the messages are being
+    /// modified at the moment, nothing is returned.
+    pub fn recent_messages(&self) -> Vec<String> {
+        let recent = match self.recent.try_lock() {
+            Ok(recent) => recent,
+            // A panic while the lock was held doesn't invalidate the messages.
+            Err(TryLockError::Poisoned(error)) => error.into_inner(),
+            Err(TryLockError::WouldBlock) => return vec![],
+        };
+        recent.messages.iter().cloned().collect()
+    }
+
     /// Writes a message to a log.
     ///
     /// This method is a wrapper around `log_raw()`.
     pub fn log(&self, level: Level, message: &str) {
-        if level == Level::UserError || level as isize <= self.get_loglevel() {
-            self.log_raw(level, message.as_bytes())
+        let max_level = self.get_loglevel();
+        if self.is_recorded(level, max_level) {
+            self.write_record(level, max_level, None, message.as_bytes())
         }
     }
 
+    /// Like `log()`, but also records where the message came from, e.g. the module path.
+    ///
+    /// Unlike `log()`, this takes into account the per-module levels set with `set_filter()`.
+    pub fn log_from(&self, level: Level, target: &str, message: &str) {
+        let max_level = self.max_level_for(Some(target));
+        if self.is_recorded(level, max_level) {
+            self.write_record(level, max_level, Some(target), message.as_bytes())
+        }
+    }
+
+    /// Checks if a message at `level` from `target` would end up anywhere: in the logs or among
+    /// the recent messages. Use this to skip work that only matters for such messages.
+    pub fn is_recorded_from(&self, level: Level, target: &str) -> bool {
+        self.is_recorded(level, self.max_level_for(Some(target)))
+    }
+
+    /// Checks if a message at `level` ends up anywhere: in the logs or among the recent messages.
+    fn is_recorded(&self, level: Level, max_level: isize) -> bool {
+        level == Level::UserError
+            || level as isize <= max_level
+            || level as isize <= self.recent_level.load(Ordering::Relaxed)
+    }
+
     /// Writes binary data to the log.
     ///
     /// This method is primarily used for logging things received from C++. Since there is no
@@ -203,39 +530,89 @@ impl Logger {
     /// If the message couldn't be written for whatever reason, this function ignores the failure.
     /// Were you to check the return value of every log() call, you'd just stop writing logs.
     pub fn log_raw(&self, level: Level, data: &[u8]) {
-        let timestamp = Local::now();
+        self.log_raw_from(level, None, data)
+    }
+
+    /// Like `log_raw()`, but also records where the message came from, if that's known.
+    ///
+    /// The origin is only written to the log in `LogFormat::Json`.
+    pub fn log_raw_from(&self, level: Level, target: Option<&str>, data: &[u8]) {
+        self.write_record(level, self.max_level_for(target), target, data)
+    }
+
+    /// Returns the maximum "importance level" of the messages from `target` that will be written
+    /// to the log.
+    fn max_level_for(&self, target: Option<&str>) -> isize {
+        if let Some(target) = target {
+            let directives = self
+                .directives
+                .lock()
+                .expect("Someone poisoned logger's mutex");
+            if let Some(&(_, level)) = directives
+                .iter()
+                .find(|(path, _)| target_matches(target, path))
+            {
+                return level as isize;
+            }
+        }
+        self.get_loglevel()
+    }
+
+    /// Writes the message to the general log if `level` is at most `max_level`, and to the user
+    /// error log if it's a user error.
+    ///
+    /// Also keeps the message among the recent ones if its level is high enough.
+    fn write_record(&self, level: Level, max_level: isize, target: Option<&str>, data: &[u8]) {
+        let now = Local::now();
         // DateTime::format() is extremely slow; format! is way faster. See
         // https://github.com/chronotope/chrono/issues/94 for details.
         let timestamp = format!(
             "[{}-{:02}-{:02} {:02}:{:02}:{:02}] ",
-            timestamp.year(),
-            timestamp.month(),
-            timestamp.day(),
-            timestamp.hour(),
-            timestamp.minute(),
-            timestamp.second()
+            now.year(),
+            now.month(),
+            now.day(),
+            now.hour(),
+            now.minute(),
+            now.second()
         );
 
+        if level as isize <= self.recent_level.load(Ordering::Relaxed) {
+            let mut recent = self.recent.lock().expect("Someone poisoned logger's mutex");
+            if recent.capacity > 0 {
+                if recent.messages.len() == recent.capacity {
+                    recent.messages.pop_front();
+                }
+                let message = format!("{}{}: {}", timestamp, level, String::from_utf8_lossy(data));
+                recent.messages.push_back(message);
+            }
+        }
+
         let mut files = self.files.lock().expect("Someone poisoned logger's mutex");
+        let format = files.format;
+        let rotation = files.rotation;
 
-        if level as isize <= self.get_loglevel() {
+        if level as isize <= max_level {
             if let Some(ref mut logfile) = files.logfile {
-                let level = format!("{}: ", level);
-
-                // Ignoring the error since checking every log() call will be too bothersome.
-                let _ = logfile.write_all(timestamp.as_bytes());
-                let _ = logfile.write_all(level.as_bytes());
-                let _ = logfile.write_all(data);
-                let _ = logfile.write_all(b"\n");
+                match format {
+                    LogFormat::Text => {
+                        let level = format!("{}: ", level);
+
+                        logfile.write(
+                            &[timestamp.as_bytes(), level.as_bytes(), data, b"\n"],
+                            rotation,
+                        );
+                    }
+                    LogFormat::Json => {
+                        let record = json_record(&now, level, target, data);
+                        logfile.write(&[record.as_bytes()], rotation);
+                    }
+                }
             }
         }
 
         if level == Level::UserError {
             if let Some(ref mut user_error_logfile) = files.user_error_logfile {
-                // Ignoring the error since checking every log() call will be too bothersome.
-                let _ = user_error_logfile.write_all(timestamp.as_bytes());
-                let _ = user_error_logfile.write_all(data);
-                let _ = user_error_logfile.write_all(b"\n");
+                user_error_logfile.write(&[timestamp.as_bytes(), data, b"\n"], rotation);
             }
         }
     }
@@ -250,6 +627,25 @@ impl Logger {
         self.loglevel.store(level as isize, Ordering::SeqCst);
     }
 
+    /// Sets the level for each module as described by `filter`. Messages from modules that the
+    /// filter doesn't mention are logged at `filter.default_level`.
+    ///
+    /// Only messages written with `log_from()` and `log_raw_from()` (e.g. by the `log!` macro) are
+    /// attributed to modules. The rest are logged at the default level.
+    ///
+    /// Calling this doesn't close already opened logs.
+    pub fn set_filter(&self, filter: LogFilter) {
+        let mut directives = self
+            .directives
+            .lock()
+            .expect("Someone poisoned logger's mutex");
+        *directives = filter.directives;
+        match filter.default_level {
+            Some(level) => self.set_loglevel(level),
+            None => self.unset_loglevel(),
+        }
+    }
+
     /// Disables Logging (Except for UserError messages).
     pub fn unset_loglevel(&self) {
         self.loglevel.store(-1_isize, Ordering::SeqCst);
@@ -257,10 +653,54 @@ impl Logger {
 
     /// Returns current maximum "importance level" of the messages that will be written to the log.
     ///
-    /// For a more detailed explanation, see `set_loglevel()`.
+    /// For a more detailed explanation, see `set_loglevel()`. Per-module levels set by
+    /// `set_filter()` aren't taken into account.
     pub fn get_loglevel(&self) -> isize {
         self.loglevel.load(Ordering::Relaxed)
     }
+
+    /// Returns the maximum "importance level" of the messages that are recorded anywhere: in the
+    /// log, or among the recent messages (see `set_recent_messages()`).
+    ///
+    /// Messages above this level can be dropped without even formatting them. Per-module levels
+    /// set by `set_filter()` aren't taken into account.
+    pub fn get_recorded_level(&self) -> isize {
+        self.get_loglevel()
+            .max(self.recent_level.load(Ordering::Relaxed))
+    }
+}
+
+/// Formats a log record as a line of JSON.
+fn json_record(
+    now: &chrono::DateTime<Local>,
+    level: Level,
+    target: Option<&str>,
+    data: &[u8],
+) -> String {
+    let timestamp = format!(
+        "{}-{:02}-{:02}T{:02}:{:02}:{:02}.{:03}{}",
+        now.year(),
+        now.month(),
+        now.day(),
+        now.hour(),
+        now.minute(),
+        now.second(),
+        now.nanosecond() % 1_000_000_000 / 1_000_000,
+        now.offset()
+    );
+    let mut record = format!(
+        "{{\"timestamp\":{},\"level\":{}",
+        utils::quote_for_json(&timestamp),
+        utils::quote_for_json(&level.to_string())
+    );
+    if let Some(target) = target {
+        record.push_str(",\"target\":");
+        record.push_str(&utils::quote_for_json(target));
+    }
+    record.push_str(",\"message\":");
+    record.push_str(&utils::quote_for_json(&String::from_utf8_lossy(data)));
+    record.push_str("}\n");
+    record
 }
 
 impl Default for Logger {
@@ -292,10 +732,10 @@ pub fn get_instance() -> &'static Logger {
 #[macro_export]
 macro_rules! log {
     ( $level:expr, $message:expr ) => {
-        logger::get_instance().log($level, $message);
+        logger::get_instance().log_from($level, module_path!(), $message);
     };
     ( $level:expr, $format:expr, $( $arg:expr ),+ ) => {
-        logger::get_instance().log($level, &format!($format, $( $arg ),+));
+        logger::get_instance().log_from($level, module_path!(), &format!($format, $( $arg ),+));
     }
 }
 
@@ -388,6 +828,8 @@ mod tests {
     struct LogLinesCounter {
         messages: Vec<(Level, String)>,
         levels: Vec<Option<Level>>,
+        filters: Vec<&'static str>,
+        target: Option<&'static str>,
         expected_log_lines: Option<usize>,
         expected_errorlog_lines: Option<usize>,
     }
@@ -397,6 +839,8 @@ mod tests {
             LogLinesCounter {
                 messages: vec![],
                 levels: vec![],
+                filters: vec![],
+                target: None,
                 expected_log_lines: None,
                 expected_errorlog_lines: None,
             }
@@ -412,6 +856,16 @@ mod tests {
             self
         }
 
+        pub fn with_filters(mut self, filters: Vec<&'static str>) -> Self {
+            self.filters = filters;
+            self
+        }
+
+        pub fn logged_from(mut self, target: &'static str) -> Self {
+            self.target = Some(target);
+            self
+        }
+
         pub fn expected_log_lines_count(mut self, n: usize) -> Self {
             self.expected_log_lines = Some(n);
             self
@@ -427,16 +881,28 @@ mod tests {
                 panic!("You failed to specify any assertions on LogLinesCounter");
             }
 
-            for level in &self.levels {
+            let levels = self.levels.iter().map(|&level| match level {
+                Some(level) => LogFilter {
+                    default_level: Some(level),
+                    ..LogFilter::default()
+                },
+                None => LogFilter::default(),
+            });
+            let filters = self
+                .filters
+                .iter()
+                .map(|spec| spec.parse::<LogFilter>().expect("Invalid filter"));
+
+            for filter in levels.chain(filters) {
                 let (_tmp, logfile, error_logfile, logger) = setup_logger()?;
 
-                match *level {
-                    Some(l) => logger.set_loglevel(l),
-                    None => logger.unset_loglevel(),
-                };
+                logger.set_filter(filter);
 
                 for &(level, ref msg) in &self.messages {
-                    logger.log(level, msg);
+                    match self.target {
+                        Some(target) => logger.log_from(level, target, msg),
+                        None => logger.log(level, msg),
+                    }
                 }
 
                 drop(logger);
@@ -838,4 +1304,514 @@ mod tests {
             }
         }
     }
+
+    #[test]
+    fn t_json_format_writes_one_object_per_message() {
+        let (_tmp, logfile, error_logfile, logger) = setup_logger().unwrap();
+
+        logger.set_loglevel(Level::Debug);
+        logger.set_log_format(LogFormat::Json);
+
+        logger.log(Level::Info, "first line\nsecond \"line\"");
+        logger.log_from(Level::Warn, "newsboat::reloader", "hello");
+        logger.log(Level::UserError, "oops");
+
+        drop(logger);
+
+        let log = std::fs::read_to_string(logfile).unwrap();
+        let lines = log.lines().collect::<Vec<_>>();
+        assert_eq!(lines.len(), 3);
+
+        for line in &lines {
+            assert!(line.starts_with("{\"timestamp\":\""));
+            assert!(line.ends_with('}'));
+        }
+        assert!(lines[0].contains(r#""level":"INFO","message":"first line\nsecond \"line\"""#));
+        assert!(lines[1]
+            .contains(r#""level":"WARNING","target":"newsboat::reloader","message":"hello""#));
+        assert!(lines[2].contains(r#""level":"USERERROR","message":"oops""#));
+
+        // The user error log stays plain text
+        let errorlog = std::fs::read_to_string(error_logfile).unwrap();
+        let (_timestamp, message) = parse_errorlog_line(errorlog.trim_end()).unwrap();
+        assert_eq!(message, "oops");
+    }
+
+    #[test]
+    fn t_json_timestamp_has_milliseconds_and_timezone() {
+        let now = Local.ymd(2021, 3, 4).and_hms_milli(5, 6, 7, 89);
+        let record = json_record(&now, Level::Debug, None, b"msg");
+        let offset = now.offset().to_string();
+        assert_eq!(
+            record,
+            format!(
+                "{{\"timestamp\":\"2021-03-04T05:06:07.089{}\",\"level\":\"DEBUG\",\"message\":\"msg\"}}\n",
+                offset
+            )
+        );
+    }
+
+    #[test]
+    fn t_text_is_the_default_format() {
+        let (_tmp, logfile, _error_logfile, logger) = setup_logger().unwrap();
+
+        logger.set_loglevel(Level::Debug);
+        logger.log_from(Level::Debug, "newsboat::reloader", "hello");
+
+        drop(logger);
+
+        let log = std::fs::read_to_string(logfile).unwrap();
+        let (_timestamp, level, message) = parse_log_line(log.trim_end()).unwrap();
+        assert_eq!(level, "DEBUG");
+        assert_eq!(message, "hello");
+    }
+
+    #[test]
+    fn t_log_format_from_str() {
+        assert_eq!("text".parse::<LogFormat>(), Ok(LogFormat::Text));
+        assert_eq!("json".parse::<LogFormat>(), Ok(LogFormat::Json));
+        assert_eq!("JSON".parse::<LogFormat>(), Err(()));
+        assert_eq!("".parse::<LogFormat>(), Err(()));
+    }
+
+    #[test]
+    fn t_level_from_str() {
+        let check = |names: &[&str], expected| {
+            for name in names {
+                assert_eq!(name.parse::<Level>(), Ok(expected));
+            }
+        };
+        check(&["1", "usererror"], Level::UserError);
+        check(&["2", "critical"], Level::Critical);
+        check(&["3", "error"], Level::Error);
+        check(&["4", "warn"], Level::Warn);
+        check(&["5", "info"], Level::Info);
+        check(&["6", "debug"], Level::Debug);
+
+        for name in &["0", "7", "", "Debug", "warning", " info"] {
+            assert_eq!(name.parse::<Level>(), Err(()));
+        }
+    }
+
+    #[test]
+    fn t_log_filter_from_str() {
+        let filter = "info,libnewsboat::fslock=debug"
+            .parse::<LogFilter>()
+            .unwrap();
+        assert_eq!(filter.default_level, Some(Level::Info));
+        assert_eq!(filter.level_for("libnewsboat::fslock"), Some(Level::Debug));
+        assert_eq!(filter.level_for("libnewsboat::utils"), Some(Level::Info));
+
+        let filter = " libnewsboat = 3 , libnewsboat::fslock=6 ,"
+            .parse::<LogFilter>()
+            .unwrap();
+        assert_eq!(filter.default_level, None);
+        assert_eq!(filter.level_for("libnewsboat::utils"), Some(Level::Error));
+        assert_eq!(filter.level_for("libnewsboat::fslock"), Some(Level::Debug));
+        assert_eq!(filter.level_for("newsboat"), None);
+
+        // The last of the conflicting directives wins
+        let filter = "2,4,libnewsboat=1,libnewsboat=5"
+            .parse::<LogFilter>()
+            .unwrap();
+        assert_eq!(filter.default_level, Some(Level::Warn));
+        assert_eq!(filter.level_for("libnewsboat"), Some(Level::Info));
+
+        assert_eq!(
+            "6".parse::<LogFilter>().unwrap().default_level,
+            Some(Level::Debug)
+        );
+    }
+
+    #[test]
+    fn t_log_filter_display_can_be_parsed_back() {
+        for spec in &[
+            "info,libnewsboat::fslock=debug",
+            "libnewsboat=3",
+            "6",
+            "usererror,a=1,a::b=2,a::b::c=3",
+        ] {
+            let filter = spec.parse::<LogFilter>().unwrap();
+            assert_eq!(filter.to_string().parse::<LogFilter>(), Ok(filter));
+        }
+        assert_eq!(
+            "info,libnewsboat=error,libnewsboat::fslock=debug"
+                .parse::<LogFilter>()
+                .unwrap()
+                .to_string(),
+            "5,libnewsboat::fslock=6,libnewsboat=3"
+        );
+    }
+
+    #[test]
+    fn t_log_filter_from_str_reports_bad_directive() {
+        assert_eq!(
+            "info,fslock=loud".parse::<LogFilter>(),
+            Err("fslock=loud".to_string())
+        );
+        assert_eq!("=debug".parse::<LogFilter>(), Err("=debug".to_string()));
+        assert_eq!("7".parse::<LogFilter>(), Err("7".to_string()));
+        assert_eq!("".parse::<LogFilter>(), Err("".to_string()));
+        assert_eq!(" , ".parse::<LogFilter>(), Err(" , ".to_string()));
+    }
+
+    #[test]
+    fn t_log_filter_matches_modules_and_their_submodules_only() {
+        let filter = "error,libnewsboat::utils=debug"
+            .parse::<LogFilter>()
+            .unwrap();
+        assert_eq!(filter.level_for("libnewsboat::utils"), Some(Level::Debug));
+        assert_eq!(
+            filter.level_for("libnewsboat::utils::tests"),
+            Some(Level::Debug)
+        );
+        assert_eq!(filter.level_for("libnewsboat::utilsx"), Some(Level::Error));
+        assert_eq!(filter.level_for("libnewsboat"), Some(Level::Error));
+    }
+
+    #[test]
+    fn t_longest_matching_module_path_wins() {
+        let filter = "libnewsboat::fslock=2,libnewsboat=6"
+            .parse::<LogFilter>()
+            .unwrap();
+        assert_eq!(
+            filter.level_for("libnewsboat::fslock"),
+            Some(Level::Critical)
+        );
+        assert_eq!(
+            filter.level_for("libnewsboat::reloader"),
+            Some(Level::Debug)
+        );
+    }
+
+    #[test]
+    fn t_filter_logs_debug_msgs_only_from_chosen_module() {
+        let message = (Level::Debug, "hello".to_string());
+
+        LogLinesCounter::new()
+            .with_messages(vec![message.clone()])
+            .with_filters(vec![
+                "info,libnewsboat::fslock=debug",
+                "libnewsboat=debug",
+                "error,libnewsboat::fslock=6",
+            ])
+            .logged_from("libnewsboat::fslock")
+            .expected_log_lines_count(1)
+            .test()
+            .unwrap();
+
+        LogLinesCounter::new()
+            .with_messages(vec![message])
+            .with_filters(vec![
+                "info,libnewsboat::fslock=debug",
+                "libnewsboat::reloader=debug",
+                "debug,libnewsboat::utils=info",
+            ])
+            .logged_from("libnewsboat::utils")
+            .expected_log_lines_count(0)
+            .test()
+            .unwrap();
+    }
+
+    #[test]
+    fn t_filter_can_lower_the_level_of_noisy_modules() {
+        let messages = vec![
+            (Level::Debug, "debug".to_string()),
+            (Level::Info, "info".to_string()),
+            (Level::Warn, "warn".to_string()),
+        ];
+
+        LogLinesCounter::new()
+            .with_messages(messages.clone())
+            .with_filters(vec!["debug,libnewsboat::utils=warn"])
+            .logged_from("libnewsboat::utils")
+            .expected_log_lines_count(1)
+            .test()
+            .unwrap();
+
+        LogLinesCounter::new()
+            .with_messages(messages)
+            .with_filters(vec!["debug,libnewsboat::utils=warn"])
+            .logged_from("libnewsboat::reloader")
+            .expected_log_lines_count(3)
+            .test()
+            .unwrap();
+    }
+
+    #[test]
+    fn t_messages_without_target_are_logged_at_default_level() {
+        LogLinesCounter::new()
+            .with_messages(vec![(Level::Debug, "hello".to_string())])
+            .with_filters(vec!["info,libnewsboat=debug", "libnewsboat=debug"])
+            .expected_log_lines_count(0)
+            .test()
+            .unwrap();
+
+        LogLinesCounter::new()
+            .with_messages(vec![(Level::Info, "hello".to_string())])
+            .with_filters(vec!["info,libnewsboat=error"])
+            .expected_log_lines_count(1)
+            .test()
+            .unwrap();
+    }
+
+    #[test]
+    fn t_user_errors_are_written_to_errorlog_regardless_of_filter() {
+        LogLinesCounter::new()
+            .with_messages(vec![(Level::UserError, "hello".to_string())])
+            .with_filters(vec!["libnewsboat::fslock=debug", "debug,libnewsboat=1"])
+            .logged_from("libnewsboat::utils")
+            .expected_errorlog_lines_count(1)
+            .test()
+            .unwrap();
+    }
+
+    #[test]
+    fn t_set_loglevel_keeps_per_module_levels() {
+        let (_tmp, logfile, _error_logfile, logger) = setup_logger().unwrap();
+
+        logger.set_filter("info,libnewsboat::fslock=debug".parse().unwrap());
+        logger.set_loglevel(Level::Error);
+        logger.log_from(Level::Debug, "libnewsboat::fslock", "kept");
+        logger.log_from(Level::Info, "libnewsboat::utils", "dropped");
+
+        drop(logger);
+
+        log_contains_n_lines(&logfile, 1).unwrap();
+    }
+
+    #[test]
+    fn t_is_recorded_from_takes_per_module_levels_into_account() {
+        let logger = Logger::new();
+        assert!(!logger.is_recorded_from(Level::Debug, "libnewsboat::fslock"));
+
+        logger.set_filter("info,libnewsboat::fslock=debug".parse().unwrap());
+        assert!(logger.is_recorded_from(Level::Debug, "libnewsboat::fslock"));
+        assert!(!logger.is_recorded_from(Level::Debug, "libnewsboat::utils"));
+        assert!(logger.is_recorded_from(Level::Info, "libnewsboat::utils"));
+    }
+
+    fn rotated(logfile: &path::Path, n: u32) -> path::PathBuf {
+        path::PathBuf::from(format!("{}.{}", logfile.display(), n))
+    }
+
+    #[test]
+    fn t_log_is_rotated_when_it_gets_too_big() {
+        let (_tmp, logfile, _error_logfile, logger) = setup_logger().unwrap();
+
+        logger.set_loglevel(Level::Debug);
+        // Each line is 22 + 7 + 4 + 1 = 34 bytes long, so two lines fit into a file.
+        logger.set_rotation(Some(LogRotation {
+            max_size: 70,
+            max_files: 3,
+        }));
+
+        for msg in &["msg1", "msg2", "msg3", "msg4", "msg5", "msg6", "msg7"] {
+            logger.log(Level::Debug, msg);
+        }
+
+        drop(logger);
+
+        let messages = |path: &path::Path| {
+            std::fs::read_to_string(path)
+                .unwrap()
+                .lines()
+                .map(|line| parse_log_line(line).unwrap().2.to_string())
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(messages(&logfile), vec!["msg7"]);
+        assert_eq!(messages(&rotated(&logfile, 1)), vec!["msg5", "msg6"]);
+        assert_eq!(messages(&rotated(&logfile, 2)), vec!["msg3", "msg4"]);
+        // msg1 and msg2 were in the oldest file, which got deleted
+        assert!(!rotated(&logfile, 3).exists());
+    }
+
+    #[test]
+    fn t_log_is_truncated_if_only_one_file_is_kept() {
+        let (_tmp, logfile, _error_logfile, logger) = setup_logger().unwrap();
+
+        logger.set_loglevel(Level::Debug);
+        logger.set_rotation(Some(LogRotation {
+            max_size: 70,
+            max_files: 1,
+        }));
+
+        for msg in &["msg1", "msg2", "msg3", "msg4", "msg5"] {
+            logger.log(Level::Debug, msg);
+        }
+
+        drop(logger);
+
+        log_contains_n_lines(&logfile, 1).unwrap();
+        assert!(!rotated(&logfile, 1).exists());
+    }
+
+    #[test]
+    fn t_existing_content_counts_towards_the_size_limit() {
+        let tmp = TempDir::new().unwrap();
+        let logfile = tmp.path().join("example.log");
+        std::fs::write(&logfile, "x".repeat(60)).unwrap();
+
+        let logger = Logger::new();
+        logger.set_logfile(logfile.to_str().unwrap());
+        logger.set_loglevel(Level::Debug);
+        logger.set_rotation(Some(LogRotation {
+            max_size: 70,
+            max_files: 2,
+        }));
+        logger.log(Level::Debug, "msg1");
+
+        drop(logger);
+
+        log_contains_n_lines(&logfile, 1).unwrap();
+        assert_eq!(
+            std::fs::read_to_string(rotated(&logfile, 1)).unwrap(),
+            "x".repeat(60)
+        );
+    }
+
+    #[test]
+    fn t_records_bigger_than_the_limit_are_written_anyway() {
+        let (_tmp, logfile, _error_logfile, logger) = setup_logger().unwrap();
+
+        logger.set_loglevel(Level::Debug);
+        logger.set_rotation(Some(LogRotation {
+            max_size: 10,
+            max_files: 2,
+        }));
+        logger.log(Level::Debug, "this is longer than ten bytes");
+        logger.log(Level::Debug, "and so is this");
+
+        drop(logger);
+
+        log_contains_n_lines(&logfile, 1).unwrap();
+        log_contains_n_lines(&rotated(&logfile, 1), 1).unwrap();
+    }
+
+    #[test]
+    fn t_user_error_log_is_rotated_independently() {
+        let (_tmp, logfile, error_logfile, logger) = setup_logger().unwrap();
+
+        logger.set_loglevel(Level::Debug);
+        // General log lines are 38 bytes long, user error log lines are 27 bytes long.
+        logger.set_rotation(Some(LogRotation {
+            max_size: 90,
+            max_files: 5,
+        }));
+        for _ in 0..3 {
+            logger.log(Level::UserError, "oops");
+        }
+
+        drop(logger);
+
+        log_contains_n_lines(&logfile, 1).unwrap();
+        log_contains_n_lines(&rotated(&logfile, 1), 2).unwrap();
+        log_contains_n_lines(&error_logfile, 3).unwrap();
+        assert!(!rotated(&error_logfile, 1).exists());
+    }
+
+    #[test]
+    fn t_logs_grow_without_bound_by_default() {
+        let (_tmp, logfile, _error_logfile, logger) = setup_logger().unwrap();
+
+        logger.set_loglevel(Level::Debug);
+        for _ in 0..100 {
+            logger.log(Level::Debug, &"x".repeat(1024));
+        }
+
+        drop(logger);
+
+        log_contains_n_lines(&logfile, 100).unwrap();
+        assert!(!rotated(&logfile, 1).exists());
+    }
+
+    fn recent_messages(logger: &Logger) -> Vec<String> {
+        logger
+            .recent_messages()
+            .iter()
+            .map(|line| parse_log_line(line).unwrap().2.to_string())
+            .collect()
+    }
+
+    #[test]
+    fn t_recent_messages_are_kept_without_logfile() {
+        let logger = Logger::new();
+
+        logger.log(Level::Info, "first");
+        logger.log_from(Level::Warn, "libnewsboat::fslock", "second");
+        logger.log_raw(Level::Error, b"third");
+        // Not kept by default
+        logger.log(Level::Debug, "fourth");
+
+        assert_eq!(recent_messages(&logger), vec!["first", "second", "third"]);
+
+        let line = &logger.recent_messages()[1];
+        let (_timestamp, level, _message) = parse_log_line(line).unwrap();
+        assert_eq!(level, "WARNING");
+    }
+
+    #[test]
+    fn t_only_the_most_recent_messages_are_kept() {
+        let logger = Logger::new();
+        logger.set_recent_messages(3, Some(Level::Debug));
+
+        for i in 0..10 {
+            logger.log(Level::Debug, &i.to_string());
+        }
+
+        assert_eq!(recent_messages(&logger), vec!["7", "8", "9"]);
+
+        // Lowering capacity drops the oldest messages
+        logger.set_recent_messages(2, Some(Level::Debug));
+        assert_eq!(recent_messages(&logger), vec!["8", "9"]);
+    }
+
+    #[test]
+    fn t_recent_messages_have_their_own_level() {
+        let (_tmp, logfile, _error_logfile, logger) = setup_logger().unwrap();
+        logger.set_loglevel(Level::Debug);
+        logger.set_recent_messages(10, Some(Level::Error));
+
+        logger.log(Level::Debug, "debug");
+        logger.log(Level::Error, "error");
+        logger.log(Level::UserError, "user error");
+
+        assert_eq!(recent_messages(&logger), vec!["error", "user error"]);
+
+        drop(logger);
+        log_contains_n_lines(&logfile, 3).unwrap();
+    }
+
+    #[test]
+    fn t_recent_messages_can_be_disabled() {
+        let logger = Logger::new();
+
+        logger.set_recent_messages(10, None);
+        logger.log(Level::UserError, "hello");
+        assert!(logger.recent_messages().is_empty());
+
+        logger.set_recent_messages(0, Some(Level::Debug));
+        logger.log(Level::UserError, "hello");
+        assert!(logger.recent_messages().is_empty());
+    }
+
+    #[test]
+    fn t_recorded_level_includes_recent_messages_level() {
+        let logger = Logger::new();
+        assert_eq!(
+            logger.get_recorded_level(),
+            DEFAULT_RECENT_MESSAGES_LEVEL as isize
+        );
+
+        logger.set_loglevel(Level::Debug);
+        assert_eq!(logger.get_recorded_level(), Level::Debug as isize);
+
+        logger.set_loglevel(Level::Error);
+        logger.set_recent_messages(10, Some(Level::Warn));
+        assert_eq!(logger.get_recorded_level(), Level::Warn as isize);
+
+        logger.unset_loglevel();
+        logger.set_recent_messages(10, None);
+        assert_eq!(logger.get_recorded_level(), -1);
+    }
 }
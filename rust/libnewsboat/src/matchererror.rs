@@ -1,3 +1,7 @@
+use gettextrs::gettext;
+use std::fmt;
+use strprintf::fmt;
+
 /// Errors produced by `Matcher::matches`.
 ///
 /// These correspond to `MatcherException` on the C++ side.
@@ -9,3 +13,19 @@ pub enum MatcherError {
     /// Compiling regular expression `regex` produced an error message `errmsg`
     InvalidRegex { regex: String, errmsg: String },
 }
+
+impl fmt::Display for MatcherError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let message = match self {
+            MatcherError::AttributeUnavailable { attr } => {
+                fmt!(&gettext("attribute `%s' is not available."), attr.as_str())
+            }
+            MatcherError::InvalidRegex { regex, errmsg } => fmt!(
+                &gettext("regular expression '%s' is invalid: %s"),
+                regex.as_str(),
+                errmsg.as_str()
+            ),
+        };
+        write!(f, "{}", message)
+    }
+}
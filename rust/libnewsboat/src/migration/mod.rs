@@ -0,0 +1,227 @@
+//! Importers that bring subscriptions and data over from other feed readers.
+//!
+//! Each importer knows where its source keeps the files, and where in Newsboat's directories the
+//! data should go. `ConfigPaths::try_migrate` asks the importers in turn whether their source is
+//! present, and runs the first one that is.
+
+mod newsbeuter;
+mod opml;
+mod xmlreader;
+
+pub use newsbeuter::{NewsbeuterDotdirImporter, NewsbeuterXdgImporter};
+pub use opml::{parse_subscriptions, OpmlImporter, Subscription};
+
+use gettextrs::gettext;
+use std::ffi::CString;
+use std::fs;
+use std::io;
+use std::os::unix::ffi::OsStrExt;
+use std::path::{Path, PathBuf};
+use strprintf::fmt;
+
+/// What an importer did.
+#[derive(Debug, Default, PartialEq)]
+pub struct MigrationReport {
+    /// Human-readable description of each step, in the order they were taken.
+    pub actions: Vec<String>,
+}
+
+/// What will happen to a single file during the migration.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum FileAction {
+    /// The file will be copied as is.
+    Copy,
+    /// The file will be converted into Newsboat's format.
+    Convert,
+    /// The source file doesn't exist, so there is nothing to do.
+    SkipMissing,
+    /// The destination already exists, and won't be overwritten.
+    SkipExisting,
+}
+
+/// A file that the migration will process.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PlannedFile {
+    pub source: PathBuf,
+    pub destination: PathBuf,
+    pub action: FileAction,
+}
+
+/// What an importer would do, as found out without touching any files.
+#[derive(Debug, Default, PartialEq)]
+pub struct MigrationPlan {
+    /// Human-readable name of the source, e.g. "~/.newsbeuter/".
+    pub source_name: String,
+
+    /// Directories that will be created before copying files.
+    pub directories: Vec<PathBuf>,
+
+    /// Files that will be processed, in order.
+    pub files: Vec<PlannedFile>,
+
+    /// Paths that already exist and make the importer refuse to run, lest it overwrite the
+    /// user's data.
+    pub conflicts: Vec<PathBuf>,
+
+    /// Explanations of why the importer wouldn't be able to read or write some of the paths.
+    pub permission_problems: Vec<String>,
+}
+
+impl MigrationPlan {
+    /// Returns `true` if nothing prevents the importer from running.
+    pub fn can_proceed(&self) -> bool {
+        self.conflicts.is_empty() && self.permission_problems.is_empty()
+    }
+
+    /// Checks that `path` is readable (and searchable if it's a directory), recording a problem
+    /// if it isn't.
+    fn check_readable(&mut self, path: &Path) {
+        let mode = if path.is_dir() {
+            libc::R_OK | libc::X_OK
+        } else {
+            libc::R_OK
+        };
+        if path.exists() && !is_accessible(path, mode) {
+            self.permission_problems.push(fmt!(
+                &gettext("`%s' is not readable"),
+                path.to_string_lossy().into_owned()
+            ));
+        }
+    }
+
+    /// Checks that `path` can be created, i.e. that its closest existing ancestor is writable,
+    /// recording a problem if it isn't.
+    fn check_creatable(&mut self, path: &Path) {
+        let ancestor = path.ancestors().skip(1).find(|dir| dir.exists());
+        if let Some(ancestor) = ancestor {
+            if !is_accessible(ancestor, libc::W_OK | libc::X_OK) {
+                self.permission_problems.push(fmt!(
+                    &gettext("can't create `%s' because `%s' is not writable"),
+                    path.to_string_lossy().into_owned(),
+                    ancestor.to_string_lossy().into_owned()
+                ));
+            }
+        }
+    }
+
+    /// Records that `destination` would make the importer refuse to run if it exists.
+    fn check_conflict(&mut self, destination: &Path) {
+        if destination.exists() {
+            self.conflicts.push(destination.to_owned());
+        }
+    }
+
+    /// Adds a file to the plan, finding out if it will be skipped.
+    fn add_file(&mut self, source: PathBuf, destination: PathBuf, action: FileAction) {
+        let action = if !source.exists() {
+            FileAction::SkipMissing
+        } else if destination.exists() {
+            FileAction::SkipExisting
+        } else {
+            self.check_readable(&source);
+            action
+        };
+        self.files.push(PlannedFile {
+            source,
+            destination,
+            action,
+        });
+    }
+
+    /// Describes the plan in a human-readable form, one item per line.
+    pub fn describe(&self) -> Vec<String> {
+        let mut lines = Vec::new();
+
+        for dir in &self.directories {
+            lines.push(fmt!(
+                &gettext("create directory `%s'"),
+                dir.to_string_lossy().into_owned()
+            ));
+        }
+
+        for file in &self.files {
+            let source = file.source.to_string_lossy().into_owned();
+            let destination = file.destination.to_string_lossy().into_owned();
+            lines.push(match file.action {
+                FileAction::Copy => fmt!(&gettext("copy `%s' to `%s'"), source, destination),
+                FileAction::Convert => {
+                    fmt!(&gettext("convert `%s' into `%s'"), source, destination)
+                }
+                FileAction::SkipMissing => {
+                    fmt!(&gettext("skip `%s' (doesn't exist)"), source)
+                }
+                FileAction::SkipExisting => fmt!(
+                    &gettext("skip `%s' (`%s' already exists)"),
+                    source,
+                    destination
+                ),
+            });
+        }
+
+        for conflict in &self.conflicts {
+            lines.push(fmt!(
+                &gettext("conflict: `%s' already exists"),
+                conflict.to_string_lossy().into_owned()
+            ));
+        }
+
+        for problem in &self.permission_problems {
+            lines.push(fmt!(&gettext("permission problem: %s"), problem.as_str()));
+        }
+
+        lines
+    }
+}
+
+/// A source of subscriptions and data that can be migrated to Newsboat.
+pub trait Importer {
+    /// Human-readable name of the source, e.g. "~/.newsbeuter/".
+    fn source_name(&self) -> String;
+
+    /// Returns `true` if the source is present and importing it won't overwrite any of Newsboat's
+    /// files.
+    fn detect(&self) -> bool;
+
+    /// Returns `true` if the source is present, even if it can't be imported.
+    fn source_exists(&self) -> bool;
+
+    /// Describes what `import` would do, without touching any files.
+    fn plan(&self) -> MigrationPlan;
+
+    /// Copies or converts the source's data into Newsboat's directories.
+    ///
+    /// Returns an explanation if the import had to be aborted.
+    fn import(&self) -> Result<MigrationReport, String>;
+}
+
+/// Copies the files that `plan` marks for copying, recording each step in `report`.
+///
+/// Errors are ignored because it's okay if some files are missing.
+fn copy_planned_files(plan: &MigrationPlan, report: &mut MigrationReport) {
+    let to_copy = plan
+        .files
+        .iter()
+        .filter(|file| file.action == FileAction::Copy);
+    for file in to_copy {
+        if copy_file(&file.source, &file.destination).is_ok() {
+            report
+                .actions
+                .push(format!("{:?} -> {:?}", file.source, file.destination));
+        }
+    }
+}
+
+/// Copies `source` to `destination`, refusing to overwrite existing files.
+fn copy_file(source: &Path, destination: &Path) -> io::Result<()> {
+    if destination.exists() {
+        return Err(io::Error::from(io::ErrorKind::AlreadyExists));
+    }
+    fs::copy(source, destination).map(|_| ())
+}
+
+fn is_accessible(path: &Path, mode: libc::c_int) -> bool {
+    match CString::new(path.as_os_str().as_bytes()) {
+        Ok(path) => unsafe { libc::access(path.as_ptr(), mode) == 0 },
+        Err(_) => false,
+    }
+}
@@ -0,0 +1,230 @@
+use super::{copy_planned_files, FileAction, Importer, MigrationPlan, MigrationReport};
+use crate::configpaths::{
+    CACHE_FILENAME, CMDLINE_HISTORY_FILENAME, CONFIG_FILENAME, NEWSBEUTER_CONFIG_SUBDIR,
+    NEWSBEUTER_SUBDIR_XDG, NEWSBOAT_CONFIG_SUBDIR, NEWSBOAT_SUBDIR_XDG, QUEUE_FILENAME,
+    SEARCH_HISTORY_FILENAME, URLS_FILENAME,
+};
+use crate::logger::{self, Level};
+use crate::utils;
+use gettextrs::gettext;
+use std::fs::DirBuilder;
+use std::io;
+use std::os::unix::fs::DirBuilderExt;
+use std::path::{Path, PathBuf};
+use strprintf::fmt;
+
+/// Migrates Newsbeuter's XDG dirs to Newsboat's XDG dirs.
+pub struct NewsbeuterXdgImporter {
+    newsbeuter_config_dir: PathBuf,
+    newsbeuter_data_dir: PathBuf,
+    newsboat_config_dir: PathBuf,
+    newsboat_data_dir: PathBuf,
+}
+
+impl NewsbeuterXdgImporter {
+    pub fn new() -> NewsbeuterXdgImporter {
+        // This can't panic because we've tested we can find the home directory in ConfigPaths::new
+        // This should be replaced with proper error handling after this is not used by c++ anymore
+        let xdg_dirs = xdg::BaseDirectories::new().unwrap();
+        let xdg_config_dir = xdg_dirs.get_config_home();
+        let xdg_data_dir = xdg_dirs.get_data_home();
+
+        NewsbeuterXdgImporter {
+            newsbeuter_config_dir: xdg_config_dir.join(NEWSBEUTER_SUBDIR_XDG),
+            newsbeuter_data_dir: xdg_data_dir.join(NEWSBEUTER_SUBDIR_XDG),
+            newsboat_config_dir: xdg_config_dir.join(NEWSBOAT_SUBDIR_XDG),
+            newsboat_data_dir: xdg_data_dir.join(NEWSBOAT_SUBDIR_XDG),
+        }
+    }
+}
+
+impl Default for NewsbeuterXdgImporter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Importer for NewsbeuterXdgImporter {
+    fn source_name(&self) -> String {
+        gettext("Newsbeuter's XDG dirs")
+    }
+
+    fn source_exists(&self) -> bool {
+        self.newsbeuter_config_dir.is_dir()
+    }
+
+    fn detect(&self) -> bool {
+        if !self.source_exists() {
+            return false;
+        }
+
+        fn exists(path: &Path) -> bool {
+            let exists = path.exists();
+            if exists {
+                log!(
+                    Level::Debug,
+                    "{:?} already exists, aborting XDG migration.",
+                    path
+                );
+            }
+            exists
+        }
+
+        !exists(&self.newsboat_config_dir) && !exists(&self.newsboat_data_dir)
+    }
+
+    fn plan(&self) -> MigrationPlan {
+        let mut plan = MigrationPlan {
+            source_name: self.source_name(),
+            ..MigrationPlan::default()
+        };
+
+        plan.check_conflict(&self.newsboat_config_dir);
+        plan.check_conflict(&self.newsboat_data_dir);
+
+        plan.check_readable(&self.newsbeuter_config_dir);
+        plan.check_readable(&self.newsbeuter_data_dir);
+
+        for dir in &[&self.newsboat_config_dir, &self.newsboat_data_dir] {
+            if !dir.exists() {
+                plan.check_creatable(dir);
+                plan.directories.push(dir.to_path_buf());
+            }
+        }
+
+        // in config
+        for file in &[URLS_FILENAME, CONFIG_FILENAME] {
+            plan.add_file(
+                self.newsbeuter_config_dir.join(file),
+                self.newsboat_config_dir.join(file),
+                FileAction::Copy,
+            );
+        }
+
+        // in data
+        for file in &[
+            CACHE_FILENAME,
+            QUEUE_FILENAME,
+            SEARCH_HISTORY_FILENAME,
+            CMDLINE_HISTORY_FILENAME,
+        ] {
+            plan.add_file(
+                self.newsbeuter_data_dir.join(file),
+                self.newsboat_data_dir.join(file),
+                FileAction::Copy,
+            );
+        }
+
+        plan
+    }
+
+    fn import(&self) -> Result<MigrationReport, String> {
+        let plan = self.plan();
+
+        for dir in &plan.directories {
+            if let Err(err) = utils::mkdir_parents(dir, 0o700) {
+                return Err(mkdir_failed(dir, err));
+            }
+        }
+
+        let mut report = MigrationReport::default();
+        copy_planned_files(&plan, &mut report);
+        Ok(report)
+    }
+}
+
+/// Migrates ~/.newsbeuter to ~/.newsboat.
+pub struct NewsbeuterDotdirImporter {
+    newsbeuter_dir: PathBuf,
+    newsboat_dir: PathBuf,
+}
+
+impl NewsbeuterDotdirImporter {
+    pub fn new(home: &Path) -> NewsbeuterDotdirImporter {
+        NewsbeuterDotdirImporter {
+            newsbeuter_dir: home.join(NEWSBEUTER_CONFIG_SUBDIR),
+            newsboat_dir: home.join(NEWSBOAT_CONFIG_SUBDIR),
+        }
+    }
+}
+
+impl Importer for NewsbeuterDotdirImporter {
+    fn source_name(&self) -> String {
+        String::from("~/.newsbeuter/")
+    }
+
+    fn source_exists(&self) -> bool {
+        self.newsbeuter_dir.is_dir()
+    }
+
+    fn detect(&self) -> bool {
+        if !self.source_exists() {
+            return false;
+        }
+
+        if self.newsboat_dir.exists() {
+            log!(
+                Level::Debug,
+                "{:?} already exists, aborting migration.",
+                self.newsboat_dir
+            );
+            return false;
+        }
+
+        true
+    }
+
+    fn plan(&self) -> MigrationPlan {
+        let mut plan = MigrationPlan {
+            source_name: self.source_name(),
+            ..MigrationPlan::default()
+        };
+
+        plan.check_conflict(&self.newsboat_dir);
+        plan.check_readable(&self.newsbeuter_dir);
+
+        if !self.newsboat_dir.exists() {
+            plan.check_creatable(&self.newsboat_dir);
+            plan.directories.push(self.newsboat_dir.clone());
+        }
+
+        for file in &[
+            URLS_FILENAME,
+            CACHE_FILENAME,
+            CONFIG_FILENAME,
+            QUEUE_FILENAME,
+            SEARCH_HISTORY_FILENAME,
+            CMDLINE_HISTORY_FILENAME,
+        ] {
+            plan.add_file(
+                self.newsbeuter_dir.join(file),
+                self.newsboat_dir.join(file),
+                FileAction::Copy,
+            );
+        }
+
+        plan
+    }
+
+    fn import(&self) -> Result<MigrationReport, String> {
+        let plan = self.plan();
+
+        match DirBuilder::new().mode(0o700).create(&self.newsboat_dir) {
+            Ok(_) => (),
+            Err(ref e) if e.kind() == io::ErrorKind::AlreadyExists => (),
+            Err(err) => return Err(mkdir_failed(&self.newsboat_dir, err)),
+        };
+
+        let mut report = MigrationReport::default();
+        copy_planned_files(&plan, &mut report);
+        Ok(report)
+    }
+}
+
+fn mkdir_failed(dir: &Path, err: io::Error) -> String {
+    fmt!(
+        &gettext("Aborting migration because mkdir on `%s' failed: %s"),
+        &dir.to_string_lossy().into_owned(),
+        err.to_string()
+    )
+}
@@ -0,0 +1,495 @@
+use super::xmlreader::{Node, Reader};
+use super::{FileAction, Importer, MigrationPlan, MigrationReport, PlannedFile};
+use crate::utils;
+use gettextrs::gettext;
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use strprintf::fmt;
+
+/// A feed found in an OPML file.
+#[derive(Debug, PartialEq)]
+pub struct Subscription {
+    /// URL in the format of the urls file, i.e. Liferea-style pipes are turned into `exec:` URLs
+    /// and filter commands into `filter:` URLs.
+    pub url: String,
+    /// Tags, which are made out of the titles of enclosing outlines.
+    pub tags: Vec<String>,
+}
+
+impl Subscription {
+    /// Formats the subscription as a line of the urls file.
+    pub fn to_urls_line(&self) -> String {
+        let mut line = utils::quote_if_necessary(self.url.clone());
+        for tag in &self.tags {
+            line.push(' ');
+            line.push_str(&utils::quote(tag.clone()));
+        }
+        line
+    }
+}
+
+/// Imports subscriptions from an OPML file, and optionally GUIDs of read articles from a plain
+/// text file with one GUID per line.
+///
+/// Most feed readers can export to these formats, but there is no standard place for the files,
+/// so the user points Newsboat at them with `--migrate-from-opml` and `--migrate-read-guids`.
+pub struct OpmlImporter {
+    opml_file: PathBuf,
+    read_guids_file: Option<PathBuf>,
+    url_file: PathBuf,
+    imported_read_guids_file: PathBuf,
+}
+
+impl OpmlImporter {
+    /// Creates an importer that converts `opml_file` into `url_file`, and adds GUIDs from
+    /// `read_guids_file` to `imported_read_guids_file`, whence they're applied after the next
+    /// reload.
+    pub fn new(
+        opml_file: &Path,
+        read_guids_file: Option<&Path>,
+        url_file: &Path,
+        imported_read_guids_file: &Path,
+    ) -> OpmlImporter {
+        OpmlImporter {
+            opml_file: opml_file.to_owned(),
+            read_guids_file: read_guids_file.map(Path::to_owned),
+            url_file: url_file.to_owned(),
+            imported_read_guids_file: imported_read_guids_file.to_owned(),
+        }
+    }
+}
+
+impl Importer for OpmlImporter {
+    fn source_name(&self) -> String {
+        self.opml_file.to_string_lossy().into_owned()
+    }
+
+    fn source_exists(&self) -> bool {
+        self.opml_file.is_file()
+    }
+
+    fn detect(&self) -> bool {
+        // The user asked for this source explicitly, so if it's missing, `import` should say so
+        // rather than silently doing nothing
+        !self.url_file.exists()
+    }
+
+    fn plan(&self) -> MigrationPlan {
+        let mut plan = MigrationPlan {
+            source_name: self.source_name(),
+            ..MigrationPlan::default()
+        };
+
+        plan.check_conflict(&self.url_file);
+        if !self.url_file.exists() {
+            plan.check_creatable(&self.url_file);
+        }
+        plan.add_file(
+            self.opml_file.clone(),
+            self.url_file.clone(),
+            FileAction::Convert,
+        );
+
+        if let Some(ref read_guids_file) = self.read_guids_file {
+            if !self.imported_read_guids_file.exists() {
+                plan.check_creatable(&self.imported_read_guids_file);
+            }
+            // GUIDs are appended to the ones that weren't applied yet, so an existing destination
+            // doesn't stop them from being imported
+            let action = if read_guids_file.exists() {
+                plan.check_readable(read_guids_file);
+                FileAction::Convert
+            } else {
+                FileAction::SkipMissing
+            };
+            plan.files.push(PlannedFile {
+                source: read_guids_file.clone(),
+                destination: self.imported_read_guids_file.clone(),
+                action,
+            });
+        }
+
+        plan
+    }
+
+    fn import(&self) -> Result<MigrationReport, String> {
+        let subscriptions = parse_subscriptions(&self.opml_file)
+            .map_err(|e| describe_error(&self.opml_file, &e))?;
+
+        // Read the GUIDs before writing anything, so that a missing file doesn't leave the
+        // migration half-done
+        let guids = match self.read_guids_file {
+            Some(ref read_guids_file) => Some(
+                fs::read_to_string(read_guids_file)
+                    .map_err(|e| describe_error(read_guids_file, &e.to_string()))?,
+            ),
+            None => None,
+        };
+
+        let mut urls = String::new();
+        for subscription in &subscriptions {
+            urls.push_str(&subscription.to_urls_line());
+            urls.push('\n');
+        }
+        if let Some(dir) = self.url_file.parent() {
+            utils::mkdir_parents(&dir, 0o700)
+                .map_err(|e| describe_error(&self.url_file, &e.to_string()))?;
+        }
+        fs::write(&self.url_file, urls)
+            .map_err(|e| describe_error(&self.url_file, &e.to_string()))?;
+
+        let mut report = MigrationReport::default();
+        report.actions.push(fmt!(
+            &gettext("Imported %u feeds from `%s' into `%s'"),
+            subscriptions.len() as u32,
+            self.opml_file.to_string_lossy().into_owned(),
+            self.url_file.to_string_lossy().into_owned()
+        ));
+
+        if let (Some(read_guids_file), Some(guids)) = (&self.read_guids_file, guids) {
+            let guids = guids
+                .lines()
+                .map(str::trim)
+                .filter(|guid| !guid.is_empty())
+                .collect::<Vec<_>>();
+            append_lines(&self.imported_read_guids_file, &guids)
+                .map_err(|e| describe_error(&self.imported_read_guids_file, &e))?;
+
+            report.actions.push(fmt!(
+                &gettext("Found %u read articles in `%s'; they will be marked read after the next reload"),
+                guids.len() as u32,
+                read_guids_file.to_string_lossy().into_owned()
+            ));
+        }
+
+        Ok(report)
+    }
+}
+
+fn describe_error(path: &Path, error: &str) -> String {
+    fmt!(
+        &gettext("Error: couldn't import `%s': %s"),
+        path.to_string_lossy().into_owned(),
+        error
+    )
+}
+
+/// Appends `lines` to `path`, creating the file and its parent directories if necessary.
+fn append_lines(path: &Path, lines: &[&str]) -> Result<(), String> {
+    if let Some(dir) = path.parent() {
+        utils::mkdir_parents(&dir, 0o700).map_err(|e| e.to_string())?;
+    }
+    let mut file = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .map_err(|e| e.to_string())?;
+    for line in lines {
+        writeln!(file, "{}", line).map_err(|e| e.to_string())?;
+    }
+    Ok(())
+}
+
+/// Extracts feeds from an OPML file, the same way `newsboat -i` does.
+///
+/// Outlines in the body that have an `xmlUrl` (or `url`) attribute are feeds; other outlines are
+/// categories, and their `text` (or `title`) becomes a tag of the feeds nested in them. Nested
+/// categories are joined with slashes, e.g. "News/Tech". Duplicate feeds are skipped.
+pub fn parse_subscriptions(opml_file: &Path) -> Result<Vec<Subscription>, String> {
+    let mut reader = Reader::open(opml_file).ok_or_else(|| gettext("can't open the file"))?;
+    let malformed = || gettext("not a valid XML document");
+
+    let mut subscriptions: Vec<Subscription> = Vec::new();
+    // One entry per currently open outline; categories contribute their title to the tags.
+    let mut categories: Vec<Option<String>> = Vec::new();
+    let mut seen_root = false;
+    let mut in_body = false;
+
+    while let Some(node) = reader.read().map_err(|_| malformed())? {
+        let (name, empty) = match node {
+            Node::Start { name, empty } => (name, empty),
+            Node::End { ref name } if name == "body" => {
+                in_body = false;
+                continue;
+            }
+            Node::End { ref name } if in_body && name == "outline" => {
+                categories.pop();
+                continue;
+            }
+            _ => continue,
+        };
+
+        if !seen_root {
+            if name != "opml" {
+                return Err(gettext("not an OPML document"));
+            }
+            seen_root = true;
+            continue;
+        }
+
+        if name == "body" {
+            in_body = !empty;
+            continue;
+        }
+        if !in_body || name != "outline" {
+            continue;
+        }
+
+        let url = reader
+            .attribute("xmlUrl")
+            .or_else(|| reader.attribute("url"));
+        let category = match url {
+            Some(url) => {
+                let subscription = Subscription {
+                    url: convert_url(&url, reader.attribute("filtercmd")),
+                    tags: current_tag(&categories).into_iter().collect(),
+                };
+                if !subscriptions.iter().any(|s| s.url == subscription.url) {
+                    subscriptions.push(subscription);
+                }
+                None
+            }
+            None => reader
+                .attribute("text")
+                .or_else(|| reader.attribute("title")),
+        };
+        if !empty {
+            categories.push(category);
+        }
+    }
+
+    if !seen_root {
+        return Err(gettext("not an OPML document"));
+    }
+
+    Ok(subscriptions)
+}
+
+/// Turns an OPML URL into the urls file syntax.
+fn convert_url(url: &str, filtercmd: Option<String>) -> String {
+    // Liferea uses a pipe to signal feeds read from the output of a program in its OPMLs.
+    let url = match url.strip_prefix('|') {
+        Some(command) => format!("exec:{}", command),
+        None => url.to_owned(),
+    };
+
+    match filtercmd {
+        Some(filtercmd) => format!("filter:{}:{}", filtercmd, url),
+        None => url,
+    }
+}
+
+fn current_tag(categories: &[Option<String>]) -> Option<String> {
+    let tag = categories
+        .iter()
+        .filter_map(|category| category.as_deref())
+        .filter(|category| !category.is_empty())
+        .collect::<Vec<_>>()
+        .join("/");
+    if tag.is_empty() {
+        None
+    } else {
+        Some(tag)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn subscription(url: &str, tags: &[&str]) -> Subscription {
+        Subscription {
+            url: url.to_owned(),
+            tags: tags.iter().map(|t| t.to_string()).collect(),
+        }
+    }
+
+    fn parse(opml: &str) -> Result<Vec<Subscription>, String> {
+        let tmp = TempDir::new().unwrap();
+        let opml_file = tmp.path().join("subscriptions.opml");
+        fs::write(&opml_file, opml).unwrap();
+        parse_subscriptions(&opml_file)
+    }
+
+    #[test]
+    fn t_parse_subscriptions_turns_nested_outlines_into_tags() {
+        let opml = r#"<?xml version="1.0" encoding="UTF-8"?>
+<!-- exported by some reader -->
+<opml version="2.0">
+  <head>
+    <title>Subscriptions</title>
+    <outline xmlUrl="https://example.com/not-in-body.xml"/>
+  </head>
+  <body>
+    <outline type="rss" xmlUrl="https://example.com/top.xml" text="Top"/>
+    <outline text="News">
+      <outline text="Tech &amp; Science">
+        <outline type="rss" xmlUrl="https://example.com/a.xml?x=1&amp;y=2" title="A"/>
+      </outline>
+      <outline type="rss" url='https://example.com/b.xml' text="B"></outline>
+    </outline>
+    <outline title="&#x41C;&#x438;&#x440;">
+      <outline xmlUrl="https://example.com/c.xml">
+        <outline xmlUrl="https://example.com/d.xml"/>
+      </outline>
+    </outline>
+    <outline title="Empty category"></outline>
+  </body>
+</opml>"#;
+
+        assert_eq!(
+            parse(opml),
+            Ok(vec![
+                subscription("https://example.com/top.xml", &[]),
+                subscription(
+                    "https://example.com/a.xml?x=1&y=2",
+                    &["News/Tech & Science"]
+                ),
+                subscription("https://example.com/b.xml", &["News"]),
+                subscription("https://example.com/c.xml", &["Мир"]),
+                subscription("https://example.com/d.xml", &["Мир"]),
+            ])
+        );
+    }
+
+    #[test]
+    fn t_parse_subscriptions_converts_liferea_pipes_and_filter_commands() {
+        let opml = r#"<opml><body>
+            <outline xmlUrl="|~/bin/feed.sh"/>
+            <outline xmlUrl="https://example.com/feed" filtercmd="~/bin/fix.py"/>
+        </body></opml>"#;
+
+        assert_eq!(
+            parse(opml),
+            Ok(vec![
+                subscription("exec:~/bin/feed.sh", &[]),
+                subscription("filter:~/bin/fix.py:https://example.com/feed", &[]),
+            ])
+        );
+    }
+
+    #[test]
+    fn t_parse_subscriptions_skips_duplicates() {
+        let opml = r#"<opml><body>
+            <outline text="One"><outline xmlUrl="https://example.com/feed"/></outline>
+            <outline text="Two"><outline xmlUrl="https://example.com/feed"/></outline>
+        </body></opml>"#;
+
+        assert_eq!(
+            parse(opml),
+            Ok(vec![subscription("https://example.com/feed", &["One"])])
+        );
+    }
+
+    #[test]
+    fn t_parse_subscriptions_rejects_files_that_are_not_opml() {
+        assert!(parse("").is_err());
+        assert!(parse("<rss><channel/></rss>").is_err());
+        assert!(parse("<opml><body><outline xmlUrl=\"http").is_err());
+        assert!(parse_subscriptions(Path::new("/nonexistent/subscriptions.opml")).is_err());
+    }
+
+    #[test]
+    fn t_to_urls_line_quotes_tags_and_urls_with_spaces() {
+        assert_eq!(
+            subscription("https://example.com/feed", &["News", "Tech"]).to_urls_line(),
+            "https://example.com/feed \"News\" \"Tech\""
+        );
+        assert_eq!(
+            subscription("exec:~/bin/feed.sh --all", &[]).to_urls_line(),
+            "\"exec:~/bin/feed.sh --all\""
+        );
+    }
+
+    #[test]
+    fn t_import_writes_urls_file_and_appends_read_guids() {
+        let tmp = TempDir::new().unwrap();
+        let opml_file = tmp.path().join("subscriptions.opml");
+        let read_guids_file = tmp.path().join("read-guids");
+        let url_file = tmp.path().join("newsboat").join("urls");
+        let imported_read_guids_file = tmp.path().join("newsboat").join("imported-read-guids");
+        fs::write(
+            &opml_file,
+            r#"<opml><body><outline text="Blogs">
+                <outline xmlUrl="https://example.com/feed.xml"/>
+            </outline></body></opml>"#,
+        )
+        .unwrap();
+        fs::write(&read_guids_file, "first\n\n  second \n").unwrap();
+
+        let importer = OpmlImporter::new(
+            &opml_file,
+            Some(&read_guids_file),
+            &url_file,
+            &imported_read_guids_file,
+        );
+        assert!(importer.source_exists());
+        assert!(importer.detect());
+
+        let plan = importer.plan();
+        assert!(plan.can_proceed());
+        assert_eq!(
+            plan.files
+                .iter()
+                .map(|file| file.action)
+                .collect::<Vec<_>>(),
+            vec![FileAction::Convert, FileAction::Convert]
+        );
+        assert!(!url_file.exists());
+
+        let report = importer.import().unwrap();
+        assert_eq!(report.actions.len(), 2);
+        assert_eq!(
+            fs::read_to_string(&url_file).unwrap(),
+            "https://example.com/feed.xml \"Blogs\"\n"
+        );
+        assert_eq!(
+            fs::read_to_string(&imported_read_guids_file).unwrap(),
+            "first\nsecond\n"
+        );
+
+        // The urls file is there now, so the importer wouldn't run again
+        assert!(!importer.detect());
+        assert_eq!(importer.plan().conflicts, vec![url_file.clone()]);
+
+        // GUIDs that weren't applied yet are kept
+        fs::remove_file(&url_file).unwrap();
+        fs::write(&read_guids_file, "third\n").unwrap();
+        importer.import().unwrap();
+        assert_eq!(
+            fs::read_to_string(&imported_read_guids_file).unwrap(),
+            "first\nsecond\nthird\n"
+        );
+    }
+
+    #[test]
+    fn t_import_writes_nothing_if_a_source_file_is_missing() {
+        let tmp = TempDir::new().unwrap();
+        let opml_file = tmp.path().join("subscriptions.opml");
+        let url_file = tmp.path().join("urls");
+        let imported_read_guids_file = tmp.path().join("imported-read-guids");
+        fs::write(&opml_file, "<opml><body/></opml>").unwrap();
+
+        let importer = OpmlImporter::new(
+            &opml_file,
+            Some(&tmp.path().join("missing")),
+            &url_file,
+            &imported_read_guids_file,
+        );
+        assert!(importer.import().is_err());
+        assert!(!url_file.exists());
+        assert!(!imported_read_guids_file.exists());
+
+        let importer = OpmlImporter::new(
+            &tmp.path().join("missing.opml"),
+            None,
+            &url_file,
+            &imported_read_guids_file,
+        );
+        assert!(!importer.source_exists());
+        assert!(importer.import().is_err());
+        assert!(!url_file.exists());
+    }
+}
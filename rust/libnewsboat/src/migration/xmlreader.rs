@@ -0,0 +1,120 @@
+//! Just enough of libxml2's `xmlTextReader` API to walk through an OPML file.
+//!
+//! The C++ code parses OPML with libxml2 too (see `opml::import`), so both sides agree on what is
+//! valid XML, and entities, CDATA sections and encodings are handled for us.
+
+use libc::{c_char, c_int};
+use std::ffi::{CStr, CString};
+use std::os::unix::ffi::OsStrExt;
+use std::path::Path;
+use std::ptr;
+
+#[allow(non_camel_case_types)]
+enum xmlTextReader {}
+
+const XML_PARSE_NOERROR: c_int = 1 << 5;
+const XML_PARSE_NOWARNING: c_int = 1 << 6;
+const XML_PARSE_NONET: c_int = 1 << 11;
+
+const XML_READER_TYPE_ELEMENT: c_int = 1;
+const XML_READER_TYPE_END_ELEMENT: c_int = 15;
+
+#[link(name = "xml2")]
+extern "C" {
+    fn xmlReaderForFile(
+        filename: *const c_char,
+        encoding: *const c_char,
+        options: c_int,
+    ) -> *mut xmlTextReader;
+    fn xmlFreeTextReader(reader: *mut xmlTextReader);
+    fn xmlTextReaderRead(reader: *mut xmlTextReader) -> c_int;
+    fn xmlTextReaderNodeType(reader: *mut xmlTextReader) -> c_int;
+    fn xmlTextReaderIsEmptyElement(reader: *mut xmlTextReader) -> c_int;
+    fn xmlTextReaderConstLocalName(reader: *mut xmlTextReader) -> *const c_char;
+    fn xmlTextReaderConstValue(reader: *mut xmlTextReader) -> *const c_char;
+    fn xmlTextReaderMoveToAttribute(reader: *mut xmlTextReader, name: *const c_char) -> c_int;
+    fn xmlTextReaderMoveToElement(reader: *mut xmlTextReader) -> c_int;
+}
+
+/// A node that the reader stopped at.
+#[derive(Debug, PartialEq)]
+pub enum Node {
+    /// Opening tag of an element. `empty` is true for elements like `<outline/>`, which have no
+    /// closing tag.
+    Start { name: String, empty: bool },
+    /// Closing tag of an element.
+    End { name: String },
+    /// Anything else: text, comments etc.
+    Other,
+}
+
+/// Reads an XML file node by node.
+pub struct Reader {
+    reader: *mut xmlTextReader,
+}
+
+impl Reader {
+    /// Opens `path` for reading. Returns `None` if the file can't be opened.
+    pub fn open(path: &Path) -> Option<Reader> {
+        let filename = CString::new(path.as_os_str().as_bytes()).ok()?;
+        // Errors are reported by `read()`, so libxml2 shouldn't print them to the terminal
+        let options = XML_PARSE_NOERROR | XML_PARSE_NOWARNING | XML_PARSE_NONET;
+        let reader = unsafe { xmlReaderForFile(filename.as_ptr(), ptr::null(), options) };
+        if reader.is_null() {
+            None
+        } else {
+            Some(Reader { reader })
+        }
+    }
+
+    /// Moves to the next node. Returns `Ok(None)` at the end of the document, and `Err(())` if
+    /// the document is malformed.
+    pub fn read(&mut self) -> Result<Option<Node>, ()> {
+        match unsafe { xmlTextReaderRead(self.reader) } {
+            1 => {}
+            0 => return Ok(None),
+            _ => return Err(()),
+        }
+
+        let node = match unsafe { xmlTextReaderNodeType(self.reader) } {
+            XML_READER_TYPE_ELEMENT => Node::Start {
+                name: self.name(),
+                empty: unsafe { xmlTextReaderIsEmptyElement(self.reader) } == 1,
+            },
+            XML_READER_TYPE_END_ELEMENT => Node::End { name: self.name() },
+            _ => Node::Other,
+        };
+        Ok(Some(node))
+    }
+
+    /// Returns the value of the attribute `name` of the element the reader is at.
+    pub fn attribute(&self, name: &str) -> Option<String> {
+        let name = CString::new(name).ok()?;
+        if unsafe { xmlTextReaderMoveToAttribute(self.reader, name.as_ptr()) } != 1 {
+            return None;
+        }
+        let value = to_string(unsafe { xmlTextReaderConstValue(self.reader) });
+        unsafe { xmlTextReaderMoveToElement(self.reader) };
+        Some(value)
+    }
+
+    fn name(&self) -> String {
+        to_string(unsafe { xmlTextReaderConstLocalName(self.reader) })
+    }
+}
+
+impl Drop for Reader {
+    fn drop(&mut self) {
+        unsafe { xmlFreeTextReader(self.reader) };
+    }
+}
+
+/// Copies a string owned by the reader.
+fn to_string(value: *const c_char) -> String {
+    if value.is_null() {
+        return String::new();
+    }
+    unsafe { CStr::from_ptr(value) }
+        .to_string_lossy()
+        .into_owned()
+}
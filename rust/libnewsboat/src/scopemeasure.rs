@@ -1,60 +1,542 @@
 //! Measures time spent in a given scope, and writes it to the log.
+//!
+//! Besides the log lines, the measurements are kept in a few other forms:
+//!
+//! - every thread has a stack of the scopes that are currently being measured, so each scope
+//!   knows its parent;
+//! - if debug messages are logged, durations are aggregated per scope name; see `summary()` and
+//!   `log_summary()`. Only a fixed amount of data is kept per scope, no matter how many times
+//!   it's measured;
+//! - if `enable_trace()` was called, each scope becomes an event that `write_trace()` saves in
+//!   Chrome's trace event format. Such files can be opened in chrome://tracing or Perfetto.
+//!
+//! If neither the summary nor the trace is needed, dropping a `ScopeMeasure` does little besides
+//! checking the log level.
 
-use std::time::Instant;
+use std::cell::{Cell, RefCell};
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::Path;
+use std::process;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use once_cell::sync::Lazy;
 
 use crate::{
     log,
     logger::{self, Level},
+    utils,
 };
 
+/// Source of unique IDs for scopes and threads.
+static NEXT_ID: AtomicU64 = AtomicU64::new(1);
+
+/// Durations of the finished scopes on a single thread, grouped by scope name.
+type Shard = Arc<Mutex<HashMap<String, Durations>>>;
+
+/// Shards of all the threads that finished at least one scope. Each thread only locks its own
+/// shard, so scopes on different threads don't contend; `summary()` merges them.
+static SHARDS: Lazy<Mutex<Vec<Shard>>> = Lazy::new(|| Mutex::new(Vec::new()));
+
+/// Set by `enable_trace()`, so that scopes don't have to lock `TRACE` to find out that tracing
+/// is disabled.
+static TRACE_ENABLED: AtomicBool = AtomicBool::new(false);
+
+/// Trace events recorded since `enable_trace()` was called. `None` if tracing is disabled.
+static TRACE: Lazy<Mutex<Option<Trace>>> = Lazy::new(|| Mutex::new(None));
+
+thread_local! {
+    /// This thread's entry in `SHARDS`.
+    static SHARD: Shard = {
+        let shard = Shard::default();
+        if let Ok(mut shards) = SHARDS.lock() {
+            shards.push(Arc::clone(&shard));
+        }
+        shard
+    };
+
+    /// Scopes that are being measured on this thread, outermost first.
+    static SPANS: RefCell<Vec<(u64, String)>> = RefCell::default();
+
+    /// Identifies this thread in the trace. Zero until first used.
+    static THREAD_ID: Cell<u64> = Cell::default();
+}
+
 /// Measures time spent in an enclosing scope, and writes it to the log.
 ///
 /// Upon construction, this struct remembers current (monotonic) time. Before being dropped, it
 /// will write a debug message to the log mentioning: 1) the name of the enclosing scope (as
 /// provided to the constructor); 2) the time that elapsed between constructing and dropping the
-/// object.
+/// object; 3) the name of the parent scope, i.e. the one that was measured on the same thread
+/// when this object was constructed, if any.
 ///
 /// Calling `stopover()` will write a debug message to the log mentioning: 1) the name of the
 /// enclosing scope; 2) the name of the stopover; 3) the time that elapsed between constructing the
-/// object and calling `stopover()`.
+/// object and calling `stopover()`; 4) the name of the parent scope, if any.
 pub struct ScopeMeasure {
+    id: u64,
     start_time: Instant,
     scope_name: String,
+    parent: Option<String>,
 }
 
 impl ScopeMeasure {
     /// Construct an object that will measure time spent in the scope named `scope_name`.
     pub fn new(scope_name: String) -> ScopeMeasure {
+        let id = NEXT_ID.fetch_add(1, Ordering::Relaxed);
+        let parent = SPANS.with(|spans| {
+            let mut spans = spans.borrow_mut();
+            let parent = spans.last().map(|(_, name)| name.clone());
+            spans.push((id, scope_name.clone()));
+            parent
+        });
+
         ScopeMeasure {
+            id,
             start_time: Instant::now(),
             scope_name,
+            parent,
         }
     }
 
+    /// Name of the scope that was being measured on this thread when this object was
+    /// constructed.
+    pub fn parent(&self) -> Option<&str> {
+        self.parent.as_deref()
+    }
+
     /// Write a message to the log mentioning the scope name, `stopover_name`, and the time elapsed
     /// since the object was constructed.
     pub fn stopover(&self, stopover_name: &str) {
+        let elapsed = self.start_time.elapsed();
         log!(
             Level::Debug,
             &format!(
-                "ScopeMeasure: function `{}' (stop over `{}') took {:.6} s so far",
+                "ScopeMeasure: function `{}' (stop over `{}') took {:.6} s so far{}",
                 self.scope_name,
                 stopover_name,
-                self.start_time.elapsed().as_secs_f64()
+                elapsed.as_secs_f64(),
+                self.parent_suffix()
             )
         );
+
+        record_trace_event(|trace| TraceEvent {
+            name: format!("{}: {}", self.scope_name, stopover_name),
+            start: (self.start_time + elapsed).saturating_duration_since(trace.epoch),
+            duration: None,
+            thread_id: thread_id(),
+        });
+    }
+
+    fn parent_suffix(&self) -> String {
+        match self.parent {
+            Some(ref parent) => format!(" (in `{}')", parent),
+            None => String::new(),
+        }
     }
 }
 
 impl Drop for ScopeMeasure {
     fn drop(&mut self) {
+        let elapsed = self.start_time.elapsed();
+
+        // Objects are normally dropped in the reverse order of construction, but one might have
+        // been moved out of its scope, so look for it instead of just popping the last one.
+        SPANS.with(|spans| {
+            let mut spans = spans.borrow_mut();
+            if let Some(pos) = spans.iter().rposition(|(id, _)| *id == self.id) {
+                spans.remove(pos);
+            }
+        });
+
+        // The summary is logged at the same level, so it's not needed either if this message
+        // would be dropped
+        if logger::get_instance().is_recorded_from(Level::Debug, module_path!()) {
+            log!(
+                Level::Debug,
+                &format!(
+                    "ScopeMeasure: function `{}' took {:.6} s{}",
+                    self.scope_name,
+                    elapsed.as_secs_f64(),
+                    self.parent_suffix()
+                )
+            );
+            record_duration(&self.scope_name, elapsed);
+        }
+
+        record_trace_event(|trace| TraceEvent {
+            name: self.scope_name.clone(),
+            start: self.start_time.saturating_duration_since(trace.epoch),
+            duration: Some(elapsed),
+            thread_id: thread_id(),
+        });
+    }
+}
+
+/// Aggregated durations of all the finished scopes with the same name.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ScopeStats {
+    /// Name of the scope, as passed to `ScopeMeasure::new()`.
+    pub name: String,
+    /// How many times the scope was measured.
+    pub count: usize,
+    /// Sum of all durations.
+    pub total: Duration,
+    /// The shortest duration.
+    pub min: Duration,
+    /// The longest duration.
+    pub max: Duration,
+    /// 95th percentile, i.e. 95% of durations are this long or shorter. This is an estimate
+    /// that can be up to 1/16 longer than the actual percentile; see `Durations`.
+    pub p95: Duration,
+}
+
+/// Number of histogram buckets per power of two. Durations in the same bucket differ by less
+/// than 1/`SUBBUCKETS`.
+const SUBBUCKETS: u64 = 16;
+
+/// Aggregated durations of a scope.
+///
+/// Instead of keeping every duration, this keeps a histogram of them, with buckets that get wider
+/// as durations get longer. The number of buckets is bounded by the longest duration; for
+/// anything shorter than a day, it's under 600.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+struct Durations {
+    count: usize,
+    total: Duration,
+    min: Duration,
+    max: Duration,
+    /// Number of durations in each bucket. See `bucket_index()` for how microseconds are mapped
+    /// to buckets.
+    buckets: Vec<u64>,
+}
+
+impl Durations {
+    fn add(&mut self, duration: Duration) {
+        if self.count == 0 || duration < self.min {
+            self.min = duration;
+        }
+        if duration > self.max {
+            self.max = duration;
+        }
+        self.count += 1;
+        self.total += duration;
+
+        let index = bucket_index(duration.as_micros() as u64);
+        if self.buckets.len() <= index {
+            self.buckets.resize(index + 1, 0);
+        }
+        self.buckets[index] += 1;
+    }
+
+    fn merge(&mut self, other: &Durations) {
+        if other.count == 0 {
+            return;
+        }
+        if self.count == 0 || other.min < self.min {
+            self.min = other.min;
+        }
+        if other.max > self.max {
+            self.max = other.max;
+        }
+        self.count += other.count;
+        self.total += other.total;
+
+        if self.buckets.len() < other.buckets.len() {
+            self.buckets.resize(other.buckets.len(), 0);
+        }
+        for (bucket, count) in self.buckets.iter_mut().zip(&other.buckets) {
+            *bucket += count;
+        }
+    }
+
+    fn stats(&self, name: &str) -> Option<ScopeStats> {
+        if self.count == 0 {
+            return None;
+        }
+
+        // Nearest-rank method: the smallest duration that's not shorter than 95% of the others.
+        // Its rank is ceil(0.95 * count). We only know which bucket it's in, so take the longest
+        // duration that would fit there.
+        // `u64::div_ceil()` needs Rust 1.73, newer than we support.
+        #[allow(unknown_lints, clippy::manual_div_ceil)]
+        let rank = (self.count as u64 * 95 + 99) / 100;
+        let mut seen = 0;
+        let index = self
+            .buckets
+            .iter()
+            .position(|count| {
+                seen += count;
+                seen >= rank
+            })
+            .unwrap_or(0);
+        let longest_in_bucket = Duration::from_micros(bucket_start(index + 1) - 1);
+        let p95 = longest_in_bucket.max(self.min).min(self.max);
+
+        Some(ScopeStats {
+            name: name.to_owned(),
+            count: self.count,
+            total: self.total,
+            min: self.min,
+            max: self.max,
+            p95,
+        })
+    }
+}
+
+/// Index of the histogram bucket for a duration of `micros` microseconds.
+///
+/// Durations shorter than `SUBBUCKETS` microseconds get a bucket each. Longer ones are grouped by
+/// their highest bit, and then split into `SUBBUCKETS` buckets by the bits that follow it.
+fn bucket_index(micros: u64) -> usize {
+    if micros < SUBBUCKETS {
+        return micros as usize;
+    }
+    let subbucket_bits = SUBBUCKETS.trailing_zeros();
+    let highest_bit = 63 - micros.leading_zeros();
+    let shift = highest_bit - subbucket_bits;
+    let subbucket = (micros >> shift) - SUBBUCKETS;
+    ((u64::from(shift) + 1) * SUBBUCKETS + subbucket) as usize
+}
+
+/// The shortest duration, in microseconds, that goes into the bucket with the given index.
+fn bucket_start(index: usize) -> u64 {
+    let index = index as u64;
+    if index < SUBBUCKETS {
+        return index;
+    }
+    let shift = index / SUBBUCKETS - 1;
+    let subbucket = index % SUBBUCKETS;
+    (SUBBUCKETS + subbucket) << shift
+}
+
+fn record_duration(scope_name: &str, duration: Duration) {
+    SHARD.with(|shard| {
+        if let Ok(mut durations) = shard.lock() {
+            if let Some(durations) = durations.get_mut(scope_name) {
+                durations.add(duration);
+                return;
+            }
+            let mut new_durations = Durations::default();
+            new_durations.add(duration);
+            durations.insert(scope_name.to_owned(), new_durations);
+        }
+    });
+}
+
+/// Returns statistics for every scope measured so far, slowest (by total time) first.
+///
+/// Scopes are only measured while debug messages from this module are logged.
+pub fn summary() -> Vec<ScopeStats> {
+    let shards = match SHARDS.lock() {
+        Ok(shards) => shards.clone(),
+        Err(_) => return Vec::new(),
+    };
+
+    let mut merged = HashMap::<String, Durations>::new();
+    for shard in shards {
+        if let Ok(durations) = shard.lock() {
+            for (name, durations) in durations.iter() {
+                merged.entry(name.clone()).or_default().merge(durations);
+            }
+        }
+    }
+
+    let mut result = merged
+        .iter()
+        .filter_map(|(name, durations)| durations.stats(name))
+        .collect::<Vec<_>>();
+    result.sort_by(|a, b| b.total.cmp(&a.total).then_with(|| a.name.cmp(&b.name)));
+    result
+}
+
+/// Writes the `summary()` to the log, one debug message per scope.
+pub fn log_summary() {
+    for stats in summary() {
         log!(
             Level::Debug,
             &format!(
-                "ScopeMeasure: function `{}' took {:.6} s",
-                self.scope_name,
-                self.start_time.elapsed().as_secs_f64()
+                "ScopeMeasure: summary for `{}': count {}, total {:.6} s, min {:.6} s, max {:.6} s, p95 {:.6} s",
+                stats.name,
+                stats.count,
+                stats.total.as_secs_f64(),
+                stats.min.as_secs_f64(),
+                stats.max.as_secs_f64(),
+                stats.p95.as_secs_f64()
             )
         );
     }
 }
+
+struct Trace {
+    /// Moment the tracing was enabled. Timestamps in the trace are relative to it.
+    epoch: Instant,
+    events: Vec<TraceEvent>,
+}
+
+struct TraceEvent {
+    name: String,
+    start: Duration,
+    /// `None` for stopovers, which are instant events.
+    duration: Option<Duration>,
+    thread_id: u64,
+}
+
+/// Starts recording scopes and stopovers for `write_trace()`. Calling this again discards
+/// everything recorded so far.
+pub fn enable_trace() {
+    if let Ok(mut trace) = TRACE.lock() {
+        *trace = Some(Trace {
+            epoch: Instant::now(),
+            events: Vec::new(),
+        });
+        TRACE_ENABLED.store(true, Ordering::Relaxed);
+    }
+}
+
+/// Writes the scopes and stopovers recorded since `enable_trace()` to `path`, in Chrome's trace
+/// event format. If tracing is not enabled, the file will contain no events.
+pub fn write_trace(path: &Path) -> io::Result<()> {
+    let mut file = BufWriter::new(File::create(path)?);
+    let pid = process::id();
+
+    writeln!(file, "{{\"traceEvents\":[")?;
+    if let Ok(trace) = TRACE.lock() {
+        let events = trace.iter().flat_map(|trace| trace.events.iter());
+        for (i, event) in events.enumerate() {
+            if i > 0 {
+                writeln!(file, ",")?;
+            }
+            write!(
+                file,
+                "{{\"name\":{},\"cat\":\"ScopeMeasure\",\"pid\":{},\"tid\":{},\"ts\":{}",
+                utils::quote_for_json(&event.name),
+                pid,
+                event.thread_id,
+                event.start.as_micros()
+            )?;
+            match event.duration {
+                Some(duration) => write!(file, ",\"ph\":\"X\",\"dur\":{}}}", duration.as_micros())?,
+                None => write!(file, ",\"ph\":\"i\",\"s\":\"t\"}}")?,
+            }
+        }
+    }
+    writeln!(file, "\n]}}")?;
+
+    file.flush()
+}
+
+fn record_trace_event<F: FnOnce(&Trace) -> TraceEvent>(make_event: F) {
+    if !TRACE_ENABLED.load(Ordering::Relaxed) {
+        return;
+    }
+    if let Ok(mut trace) = TRACE.lock() {
+        if let Some(ref mut trace) = *trace {
+            let event = make_event(trace);
+            trace.events.push(event);
+        }
+    }
+}
+
+fn thread_id() -> u64 {
+    THREAD_ID.with(|id| {
+        if id.get() == 0 {
+            id.set(NEXT_ID.fetch_add(1, Ordering::Relaxed));
+        }
+        id.get()
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn durations(durations: &[Duration]) -> Durations {
+        let mut result = Durations::default();
+        for duration in durations {
+            result.add(*duration);
+        }
+        result
+    }
+
+    #[test]
+    fn t_scope_stats_aggregates_durations() {
+        let durations = (1..=20)
+            .rev()
+            .map(Duration::from_millis)
+            .collect::<Vec<_>>();
+        let stats = self::durations(&durations).stats("test").unwrap();
+
+        assert_eq!(stats.name, "test");
+        assert_eq!(stats.count, 20);
+        assert_eq!(stats.total, Duration::from_millis(210));
+        assert_eq!(stats.min, Duration::from_millis(1));
+        assert_eq!(stats.max, Duration::from_millis(20));
+        assert!(stats.p95 >= Duration::from_millis(19));
+        assert!(stats.p95 < Duration::from_millis(19) * 17 / 16);
+    }
+
+    #[test]
+    fn t_scope_stats_p95_of_a_single_duration_is_that_duration() {
+        let stats = durations(&[Duration::from_secs(3)]).stats("test").unwrap();
+        assert_eq!(stats.p95, Duration::from_secs(3));
+    }
+
+    #[test]
+    fn t_scope_stats_are_not_computed_without_durations() {
+        assert_eq!(Durations::default().stats("test"), None);
+    }
+
+    #[test]
+    fn t_merged_durations_are_the_same_as_durations_added_together() {
+        let all = (1..=1000)
+            .map(|i| Duration::from_micros(i * i))
+            .collect::<Vec<_>>();
+        let (first, second) = all.split_at(300);
+
+        let mut merged = durations(second);
+        merged.merge(&durations(first));
+        merged.merge(&Durations::default());
+
+        assert_eq!(merged, durations(&all));
+    }
+
+    #[test]
+    fn t_durations_take_bounded_space() {
+        let mut durations = Durations::default();
+        for i in 0..100_000 {
+            durations.add(Duration::from_millis(i % 5000));
+        }
+        durations.add(Duration::from_secs(24 * 60 * 60));
+        assert!(durations.buckets.len() < 600);
+    }
+
+    #[test]
+    fn t_bucket_start_is_the_inverse_of_bucket_index() {
+        let mut previous_start = None;
+        for index in 0..bucket_index(u64::MAX) {
+            let start = bucket_start(index);
+            assert_eq!(bucket_index(start), index);
+            assert_eq!(bucket_index(bucket_start(index + 1) - 1), index);
+            if let Some(previous_start) = previous_start {
+                assert!(start > previous_start);
+            }
+            previous_start = Some(start);
+        }
+    }
+
+    #[test]
+    fn t_nested_scopes_know_their_parent() {
+        let outer = ScopeMeasure::new(String::from("t_nested_scopes outer"));
+        assert_eq!(outer.parent(), None);
+        {
+            let inner = ScopeMeasure::new(String::from("t_nested_scopes inner"));
+            assert_eq!(inner.parent(), Some("t_nested_scopes outer"));
+        }
+        let sibling = ScopeMeasure::new(String::from("t_nested_scopes sibling"));
+        assert_eq!(sibling.parent(), Some("t_nested_scopes outer"));
+    }
+}
@@ -1,17 +1,21 @@
 use crate::htmlrenderer;
 use crate::logger::{self, Level};
 use libc::{
-    c_char, c_int, c_ulong, c_void, close, execvp, exit, fork, size_t, waitpid, E2BIG, EILSEQ,
-    EINVAL,
+    c_char, c_int, c_ulong, c_void, close, execvp, exit, fork, kill, pid_t, setpgid, size_t,
+    waitpid, E2BIG, EILSEQ, EINVAL, SIGKILL,
 };
 use percent_encoding::*;
 use std::ffi::CString;
 use std::fs::DirBuilder;
-use std::io::{self, Write};
+use std::io::{self, Read, Write};
 use std::os::unix::fs::DirBuilderExt;
+use std::os::unix::process::CommandExt;
 use std::path::{Path, PathBuf};
 use std::process::{Command, Stdio};
 use std::ptr;
+use std::sync::mpsc;
+use std::thread;
+use std::time::{Duration, Instant};
 use unicode_width::{UnicodeWidthChar, UnicodeWidthStr};
 use url::Url;
 
@@ -205,6 +209,32 @@ pub fn quote_for_stfl(string: &str) -> String {
     string.replace("<", "<>")
 }
 
+/// Quote a string for use as a JSON string literal, escaping everything that JSON requires to be
+/// escaped
+/// ```
+/// use libnewsboat::utils::quote_for_json;
+/// assert_eq!(&quote_for_json("test"), r#""test""#);
+/// assert_eq!(&quote_for_json(r#"say "hi"\n"#), r#""say \"hi\"\\n""#);
+/// assert_eq!(&quote_for_json("line\nbreak\u{1}"), r#""line\nbreak\u0001""#);
+/// ```
+pub fn quote_for_json(string: &str) -> String {
+    let mut result = String::with_capacity(string.len() + 2);
+    result.push('"');
+    for c in string.chars() {
+        match c {
+            '"' => result.push_str("\\\""),
+            '\\' => result.push_str("\\\\"),
+            '\n' => result.push_str("\\n"),
+            '\r' => result.push_str("\\r"),
+            '\t' => result.push_str("\\t"),
+            c if (c as u32) < 0x20 => result.push_str(&format!("\\u{:04x}", c as u32)),
+            c => result.push(c),
+        }
+    }
+    result.push('"');
+    result
+}
+
 /// Get basename from a URL if available else return an empty string
 /// ```
 /// use libnewsboat::utils::get_basename;
@@ -606,6 +636,336 @@ pub fn run_program(cmd_with_args: &[&str], input: &str) -> String {
         .unwrap_or_else(|_| String::new())
 }
 
+/// Default for `CommandLimits::max_output_size`.
+pub const DEFAULT_MAX_COMMAND_OUTPUT_SIZE: usize = 32 * 1024 * 1024;
+
+/// How often a command started by `run_with_limits()` is checked for having finished.
+const COMMAND_POLL_INTERVAL: Duration = Duration::from_millis(10);
+
+/// Limits for the functions that run external commands, like `get_command_output_with_limits()`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CommandLimits {
+    /// If the command is still running after this long, it's killed along with all the processes
+    /// it started. `None` means to wait for as long as it takes.
+    pub timeout: Option<Duration>,
+
+    /// Maximum number of bytes kept of each of stdout and stderr. The rest is read and discarded.
+    pub max_output_size: usize,
+}
+
+impl Default for CommandLimits {
+    fn default() -> CommandLimits {
+        CommandLimits {
+            timeout: None,
+            max_output_size: DEFAULT_MAX_COMMAND_OUTPUT_SIZE,
+        }
+    }
+}
+
+/// What happened to a command run by one of the functions that take `CommandLimits`.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CommandOutput {
+    /// Exit code of the command, or `None` if it was killed by a signal (e.g. because it timed
+    /// out).
+    pub exit_code: Option<i32>,
+
+    /// Captured stdout. Invalid UTF-8 is replaced with U+FFFD.
+    pub stdout: String,
+
+    /// Captured stderr. Invalid UTF-8 is replaced with U+FFFD.
+    pub stderr: String,
+
+    /// True if the command was killed because it ran for longer than `CommandLimits::timeout`.
+    pub timed_out: bool,
+
+    /// True if stdout or stderr were longer than `CommandLimits::max_output_size` and got cut.
+    pub truncated: bool,
+}
+
+impl CommandOutput {
+    /// True if the command finished in time and exited with code 0.
+    pub fn success(&self) -> bool {
+        !self.timed_out && self.exit_code == Some(0)
+    }
+}
+
+/// Runs given command in a shell, like `get_command_output()`, but within `limits`. Both stdout
+/// and stderr are captured.
+///
+/// The command runs in its own process group, so that the processes it starts can be killed along
+/// with it. Because of that, it can't read from the terminal, and its stdin is closed.
+///
+/// Returns an error if the command couldn't be started.
+/// ```
+/// use libnewsboat::utils::{get_command_output_with_limits, CommandLimits};
+/// use std::time::Duration;
+///
+/// let result = get_command_output_with_limits("echo hello; echo world >&2", CommandLimits::default())
+///     .unwrap();
+/// assert!(result.success());
+/// assert_eq!(result.stdout, "hello\n");
+/// assert_eq!(result.stderr, "world\n");
+///
+/// let limits = CommandLimits {
+///     timeout: Some(Duration::from_millis(100)),
+///     max_output_size: 3,
+/// };
+/// let result = get_command_output_with_limits("echo hello; sleep 10", limits).unwrap();
+/// assert!(result.timed_out);
+/// assert!(result.truncated);
+/// assert_eq!(result.exit_code, None);
+/// assert_eq!(result.stdout, "hel");
+/// ```
+pub fn get_command_output_with_limits(
+    cmd: &str,
+    limits: CommandLimits,
+) -> io::Result<CommandOutput> {
+    let mut command = Command::new("sh");
+    command.arg("-c").arg(cmd).stdin(Stdio::null());
+    run_with_limits(
+        &mut command,
+        None,
+        limits,
+        "utils::get_command_output_with_limits",
+        cmd,
+    )
+}
+
+/// Runs the program with the given arguments, feeding it `input`, like `run_program()`, but
+/// within `limits`. Both stdout and stderr are captured.
+///
+/// The program runs in its own process group, so that the processes it starts can be killed along
+/// with it.
+///
+/// Returns an error if `cmd_with_args` is empty, or the program couldn't be started.
+/// ```
+/// use libnewsboat::utils::{run_program_with_limits, CommandLimits};
+///
+/// let result = run_program_with_limits(&["tr", "a-z", "A-Z"], "hello", CommandLimits::default())
+///     .unwrap();
+/// assert!(result.success());
+/// assert_eq!(result.stdout, "HELLO");
+///
+/// let result = run_program_with_limits(&["false"], "", CommandLimits::default()).unwrap();
+/// assert!(!result.success());
+/// assert_eq!(result.exit_code, Some(1));
+///
+/// assert!(run_program_with_limits(&[], "", CommandLimits::default()).is_err());
+/// ```
+pub fn run_program_with_limits(
+    cmd_with_args: &[&str],
+    input: &str,
+    limits: CommandLimits,
+) -> io::Result<CommandOutput> {
+    if cmd_with_args.is_empty() {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "no program to run",
+        ));
+    }
+
+    let mut command = Command::new(cmd_with_args[0]);
+    command.args(&cmd_with_args[1..]);
+    run_with_limits(
+        &mut command,
+        Some(input),
+        limits,
+        "utils::run_program_with_limits",
+        &cmd_with_args.join(" "),
+    )
+}
+
+/// Runs `command` within `limits`, capturing stdout and stderr. If `input` is `Some`, it's written
+/// to stdin.
+fn run_with_limits(
+    command: &mut Command,
+    input: Option<&str>,
+    limits: CommandLimits,
+    caller: &str,
+    description: &str,
+) -> io::Result<CommandOutput> {
+    command.stdout(Stdio::piped()).stderr(Stdio::piped());
+    let input = input.map(|input| {
+        command.stdin(Stdio::piped());
+        input.to_owned()
+    });
+
+    unsafe {
+        command.pre_exec(|| {
+            if setpgid(0, 0) == -1 {
+                return Err(io::Error::last_os_error());
+            }
+            Ok(())
+        });
+    }
+
+    let mut child = command.spawn().map_err(|error| {
+        log!(
+            Level::Warn,
+            "{}: couldn't start `{}': {}",
+            caller,
+            description,
+            error
+        );
+        error
+    })?;
+    let pgid = child.id() as pid_t;
+    // The child does this too, but we can't know if it already did, and `kill()` needs the group
+    // to exist. This fails if the child already called `exec()`, which is fine.
+    unsafe { setpgid(pgid, pgid) };
+
+    if let (Some(mut stdin), Some(input)) = (child.stdin.take(), input) {
+        // Write from a separate thread, so that the child doesn't block on a full stdout while we
+        // block on a full stdin.
+        thread::spawn(move || {
+            if let Err(error) = stdin.write_all(input.as_bytes()) {
+                log!(
+                    Level::Debug,
+                    "utils::run_with_limits: failed to write to child's stdin: {}",
+                    error
+                );
+            }
+        });
+    }
+    let stdout = read_with_limit(child.stdout.take(), limits.max_output_size);
+    let stderr = read_with_limit(child.stderr.take(), limits.max_output_size);
+
+    let deadline = limits.timeout.map(|timeout| Instant::now() + timeout);
+    let kill_group = || {
+        log!(
+            Level::Warn,
+            "{}: `{}' didn't finish in {:.3} s, killing it",
+            caller,
+            description,
+            limits.timeout.unwrap_or_default().as_secs_f64()
+        );
+        unsafe { kill(-pgid, SIGKILL) };
+    };
+
+    let mut timed_out = false;
+    let status = loop {
+        match (child.try_wait(), deadline) {
+            (Ok(Some(status)), _) => break Ok(status),
+            (Ok(None), Some(deadline)) if Instant::now() >= deadline => {
+                kill_group();
+                timed_out = true;
+                break child.wait();
+            }
+            (Ok(None), _) => thread::sleep(COMMAND_POLL_INTERVAL),
+            (Err(error), _) => break Err(error),
+        }
+    };
+
+    let status = status.map_err(|error| {
+        log!(
+            Level::Warn,
+            "{}: failed to wait for `{}': {}",
+            caller,
+            description,
+            error
+        );
+        error
+    })?;
+
+    // Processes started by the command might still be holding the pipes open, so keep watching
+    // the clock while reading them.
+    let mut receive = |output: &mpsc::Receiver<(Vec<u8>, bool)>| {
+        if !timed_out {
+            if let Some(deadline) = deadline {
+                let now = Instant::now();
+                let remaining = if deadline > now {
+                    deadline - now
+                } else {
+                    Duration::from_secs(0)
+                };
+                match output.recv_timeout(remaining) {
+                    Ok(result) => return result,
+                    Err(_) => {
+                        kill_group();
+                        timed_out = true;
+                    }
+                }
+            }
+        }
+        output.recv().unwrap_or_default()
+    };
+    let (stdout, stdout_truncated) = receive(&stdout);
+    let (stderr, stderr_truncated) = receive(&stderr);
+
+    let result = CommandOutput {
+        exit_code: status.code(),
+        stdout: String::from_utf8_lossy(&stdout).into_owned(),
+        stderr: String::from_utf8_lossy(&stderr).into_owned(),
+        timed_out,
+        truncated: stdout_truncated || stderr_truncated,
+    };
+
+    if result.truncated {
+        log!(
+            Level::Warn,
+            "{}: output of `{}' was longer than {} bytes, the rest was discarded",
+            caller,
+            description,
+            limits.max_output_size
+        );
+    }
+    if !result.timed_out {
+        match result.exit_code {
+            Some(0) => {}
+            Some(code) => {
+                log!(
+                    Level::Info,
+                    "{}: `{}' exited with code {}",
+                    caller,
+                    description,
+                    code
+                );
+            }
+            None => {
+                log!(
+                    Level::Info,
+                    "{}: `{}' was killed by a signal",
+                    caller,
+                    description
+                );
+            }
+        }
+    }
+
+    Ok(result)
+}
+
+/// Reads `reader` to the end in a separate thread, keeping at most `limit` bytes. The result is
+/// sent to the returned channel along with a flag telling if anything was discarded.
+fn read_with_limit<R: Read + Send + 'static>(
+    reader: Option<R>,
+    limit: usize,
+) -> mpsc::Receiver<(Vec<u8>, bool)> {
+    let (sender, receiver) = mpsc::channel();
+    thread::spawn(move || {
+        let mut data = Vec::new();
+        let mut truncated = false;
+        if let Some(mut reader) = reader {
+            let mut buffer = [0u8; 8192];
+            loop {
+                match reader.read(&mut buffer) {
+                    Ok(0) => break,
+                    Ok(n) => {
+                        let room = limit.saturating_sub(data.len());
+                        truncated |= n > room;
+                        data.extend_from_slice(&buffer[..n.min(room)]);
+                    }
+                    Err(ref error) if error.kind() == io::ErrorKind::Interrupted => {}
+                    Err(_) => break,
+                }
+            }
+        }
+        // The receiver is gone only if the caller gave up on us, so there's no one to tell.
+        let _ = sender.send((data, truncated));
+    });
+    receiver
+}
+
 pub fn make_title(rs_str: String) -> String {
     /* Sometimes it is possible to construct the title from the URL
      * This attempts to do just that. eg:
@@ -861,6 +1221,55 @@ pub fn strip_comments(line: &str) -> &str {
     &line[0..first_pound_chr_idx]
 }
 
+/// Splits the line into whitespace-separated tokens, obeying double quotes and stopping at
+/// a comment. Inside double quotes, `\n`, `\r` and `\t` stand for newline, carriage return and
+/// tab; any other escaped character is taken literally.
+pub fn tokenize_quoted(line: &str) -> Vec<String> {
+    let mut tokens = Vec::new();
+    let mut chars = line.chars().peekable();
+    loop {
+        while let Some(c) = chars.peek() {
+            if !c.is_whitespace() {
+                break;
+            }
+            chars.next();
+        }
+        match chars.peek() {
+            None | Some('#') => break,
+            Some('"') => {
+                chars.next();
+                let mut token = String::new();
+                while let Some(c) = chars.next() {
+                    match c {
+                        '"' => break,
+                        '\\' => match chars.next() {
+                            Some('n') => token.push('\n'),
+                            Some('r') => token.push('\r'),
+                            Some('t') => token.push('\t'),
+                            Some(other) => token.push(other),
+                            None => {}
+                        },
+                        _ => token.push(c),
+                    }
+                }
+                tokens.push(token);
+            }
+            Some(_) => {
+                let mut token = String::new();
+                while let Some(c) = chars.peek() {
+                    if c.is_whitespace() {
+                        break;
+                    }
+                    token.push(*c);
+                    chars.next();
+                }
+                tokens.push(token);
+            }
+        }
+    }
+    tokens
+}
+
 /// The result of executing `extract_filter()`.
 pub struct FilterUrlParts {
     /// "~/bin/foo.sh" in "filter:~/bin/foo.sh:https://example.com/news.atom"
@@ -871,6 +1280,9 @@ pub struct FilterUrlParts {
 }
 
 /// Extract filter and url from line separated by ':'.
+///
+/// Malformed lines result in an empty script or URL. Use `feedsource::FeedSource` to have them
+/// reported as errors instead.
 pub fn extract_filter(line: &str) -> FilterUrlParts {
     debug_assert!(line.starts_with("filter:"));
     // line must start with "filter:"
@@ -1572,6 +1984,53 @@ mod tests {
         );
     }
 
+    #[test]
+    fn t_run_program_with_limits_feeds_input_bigger_than_a_pipe_buffer() {
+        let input = "a".repeat(1024 * 1024);
+        let result = run_program_with_limits(&["cat"], &input, CommandLimits::default()).unwrap();
+        assert!(result.success());
+        assert!(!result.truncated);
+        assert_eq!(result.stdout, input);
+    }
+
+    #[test]
+    fn t_run_program_with_limits_keeps_reading_after_output_is_truncated() {
+        let limits = CommandLimits {
+            timeout: Some(Duration::from_secs(10)),
+            max_output_size: 10,
+        };
+        // `head` would block on a full pipe if we stopped reading it
+        let result =
+            run_program_with_limits(&["head", "-c", "1000000", "/dev/zero"], "", limits).unwrap();
+        assert!(result.success());
+        assert!(result.truncated);
+        assert_eq!(result.stdout.len(), 10);
+    }
+
+    #[test]
+    fn t_get_command_output_with_limits_kills_the_whole_process_group() {
+        let limits = CommandLimits {
+            timeout: Some(Duration::from_millis(200)),
+            ..CommandLimits::default()
+        };
+        let start = Instant::now();
+        // The background `sleep` keeps stdout open after the shell exits
+        let result = get_command_output_with_limits("sleep 30 & echo started", limits).unwrap();
+        assert!(start.elapsed() < Duration::from_secs(10));
+        assert!(result.timed_out);
+        assert!(!result.success());
+        assert_eq!(result.exit_code, Some(0));
+        assert_eq!(result.stdout, "started\n");
+    }
+
+    #[test]
+    fn t_get_command_output_with_limits_reports_exit_code() {
+        let result = get_command_output_with_limits("exit 42", CommandLimits::default()).unwrap();
+        assert!(!result.timed_out);
+        assert!(!result.success());
+        assert_eq!(result.exit_code, Some(42));
+    }
+
     #[test]
     fn t_make_title() {
         let mut input = String::from("http://example.com/Item");
@@ -1795,6 +2254,16 @@ mod tests {
         assert_eq!(strnaturalcmp("aa10", "aa2"), Ordering::Greater);
     }
 
+    #[test]
+    fn t_tokenize_quoted_splits_on_whitespace_obeying_quotes_and_comments() {
+        assert_eq!(
+            tokenize_quoted(r#"  url tag "two words" "a \"b\"\tc" # comment"#),
+            vec!["url", "tag", "two words", "a \"b\"\tc"]
+        );
+        assert!(tokenize_quoted("# only a comment").is_empty());
+        assert!(tokenize_quoted("   ").is_empty());
+    }
+
     #[test]
     fn t_strip_comments() {
         // no comments in line
@@ -0,0 +1,32 @@
+use libnewsboat::cliargsparser::CliArgsParser;
+use std::env;
+use std::path::PathBuf;
+use tempfile::TempDir;
+
+#[test]
+fn t_cliargsparser_subcommands_resolve_tilde_to_homedir() {
+    let tmp = TempDir::new().unwrap();
+
+    env::set_var("HOME", tmp.path());
+
+    let filename = "file.txt";
+    let arg = format!("~/{}", filename);
+
+    let parse = |subcommand: &[&str]| {
+        let mut opts = vec!["newsboat".to_string()];
+        opts.extend(subcommand.iter().map(|s| s.to_string()));
+        opts.push(arg.clone());
+        CliArgsParser::new(opts)
+    };
+    let expected: Option<PathBuf> = Some(tmp.path().join(filename));
+
+    assert_eq!(parse(&["import", "opml"]).importfile, expected);
+    assert_eq!(
+        parse(&["readinfo", "import"]).readinfo_import_file,
+        expected
+    );
+    assert_eq!(
+        parse(&["readinfo", "export"]).readinfo_export_file,
+        expected
+    );
+}
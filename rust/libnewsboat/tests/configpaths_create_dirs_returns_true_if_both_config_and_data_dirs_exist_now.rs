@@ -16,6 +16,9 @@ fn assert_dirs_exist_after_create_dirs(dirs: &[&path::Path], tmp: &TempDir) {
     assert!(paths.queue_file().starts_with(&prefix));
     assert!(paths.search_file().starts_with(&prefix));
     assert!(paths.cmdline_file().starts_with(&prefix));
+    assert!(paths.article_search_file().starts_with(&prefix));
+    assert!(paths.feed_search_file().starts_with(&prefix));
+    assert!(paths.filter_file().starts_with(&prefix));
 
     assert!(paths.create_dirs());
 
@@ -0,0 +1,37 @@
+use libnewsboat::configpaths::ConfigPaths;
+use std::env;
+use std::fs;
+use tempfile::TempDir;
+
+#[test]
+fn t_configpaths_ignores_empty_env_vars() {
+    let tmp = TempDir::new().unwrap();
+    let newsboat_dir = tmp.path().join(".newsboat");
+    fs::create_dir_all(&newsboat_dir).unwrap();
+
+    env::set_var("HOME", tmp.path());
+
+    // ConfigPaths rely on these variables, so let's sanitize them to ensure
+    // that the tests aren't affected
+    env::remove_var("XDG_CONFIG_HOME");
+    env::remove_var("XDG_DATA_HOME");
+
+    for var in &[
+        "NEWSBOAT_CONFIG_DIR",
+        "NEWSBOAT_DATA_DIR",
+        "NEWSBOAT_URL_FILE",
+        "NEWSBOAT_CONFIG_FILE",
+        "NEWSBOAT_CACHE_FILE",
+        "NEWSBOAT_QUEUE_FILE",
+    ] {
+        env::set_var(var, "");
+    }
+
+    let paths = ConfigPaths::new();
+    assert!(paths.initialized());
+    assert_eq!(paths.url_file(), newsboat_dir.join("urls"));
+    assert_eq!(paths.config_file(), newsboat_dir.join("config"));
+    assert_eq!(paths.cache_file(), newsboat_dir.join("cache.db"));
+    assert_eq!(paths.queue_file(), newsboat_dir.join("queue"));
+    assert!(!paths.has_explicit_cache_file());
+}
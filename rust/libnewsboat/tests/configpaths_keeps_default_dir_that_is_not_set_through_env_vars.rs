@@ -0,0 +1,48 @@
+use libnewsboat::configpaths::ConfigPaths;
+use section_testing::{enable_sections, section};
+use std::env;
+use tempfile::TempDir;
+
+enable_sections! {
+#[test]
+fn t_configpaths_keeps_default_dir_that_is_not_set_through_env_vars() {
+    let tmp = TempDir::new().unwrap();
+    let dir = tmp.path().join("newsboat");
+    let dotdir = tmp.path().join(".newsboat");
+
+    env::set_var("HOME", tmp.path());
+
+    // ConfigPaths rely on these variables, so let's sanitize them to ensure
+    // that the tests aren't affected
+    env::remove_var("XDG_CONFIG_HOME");
+    env::remove_var("XDG_DATA_HOME");
+    env::remove_var("NEWSBOAT_URL_FILE");
+    env::remove_var("NEWSBOAT_CONFIG_FILE");
+    env::remove_var("NEWSBOAT_CACHE_FILE");
+    env::remove_var("NEWSBOAT_QUEUE_FILE");
+
+    if section!("Only the config dir is set") {
+        env::remove_var("NEWSBOAT_DATA_DIR");
+        env::set_var("NEWSBOAT_CONFIG_DIR", &dir);
+
+        let paths = ConfigPaths::new();
+        assert!(paths.initialized());
+        assert_eq!(paths.url_file(), dir.join("urls"));
+        assert_eq!(paths.config_file(), dir.join("config"));
+        assert_eq!(paths.cache_file(), dotdir.join("cache.db"));
+        assert_eq!(paths.queue_file(), dotdir.join("queue"));
+    }
+
+    if section!("Only the data dir is set") {
+        env::remove_var("NEWSBOAT_CONFIG_DIR");
+        env::set_var("NEWSBOAT_DATA_DIR", &dir);
+
+        let paths = ConfigPaths::new();
+        assert!(paths.initialized());
+        assert_eq!(paths.url_file(), dotdir.join("urls"));
+        assert_eq!(paths.config_file(), dotdir.join("config"));
+        assert_eq!(paths.cache_file(), dir.join("cache.db"));
+        assert_eq!(paths.queue_file(), dir.join("queue"));
+    }
+}
+}
@@ -0,0 +1,37 @@
+use libnewsboat::configpaths::ConfigPaths;
+use std::env;
+use std::fs;
+use tempfile::TempDir;
+
+#[test]
+fn t_configpaths_list_profiles_returns_names_of_existing_profiles() {
+    let tmp = TempDir::new().unwrap();
+    let config_home = tmp.path().join("config");
+    let data_home = tmp.path().join("data");
+
+    env::set_var("HOME", tmp.path());
+    env::set_var("XDG_CONFIG_HOME", &config_home);
+    env::set_var("XDG_DATA_HOME", &data_home);
+
+    fs::create_dir_all(config_home.join("newsboat")).unwrap();
+    assert!(ConfigPaths::new().list_profiles().is_empty());
+
+    let config_profiles = config_home.join("newsboat/profiles");
+    let data_profiles = data_home.join("newsboat/profiles");
+    fs::create_dir_all(config_profiles.join("work")).unwrap();
+    fs::create_dir_all(config_profiles.join("personal")).unwrap();
+    fs::create_dir_all(data_profiles.join("work")).unwrap();
+    fs::create_dir_all(data_profiles.join("archive")).unwrap();
+    // Neither a directory nor a valid profile name, so these are skipped
+    fs::write(config_profiles.join("notes"), "").unwrap();
+    fs::create_dir_all(config_profiles.join(".hidden")).unwrap();
+
+    assert_eq!(
+        ConfigPaths::new().list_profiles(),
+        vec![
+            "archive".to_string(),
+            "personal".to_string(),
+            "work".to_string()
+        ]
+    );
+}
@@ -0,0 +1,36 @@
+use libnewsboat::configpaths::ConfigPaths;
+use std::env;
+use tempfile::TempDir;
+
+mod configpaths_helpers;
+
+#[test]
+fn t_configpaths_plan_migration_explains_why_nothing_would_be_migrated() {
+    let tmp = TempDir::new().unwrap();
+
+    env::set_var("HOME", tmp.path());
+
+    // ConfigPaths rely on these variables, so let's sanitize them to ensure
+    // that the tests aren't affected
+    env::remove_var("XDG_CONFIG_HOME");
+    env::remove_var("XDG_DATA_HOME");
+
+    {
+        let paths = ConfigPaths::new();
+        assert!(paths.initialized());
+        assert_eq!(paths.plan_migration(), Ok(vec![]));
+        assert_eq!(
+            paths.describe_migration_plan(),
+            "Found nothing to migrate.\n"
+        );
+    }
+
+    configpaths_helpers::mock_newsbeuter_dotdir(&tmp);
+    configpaths_helpers::mock_newsboat_dotdir(&tmp);
+
+    let paths = ConfigPaths::new();
+    assert!(paths.initialized());
+    let reason = paths.plan_migration().unwrap_err();
+    assert!(reason.contains("already exists"));
+    assert_eq!(paths.describe_migration_plan(), reason + "\n");
+}
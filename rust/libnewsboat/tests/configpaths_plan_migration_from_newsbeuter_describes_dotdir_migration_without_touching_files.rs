@@ -0,0 +1,61 @@
+use libnewsboat::configpaths::ConfigPaths;
+use libnewsboat::migration::{FileAction, PlannedFile};
+use std::{env, fs};
+use tempfile::TempDir;
+
+mod configpaths_helpers;
+
+#[test]
+fn t_configpaths_plan_migration_from_newsbeuter_describes_dotdir_migration_without_touching_files()
+{
+    let tmp = TempDir::new().unwrap();
+
+    env::set_var("HOME", tmp.path());
+
+    // ConfigPaths rely on these variables, so let's sanitize them to ensure
+    // that the tests aren't affected
+    env::remove_var("XDG_CONFIG_HOME");
+    env::remove_var("XDG_DATA_HOME");
+
+    configpaths_helpers::mock_newsbeuter_dotdir(&tmp);
+    let newsbeuter_dir = tmp.path().join(".newsbeuter");
+    fs::remove_file(newsbeuter_dir.join("queue")).unwrap();
+
+    let paths = ConfigPaths::new();
+    assert!(paths.initialized());
+
+    let plans = paths.plan_migration_from_newsbeuter().unwrap();
+    assert_eq!(plans.len(), 1);
+
+    let plan = &plans[0];
+    let newsboat_dir = tmp.path().join(".newsboat");
+    assert_eq!(plan.source_name, "~/.newsbeuter/");
+    assert_eq!(plan.directories, vec![newsboat_dir.clone()]);
+    assert!(plan.conflicts.is_empty());
+    assert!(plan.permission_problems.is_empty());
+    assert!(plan.can_proceed());
+
+    let file = |name: &str, action| PlannedFile {
+        source: newsbeuter_dir.join(name),
+        destination: newsboat_dir.join(name),
+        action,
+    };
+    assert_eq!(
+        plan.files,
+        vec![
+            file("urls", FileAction::Copy),
+            file("cache.db", FileAction::Copy),
+            file("config", FileAction::Copy),
+            file("queue", FileAction::SkipMissing),
+            file("history.search", FileAction::Copy),
+            file("history.cmdline", FileAction::Copy),
+        ]
+    );
+
+    let description = paths.describe_migration_plan();
+    assert!(description.starts_with("Would migrate configs and data from ~/.newsbeuter/:\n"));
+    assert!(description.contains("(doesn't exist)"));
+
+    // Nothing was touched
+    assert!(!newsboat_dir.exists());
+}
@@ -0,0 +1,56 @@
+use libnewsboat::configpaths::ConfigPaths;
+use libnewsboat::migration::FileAction;
+use std::{env, fs};
+use tempfile::TempDir;
+
+mod configpaths_helpers;
+
+#[test]
+fn t_configpaths_plan_migration_reports_conflicts() {
+    let tmp = TempDir::new().unwrap();
+
+    env::set_var("HOME", tmp.path());
+
+    // ConfigPaths rely on these variables, so let's sanitize them to ensure
+    // that the tests aren't affected
+    env::remove_var("XDG_CONFIG_HOME");
+    env::remove_var("XDG_DATA_HOME");
+
+    configpaths_helpers::mock_newsbeuter_xdg_dirs(&tmp);
+    configpaths_helpers::mock_newsbeuter_dotdir(&tmp);
+
+    // Newsboat's XDG data dir already exists, and has a cache in it
+    let data_dir = tmp.path().join(".local").join("share").join("newsboat");
+    fs::create_dir_all(&data_dir).unwrap();
+    assert!(configpaths_helpers::create_file(
+        &data_dir.join("cache.db"),
+        "cache"
+    ));
+
+    let paths = ConfigPaths::new();
+    assert!(paths.initialized());
+
+    let plans = paths.plan_migration().unwrap();
+    assert_eq!(plans.len(), 2);
+
+    let xdg_plan = &plans[0];
+    assert_eq!(xdg_plan.conflicts, vec![data_dir.clone()]);
+    assert!(!xdg_plan.can_proceed());
+    let cache = xdg_plan
+        .files
+        .iter()
+        .find(|file| file.destination == data_dir.join("cache.db"))
+        .unwrap();
+    assert_eq!(cache.action, FileAction::SkipExisting);
+
+    // The dotdir is migrated instead
+    assert_eq!(plans[1].source_name, "~/.newsbeuter/");
+    assert!(plans[1].can_proceed());
+
+    let description = paths.describe_migration_plan();
+    assert!(description.contains(&format!(
+        "conflict: `{}' already exists",
+        data_dir.display()
+    )));
+    assert!(description.contains("Would migrate configs and data from ~/.newsbeuter/:"));
+}
@@ -0,0 +1,44 @@
+use libnewsboat::{cliargsparser::CliArgsParser, configpaths::ConfigPaths};
+use std::env;
+use std::path::Path;
+use tempfile::TempDir;
+
+#[test]
+fn t_configpaths_process_args_prefers_paths_supplied_by_cliargsparser_to_env_vars() {
+    let tmp = TempDir::new().unwrap();
+    let dir = tmp.path().join("from env");
+
+    env::set_var("HOME", tmp.path());
+
+    // ConfigPaths rely on these variables, so let's sanitize them to ensure
+    // that the tests aren't affected
+    env::remove_var("XDG_CONFIG_HOME");
+    env::remove_var("XDG_DATA_HOME");
+    env::remove_var("NEWSBOAT_DATA_DIR");
+    env::remove_var("NEWSBOAT_CONFIG_FILE");
+
+    env::set_var("NEWSBOAT_CONFIG_DIR", &dir);
+    env::set_var("NEWSBOAT_URL_FILE", dir.join("urls from env"));
+    env::set_var("NEWSBOAT_CACHE_FILE", dir.join("cache from env.db"));
+
+    let url_file = Path::new("my urls file");
+    let cache_file = Path::new("/path/to/cache file.db");
+    let parser = CliArgsParser::new(vec![
+        "newsboat".to_string(),
+        "-u".to_string(),
+        url_file.to_string_lossy().into_owned(),
+        "-c".to_string(),
+        cache_file.to_string_lossy().into_owned(),
+    ]);
+
+    let mut paths = ConfigPaths::new();
+    assert!(paths.initialized());
+    paths.process_args(&parser);
+
+    assert_eq!(paths.url_file(), url_file);
+    assert_eq!(paths.cache_file(), cache_file);
+    assert_eq!(paths.lock_file(), Path::new("/path/to/cache file.db.lock"));
+
+    // Paths that weren't given on the command line still come from the environment
+    assert_eq!(paths.config_file(), dir.join("config"));
+}
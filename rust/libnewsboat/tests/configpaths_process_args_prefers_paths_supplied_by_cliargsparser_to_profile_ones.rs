@@ -0,0 +1,35 @@
+use libnewsboat::{cliargsparser::CliArgsParser, configpaths::ConfigPaths};
+use std::env;
+use std::fs;
+use std::path::Path;
+use tempfile::TempDir;
+
+#[test]
+fn t_configpaths_process_args_prefers_paths_supplied_by_cliargsparser_to_profile_ones() {
+    let tmp = TempDir::new().unwrap();
+    fs::create_dir_all(tmp.path().join(".newsboat")).unwrap();
+
+    env::set_var("HOME", tmp.path());
+
+    // ConfigPaths rely on these variables, so let's sanitize them to ensure
+    // that the tests aren't affected
+    env::remove_var("XDG_CONFIG_HOME");
+    env::remove_var("XDG_DATA_HOME");
+
+    let url_file = Path::new("/path/to/shared urls");
+    let parser = CliArgsParser::new(vec![
+        "newsboat".to_string(),
+        "--profile=work".to_string(),
+        "-u".to_string(),
+        url_file.to_string_lossy().into_owned(),
+    ]);
+
+    let mut paths = ConfigPaths::new();
+    assert!(paths.initialized());
+    paths.process_args(&parser);
+
+    let profile_dir = tmp.path().join(".newsboat/profiles/work");
+    assert_eq!(paths.url_file(), url_file);
+    assert_eq!(paths.config_file(), profile_dir.join("config"));
+    assert_eq!(paths.cache_file(), profile_dir.join("cache.db"));
+}
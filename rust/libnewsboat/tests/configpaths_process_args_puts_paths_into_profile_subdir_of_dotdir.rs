@@ -0,0 +1,54 @@
+use libnewsboat::{cliargsparser::CliArgsParser, configpaths::ConfigPaths};
+use std::env;
+use std::fs;
+use tempfile::TempDir;
+
+#[test]
+fn t_configpaths_process_args_puts_paths_into_profile_subdir_of_dotdir() {
+    let tmp = TempDir::new().unwrap();
+    fs::create_dir_all(tmp.path().join(".newsboat")).unwrap();
+
+    env::set_var("HOME", tmp.path());
+
+    // ConfigPaths rely on these variables, so let's sanitize them to ensure
+    // that the tests aren't affected
+    env::remove_var("XDG_CONFIG_HOME");
+    env::remove_var("XDG_DATA_HOME");
+
+    let parser = CliArgsParser::new(vec!["newsboat".to_string(), "--profile=work".to_string()]);
+
+    let mut paths = ConfigPaths::new();
+    assert!(paths.initialized());
+    paths.process_args(&parser);
+
+    let profile_dir = tmp.path().join(".newsboat").join("profiles").join("work");
+    assert_eq!(paths.profile(), Some("work"));
+    assert_eq!(paths.url_file(), profile_dir.join("urls"));
+    assert_eq!(paths.config_file(), profile_dir.join("config"));
+    assert_eq!(paths.cache_file(), profile_dir.join("cache.db"));
+    assert_eq!(paths.lock_file(), profile_dir.join("cache.db.lock"));
+    assert_eq!(
+        paths.control_socket_file(),
+        profile_dir.join("cache.db.sock")
+    );
+    // The queue is shared with Podboat, which doesn't know about profiles
+    assert_eq!(
+        paths.queue_file(),
+        tmp.path().join(".newsboat").join("queue")
+    );
+    assert_eq!(paths.search_file(), profile_dir.join("history.search"));
+    assert_eq!(paths.cmdline_file(), profile_dir.join("history.cmdline"));
+    assert_eq!(
+        paths.article_search_file(),
+        profile_dir.join("history.search.articles")
+    );
+    assert_eq!(
+        paths.feed_search_file(),
+        profile_dir.join("history.search.feeds")
+    );
+    assert_eq!(paths.filter_file(), profile_dir.join("history.filter"));
+    assert_eq!(paths.crash_reports_dir(), profile_dir.join("crash-reports"));
+
+    assert!(paths.create_dirs());
+    assert!(profile_dir.is_dir());
+}
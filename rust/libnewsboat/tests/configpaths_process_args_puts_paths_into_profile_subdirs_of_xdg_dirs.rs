@@ -0,0 +1,36 @@
+use libnewsboat::{cliargsparser::CliArgsParser, configpaths::ConfigPaths};
+use std::env;
+use std::fs;
+use tempfile::TempDir;
+
+#[test]
+fn t_configpaths_process_args_puts_paths_into_profile_subdirs_of_xdg_dirs() {
+    let tmp = TempDir::new().unwrap();
+    let config_home = tmp.path().join("config");
+    let data_home = tmp.path().join("data");
+    fs::create_dir_all(config_home.join("newsboat")).unwrap();
+
+    env::set_var("HOME", tmp.path());
+    env::set_var("XDG_CONFIG_HOME", &config_home);
+    env::set_var("XDG_DATA_HOME", &data_home);
+
+    let parser = CliArgsParser::new(vec![
+        "newsboat".to_string(),
+        "--profile".to_string(),
+        "personal".to_string(),
+    ]);
+
+    let mut paths = ConfigPaths::new();
+    assert!(paths.initialized());
+    paths.process_args(&parser);
+
+    let config_dir = config_home.join("newsboat/profiles/personal");
+    let data_dir = data_home.join("newsboat/profiles/personal");
+    assert_eq!(paths.url_file(), config_dir.join("urls"));
+    assert_eq!(paths.config_file(), config_dir.join("config"));
+    assert_eq!(paths.cache_file(), data_dir.join("cache.db"));
+    assert_eq!(paths.lock_file(), data_dir.join("cache.db.lock"));
+    // The queue is shared with Podboat, which doesn't know about profiles
+    assert_eq!(paths.queue_file(), data_home.join("newsboat/queue"));
+    assert_eq!(paths.cmdline_file(), data_dir.join("history.cmdline"));
+}
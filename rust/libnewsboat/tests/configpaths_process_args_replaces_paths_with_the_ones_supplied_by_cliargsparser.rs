@@ -13,6 +13,7 @@ fn t_configpaths_process_args_replaces_paths_with_the_ones_supplied_by_cliargspa
     let url_file = Path::new("my urls file");
     let cache_file = Path::new("/path/to/cache file.db");
     let lock_file = Path::new("/path/to/cache file.db.lock");
+    let control_socket_file = Path::new("/path/to/cache file.db.sock");
     let config_file = Path::new("this is a/config");
 
     let parser = CliArgsParser::new(vec![
@@ -32,5 +33,6 @@ fn t_configpaths_process_args_replaces_paths_with_the_ones_supplied_by_cliargspa
     assert_eq!(paths.url_file(), url_file);
     assert_eq!(paths.cache_file(), cache_file);
     assert_eq!(paths.lock_file(), lock_file);
+    assert_eq!(paths.control_socket_file(), control_socket_file);
     assert_eq!(paths.config_file(), config_file);
 }
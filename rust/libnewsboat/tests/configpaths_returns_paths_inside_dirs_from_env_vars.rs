@@ -0,0 +1,37 @@
+use libnewsboat::configpaths::ConfigPaths;
+use std::env;
+use std::fs;
+use tempfile::TempDir;
+
+#[test]
+fn t_configpaths_returns_paths_inside_dirs_from_env_vars() {
+    let tmp = TempDir::new().unwrap();
+    let config_dir = tmp.path().join("config");
+    let data_dir = tmp.path().join("data");
+
+    env::set_var("HOME", tmp.path());
+
+    // The XDG dirs exist, but the variables should take precedence
+    fs::create_dir_all(tmp.path().join(".config/newsboat")).unwrap();
+    env::remove_var("XDG_CONFIG_HOME");
+    env::remove_var("XDG_DATA_HOME");
+
+    env::set_var("NEWSBOAT_CONFIG_DIR", &config_dir);
+    env::set_var("NEWSBOAT_DATA_DIR", &data_dir);
+
+    let paths = ConfigPaths::new();
+    assert!(paths.initialized());
+    assert_eq!(paths.url_file(), config_dir.join("urls"));
+    assert_eq!(paths.config_file(), config_dir.join("config"));
+    assert_eq!(paths.cache_file(), data_dir.join("cache.db"));
+    assert_eq!(paths.lock_file(), data_dir.join("cache.db.lock"));
+    assert_eq!(paths.control_socket_file(), data_dir.join("cache.db.sock"));
+    assert_eq!(paths.queue_file(), data_dir.join("queue"));
+    assert_eq!(paths.search_file(), data_dir.join("history.search"));
+    assert_eq!(paths.cmdline_file(), data_dir.join("history.cmdline"));
+    assert!(!paths.has_explicit_cache_file());
+
+    assert!(paths.create_dirs());
+    assert!(config_dir.is_dir());
+    assert!(data_dir.is_dir());
+}
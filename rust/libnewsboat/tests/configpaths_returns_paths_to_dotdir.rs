@@ -21,8 +21,25 @@ fn t_returns_paths_to_newsboat_dotdir_if_no_newsboat_dirs_exist() {
     assert_eq!(paths.url_file(), newsboat_dir.join("urls"));
     assert_eq!(paths.cache_file(), newsboat_dir.join("cache.db"));
     assert_eq!(paths.lock_file(), newsboat_dir.join("cache.db.lock"));
+    assert_eq!(
+        paths.control_socket_file(),
+        newsboat_dir.join("cache.db.sock")
+    );
     assert_eq!(paths.config_file(), newsboat_dir.join("config"));
     assert_eq!(paths.queue_file(), newsboat_dir.join("queue"));
     assert_eq!(paths.search_file(), newsboat_dir.join("history.search"));
     assert_eq!(paths.cmdline_file(), newsboat_dir.join("history.cmdline"));
+    assert_eq!(
+        paths.article_search_file(),
+        newsboat_dir.join("history.search.articles")
+    );
+    assert_eq!(
+        paths.feed_search_file(),
+        newsboat_dir.join("history.search.feeds")
+    );
+    assert_eq!(paths.filter_file(), newsboat_dir.join("history.filter"));
+    assert_eq!(
+        paths.crash_reports_dir(),
+        newsboat_dir.join("crash-reports")
+    );
 }
@@ -0,0 +1,46 @@
+use libnewsboat::configpaths::ConfigPaths;
+use std::env;
+use std::fs;
+use tempfile::TempDir;
+
+#[test]
+fn t_configpaths_returns_paths_to_files_from_env_vars() {
+    let tmp = TempDir::new().unwrap();
+    let newsboat_dir = tmp.path().join(".newsboat");
+    fs::create_dir_all(&newsboat_dir).unwrap();
+
+    env::set_var("HOME", tmp.path());
+
+    // ConfigPaths rely on these variables, so let's sanitize them to ensure
+    // that the tests aren't affected
+    env::remove_var("XDG_CONFIG_HOME");
+    env::remove_var("XDG_DATA_HOME");
+    env::remove_var("NEWSBOAT_CONFIG_DIR");
+    env::remove_var("NEWSBOAT_DATA_DIR");
+
+    let url_file = tmp.path().join("feeds/my urls");
+    let config_file = tmp.path().join("newsboat.conf");
+    let cache_file = tmp.path().join("var/cache.sqlite3");
+    let queue_file = tmp.path().join("podcasts");
+    env::set_var("NEWSBOAT_URL_FILE", &url_file);
+    env::set_var("NEWSBOAT_CONFIG_FILE", &config_file);
+    env::set_var("NEWSBOAT_CACHE_FILE", &cache_file);
+    env::set_var("NEWSBOAT_QUEUE_FILE", &queue_file);
+
+    let paths = ConfigPaths::new();
+    assert!(paths.initialized());
+    assert_eq!(paths.url_file(), url_file);
+    assert_eq!(paths.config_file(), config_file);
+    assert_eq!(paths.cache_file(), cache_file);
+    assert_eq!(paths.lock_file(), tmp.path().join("var/cache.sqlite3.lock"));
+    assert_eq!(
+        paths.control_socket_file(),
+        tmp.path().join("var/cache.sqlite3.sock")
+    );
+    assert_eq!(paths.queue_file(), queue_file);
+    assert!(paths.has_explicit_cache_file());
+
+    // Files that aren't overridden stay in the usual place
+    assert_eq!(paths.search_file(), newsboat_dir.join("history.search"));
+    assert_eq!(paths.cmdline_file(), newsboat_dir.join("history.cmdline"));
+}
@@ -11,9 +11,20 @@ fn assert_paths_are_inside_dirs(config_dir: &path::Path, data_dir: &path::Path)
 
     assert_eq!(paths.cache_file(), data_dir.join("cache.db"));
     assert_eq!(paths.lock_file(), data_dir.join("cache.db.lock"));
+    assert_eq!(paths.control_socket_file(), data_dir.join("cache.db.sock"));
     assert_eq!(paths.queue_file(), data_dir.join("queue"));
     assert_eq!(paths.search_file(), data_dir.join("history.search"));
     assert_eq!(paths.cmdline_file(), data_dir.join("history.cmdline"));
+    assert_eq!(
+        paths.article_search_file(),
+        data_dir.join("history.search.articles")
+    );
+    assert_eq!(
+        paths.feed_search_file(),
+        data_dir.join("history.search.feeds")
+    );
+    assert_eq!(paths.filter_file(), data_dir.join("history.filter"));
+    assert_eq!(paths.crash_reports_dir(), data_dir.join("crash-reports"));
 }
 
 enable_sections! {
@@ -18,6 +18,10 @@ fn t_configpaths_set_cache_file_changes_paths_to_cache_and_lock_files() {
 
     assert_eq!(paths.cache_file(), newsboat_dir.join("cache.db"));
     assert_eq!(paths.lock_file(), newsboat_dir.join("cache.db.lock"));
+    assert_eq!(
+        paths.control_socket_file(),
+        newsboat_dir.join("cache.db.sock")
+    );
 
     let new_cache = path::Path::new("something/entirely different.sqlite3");
     paths.set_cache_file(new_cache.to_path_buf());
@@ -25,4 +29,7 @@ fn t_configpaths_set_cache_file_changes_paths_to_cache_and_lock_files() {
 
     let lock_file_path = path::Path::new("something/entirely different.sqlite3.lock");
     assert_eq!(paths.lock_file(), lock_file_path);
+
+    let socket_path = path::Path::new("something/entirely different.sqlite3.sock");
+    assert_eq!(paths.control_socket_file(), socket_path);
 }
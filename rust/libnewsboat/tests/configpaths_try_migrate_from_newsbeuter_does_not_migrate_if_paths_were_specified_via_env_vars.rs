@@ -0,0 +1,42 @@
+use libnewsboat::configpaths::ConfigPaths;
+use section_testing::{enable_sections, section};
+use std::env;
+use tempfile::TempDir;
+
+mod configpaths_helpers;
+
+enable_sections! {
+#[test]
+fn t_configpaths_try_migrate_from_newsbeuter_does_not_migrate_if_paths_were_specified_via_env_vars(
+) {
+    let tmp = TempDir::new().unwrap();
+
+    env::set_var("HOME", tmp.path());
+
+    // ConfigPaths rely on these variables, so let's sanitize them to ensure
+    // that the tests aren't affected
+    env::remove_var("XDG_CONFIG_HOME");
+    env::remove_var("XDG_DATA_HOME");
+
+    if section!("Newsbeuter dotdir exists")
+    {
+        configpaths_helpers::mock_newsbeuter_dotdir(&tmp);
+    }
+
+    if section!("Newsbeuter XDG dirs exist")
+    {
+        configpaths_helpers::mock_newsbeuter_xdg_dirs(&tmp);
+    }
+
+    let newsboat_dir = tmp.path().join("newsboat");
+    env::set_var("NEWSBOAT_CONFIG_DIR", &newsboat_dir);
+
+    let mut paths = ConfigPaths::new();
+    assert!(paths.initialized());
+
+    // No migration should occur, so should return false.
+    assert!(!paths.try_migrate_from_newsbeuter());
+    assert!(!newsboat_dir.exists());
+    assert!(!tmp.path().join(".newsboat").exists());
+}
+}
@@ -0,0 +1,34 @@
+use libnewsboat::{cliargsparser::CliArgsParser, configpaths::ConfigPaths};
+use std::env;
+use tempfile::TempDir;
+
+mod configpaths_helpers;
+
+#[test]
+fn t_configpaths_try_migrate_from_newsbeuter_does_not_migrate_if_profile_is_used() {
+    let tmp = TempDir::new().unwrap();
+
+    env::set_var("HOME", tmp.path());
+
+    // ConfigPaths rely on these variables, so let's sanitize them to ensure
+    // that the tests aren't affected
+    env::remove_var("XDG_CONFIG_HOME");
+    env::remove_var("XDG_DATA_HOME");
+
+    configpaths_helpers::mock_newsbeuter_dotdir(&tmp);
+
+    let parser = CliArgsParser::new(vec![
+        "newsboat".to_string(),
+        "--profile=work".to_string(),
+        "-q".to_string(),
+    ]);
+
+    let mut paths = ConfigPaths::new();
+    assert!(paths.initialized());
+    paths.process_args(&parser);
+
+    assert!(paths.plan_migration_from_newsbeuter().is_err());
+    assert!(!paths.try_migrate_from_newsbeuter());
+    assert!(!paths.url_file().exists());
+    assert!(!tmp.path().join(".newsboat").exists());
+}
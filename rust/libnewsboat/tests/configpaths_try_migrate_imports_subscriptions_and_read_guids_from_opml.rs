@@ -0,0 +1,79 @@
+use libnewsboat::{cliargsparser::CliArgsParser, configpaths::ConfigPaths};
+use std::env;
+use std::fs;
+use tempfile::TempDir;
+
+mod configpaths_helpers;
+
+#[test]
+fn t_configpaths_try_migrate_imports_subscriptions_and_read_guids_from_opml() {
+    let tmp = TempDir::new().unwrap();
+
+    env::set_var("HOME", tmp.path());
+
+    // ConfigPaths rely on these variables, so let's sanitize them to ensure
+    // that the tests aren't affected
+    env::remove_var("XDG_CONFIG_HOME");
+    env::remove_var("XDG_DATA_HOME");
+
+    // Newsbeuter's files are ignored when the user names an OPML file
+    configpaths_helpers::mock_newsbeuter_dotdir(&tmp);
+
+    let opml_file = tmp.path().join("subscriptions.opml");
+    fs::write(
+        &opml_file,
+        r#"<?xml version="1.0"?>
+<opml version="1.0">
+  <body>
+    <outline title="News">
+      <outline type="rss" xmlUrl="https://example.com/world.xml"/>
+      <outline title="Tech &amp; Science">
+        <outline type="rss" xmlUrl="https://example.com/tech.xml"/>
+      </outline>
+    </outline>
+    <outline type="rss" xmlUrl="https://example.com/untagged.xml"/>
+  </body>
+</opml>
+"#,
+    )
+    .unwrap();
+
+    let read_guids_file = tmp.path().join("read.txt");
+    fs::write(&read_guids_file, "first-guid\nsecond-guid\n").unwrap();
+
+    let parser = CliArgsParser::new(vec![
+        "newsboat".to_string(),
+        format!("--migrate-from-opml={}", opml_file.display()),
+        format!("--migrate-read-guids={}", read_guids_file.display()),
+        "-q".to_string(),
+    ]);
+
+    let mut paths = ConfigPaths::new();
+    assert!(paths.initialized());
+    paths.process_args(&parser);
+
+    let opml_source = opml_file.to_string_lossy().into_owned();
+    let plans = paths.plan_migration().unwrap();
+    assert_eq!(plans.len(), 1);
+    assert_eq!(plans[0].source_name, opml_source);
+
+    // The OPML file isn't a Newsbeuter source
+    let newsbeuter_plans = paths.plan_migration_from_newsbeuter().unwrap();
+    assert!(!newsbeuter_plans.is_empty());
+    assert!(newsbeuter_plans
+        .iter()
+        .all(|plan| plan.source_name != opml_source));
+
+    assert!(paths.try_migrate());
+
+    assert_eq!(
+        configpaths_helpers::file_contents(paths.url_file()),
+        "https://example.com/world.xml \"News\"\n\
+         https://example.com/tech.xml \"News/Tech & Science\"\n\
+         https://example.com/untagged.xml\n"
+    );
+    assert_eq!(
+        configpaths_helpers::file_contents(paths.imported_read_guids_file()),
+        "first-guid\nsecond-guid\n"
+    );
+}
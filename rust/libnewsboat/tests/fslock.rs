@@ -1,9 +1,12 @@
-use libnewsboat::fslock::FsLock;
+use libnewsboat::fslock::{self, FsLock, LockOwner, LockOwnerStatus};
 use std::env;
+use std::fs;
 use std::io::{Error, ErrorKind};
 use std::io::{Read, Write};
-use std::path::PathBuf;
-use std::process::{Command, Stdio};
+use std::path::{Path, PathBuf};
+use std::process::{self, Child, Command, Stdio};
+use std::thread;
+use std::time::{Duration, Instant};
 use tempfile::NamedTempFile;
 
 fn get_exe_path(exe: &str) -> Result<PathBuf, Error> {
@@ -16,6 +19,83 @@ fn get_exe_path(exe: &str) -> Result<PathBuf, Error> {
     Err(Error::new(ErrorKind::NotFound, exe))
 }
 
+fn lock_process_cmd() -> String {
+    match env::var("CARGO_BIN_EXE_lock-process") {
+        Ok(dir) => dir,
+        Err(_) => get_exe_path("lock-process")
+            .unwrap()
+            .to_str()
+            .unwrap()
+            .to_string(),
+    }
+}
+
+/// Starts `lock-process` and waits until it locks `path`. If `owner` is given, the child writes it
+/// into the lock file.
+fn spawn_lock_process(path: &Path, owner: Option<&str>) -> Child {
+    let mut cmd = Command::new(lock_process_cmd());
+    cmd.arg(path);
+    if let Some(owner) = owner {
+        cmd.arg(owner);
+    }
+    wait_until_locked(cmd)
+}
+
+/// Starts `lock-process` and waits until it takes a shared lock for `path`.
+fn spawn_shared_lock_process(path: &Path) -> Child {
+    let mut cmd = Command::new(lock_process_cmd());
+    cmd.arg("--shared").arg(path);
+    wait_until_locked(cmd)
+}
+
+fn wait_until_locked(mut cmd: Command) -> Child {
+    let mut child = cmd
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()
+        .unwrap();
+
+    // wait for locked signal
+    let stdout = child.stdout.as_mut().unwrap();
+    stdout.read_exact(&mut [0]).unwrap();
+
+    child
+}
+
+/// Runs `lock-process` and returns `true` if it managed to lock `path`.
+fn try_lock_in_another_process(path: &Path) -> bool {
+    let mut child = Command::new(lock_process_cmd())
+        .arg(path)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .spawn()
+        .unwrap();
+
+    // The child signals that it locked the file, or exits without a word if it couldn't
+    let locked = child.stdout.as_mut().unwrap().read_exact(&mut [0]).is_ok();
+    if locked {
+        stop_lock_process(child);
+    } else {
+        child.wait().unwrap();
+    }
+    locked
+}
+
+fn stop_lock_process(mut child: Child) {
+    // notify child to exit and drop lock
+    let stdin = child.stdin.as_mut().unwrap();
+    stdin.write_all(b"\n").unwrap();
+    child.wait().unwrap();
+}
+
+/// PID of a process that has already exited.
+fn dead_pid() -> u32 {
+    let mut child = Command::new("true").spawn().unwrap();
+    child.wait().unwrap();
+    child.id()
+}
+
 #[test]
 fn t_returns_an_error_if_invalid_lock_location() {
     let tmp = tempfile::tempdir().unwrap();
@@ -46,30 +126,12 @@ fn t_returns_an_error_lock_file_without_write_access() {
 fn t_fails_if_lock_was_already_created() {
     let lock_location = NamedTempFile::new().unwrap();
 
-    let cmd = match env::var("CARGO_BIN_EXE_lock-process") {
-        Ok(dir) => dir,
-        Err(_) => get_exe_path("lock-process")
-            .unwrap()
-            .to_str()
-            .unwrap()
-            .to_string(),
-    };
-
-    let mut child = Command::new(cmd)
-        .arg(lock_location.path())
-        .stdin(Stdio::piped())
-        .stdout(Stdio::piped())
-        .spawn()
-        .unwrap();
+    let child = spawn_lock_process(lock_location.path(), None);
 
     let mut lock = FsLock::default();
     let mut pid = 0;
     let cid = child.id() as i32;
 
-    // wait for locked signal
-    let stdout = child.stdout.as_mut().unwrap();
-    stdout.read_exact(&mut [0]).unwrap();
-
     let result = lock.try_lock(lock_location.as_ref(), &mut pid);
     assert!(result.is_err());
     if let Err(e) = result {
@@ -77,9 +139,7 @@ fn t_fails_if_lock_was_already_created() {
     }
     assert_eq!(pid, cid, "pid should be process holding the lock");
 
-    // notify child to exit and drop lock
-    let stdin = child.stdin.as_mut().unwrap();
-    stdin.write_all(b"\n").unwrap();
+    stop_lock_process(child);
 }
 
 #[test]
@@ -102,3 +162,339 @@ fn t_succeeds_if_lock_file_location_is_valid_and_not_locked_by_different_process
     assert!(!lock_location.path().exists());
     assert!(new_lock_location.path().exists());
 }
+
+#[test]
+fn t_lock_file_records_pid_hostname_and_start_time() {
+    let lock_location = NamedTempFile::new().unwrap();
+    let mut lock = FsLock::default();
+    assert!(lock.try_lock(lock_location.as_ref(), &mut 0).is_ok());
+
+    let owner = LockOwner::read(lock_location.path()).unwrap();
+    assert_eq!(owner.pid, process::id() as i32);
+    assert_eq!(owner.hostname, fslock::hostname());
+    assert_eq!(owner.start_time, fslock::process_start_time(owner.pid));
+    assert_eq!(owner, LockOwner::current());
+    assert_eq!(owner.status(), LockOwnerStatus::Alive);
+}
+
+#[test]
+fn t_reads_lock_files_written_by_older_versions() {
+    let lock_location = NamedTempFile::new().unwrap();
+    fs::write(lock_location.path(), process::id().to_string()).unwrap();
+
+    let owner = LockOwner::read(lock_location.path()).unwrap();
+    assert_eq!(owner.pid, process::id() as i32);
+    assert_eq!(owner.hostname, "");
+    assert_eq!(owner.start_time, None);
+    // Lock files without a hostname are assumed to come from this host
+    assert_eq!(owner.status(), LockOwnerStatus::Alive);
+}
+
+#[test]
+fn t_live_owner_is_not_stale_and_its_lock_is_not_taken_over() {
+    let lock_location = NamedTempFile::new().unwrap();
+    let child = spawn_lock_process(lock_location.path(), None);
+
+    let owner = LockOwner::read(lock_location.path()).unwrap();
+    assert_eq!(owner.pid, child.id() as i32);
+    assert_eq!(owner.status(), LockOwnerStatus::Alive);
+
+    let mut lock = FsLock::default();
+    // Not even when forced
+    let result = lock.take_over_stale_lock(lock_location.as_ref(), Duration::from_secs(30), true);
+    assert!(result.is_err());
+    assert_eq!(
+        LockOwner::read(lock_location.path()).unwrap().pid,
+        child.id() as i32
+    );
+
+    stop_lock_process(child);
+}
+
+#[test]
+fn t_owner_that_is_gone_is_stale() {
+    let owner = LockOwner {
+        pid: dead_pid() as i32,
+        hostname: fslock::hostname(),
+        start_time: None,
+    };
+    assert_eq!(owner.status(), LockOwnerStatus::Stale);
+}
+
+#[test]
+fn t_owner_whose_pid_was_reused_is_stale() {
+    let pid = process::id() as i32;
+    let start_time = match fslock::process_start_time(pid) {
+        Some(start_time) => start_time,
+        // Start time can't be determined on this platform, so PID reuse can't be detected
+        None => return,
+    };
+
+    let owner = LockOwner {
+        pid,
+        hostname: fslock::hostname(),
+        start_time: Some(start_time - 1),
+    };
+    assert_eq!(owner.status(), LockOwnerStatus::Stale);
+}
+
+#[test]
+fn t_owner_on_another_host_is_unknown_and_its_lock_is_not_taken_over() {
+    let lock_location = NamedTempFile::new().unwrap();
+    let fake_owner = format!("{}\nsome-other-host.invalid\n", dead_pid());
+    let child = spawn_lock_process(lock_location.path(), Some(&fake_owner));
+
+    let owner = LockOwner::read(lock_location.path()).unwrap();
+    assert_eq!(owner.hostname, "some-other-host.invalid");
+    assert_eq!(owner.status(), LockOwnerStatus::Unknown);
+
+    let mut lock = FsLock::default();
+    assert!(lock
+        .take_over_stale_lock(lock_location.as_ref(), Duration::from_secs(30), false)
+        .is_err());
+
+    stop_lock_process(child);
+}
+
+#[test]
+fn t_lock_taken_on_another_host_is_taken_over_if_forced_once_it_is_released() {
+    let tmp = tempfile::tempdir().unwrap();
+    let lock_location = tmp.path().join("cache.db.lock");
+    let fake_owner = format!("{}\nsome-other-host.invalid\n", dead_pid());
+    let child = spawn_lock_process(&lock_location, Some(&fake_owner));
+
+    let releaser = thread::spawn(move || {
+        thread::sleep(Duration::from_millis(300));
+        stop_lock_process(child);
+    });
+
+    let mut lock = FsLock::default();
+    assert!(lock
+        .take_over_stale_lock(&lock_location, Duration::from_secs(30), true)
+        .is_ok());
+    releaser.join().unwrap();
+
+    assert!(!try_lock_in_another_process(&lock_location));
+    assert_eq!(
+        LockOwner::read(&lock_location).unwrap(),
+        LockOwner::current()
+    );
+}
+
+#[test]
+fn t_lock_error_describes_the_owner_and_whether_it_is_running() {
+    let lock_location = NamedTempFile::new().unwrap();
+    let child = spawn_lock_process(lock_location.path(), None);
+
+    let mut lock = FsLock::default();
+    let message = lock.try_lock(lock_location.as_ref(), &mut 0).unwrap_err();
+    assert!(message.contains(&format!(
+        "already locked by process with PID {} on host `{}', which is still running",
+        child.id(),
+        fslock::hostname()
+    )));
+
+    stop_lock_process(child);
+
+    let other_pid = dead_pid();
+    let fake_owner = format!("{}\nsome-other-host.invalid\n", other_pid);
+    let child = spawn_lock_process(lock_location.path(), Some(&fake_owner));
+    let message = lock.try_lock(lock_location.as_ref(), &mut 0).unwrap_err();
+    assert!(message.contains(&format!(
+        "already locked by process with PID {} on host `some-other-host.invalid', \
+         which can't be checked from this host",
+        other_pid
+    )));
+
+    stop_lock_process(child);
+}
+
+#[test]
+fn t_stale_lock_is_taken_over_once_it_is_released() {
+    let tmp = tempfile::tempdir().unwrap();
+    let lock_location = tmp.path().join("cache.db.lock");
+    // The child holds the lock, but the lock file says it belongs to a process that's gone. This
+    // is what a lock left behind on NFS looks like until the server releases it.
+    let fake_owner = format!("{}\n{}\n", dead_pid(), fslock::hostname());
+    let child = spawn_lock_process(&lock_location, Some(&fake_owner));
+
+    let releaser = thread::spawn(move || {
+        thread::sleep(Duration::from_millis(300));
+        stop_lock_process(child);
+    });
+
+    let mut lock = FsLock::default();
+    assert!(lock.try_lock(&lock_location, &mut 0).is_err());
+    assert!(lock
+        .take_over_stale_lock(&lock_location, Duration::from_secs(30), false)
+        .is_ok());
+    releaser.join().unwrap();
+
+    // The previous owner didn't remove the lock file, which is now ours
+    assert!(lock_location.exists());
+    assert!(!try_lock_in_another_process(&lock_location));
+    // Reading the file closes a descriptor and thus releases our lock, so this goes last
+    assert_eq!(
+        LockOwner::read(&lock_location).unwrap(),
+        LockOwner::current()
+    );
+
+    drop(lock);
+    assert!(!lock_location.exists());
+}
+
+#[test]
+fn t_stale_lock_that_is_not_released_is_not_taken_over() {
+    let tmp = tempfile::tempdir().unwrap();
+    let lock_location = tmp.path().join("cache.db.lock");
+    let fake_owner = format!("{}\n{}\n", dead_pid(), fslock::hostname());
+    let child = spawn_lock_process(&lock_location, Some(&fake_owner));
+
+    let mut lock = FsLock::default();
+    assert!(lock
+        .take_over_stale_lock(&lock_location, Duration::from_millis(300), false)
+        .is_err());
+    // The lock file is left alone, so nobody else can lock it while the child holds the lock
+    assert_eq!(
+        LockOwner::read(&lock_location).unwrap().pid.to_string(),
+        fake_owner.lines().next().unwrap()
+    );
+    assert!(!try_lock_in_another_process(&lock_location));
+
+    stop_lock_process(child);
+}
+
+#[test]
+fn t_lock_passed_between_two_lockers_is_never_held_by_both() {
+    let tmp = tempfile::tempdir().unwrap();
+    let lock_location = tmp.path().join("cache.db.lock");
+
+    // Releasing the lock used to open a second descriptor of the lock file, which released the
+    // lock before the file was removed. The next locker could take the lock in that window, and
+    // then lose the file, letting a third process lock a new one. Repeat a few times to give the
+    // race a chance to show up.
+    for _ in 0..10 {
+        let child = spawn_lock_process(&lock_location, None);
+        let releaser = thread::spawn(move || stop_lock_process(child));
+
+        let mut lock = FsLock::default();
+        let result = lock.lock_with_timeout(&lock_location, Duration::from_secs(30), &mut 0);
+        assert!(result.is_ok());
+        releaser.join().unwrap();
+
+        assert!(!try_lock_in_another_process(&lock_location));
+        assert_eq!(
+            LockOwner::read(&lock_location).unwrap(),
+            LockOwner::current()
+        );
+    }
+}
+
+#[test]
+fn t_lock_with_timeout_gives_up_after_timeout_if_lock_is_held() {
+    let lock_location = NamedTempFile::new().unwrap();
+    let child = spawn_lock_process(lock_location.path(), None);
+
+    let mut lock = FsLock::default();
+    let mut pid = 0;
+    let timeout = Duration::from_millis(300);
+    let start = Instant::now();
+    let result = lock.lock_with_timeout(lock_location.as_ref(), timeout, &mut pid);
+    assert!(result.is_err());
+    assert!(start.elapsed() >= timeout);
+    assert_eq!(pid, child.id() as i32);
+
+    stop_lock_process(child);
+}
+
+#[test]
+fn t_lock_with_timeout_succeeds_once_lock_is_released() {
+    let lock_location = NamedTempFile::new().unwrap();
+    let child = spawn_lock_process(lock_location.path(), None);
+
+    let releaser = thread::spawn(move || {
+        thread::sleep(Duration::from_millis(300));
+        stop_lock_process(child);
+    });
+
+    let mut lock = FsLock::default();
+    let result = lock.lock_with_timeout(lock_location.as_ref(), Duration::from_secs(30), &mut 0);
+    assert!(result.is_ok());
+    assert_eq!(
+        LockOwner::read(lock_location.path()).unwrap(),
+        LockOwner::current()
+    );
+
+    releaser.join().unwrap();
+}
+
+#[test]
+fn t_lock_with_timeout_does_not_retry_if_lock_file_cannot_be_opened() {
+    let tmp = tempfile::tempdir().unwrap();
+    let lock_location = tmp.path().join("does-not-exist").join("lockfile");
+
+    let mut lock = FsLock::default();
+    let start = Instant::now();
+    let result = lock.lock_with_timeout(&lock_location, Duration::from_secs(30), &mut 0);
+    assert!(result.is_err());
+    assert!(start.elapsed() < Duration::from_secs(30));
+}
+
+#[test]
+fn t_shared_lock_can_be_held_by_several_processes() {
+    let tmp = tempfile::tempdir().unwrap();
+    let lock_location = tmp.path().join("lockfile");
+    let child = spawn_shared_lock_process(&lock_location);
+
+    let mut lock = FsLock::default();
+    assert!(lock.try_lock_shared(&lock_location).is_ok());
+
+    stop_lock_process(child);
+}
+
+#[test]
+fn t_shared_lock_can_be_taken_while_exclusive_lock_is_held() {
+    let tmp = tempfile::tempdir().unwrap();
+    let lock_location = tmp.path().join("lockfile");
+    let child = spawn_lock_process(&lock_location, None);
+
+    let mut lock = FsLock::default();
+    assert!(lock.try_lock_shared(&lock_location).is_ok());
+
+    stop_lock_process(child);
+}
+
+#[test]
+fn t_exclusive_lock_can_be_taken_while_shared_lock_is_held() {
+    let tmp = tempfile::tempdir().unwrap();
+    let lock_location = tmp.path().join("lockfile");
+    let child = spawn_shared_lock_process(&lock_location);
+
+    let mut lock = FsLock::default();
+    assert!(lock.try_lock(&lock_location, &mut 0).is_ok());
+
+    stop_lock_process(child);
+}
+
+#[test]
+fn t_has_readers_reports_shared_locks_held_by_other_processes() {
+    let tmp = tempfile::tempdir().unwrap();
+    let lock_location = tmp.path().join("lockfile");
+    assert!(!FsLock::has_readers(&lock_location));
+
+    let child = spawn_shared_lock_process(&lock_location);
+    assert!(FsLock::has_readers(&lock_location));
+    assert!(fslock::readers_lock_path(&lock_location).exists());
+
+    stop_lock_process(child);
+    assert!(!FsLock::has_readers(&lock_location));
+}
+
+#[test]
+fn t_try_lock_shared_returns_an_error_if_invalid_lock_location() {
+    let tmp = tempfile::tempdir().unwrap();
+    let lock_location = tmp.path().join("does-not-exist").join("lockfile");
+
+    let mut lock = FsLock::default();
+    assert!(lock.try_lock_shared(&lock_location).is_err());
+}
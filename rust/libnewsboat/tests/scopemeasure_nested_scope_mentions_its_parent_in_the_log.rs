@@ -0,0 +1,30 @@
+use libnewsboat::{
+    logger::{self, Level},
+    scopemeasure::ScopeMeasure,
+};
+use std::fs;
+use tempfile::TempDir;
+
+#[test]
+fn nested_scope_mentions_its_parent_in_the_log() {
+    let tmp = TempDir::new().unwrap();
+    let logfile = tmp.path().join("example.log");
+
+    {
+        logger::get_instance().set_logfile(logfile.to_str().unwrap());
+        logger::get_instance().set_loglevel(Level::Debug);
+        let _outer = ScopeMeasure::new(String::from("outer"));
+        let inner = ScopeMeasure::new(String::from("inner"));
+        inner.stopover("here");
+    }
+
+    let log = fs::read_to_string(&logfile).unwrap();
+    let lines = log.lines().collect::<Vec<_>>();
+    assert_eq!(lines.len(), 3);
+    assert!(lines[0].contains("function `inner' (stop over `here') took"));
+    assert!(lines[0].ends_with("s so far (in `outer')"));
+    assert!(lines[1].contains("function `inner' took"));
+    assert!(lines[1].ends_with(" s (in `outer')"));
+    assert!(lines[2].contains("function `outer' took"));
+    assert!(lines[2].ends_with(" s"));
+}
@@ -0,0 +1,30 @@
+use libnewsboat::{
+    logger::{self, Level},
+    scopemeasure::{self, ScopeMeasure},
+};
+use std::thread;
+
+#[test]
+fn summary_counts_finished_scopes_on_all_threads() {
+    logger::get_instance().set_loglevel(Level::Debug);
+
+    let name = "summary_counts_finished_scopes";
+    for _ in 0..3 {
+        let _sm = ScopeMeasure::new(name.to_owned());
+    }
+    thread::spawn(move || {
+        let _sm = ScopeMeasure::new(name.to_owned());
+    })
+    .join()
+    .unwrap();
+    let _unfinished = ScopeMeasure::new(name.to_owned());
+
+    let stats = scopemeasure::summary()
+        .into_iter()
+        .find(|s| s.name == name)
+        .unwrap();
+    assert_eq!(stats.count, 4);
+    assert!(stats.min <= stats.p95);
+    assert!(stats.p95 <= stats.max);
+    assert!(stats.max <= stats.total);
+}
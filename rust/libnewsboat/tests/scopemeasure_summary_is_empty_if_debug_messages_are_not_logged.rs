@@ -0,0 +1,15 @@
+use libnewsboat::{
+    logger::{self, Level},
+    scopemeasure::{self, ScopeMeasure},
+};
+
+#[test]
+fn summary_is_empty_if_debug_messages_are_not_logged() {
+    logger::get_instance().set_loglevel(Level::Info);
+
+    for _ in 0..3 {
+        let _sm = ScopeMeasure::new(String::from("test"));
+    }
+
+    assert!(scopemeasure::summary().is_empty());
+}
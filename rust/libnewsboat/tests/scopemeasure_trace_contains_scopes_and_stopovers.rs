@@ -0,0 +1,39 @@
+use libnewsboat::scopemeasure::{self, ScopeMeasure};
+use std::fs;
+use tempfile::TempDir;
+
+#[test]
+fn trace_contains_scopes_and_stopovers() {
+    let tmp = TempDir::new().unwrap();
+    let tracefile = tmp.path().join("trace.json");
+
+    {
+        let _before = ScopeMeasure::new(String::from("before tracing"));
+    }
+
+    scopemeasure::enable_trace();
+    {
+        let outer = ScopeMeasure::new(String::from("outer"));
+        outer.stopover("halfway \"there\"");
+        let _inner = ScopeMeasure::new(String::from("inner"));
+    }
+    scopemeasure::write_trace(&tracefile).unwrap();
+
+    let trace = fs::read_to_string(&tracefile).unwrap();
+    assert!(trace.starts_with("{\"traceEvents\":["));
+    assert!(trace.trim_end().ends_with("]}"));
+
+    let events = trace
+        .lines()
+        .filter(|l| l.contains("\"ph\":"))
+        .collect::<Vec<_>>();
+    assert_eq!(events.len(), 3);
+    assert!(events[0].contains(r#""name":"outer: halfway \"there\"""#));
+    assert!(events[0].contains(r#""ph":"i""#));
+    // The inner scope is dropped first
+    assert!(events[1].contains(r#""name":"inner""#));
+    assert!(events[1].contains(r#""ph":"X""#));
+    assert!(events[2].contains(r#""name":"outer""#));
+    assert!(events[2].contains(r#""dur":"#));
+    assert!(!trace.contains("before tracing"));
+}
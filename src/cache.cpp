@@ -258,11 +258,16 @@ static int guid_callback(void* myguids, int argc, char** argv,
 	return 0;
 }
 
-Cache::Cache(const std::string& cachefile, ConfigContainer* c)
+Cache::Cache(const std::string& cachefile, ConfigContainer* c,
+	bool read_only)
 	: db(0)
 	, cfg(c)
+	, read_only(read_only)
 {
-	const int error = sqlite3_open(cachefile.c_str(), &db);
+	const int flags = read_only
+		? SQLITE_OPEN_READONLY
+		: SQLITE_OPEN_READWRITE | SQLITE_OPEN_CREATE;
+	const int error = sqlite3_open_v2(cachefile.c_str(), &db, flags, nullptr);
 	if (error != SQLITE_OK) {
 		LOG(Level::ERROR,
 			"couldn't sqlite3_open(%s): error = %d",
@@ -271,6 +276,12 @@ Cache::Cache(const std::string& cachefile, ConfigContainer* c)
 		throw DbException(db);
 	}
 
+	if (read_only) {
+		check_schema_version();
+		run_sql("PRAGMA case_sensitive_like=OFF;");
+		return;
+	}
+
 	populate_tables();
 	set_pragmas();
 
@@ -392,6 +403,35 @@ static const schema_patches schemaPatches{
 	// Note: schema changes should use the version number of the release that introduced them.
 };
 
+// `newsboat query` reads the cache without going through this class. Its SQL
+// lives in rust/libnewsboat/src/cachequery/queries.rs, and has to be updated
+// along with the schema above. The "Queries run by `newsboat query` work on
+// caches created by Cache" test checks that the two agree.
+
+void Cache::check_schema_version()
+{
+	const SchemaVersion version = get_schema_version();
+	LOG(Level::INFO,
+		"Cache::check_schema_version: DB schema version %u.%u",
+		version.major,
+		version.minor);
+
+	if (version.major > utils::newsboat_major_version()) {
+		const std::string msg =
+			"Database schema isn't supported because it's too new";
+		LOG(Level::ERROR, msg);
+		throw std::runtime_error(msg);
+	}
+
+	if (version < schemaPatches.crbegin()->first) {
+		const std::string msg =
+			"Database schema needs to be upgraded, which can't be done "
+			"in read-only mode";
+		LOG(Level::ERROR, msg);
+		throw std::runtime_error(msg);
+	}
+}
+
 void Cache::populate_tables()
 {
 	const SchemaVersion version = get_schema_version();
@@ -453,6 +493,10 @@ void Cache::update_lastmodified(const std::string& feedurl,
 	time_t t,
 	const std::string& etag)
 {
+	if (read_only) {
+		return;
+	}
+
 	if (t == 0 && etag.length() == 0) {
 		LOG(Level::INFO,
 			"Cache::update_lastmodified: both time and etag are "
@@ -476,6 +520,10 @@ void Cache::update_lastmodified(const std::string& feedurl,
 
 void Cache::mark_item_deleted(const std::string& guid, bool b)
 {
+	if (read_only) {
+		return;
+	}
+
 	std::lock_guard<std::mutex> lock(mtx);
 	std::string query = prepare_query(
 			"UPDATE rss_item SET deleted = %u WHERE guid = '%q'",
@@ -488,6 +536,10 @@ void Cache::mark_item_deleted(const std::string& guid, bool b)
 void Cache::externalize_rssfeed(std::shared_ptr<RssFeed> feed,
 	bool reset_unread)
 {
+	if (read_only) {
+		return;
+	}
+
 	ScopeMeasure m1("Cache::externalize_feed");
 	if (feed->is_query_feed()) {
 		return;
@@ -726,6 +778,10 @@ std::unordered_set<std::string> Cache::search_in_items(
 
 void Cache::delete_item(const std::shared_ptr<RssItem>& item)
 {
+	if (read_only) {
+		return;
+	}
+
 	const std::string query = prepare_query(
 			"DELETE FROM rss_item WHERE guid = '%q';", item->guid());
 	run_sql(query);
@@ -733,6 +789,10 @@ void Cache::delete_item(const std::shared_ptr<RssItem>& item)
 
 void Cache::do_vacuum()
 {
+	if (read_only) {
+		return;
+	}
+
 	std::lock_guard<std::mutex> lock(mtx);
 	run_sql("VACUUM;");
 }
@@ -740,6 +800,10 @@ void Cache::do_vacuum()
 std::uint64_t Cache::cleanup_cache(std::vector<std::shared_ptr<RssFeed>> feeds,
 	bool always_clean)
 {
+	if (read_only) {
+		return 0;
+	}
+
 	std::uint64_t unreachable = 0u;
 	std::string list = "(";
 
@@ -916,6 +980,10 @@ void Cache::update_rssitem_unlocked(std::shared_ptr<RssItem> item,
 
 void Cache::mark_all_read(std::shared_ptr<RssFeed> feed)
 {
+	if (read_only) {
+		return;
+	}
+
 	std::lock_guard<std::mutex> lock(mtx);
 	std::lock_guard<std::mutex> itemlock(feed->item_mutex);
 	std::string query =
@@ -934,6 +1002,10 @@ void Cache::mark_all_read(std::shared_ptr<RssFeed> feed)
  */
 void Cache::mark_all_read(const std::string& feedurl)
 {
+	if (read_only) {
+		return;
+	}
+
 	std::lock_guard<std::mutex> lock(mtx);
 
 	std::string query;
@@ -956,6 +1028,10 @@ void Cache::mark_all_read(const std::string& feedurl)
 void Cache::update_rssitem_unread_and_enqueued(RssItem* item,
 	const std::string& /* feedurl */)
 {
+	if (read_only) {
+		return;
+	}
+
 	std::lock_guard<std::mutex> lock(mtx);
 
 	const auto query = prepare_query(
@@ -1009,6 +1085,10 @@ std::string Cache::prepare_query(const std::string& format, const T& argument,
 
 void Cache::update_rssitem_flags(RssItem* item)
 {
+	if (read_only) {
+		return;
+	}
+
 	std::lock_guard<std::mutex> lock(mtx);
 
 	const std::string update = prepare_query(
@@ -1021,6 +1101,10 @@ void Cache::update_rssitem_flags(RssItem* item)
 
 void Cache::remove_old_deleted_items(RssFeed* feed)
 {
+	if (read_only) {
+		return;
+	}
+
 	ScopeMeasure m1("Cache::remove_old_deleted_items");
 
 	std::lock_guard<std::mutex> cache_lock(mtx);
@@ -1055,6 +1139,10 @@ void Cache::remove_old_deleted_items(RssFeed* feed)
 
 void Cache::mark_items_read_by_guid(const std::vector<std::string>& guids)
 {
+	if (read_only) {
+		return;
+	}
+
 	ScopeMeasure m1("Cache::mark_items_read_by_guid");
 	std::string guidset("(");
 	for (const auto& guid : guids) {
@@ -1082,6 +1170,35 @@ std::vector<std::string> Cache::get_read_item_guids()
 	return guids;
 }
 
+std::vector<std::string> Cache::get_unknown_item_guids(
+	const std::vector<std::string>& guids)
+{
+	std::string guidset("(");
+	for (const auto& guid : guids) {
+		guidset.append(prepare_query("'%q', ", guid));
+	}
+	guidset.append("'')");
+
+	const std::string query = prepare_query(
+			"SELECT guid FROM rss_item WHERE guid IN %s;", guidset);
+
+	std::vector<std::string> known_guids;
+	{
+		std::lock_guard<std::mutex> lock(mtx);
+		run_sql(query, vectorofstring_callback, &known_guids);
+	}
+
+	const std::unordered_set<std::string> known(known_guids.cbegin(),
+		known_guids.cend());
+	std::vector<std::string> unknown;
+	for (const auto& guid : guids) {
+		if (known.count(guid) == 0) {
+			unknown.push_back(guid);
+		}
+	}
+	return unknown;
+}
+
 void Cache::clean_old_articles()
 {
 	std::lock_guard<std::mutex> lock(mtx);
@@ -169,6 +169,15 @@ nonstd::optional<std::string> CliArgsParser::log_file() const
 	return nonstd::nullopt;
 }
 
+nonstd::optional<std::string> CliArgsParser::trace_file() const
+{
+	rust::String path;
+	if (newsboat::cliargsparser::bridged::trace_file(*rs_object, path)) {
+		return std::string(path);
+	}
+	return nonstd::nullopt;
+}
+
 nonstd::optional<Level> CliArgsParser::log_level() const
 {
 	std::int8_t level;
@@ -178,6 +187,117 @@ nonstd::optional<Level> CliArgsParser::log_level() const
 	return nonstd::nullopt;
 }
 
+nonstd::optional<std::string> CliArgsParser::log_filter() const
+{
+	rust::String filter;
+	if (newsboat::cliargsparser::bridged::log_filter(*rs_object, filter)) {
+		return std::string(filter);
+	}
+	return nonstd::nullopt;
+}
+
+nonstd::optional<LogFormat> CliArgsParser::log_format() const
+{
+	std::uint8_t format;
+	if (newsboat::cliargsparser::bridged::log_format(*rs_object, format)) {
+		return static_cast<LogFormat>(format);
+	}
+	return nonstd::nullopt;
+}
+
+nonstd::optional<LogRotation> CliArgsParser::log_rotation() const
+{
+	LogRotation rotation;
+	if (newsboat::cliargsparser::bridged::log_rotation(*rs_object,
+			rotation.max_size, rotation.max_files)) {
+		return rotation;
+	}
+	return nonstd::nullopt;
+}
+
+nonstd::optional<KeymapDumpFormat> CliArgsParser::keymap_dump_format() const
+{
+	std::uint8_t format;
+	if (newsboat::cliargsparser::bridged::keymap_dump_format(*rs_object, format)) {
+		return static_cast<KeymapDumpFormat>(format);
+	}
+	return nonstd::nullopt;
+}
+
+nonstd::optional<std::chrono::seconds> CliArgsParser::wait_for_lock() const
+{
+	std::uint64_t seconds;
+	if (newsboat::cliargsparser::bridged::wait_for_lock(*rs_object, seconds)) {
+		return std::chrono::seconds(seconds);
+	}
+	return nonstd::nullopt;
+}
+
+bool CliArgsParser::read_only() const
+{
+	return newsboat::cliargsparser::bridged::read_only(*rs_object);
+}
+
+bool CliArgsParser::take_over_lock() const
+{
+	return newsboat::cliargsparser::bridged::take_over_lock(*rs_object);
+}
+
+nonstd::optional<std::string> CliArgsParser::profile() const
+{
+	rust::String name;
+	if (newsboat::cliargsparser::bridged::profile(*rs_object, name)) {
+		return std::string(name);
+	}
+	return nonstd::nullopt;
+}
+
+bool CliArgsParser::list_profiles() const
+{
+	return newsboat::cliargsparser::bridged::list_profiles(*rs_object);
+}
+
+bool CliArgsParser::last_crash_report() const
+{
+	return newsboat::cliargsparser::bridged::last_crash_report(*rs_object);
+}
+
+bool CliArgsParser::migration_dry_run() const
+{
+	return newsboat::cliargsparser::bridged::migration_dry_run(*rs_object);
+}
+
+bool CliArgsParser::check_config() const
+{
+	return newsboat::cliargsparser::bridged::check_config(*rs_object);
+}
+
+nonstd::optional<std::string> CliArgsParser::completions_script() const
+{
+	rust::String script;
+	if (newsboat::cliargsparser::bridged::completions_script(*rs_object, script)) {
+		return std::string(script);
+	}
+	return nonstd::nullopt;
+}
+
+bool CliArgsParser::has_query() const
+{
+	return newsboat::cliargsparser::bridged::has_query(*rs_object);
+}
+
+nonstd::expected<std::string, std::string> CliArgsParser::run_query(
+	const std::string& cache_file,
+	const std::string& url_file) const
+{
+	rust::String output;
+	if (newsboat::cliargsparser::bridged::run_query(*rs_object, cache_file,
+			url_file, output)) {
+		return std::string(output);
+	}
+	return nonstd::make_unexpected(std::string(output));
+}
+
 void* CliArgsParser::get_rust_pointer() const
 {
 	return (void*)&*rs_object;
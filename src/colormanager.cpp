@@ -1,6 +1,9 @@
 #include "colormanager.h"
 
+#include <algorithm>
+
 #include "config.h"
+#include "configcheck.h"
 #include "confighandlerexception.h"
 #include "feedlistformaction.h"
 #include "filebrowserformaction.h"
@@ -19,6 +22,22 @@ using namespace podboat;
 
 namespace newsboat {
 
+namespace {
+
+/// Elements whose colors can be changed with the `color` command.
+const std::vector<std::string> COLOR_ELEMENTS = {
+	"listnormal",
+	"listfocus",
+	"listnormal_unread",
+	"listfocus_unread",
+	"info",
+	"background",
+	"article",
+	"end-of-text-marker",
+};
+
+} // namespace
+
 ColorManager::ColorManager()
 {
 }
@@ -30,6 +49,13 @@ void ColorManager::register_commands(ConfigParser& cfgparser)
 	cfgparser.register_handler("color", *this);
 }
 
+void ColorManager::register_elements(ConfigChecker& checker) const
+{
+	for (const auto& element : COLOR_ELEMENTS) {
+		checker.add_color_element(element);
+	}
+}
+
 void ColorManager::handle_action(const std::string& action,
 	const std::vector<std::string>& params)
 {
@@ -70,11 +96,8 @@ void ColorManager::handle_action(const std::string& action,
 
 		/* we only allow certain elements to be configured, also to
 		 * indicate the user possible mis-spellings */
-		if (element == "listnormal" || element == "listfocus" ||
-			element == "listnormal_unread" ||
-			element == "listfocus_unread" || element == "info" ||
-			element == "background" || element == "article" ||
-			element == "end-of-text-marker") {
+		if (std::find(COLOR_ELEMENTS.cbegin(), COLOR_ELEMENTS.cend(),
+				element) != COLOR_ELEMENTS.cend()) {
 			element_styles[element] = {fgcolor, bgcolor, attribs};
 		} else {
 			throw ConfigHandlerException(strprintf::fmt(
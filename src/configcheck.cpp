@@ -0,0 +1,80 @@
+#include "configcheck.h"
+
+#include "configdata.h"
+
+namespace newsboat {
+
+ConfigChecker::ConfigChecker()
+	: rs_object(configcheck::bridged::create())
+{
+}
+
+void ConfigChecker::add_command(const std::string& name)
+{
+	configcheck::bridged::add_command(*rs_object, name);
+}
+
+void ConfigChecker::add_setting(const std::string& name,
+	const ConfigData& data)
+{
+	switch (data.type()) {
+	case ConfigDataType::BOOL:
+		configcheck::bridged::add_bool_setting(*rs_object, name);
+		break;
+
+	case ConfigDataType::INT:
+		configcheck::bridged::add_int_setting(*rs_object, name);
+		break;
+
+	case ConfigDataType::STR:
+	case ConfigDataType::PATH:
+		configcheck::bridged::add_text_setting(*rs_object, name);
+		break;
+
+	case ConfigDataType::ENUM: {
+		rust::Vec<rust::String> values;
+		for (const auto& value : data.enum_values()) {
+			values.push_back(value);
+		}
+		configcheck::bridged::add_enum_setting(*rs_object, name, values);
+	}
+	break;
+
+	case ConfigDataType::INVALID:
+		break;
+	}
+}
+
+void ConfigChecker::add_operation(const std::string& name)
+{
+	configcheck::bridged::add_operation(*rs_object, name);
+}
+
+void ConfigChecker::add_context(const std::string& name)
+{
+	configcheck::bridged::add_context(*rs_object, name);
+}
+
+void ConfigChecker::add_color_element(const std::string& name)
+{
+	configcheck::bridged::add_color_element(*rs_object, name);
+}
+
+nonstd::expected<std::vector<std::string>, std::string> ConfigChecker::check(
+	const std::string& config_file) const
+{
+	rust::Vec<rust::String> problems;
+	rust::String error_message;
+	if (!configcheck::bridged::check(*rs_object, config_file, problems,
+			error_message)) {
+		return nonstd::make_unexpected(std::string(error_message));
+	}
+
+	std::vector<std::string> result;
+	for (const auto& problem : problems) {
+		result.push_back(std::string(problem));
+	}
+	return result;
+}
+
+} // namespace newsboat
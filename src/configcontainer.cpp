@@ -8,6 +8,7 @@
 #include <sys/types.h>
 
 #include "config.h"
+#include "configcheck.h"
 #include "configparser.h"
 #include "configdata.h"
 #include "confighandlerexception.h"
@@ -89,6 +90,7 @@ ConfigContainer::ConfigContainer()
 	{"goto-first-unread", ConfigData("true", ConfigDataType::BOOL)},
 	{"goto-next-feed", ConfigData("yes", ConfigDataType::BOOL)},
 	{"history-limit", ConfigData("100", ConfigDataType::INT)},
+	{"history-timestamps", ConfigData("no", ConfigDataType::BOOL)},
 	{"html-renderer", ConfigData("internal", ConfigDataType::PATH)},
 	{
 		"http-auth-method",
@@ -205,6 +207,7 @@ ConfigContainer::ConfigContainer()
 	{"reload-threads", ConfigData("1", ConfigDataType::INT)},
 	{"reload-time", ConfigData("60", ConfigDataType::INT)},
 	{"save-path", ConfigData("~/", ConfigDataType::PATH)},
+	{"script-timeout", ConfigData("0", ConfigDataType::INT)},
 	{"scrolloff", ConfigData("0", ConfigDataType::INT)},
 	{
 		"search-highlight-colors",
@@ -337,6 +340,14 @@ void ConfigContainer::register_commands(ConfigParser& cfgparser)
 	}
 }
 
+void ConfigContainer::register_settings(ConfigChecker& checker) const
+{
+	std::lock_guard<std::recursive_mutex> guard(config_data_mtx);
+	for (const auto& cfg : config_data) {
+		checker.add_setting(cfg.first, cfg.second);
+	}
+}
+
 void ConfigContainer::handle_action(const std::string& action,
 	const std::vector<std::string>& params)
 {
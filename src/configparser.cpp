@@ -8,6 +8,7 @@
 #include <sys/types.h>
 
 #include "config.h"
+#include "configcheck.h"
 #include "configexception.h"
 #include "confighandlerexception.h"
 #include "logger.h"
@@ -143,6 +144,13 @@ void ConfigParser::register_handler(const std::string& cmd,
 	action_handlers.insert({cmd, handler});
 }
 
+void ConfigParser::register_commands(ConfigChecker& checker) const
+{
+	for (const auto& handler : action_handlers) {
+		checker.add_command(handler.first);
+	}
+}
+
 /* Note that this function not only finds next backtick that isn't prefixed
  * with a backslash, but also un-escapes all the escaped backticks it finds in
  * the process */
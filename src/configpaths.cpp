@@ -13,6 +13,7 @@
 #include "globals.h"
 #include "ruststring.h"
 #include "strprintf.h"
+#include "utils.h"
 
 extern "C" {
 	void* create_rs_configpaths();
@@ -25,6 +26,10 @@ extern "C" {
 
 	void rs_configpaths_process_args(void* rs_configpaths, void* rs_cliargsparser);
 
+	bool rs_configpaths_try_migrate(void* rs_configpaths);
+
+	char* rs_configpaths_describe_migration_plan(void* rs_configpaths);
+
 	bool rs_configpaths_try_migrate_from_newsbeuter(void* rs_configpaths);
 
 	bool rs_configpaths_create_dirs(void* rs_configpaths);
@@ -39,11 +44,29 @@ extern "C" {
 
 	char* rs_configpaths_lock_file(void* rs_configpaths);
 
+	char* rs_configpaths_control_socket_file(void* rs_configpaths);
+
 	char* rs_configpaths_queue_file(void* rs_configpaths);
 
 	char* rs_configpaths_search_file(void* rs_configpaths);
 
 	char* rs_configpaths_cmdline_file(void* rs_configpaths);
+
+	char* rs_configpaths_article_search_file(void* rs_configpaths);
+
+	char* rs_configpaths_feed_search_file(void* rs_configpaths);
+
+	char* rs_configpaths_filter_file(void* rs_configpaths);
+
+	char* rs_configpaths_imported_read_guids_file(void* rs_configpaths);
+
+	char* rs_configpaths_crash_reports_dir(void* rs_configpaths);
+
+	bool rs_configpaths_has_explicit_cache_file(void* rs_configpaths);
+
+	char* rs_configpaths_profile(void* rs_configpaths);
+
+	char* rs_configpaths_list_profiles(void* rs_configpaths);
 }
 
 #define SIMPLY_RUN(NAME) \
@@ -96,6 +119,16 @@ void ConfigPaths::process_args(const CliArgsParser& args)
 	}
 }
 
+bool ConfigPaths::try_migrate()
+{
+	GET_VALUE(try_migrate, false);
+}
+
+std::string ConfigPaths::describe_migration_plan() const
+{
+	GET_STRING(describe_migration_plan);
+}
+
 bool ConfigPaths::try_migrate_from_newsbeuter()
 {
 	GET_VALUE(try_migrate_from_newsbeuter, false);
@@ -133,6 +166,11 @@ std::string ConfigPaths::lock_file() const
 	GET_STRING(lock_file);
 }
 
+std::string ConfigPaths::control_socket_file() const
+{
+	GET_STRING(control_socket_file);
+}
+
 std::string ConfigPaths::queue_file() const
 {
 	GET_STRING(queue_file);
@@ -148,4 +186,49 @@ std::string ConfigPaths::cmdline_file() const
 	GET_STRING(cmdline_file);
 }
 
+std::string ConfigPaths::article_search_file() const
+{
+	GET_STRING(article_search_file);
+}
+
+std::string ConfigPaths::feed_search_file() const
+{
+	GET_STRING(feed_search_file);
+}
+
+std::string ConfigPaths::filter_file() const
+{
+	GET_STRING(filter_file);
+}
+
+std::string ConfigPaths::imported_read_guids_file() const
+{
+	GET_STRING(imported_read_guids_file);
+}
+
+std::string ConfigPaths::crash_reports_dir() const
+{
+	GET_STRING(crash_reports_dir);
+}
+
+bool ConfigPaths::has_explicit_cache_file() const
+{
+	GET_VALUE(has_explicit_cache_file, false);
+}
+
+std::string ConfigPaths::profile() const
+{
+	GET_STRING(profile);
+}
+
+std::vector<std::string> ConfigPaths::list_profiles() const
+{
+	if (rs_configpaths) {
+		const std::string profiles =
+			RustString(rs_configpaths_list_profiles(rs_configpaths));
+		return utils::tokenize(profiles, "\n");
+	}
+	return {};
+}
+
 } // namespace newsboat
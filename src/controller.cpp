@@ -1,9 +1,11 @@
 #include "controller.h"
 
+#include <algorithm>
 #include <cassert>
 #include <cerrno>
 #include <cstdint>
 #include <cstdlib>
+#include <cstring>
 #include <ctime>
 #include <curl/curl.h>
 #include <fstream>
@@ -27,9 +29,11 @@
 #include "cliargsparser.h"
 #include "colormanager.h"
 #include "config.h"
+#include "configcheck.h"
 #include "configcontainer.h"
 #include "configexception.h"
 #include "configpaths.h"
+#include "controlsocket.h"
 #include "dbexception.h"
 #include "downloadthread.h"
 #include "exception.h"
@@ -71,6 +75,18 @@ void sighup_action(int /* sig */)
 	::exit(EXIT_FAILURE);
 }
 
+/// Returns true if `args` ask for something that would modify the cache, which
+/// can't be done in read-only mode.
+bool args_change_cache(const CliArgsParser& args)
+{
+	if (args.do_vacuum() || args.do_cleanup() ||
+		args.readinfo_import_file().has_value()) {
+		return true;
+	}
+	const auto cmds = args.cmds_to_execute();
+	return std::find(cmds.cbegin(), cmds.cend(), "reload") != cmds.cend();
+}
+
 void ignore_signal(int sig)
 {
 	LOG(Level::WARN, "caught signal %d but ignored it", sig);
@@ -81,7 +97,9 @@ Controller::Controller(ConfigPaths& configpaths)
 	, urlcfg(0)
 	, rsscache(0)
 	, refresh_on_start(false)
+	, read_only(false)
 	, api(0)
+	, stop_control_thread(false)
 	, configpaths(configpaths)
 	, queueManager(&cfg, &configpaths)
 {
@@ -89,6 +107,7 @@ Controller::Controller(ConfigPaths& configpaths)
 
 Controller::~Controller()
 {
+	stop_control_server();
 	delete rsscache;
 	delete urlcfg;
 	delete api;
@@ -99,6 +118,37 @@ void Controller::set_view(View* vv)
 	v = vv;
 }
 
+void Controller::register_config_checks(ConfigChecker& checker)
+{
+	ConfigParser parser;
+	KeyMap keys(KM_NEWSBOAT);
+	register_config_handlers(parser, keys);
+
+	parser.register_commands(checker);
+	cfg.register_settings(checker);
+	keys.register_operations(checker);
+	colorman.register_elements(checker);
+}
+
+void Controller::register_config_handlers(ConfigParser& parser, KeyMap& keys)
+{
+	cfg.register_commands(parser);
+	colorman.register_commands(parser);
+
+	parser.register_handler("bind-key", keys);
+	parser.register_handler("unbind-key", keys);
+	parser.register_handler("macro", keys);
+	parser.register_handler("run-on-startup", keys);
+
+	parser.register_handler("ignore-article", ign);
+	parser.register_handler("always-download", ign);
+	parser.register_handler("reset-unread-on-update", ign);
+
+	parser.register_handler("define-filter", filters);
+	parser.register_handler("highlight", rxman);
+	parser.register_handler("highlight-article", rxman);
+}
+
 int Controller::run(const CliArgsParser& args)
 {
 	::signal(SIGINT, View::ctrl_c_action);
@@ -111,8 +161,16 @@ int Controller::run(const CliArgsParser& args)
 		Logger::set_logfile(args.log_file().value());
 	}
 
-	if (args.log_level().has_value()) {
-		Logger::set_loglevel(args.log_level().value());
+	if (args.log_filter().has_value()) {
+		Logger::set_log_filter(args.log_filter().value());
+	}
+
+	if (args.log_format().has_value()) {
+		Logger::set_log_format(args.log_format().value());
+	}
+
+	if (args.log_rotation().has_value()) {
+		Logger::set_log_rotation(args.log_rotation().value());
 	}
 
 	if (!args.display_msg().empty()) {
@@ -123,7 +181,7 @@ int Controller::run(const CliArgsParser& args)
 		return args.return_code().value();
 	}
 
-	const auto migrated = configpaths.try_migrate_from_newsbeuter();
+	const auto migrated = configpaths.try_migrate();
 	if (migrated) {
 		std::cerr << "\nPlease check the results and press Enter to "
 			"continue.";
@@ -155,22 +213,8 @@ int Controller::run(const CliArgsParser& args)
 	}
 	std::cout.flush();
 
-	cfg.register_commands(cfgparser);
-	colorman.register_commands(cfgparser);
-
 	KeyMap keys(KM_NEWSBOAT);
-	cfgparser.register_handler("bind-key", keys);
-	cfgparser.register_handler("unbind-key", keys);
-	cfgparser.register_handler("macro", keys);
-	cfgparser.register_handler("run-on-startup", keys);
-
-	cfgparser.register_handler("ignore-article", ign);
-	cfgparser.register_handler("always-download", ign);
-	cfgparser.register_handler("reset-unread-on-update", ign);
-
-	cfgparser.register_handler("define-filter", filters);
-	cfgparser.register_handler("highlight", rxman);
-	cfgparser.register_handler("highlight-article", rxman);
+	register_config_handlers(cfgparser, keys);
 
 	try {
 		cfgparser.parse_file("/etc/" PROGRAM_NAME "/config");
@@ -190,36 +234,100 @@ int Controller::run(const CliArgsParser& args)
 		std::cout << _("done.") << std::endl;
 	}
 
+	if (args.keymap_dump_format().has_value()) {
+		std::cout << keys.dump_effective_bindings(args.keymap_dump_format().value());
+		return EXIT_SUCCESS;
+	}
+
 	// create cache object
 	std::string cachefilepath = cfg.get_configvalue("cache-file");
-	if (cachefilepath.length() > 0 && !args.cache_file().has_value()) {
+	if (cachefilepath.length() > 0 && !configpaths.has_explicit_cache_file()) {
 		configpaths.set_cache_file(cachefilepath);
 	}
 
+	read_only = args.read_only();
+	if (read_only && args_change_cache(args)) {
+		std::cout << _("Error: the requested action would change the cache, "
+				"which is not possible in read-only mode")
+			<< std::endl;
+		return EXIT_FAILURE;
+	}
+
 	pid_t pid;
 	std::string error;
-	if (!fslock.try_lock(configpaths.lock_file(), pid, error)) {
-		if (pid != 0) {
-			// pid_t size could vary so cast to known integer format to get correct print format
-			std::int64_t p = pid;
+	const auto wait_for_lock = args.wait_for_lock();
+	bool locked;
+	if (read_only) {
+		locked = fslock.try_lock_shared(configpaths.lock_file(), error);
+	} else if (wait_for_lock.has_value()) {
+		locked = fslock.lock_with_timeout(configpaths.lock_file(),
+				wait_for_lock.value(), pid, error);
+	} else {
+		locked = fslock.try_lock(configpaths.lock_file(), pid, error);
+	}
+	if (!locked && !read_only && args.take_over_lock()) {
+		// The owner is given as long to release the lock as --wait-for-lock
+		// allows, or half a minute
+		const auto timeout = wait_for_lock.value_or(std::chrono::seconds(30));
+		std::string takeover_error;
+		locked = fslock.take_over_stale_lock(configpaths.lock_file(), timeout,
+				true, takeover_error);
+		if (!locked) {
+			std::cout << _("Error: ") << takeover_error << std::endl;
+			return EXIT_FAILURE;
+		}
+	}
+	if (!locked && read_only) {
+		std::cout << _("Error: ") << error << std::endl;
+		return EXIT_FAILURE;
+	} else if (!locked) {
+		const auto cmds_to_execute = args.cmds_to_execute();
+		std::string forward_error;
+		if (!cmds_to_execute.empty()) {
+			// Let the running instance do the work
+			const auto messages = controlsocket::forward_commands(
+					configpaths.control_socket_file(), cmds_to_execute, forward_error);
+			if (forward_error.empty()) {
+				for (const auto& message : messages) {
+					if (!message.empty()) {
+						std::cout << message << std::endl;
+					}
+				}
+				return EXIT_SUCCESS;
+			}
+		}
+
+		std::cout << _("Error: ") << error << std::endl;
+		if (pid != 0 && FsLock::owner_might_be_gone(configpaths.lock_file())) {
 			std::cout << strprintf::fmt(
-					_("Error: an instance of %s is "
-						"already running (PID: %" PRId64 ")"),
-					PROGRAM_NAME,
-					p)
+					_("If that process is gone, run %s with --take-over-lock "
+						"to take the lock over"),
+					PROGRAM_NAME)
 				<< std::endl;
-		} else {
-			std::cout << _("Error: ") << error << std::endl;
+		} else if (pid != 0 && !args_change_cache(args)) {
+			std::cout << strprintf::fmt(
+					_("To browse the cache while it's running, start %s "
+						"with --read-only"),
+					PROGRAM_NAME)
+				<< std::endl;
+		}
+		if (!forward_error.empty()) {
+			std::cerr << forward_error << std::endl;
 		}
 		return EXIT_FAILURE;
 	}
 
+	if (read_only) {
+		LOG(Level::INFO, "Controller::run: running in read-only mode");
+		refresh_on_start = false;
+	}
+
 	if (!args.silent()) {
 		std::cout << _("Opening cache...");
 		std::cout.flush();
 	}
 	try {
-		rsscache = new Cache(configpaths.cache_file(), &cfg);
+		rsscache = new Cache(configpaths.cache_file(), &cfg, read_only);
 	} catch (const DbException& e) {
 		std::cerr << strprintf::fmt(
 				_("Error: opening the cache file `%s' "
@@ -394,6 +502,15 @@ int Controller::run(const CliArgsParser& args)
 	}
 
 	if (args.do_vacuum()) {
+		if (FsLock::has_readers(configpaths.lock_file())) {
+			// VACUUM rewrites the whole database, which readers would block
+			std::cout << strprintf::fmt(
+					_("Error: read-only instances of %s are using the cache; "
+						"quit them before compacting it"),
+					PROGRAM_NAME)
+				<< std::endl;
+			return EXIT_FAILURE;
+		}
 		std::cout << _("Opening cache...");
 		std::cout << _("done.") << std::endl;
 		std::cout << _("Cleaning up cache thoroughly...");
@@ -510,23 +627,32 @@ int Controller::run(const CliArgsParser& args)
 
 	// if the user wants to refresh on startup via configuration file, then
 	// do so, but only if -r hasn't been supplied.
-	if (!refresh_on_start &&
+	if (!refresh_on_start && !read_only &&
 		cfg.get_configvalue_as_bool("refresh-on-startup")) {
 		refresh_on_start = true;
 	}
 
-	FormAction::load_histories(
-		configpaths.search_file(), configpaths.cmdline_file());
+	FormAction::load_histories(configpaths);
+
+	if (!read_only) {
+		start_control_server();
+	}
 
 	// run the View
 	int ret = v->run();
 
+	stop_control_server();
+
 	unsigned int history_limit =
 		cfg.get_configvalue_as_int("history-limit");
 	LOG(Level::DEBUG, "Controller::run: history-limit = %u", history_limit);
-	FormAction::save_histories(configpaths.search_file(),
-		configpaths.cmdline_file(),
-		history_limit);
+	FormAction::save_histories(configpaths,
+		history_limit,
+		cfg.get_configvalue_as_bool("history-timestamps"));
+
+	if (read_only) {
+		return ret;
+	}
 
 	if (!args.silent()) {
 		std::cout << _("Cleaning up cache...");
@@ -919,6 +1045,83 @@ void Controller::import_read_information(const std::string& readinfofile)
 		getline(f, line);
 	}
 	rsscache->mark_items_read_by_guid(guids);
+
+	// Articles that aren't in the cache yet are marked read once they're
+	// fetched, see apply_imported_read_information()
+	std::vector<std::string> unknown_guids;
+	for (const auto& guid : rsscache->get_unknown_item_guids(guids)) {
+		if (!guid.empty()) {
+			unknown_guids.push_back(guid);
+		}
+	}
+	if (unknown_guids.empty()) {
+		return;
+	}
+
+	const std::string pending_file = configpaths.imported_read_guids_file();
+	std::ofstream out(pending_file, std::ios::app);
+	for (const auto& guid : unknown_guids) {
+		out << guid << '\n';
+	}
+	out.close();
+	if (out.fail()) {
+		LOG(Level::ERROR,
+			"Controller::import_read_information: couldn't write %s",
+			pending_file);
+		return;
+	}
+
+	LOG(Level::INFO,
+		"Controller::import_read_information: %" PRIu64
+		" articles aren't in the cache yet, saved them to %s",
+		static_cast<uint64_t>(unknown_guids.size()),
+		pending_file);
+}
+
+void Controller::apply_imported_read_information()
+{
+	if (read_only) {
+		return;
+	}
+
+	const std::string readinfofile = configpaths.imported_read_guids_file();
+	std::ifstream f(readinfofile);
+	if (!f.is_open()) {
+		return;
+	}
+
+	std::unordered_set<std::string> guids;
+	std::string line;
+	while (std::getline(f, line)) {
+		if (!line.empty()) {
+			guids.insert(line);
+		}
+	}
+	f.close();
+
+	LOG(Level::INFO,
+		"Controller::apply_imported_read_information: marking %" PRIu64
+		" imported articles read",
+		static_cast<uint64_t>(guids.size()));
+
+	rsscache->mark_items_read_by_guid(
+		std::vector<std::string>(guids.cbegin(), guids.cend()));
+	for (const auto& feed : feedcontainer.get_all_feeds()) {
+		std::lock_guard<std::mutex> lock(feed->item_mutex);
+		for (const auto& item : feed->items()) {
+			if (item->unread() && guids.count(item->guid()) > 0) {
+				item->set_unread_nowrite(false);
+			}
+		}
+	}
+
+	if (::unlink(readinfofile.c_str()) != 0) {
+		LOG(Level::WARN,
+			"Controller::apply_imported_read_information: couldn't remove "
+			"%s: %s",
+			readinfofile,
+			strerror(errno));
+	}
 }
 
 void Controller::export_read_information(const std::string& readinfofile)
@@ -990,4 +1193,120 @@ void Controller::update_flags(std::shared_ptr<RssItem> item)
 	item->update_flags();
 }
 
+void Controller::start_control_server()
+{
+	const auto socket_path = configpaths.control_socket_file();
+	control_server.reset(new ControlServer(socket_path));
+	if (!control_server->is_listening()) {
+		LOG(Level::ERROR,
+			"Controller::start_control_server: couldn't listen on `%s': %s",
+			socket_path,
+			control_server->error_message());
+		control_server.reset();
+		return;
+	}
+
+	stop_control_thread = false;
+	control_thread = std::thread(&Controller::serve_control_requests, this);
+}
+
+void Controller::stop_control_server()
+{
+	if (control_thread.joinable()) {
+		stop_control_thread = true;
+		control_thread.join();
+	}
+	control_server.reset();
+}
+
+void Controller::serve_control_requests()
+{
+	while (!stop_control_thread) {
+		// The timeout is short so that we notice `stop_control_thread` soon
+		const auto request = control_server->next_request(
+				std::chrono::milliseconds(500));
+		if (!request.has_value()) {
+			continue;
+		}
+
+		std::future<ControlResponse> response;
+		{
+			std::lock_guard<std::mutex> lock(control_requests_mtx);
+			control_requests.push_back(PendingControlRequest{request.value(), {}});
+			response = control_requests.back().response.get_future();
+		}
+		v->wake_up();
+
+		// The View stops handling requests once it quits, so don't wait for
+		// it past that point
+		while (response.wait_for(std::chrono::milliseconds(500)) !=
+			std::future_status::ready) {
+			if (stop_control_thread) {
+				break;
+			}
+		}
+
+		if (response.wait_for(std::chrono::seconds(0)) ==
+			std::future_status::ready) {
+			const auto result = response.get();
+			control_server->respond(result.success, result.message);
+		} else {
+			control_server->respond(false, _("Newsboat is shutting down"));
+		}
+	}
+}
+
+void Controller::handle_control_requests()
+{
+	std::deque<PendingControlRequest> requests;
+	{
+		std::lock_guard<std::mutex> lock(control_requests_mtx);
+		requests.swap(control_requests);
+	}
+
+	for (auto& pending : requests) {
+		pending.response.set_value(handle_control_request(pending.request));
+	}
+}
+
+Controller::ControlResponse Controller::handle_control_request(
+	const ControlRequest& request)
+{
+	if (!request.url.empty() && !feedcontainer.get_feed_by_url(request.url)) {
+		return {false, strprintf::fmt(_("no feed with URL `%s'"), request.url)};
+	}
+
+	switch (request.type) {
+	case ControlRequestType::RELOAD:
+		if (request.url.empty()) {
+			reloader->start_reload_all_thread();
+		} else {
+			const auto feeds = feedcontainer.get_all_feeds();
+			for (unsigned int i = 0; i < feeds.size(); ++i) {
+				if (feeds[i]->rssurl() == request.url) {
+					reloader->start_reload_all_thread({static_cast<int>(i)});
+					break;
+				}
+			}
+		}
+		// The reload runs in the background, so this only means that it
+		// started
+		return {true, ""};
+	case ControlRequestType::MARK_READ:
+		mark_all_read(request.url);
+		update_feedlist();
+		v->force_redraw();
+		return {true, ""};
+	case ControlRequestType::QUIT:
+		v->request_quit();
+		return {true, ""};
+	case ControlRequestType::STATUS:
+		return {true,
+				strprintf::fmt(_("%u unread articles"),
+					feedcontainer.unread_item_count())};
+	default:
+		return {false, _("unsupported request")};
+	}
+}
+
 } // namespace newsboat
@@ -0,0 +1,75 @@
+#include "controlsocket.h"
+
+namespace newsboat {
+
+namespace {
+
+rust::Box<controlsocket::bridged::ControlServer> create_server(
+	const std::string& socket_path, std::string& error)
+{
+	rust::String message;
+	auto server = controlsocket::bridged::create_server(socket_path, message);
+	error = std::string(message);
+	return server;
+}
+
+} // namespace
+
+ControlServer::ControlServer(const std::string& socket_path)
+	: rs_object(create_server(socket_path, error))
+{
+}
+
+bool ControlServer::is_listening() const
+{
+	return controlsocket::bridged::is_listening(*rs_object);
+}
+
+std::string ControlServer::error_message() const
+{
+	return error;
+}
+
+nonstd::optional<ControlRequest> ControlServer::next_request(
+	std::chrono::milliseconds timeout)
+{
+	std::uint8_t type;
+	rust::String url;
+	if (controlsocket::bridged::next_request(*rs_object, timeout.count(), type,
+			url)) {
+		return ControlRequest{static_cast<ControlRequestType>(type), std::string(url)};
+	}
+	return nonstd::nullopt;
+}
+
+void ControlServer::respond(bool success, const std::string& message)
+{
+	controlsocket::bridged::respond(*rs_object, success, message);
+}
+
+namespace controlsocket {
+
+std::vector<std::string> forward_commands(const std::string& socket_path,
+	const std::vector<std::string>& commands,
+	std::string& error_message)
+{
+	rust::Vec<rust::String> rs_commands;
+	for (const auto& command : commands) {
+		rs_commands.push_back(command);
+	}
+
+	rust::String message;
+	const auto rs_messages = bridged::forward_commands(socket_path,
+			std::move(rs_commands), message);
+	error_message = std::string(message);
+
+	std::vector<std::string> messages;
+	for (const auto& m : rs_messages) {
+		messages.push_back(std::string(m));
+	}
+	return messages;
+}
+
+} // namespace controlsocket
+
+} // namespace newsboat
@@ -60,7 +60,9 @@ void FeedListFormAction::init()
 	 * ReloadThread, which is responsible for regularly spawning
 	 * DownloadThreads.
 	 */
-	v->get_ctrl()->get_reloader()->spawn_reloadthread();
+	if (!v->get_ctrl()->is_read_only()) {
+		v->get_ctrl()->get_reloader()->spawn_reloadthread();
+	}
 }
 
 FeedListFormAction::~FeedListFormAction() {}
@@ -456,7 +458,7 @@ REDO:
 			std::vector<QnaPair> qna;
 			qna.push_back(QnaPair(_("Search for: "), ""));
 			this->start_qna(
-				qna, OP_INT_START_SEARCH, &searchhistory);
+				qna, OP_INT_START_SEARCH, &feedsearchhistory);
 		}
 		break;
 	case OP_GOTO_TITLE:
@@ -979,7 +981,7 @@ void FeedListFormAction::op_start_search()
 		searchphrase);
 	if (searchphrase.length() > 0) {
 		v->set_status(_("Searching..."));
-		searchhistory.add_line(searchphrase);
+		feedsearchhistory.add_line(searchphrase);
 		std::vector<std::shared_ptr<RssItem>> items;
 		try {
 			const auto utf8searchphrase = utils::locale_to_utf8(searchphrase);
@@ -6,6 +6,8 @@
 
 #include "config.h"
 #include "configexception.h"
+#include "configpaths.h"
+#include "controller.h"
 #include "logger.h"
 #include "matcherexception.h"
 #include "strprintf.h"
@@ -14,7 +16,37 @@
 
 namespace newsboat {
 
+/// Returns true if `op` modifies the cache or the list of feeds, and thus
+/// isn't available in read-only mode.
+static bool changes_cache(Operation op)
+{
+	switch (op) {
+	case OP_RELOAD:
+	case OP_RELOADALL:
+	case OP_MARKFEEDREAD:
+	case OP_MARKALLFEEDSREAD:
+	case OP_MARKALLABOVEASREAD:
+	case OP_OPENBROWSER_AND_MARK:
+	case OP_OPENALLUNREADINBROWSER_AND_MARK:
+	case OP_TOGGLEITEMREAD:
+	case OP_ENQUEUE:
+	case OP_RELOADURLS:
+	case OP_EDITFLAGS:
+	case OP_INT_EDITFLAGS_END:
+	case OP_DELETE:
+	case OP_DELETE_ALL:
+	case OP_PURGE_DELETED:
+	case OP_EDIT_URLS:
+		return true;
+	default:
+		return false;
+	}
+}
+
 History FormAction::searchhistory;
+History FormAction::articlesearchhistory;
+History FormAction::feedsearchhistory;
+History FormAction::filterhistory;
 History FormAction::cmdlinehistory;
 
 FormAction::FormAction(View* vv, std::string formstr, ConfigContainer* cfg)
@@ -116,10 +148,23 @@ void FormAction::start_cmdline(std::string default_value)
 	this->start_qna(qna, OP_INT_END_CMDLINE, &FormAction::cmdlinehistory);
 }
 
+void FormAction::start_macro_qna(const std::string& key)
+{
+	macro_key = key;
+	std::vector<QnaPair> qna;
+	qna.push_back(QnaPair(strprintf::fmt(_("Arguments for macro %s: "), key), ""));
+	this->start_qna(qna, OP_INT_MACRO_ARGS_END);
+}
+
 bool FormAction::process_op(Operation op,
 	bool automatic,
 	std::vector<std::string>* args)
 {
+	if (v->get_ctrl()->is_read_only() && changes_cache(op)) {
+		v->show_error(_("This operation is not available in read-only mode"));
+		return false;
+	}
+
 	switch (op) {
 	case OP_REDRAW:
 		LOG(Level::DEBUG, "FormAction::process_op: redrawing screen");
@@ -430,6 +475,13 @@ void FormAction::finished_qna(Operation op)
 		this->handle_cmdline(cmdline);
 	}
 	break;
+	case OP_INT_MACRO_ARGS_END: {
+		const auto args = utils::tokenize_quoted(qna_responses[0]);
+		LOG(Level::DEBUG, "FormAction: running macro `%s' with %" PRIu64
+			" arguments", macro_key, static_cast<uint64_t>(args.size()));
+		v->run_macro(macro_key, args);
+	}
+	break;
 	default:
 		break;
 	}
@@ -541,19 +593,30 @@ void FormAction::start_next_question()
 	}
 }
 
-void FormAction::load_histories(const std::string& searchfile,
-	const std::string& cmdlinefile)
+void FormAction::load_histories(const ConfigPaths& configpaths)
 {
-	searchhistory.load_from_file(searchfile);
-	cmdlinehistory.load_from_file(cmdlinefile);
+	searchhistory.load_from_file(configpaths.search_file());
+	articlesearchhistory.load_from_file(configpaths.article_search_file());
+	feedsearchhistory.load_from_file(configpaths.feed_search_file());
+	filterhistory.load_from_file(configpaths.filter_file());
+	cmdlinehistory.load_from_file(configpaths.cmdline_file());
 }
 
-void FormAction::save_histories(const std::string& searchfile,
-	const std::string& cmdlinefile,
-	unsigned int limit)
+void FormAction::save_histories(const ConfigPaths& configpaths,
+	unsigned int limit,
+	bool timestamps)
 {
-	searchhistory.save_to_file(searchfile, limit);
-	cmdlinehistory.save_to_file(cmdlinefile, limit);
+	const std::vector<std::pair<History*, std::string>> histories = {
+		{&searchhistory, configpaths.search_file()},
+		{&articlesearchhistory, configpaths.article_search_file()},
+		{&feedsearchhistory, configpaths.feed_search_file()},
+		{&filterhistory, configpaths.filter_file()},
+		{&cmdlinehistory, configpaths.cmdline_file()},
+	};
+	for (const auto& history : histories) {
+		history.first->set_timestamps(timestamps);
+		history.first->save_to_file(history.second, limit);
+	}
 }
 
 std::string FormAction::bookmark(const std::string& url,
@@ -30,4 +30,51 @@ bool FsLock::try_lock(const std::string& new_lock_filepath, pid_t& pid,
 	return result;
 }
 
+bool FsLock::lock_with_timeout(const std::string& new_lock_filepath,
+	std::chrono::seconds timeout, pid_t& pid, std::string& error_message)
+{
+	std::int64_t p;
+	rust::String message;
+	const bool result = newsboat::fslock::bridged::lock_with_timeout(*rs_object,
+			new_lock_filepath, timeout.count(), p, message);
+
+	// We use `libc::pid_t` on the rust side so we can guarantee this will fit
+	pid = static_cast<std::int64_t>(p);
+	error_message = std::string(message);
+
+	return result;
+}
+
+bool FsLock::try_lock_shared(const std::string& lock_filepath,
+	std::string& error_message)
+{
+	rust::String message;
+	const bool result = newsboat::fslock::bridged::try_lock_shared(*rs_object,
+			lock_filepath, message);
+	error_message = std::string(message);
+
+	return result;
+}
+
+bool FsLock::has_readers(const std::string& lock_filepath)
+{
+	return newsboat::fslock::bridged::has_readers(lock_filepath);
+}
+
+bool FsLock::take_over_stale_lock(const std::string& lock_filepath,
+	std::chrono::seconds timeout, bool force, std::string& error_message)
+{
+	rust::String message;
+	const bool result = newsboat::fslock::bridged::take_over_stale_lock(
+			*rs_object, lock_filepath, timeout.count(), force, message);
+	error_message = std::string(message);
+
+	return result;
+}
+
+bool FsLock::owner_might_be_gone(const std::string& lock_filepath)
+{
+	return newsboat::fslock::bridged::owner_might_be_gone(lock_filepath);
+}
+
 } // namespace newsboat
@@ -9,6 +9,8 @@ extern "C" {
 
 	void rs_history_free(void* hst);
 
+	void rs_history_set_timestamps(void* hst, bool timestamps);
+
 	void rs_history_add_line(
 		void* hst,
 		const char* line);
@@ -39,6 +41,11 @@ History::~History()
 	rs_history_free(rs_hst);
 }
 
+void History::set_timestamps(bool timestamps)
+{
+	rs_history_set_timestamps(rs_hst, timestamps);
+}
+
 void History::add_line(const std::string& line)
 {
 	rs_history_add_line(rs_hst, line.c_str());
@@ -592,7 +592,7 @@ bool ItemListFormAction::process_operation(Operation op,
 		} else {
 			qna.push_back(QnaPair(_("Search for: "), ""));
 			this->start_qna(
-				qna, OP_INT_START_SEARCH, &searchhistory);
+				qna, OP_INT_START_SEARCH, &articlesearchhistory);
 		}
 	}
 	break;
@@ -879,7 +879,7 @@ void ItemListFormAction::qna_start_search()
 	}
 
 	v->set_status(_("Searching..."));
-	searchhistory.add_line(searchphrase);
+	articlesearchhistory.add_line(searchphrase);
 	std::vector<std::shared_ptr<RssItem>> items;
 	try {
 		const auto utf8searchphrase = utils::locale_to_utf8(searchphrase);
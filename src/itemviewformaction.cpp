@@ -299,7 +299,7 @@ bool ItemViewFormAction::process_operation(Operation op,
 		} else {
 			qna.push_back(QnaPair(_("Search for: "), ""));
 			this->start_qna(
-				qna, OP_INT_START_SEARCH, &searchhistory);
+				qna, OP_INT_START_SEARCH, &articlesearchhistory);
 		}
 	}
 	break;
@@ -682,7 +682,7 @@ void ItemViewFormAction::do_search()
 		return;
 	}
 
-	searchhistory.add_line(searchphrase);
+	articlesearchhistory.add_line(searchphrase);
 
 	LOG(Level::DEBUG,
 		"ItemViewFormAction::do_search: searchphrase = %s",
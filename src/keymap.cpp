@@ -7,6 +7,7 @@
 #include <vector>
 
 #include "config.h"
+#include "configcheck.h"
 #include "confighandlerexception.h"
 #include "configparser.h"
 #include "logger.h"
@@ -677,6 +678,42 @@ void KeyMap::dump_config(std::vector<std::string>& config_output) const
 		}
 		config_output.push_back(configline);
 	}
+	for (const auto& macro : parameterized_macros_) {
+		config_output.push_back("macro " + macro.first + " " + macro.second);
+	}
+}
+
+std::string KeyMap::dump_effective_bindings(KeymapDumpFormat format) const
+{
+	auto dump = keymap::bridged::create_keymap_dump();
+	for (const auto& ctx : contexts) {
+		const std::string& context = ctx.first;
+		const auto keys = keymap_.find(context);
+		if (keys == keymap_.end()) {
+			continue;
+		}
+		for (const auto& keymap : keys->second) {
+			if (keymap.second != OP_NIL && keymap.second < OP_INT_MIN) {
+				const std::string opname = getopname(keymap.second);
+				keymap::bridged::add_binding(*dump, context, keymap.first, opname);
+			}
+		}
+	}
+	for (const auto& macro : macros_) {
+		for (const auto& cmd : macro.second) {
+			rust::Vec<rust::String> tokens;
+			tokens.push_back(getopname(cmd.op));
+			for (const auto& arg : cmd.args) {
+				tokens.push_back(arg);
+			}
+			keymap::bridged::add_macro_operation(*dump, macro.first, std::move(tokens));
+		}
+	}
+	for (const auto& macro : parameterized_macros_) {
+		keymap::bridged::add_macro_definition(*dump, macro.first, macro.second);
+	}
+	return std::string(keymap::bridged::format_keymap_dump(*dump,
+				static_cast<std::uint8_t>(format)));
 }
 
 std::string KeyMap::getopname(Operation op) const
@@ -689,6 +726,40 @@ std::string KeyMap::getopname(Operation op) const
 	return "<none>";
 }
 
+static bool is_parameterized_macro(const std::string& operations)
+{
+	return keymap::bridged::macro_takes_arguments(operations) ||
+		!keymap::bridged::macro_variable_names(operations).empty();
+}
+
+static std::vector<MacroCmd> to_macro_cmds(KeyMap& keys,
+	const rust::Vec<keymap::bridged::Operation>& operations)
+{
+	std::vector<MacroCmd> cmds;
+	for (const auto& operation : operations) {
+		const auto& tokens = keymap::bridged::operation_tokens(operation);
+		if (tokens.empty()) {
+			continue;
+		}
+
+		const auto command_name = std::string(tokens[0]);
+		const auto arguments = std::vector<std::string>(std::next(std::begin(tokens)),
+				std::end(tokens));
+
+		MacroCmd cmd;
+		cmd.op = keys.get_opcode(command_name);
+		if (cmd.op == OP_NIL) {
+			throw ConfigHandlerException(strprintf::fmt(_("`%s' is not a valid operation"),
+					command_name));
+		}
+		cmd.args = arguments;
+
+		cmds.push_back(cmd);
+	}
+
+	return cmds;
+}
+
 void KeyMap::handle_action(const std::string& action, const std::string& params)
 {
 	/*
@@ -735,12 +806,33 @@ void KeyMap::handle_action(const std::string& action, const std::string& params)
 	} else if (action == "macro") {
 		std::string remaining_params = params;
 		const auto token = utils::extract_token_quoted(remaining_params);
+		if (token.has_value() && is_parameterized_macro(remaining_params)) {
+			const auto names = keymap::bridged::macro_operation_names(remaining_params);
+			if (names.empty()) {
+				throw ConfigHandlerException(ActionHandlerStatus::TOO_FEW_PARAMS);
+			}
+			// Names that contain placeholders are checked when the macro is run
+			for (const auto& name : names) {
+				if (name.size() != 0 && get_opcode(std::string(name)) == OP_NIL) {
+					throw ConfigHandlerException(strprintf::fmt(
+							_("`%s' is not a valid operation"), std::string(name)));
+				}
+			}
+			const std::string macrokey = token.value();
+			utils::trim(remaining_params);
+
+			macros_.erase(macrokey);
+			parameterized_macros_[macrokey] = remaining_params;
+			return;
+		}
+
 		const std::vector<MacroCmd> cmds = parse_operation_sequence(remaining_params);
 		if (!token.has_value() || cmds.empty()) {
 			throw ConfigHandlerException(ActionHandlerStatus::TOO_FEW_PARAMS);
 		}
 		const std::string macrokey = token.value();
 
+		parameterized_macros_.erase(macrokey);
 		macros_[macrokey] = cmds;
 	} else if (action == "run-on-startup") {
 		startup_operations_sequence = parse_operation_sequence(params);
@@ -752,31 +844,7 @@ void KeyMap::handle_action(const std::string& action, const std::string& params)
 
 std::vector<MacroCmd> KeyMap::parse_operation_sequence(const std::string& line)
 {
-	const auto operations = keymap::bridged::tokenize_operation_sequence(line);
-
-	std::vector<MacroCmd> cmds;
-	for (const auto& operation : operations) {
-		const auto& tokens = keymap::bridged::operation_tokens(operation);
-		if (tokens.empty()) {
-			continue;
-		}
-
-		const auto command_name = std::string(tokens[0]);
-		const auto arguments = std::vector<std::string>(std::next(std::begin(tokens)),
-				std::end(tokens));
-
-		MacroCmd cmd;
-		cmd.op = get_opcode(command_name);
-		if (cmd.op == OP_NIL) {
-			throw ConfigHandlerException(strprintf::fmt(_("`%s' is not a valid operation"),
-					command_name));
-		}
-		cmd.args = arguments;
-
-		cmds.push_back(cmd);
-	}
-
-	return cmds;
+	return to_macro_cmds(*this, keymap::bridged::tokenize_operation_sequence(line));
 }
 
 std::vector<MacroCmd> KeyMap::get_startup_operation_sequence()
@@ -784,6 +852,16 @@ std::vector<MacroCmd> KeyMap::get_startup_operation_sequence()
 	return startup_operations_sequence;
 }
 
+void KeyMap::register_operations(ConfigChecker& checker) const
+{
+	for (const auto& opdesc : opdescs) {
+		checker.add_operation(opdesc.opstr);
+	}
+	for (const auto& ctx : contexts) {
+		checker.add_context(ctx.first);
+	}
+}
+
 std::vector<std::string> KeyMap::get_keys(Operation op,
 	const std::string& context)
 {
@@ -804,6 +882,66 @@ std::vector<MacroCmd> KeyMap::get_macro(const std::string& key)
 	return {};
 }
 
+bool KeyMap::macro_takes_arguments(const std::string& key) const
+{
+	const auto macro = parameterized_macros_.find(key);
+	if (macro == parameterized_macros_.end()) {
+		return false;
+	}
+	return keymap::bridged::macro_takes_arguments(macro->second);
+}
+
+std::vector<std::string> KeyMap::get_macro_variable_names(
+	const std::string& key) const
+{
+	const auto macro = parameterized_macros_.find(key);
+	if (macro == parameterized_macros_.end()) {
+		return {};
+	}
+
+	std::vector<std::string> names;
+	for (const auto& name : keymap::bridged::macro_variable_names(macro->second)) {
+		names.push_back(std::string(name));
+	}
+	return names;
+}
+
+nonstd::expected<std::vector<MacroCmd>, std::string> KeyMap::expand_macro(
+	const std::string& key,
+	const std::vector<std::string>& args,
+	const std::map<std::string, std::string>& variables)
+{
+	const auto macro = parameterized_macros_.find(key);
+	if (macro == parameterized_macros_.end()) {
+		return get_macro(key);
+	}
+
+	rust::Vec<rust::String> arguments;
+	for (const auto& arg : args) {
+		arguments.push_back(arg);
+	}
+	rust::Vec<rust::String> variable_names;
+	rust::Vec<rust::String> variable_values;
+	for (const auto& variable : variables) {
+		variable_names.push_back(variable.first);
+		variable_values.push_back(variable.second);
+	}
+
+	rust::String error_message;
+	const auto operations = keymap::bridged::expand_macro(macro->second,
+			std::move(arguments), std::move(variable_names),
+			std::move(variable_values), error_message);
+	if (error_message.size() != 0) {
+		return nonstd::make_unexpected(std::string(error_message));
+	}
+
+	try {
+		return to_macro_cmds(*this, operations);
+	} catch (const ConfigHandlerException& e) {
+		return nonstd::make_unexpected(std::string(e.what()));
+	}
+}
+
 bool KeyMap::is_valid_context(const std::string& context)
 {
 	if (context == "all") {
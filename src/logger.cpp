@@ -5,6 +5,9 @@ extern "C" {
 	void rs_set_user_error_logfile(const char* logfile);
 	void rs_set_loglevel(newsboat::Level level);
 	void rs_unset_loglevel();
+	void rs_set_log_filter(const char* filter);
+	void rs_set_log_format(newsboat::LogFormat format);
+	void rs_set_log_rotation(std::uint64_t max_size, std::uint32_t max_files);
 }
 
 namespace newsboat {
@@ -28,4 +31,19 @@ void Logger::unset_loglevel()
 	rs_unset_loglevel();
 }
 
+void Logger::set_log_filter(const std::string& filter)
+{
+	rs_set_log_filter(filter.c_str());
+}
+
+void Logger::set_log_format(LogFormat format)
+{
+	rs_set_log_format(format);
+}
+
+void Logger::set_log_rotation(const LogRotation& rotation)
+{
+	rs_set_log_rotation(rotation.max_size, rotation.max_files);
+}
+
 } // namespace newsboat
@@ -179,6 +179,13 @@ void PbController::initialize(int argc, char* argv[])
 
 	::signal(SIGINT, ctrl_c_action);
 
+	// Shared with Newsboat, so that both agree on the queue; --queue-file
+	// still takes precedence
+	const char* env_queue_file = ::getenv("NEWSBOAT_QUEUE_FILE");
+	if (env_queue_file != nullptr && env_queue_file[0] != '\0') {
+		queue_file = env_queue_file;
+	}
+
 	static const char getopt_str[] = "C:q:d:l:havV";
 	static const struct option longopts[] = {
 		{"config-file", required_argument, 0, 'C'},
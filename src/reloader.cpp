@@ -173,6 +173,8 @@ void Reloader::reload_all(bool unattended)
 		}
 	}
 
+	ctrl->apply_imported_read_information();
+
 	// refresh query feeds (update and sort)
 	LOG(Level::DEBUG, "Reloader::reload_all: refresh query feeds");
 	for (const auto& feed : ctrl->get_feedcontainer()->get_all_feeds()) {
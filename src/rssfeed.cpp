@@ -42,21 +42,13 @@ RssFeed::RssFeed(Cache* c, const std::string& rssurl)
 		 *
 		 * query:Title:unread = "yes" and age between 0:7
 		 *
-		 * So we split by colons to get title and the query itself. */
-		const auto tokens = utils::tokenize(rssurl_, ":");
-
-		if (tokens.size() < 3) {
-			throw _s("too few arguments");
+		 * The title ends at the first colon, the rest is the query itself. */
+		const auto parts = utils::parse_query_url(rssurl_);
+		if (!parts.has_value()) {
+			throw parts.error();
 		}
+		const std::string& query = parts->expression;
 
-		/* "Between" operator requires a range, which contains a colon.
-		 * Since we've been tokenizing by colon, we might've
-		 * inadertently split the query itself. Let's reconstruct it! */
-		auto query = tokens[2];
-		for (auto it = tokens.begin() + 3; it != tokens.end(); ++it) {
-			query += ":";
-			query += *it;
-		}
 		// Have to check if the result is a valid query, just in case
 		Matcher m;
 		if (!m.parse(query)) {
@@ -67,10 +59,10 @@ RssFeed::RssFeed(Cache* c, const std::string& rssurl)
 
 		LOG(Level::DEBUG,
 			"RssFeed constructor: query name = `%s' expr = `%s'",
-			tokens[1],
+			parts->title,
 			query);
 
-		set_title(tokens[1]);
+		set_title(parts->title);
 		this->query = query;
 	}
 }
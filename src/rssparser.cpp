@@ -145,13 +145,6 @@ void RssParser::set_rtl(std::shared_ptr<RssFeed> feed,
 
 void RssParser::retrieve_uri(const std::string& uri)
 {
-	/*
-	 *	- http:// and https:// URLs are downloaded and parsed regularly
-	 *	- exec: URLs are executed and their output is parsed
-	 *	- filter: URLs are downloaded, executed, and their output is
-	 *parsed
-	 *	- query: URLs are ignored
-	 */
 	if (is_ttrss) {
 		std::string::size_type pound = uri.find_first_of('#');
 		if (pound != std::string::npos) {
@@ -163,19 +156,36 @@ void RssParser::retrieve_uri(const std::string& uri)
 		fetch_ocnews(uri);
 	} else if (is_miniflux) {
 		fetch_miniflux(uri);
-	} else if (utils::is_http_url(uri)) {
-		download_http(uri);
-	} else if (utils::is_exec_url(uri)) {
-		get_execplugin(uri.substr(5, uri.length() - 5));
-	} else if (utils::is_filter_url(uri)) {
-		const auto parts = utils::extract_filter(uri);
-		download_filterplugin(std::string(parts.script_name), std::string(parts.url));
-	} else if (utils::is_query_url(my_uri)) {
-		f.rss_version = rsspp::Feed::Version::UNKNOWN;
-	} else if (my_uri.substr(0, 7) == "file://") {
-		parse_file(my_uri.substr(7, my_uri.length() - 7));
 	} else {
-		throw strprintf::fmt(_("Error: unsupported URL: %s"), my_uri);
+		retrieve_feed_source(uri);
+	}
+}
+
+void RssParser::retrieve_feed_source(const std::string& uri)
+{
+	const auto source = utils::parse_feed_source(uri);
+	if (!source.has_value()) {
+		throw source.error();
+	}
+
+	switch (source->kind) {
+	case utils::FeedSourceKind::URL:
+		download_http(source->location);
+		break;
+	case utils::FeedSourceKind::LOCAL_FILE:
+		parse_file(source->location);
+		break;
+	case utils::FeedSourceKind::QUERY:
+		// Query feeds aren't downloaded, their articles come from other
+		// feeds
+		f.rss_version = rsspp::Feed::Version::UNKNOWN;
+		break;
+	case utils::FeedSourceKind::FILTER:
+		download_filterplugin(source->command, source->location);
+		break;
+	case utils::FeedSourceKind::EXEC:
+		get_execplugin(source->command);
+		break;
 	}
 }
 
@@ -258,11 +268,46 @@ void RssParser::download_http(const std::string& uri)
 		(f.rss_version != rsspp::Feed::Version::UNKNOWN) ? "true" : "false");
 }
 
+static std::chrono::seconds plugin_timeout(ConfigContainer* cfgcont)
+{
+	const int timeout = cfgcont->get_configvalue_as_int("script-timeout");
+	return std::chrono::seconds(std::max(timeout, 0));
+}
+
+/// Throws an error message if the exec: or filter: command didn't produce
+/// usable output.
+static void check_plugin_result(const std::string& command,
+	const nonstd::expected<utils::CommandOutput, std::string>& result,
+	std::chrono::seconds timeout)
+{
+	if (!result.has_value()) {
+		throw strprintf::fmt(_("couldn't run `%s': %s"), command,
+				result.error());
+	}
+	if (!result.value().error_output.empty()) {
+		LOG(Level::INFO,
+			"RssParser: `%s' wrote to stderr: %s",
+			command,
+			result.value().error_output);
+	}
+	if (result.value().timed_out) {
+		throw strprintf::fmt(
+				_("`%s' didn't finish in %u seconds (see script-timeout)"),
+				command,
+				static_cast<unsigned int>(timeout.count()));
+	}
+	if (result.value().truncated) {
+		throw strprintf::fmt(_("output of `%s' is too big"), command);
+	}
+}
+
 void RssParser::get_execplugin(const std::string& plugin)
 {
-	std::string buf = utils::get_command_output(plugin);
+	const auto timeout = plugin_timeout(cfgcont);
+	const auto result = utils::get_command_output_with_limits(plugin, timeout);
+	check_plugin_result(plugin, result, timeout);
 	rsspp::Parser p;
-	f = p.parse_buffer(buf);
+	f = p.parse_buffer(result.value().output);
 	LOG(Level::DEBUG,
 		"RssParser::parse: execplugin %s, valid = %s",
 		plugin,
@@ -289,13 +334,15 @@ void RssParser::download_filterplugin(const std::string& filter,
 			filter.c_str(),
 			nullptr
 		};
-	std::string result = utils::run_program(argv, buf);
+	const auto timeout = plugin_timeout(cfgcont);
+	const auto result = utils::run_program_with_limits(argv, buf, timeout);
+	check_plugin_result(filter, result, timeout);
 	LOG(Level::DEBUG,
 		"RssParser::parse: output of `%s' is: %s",
 		filter,
-		result);
+		result.value().output);
 	rsspp::Parser p;
-	f = p.parse_buffer(result);
+	f = p.parse_buffer(result.value().output);
 	LOG(Level::DEBUG,
 		"RssParser::parse: filterplugin %s, valid = %s",
 		filter,
@@ -12,4 +12,23 @@ void ScopeMeasure::stopover(const std::string& son)
 	rs_object->stopover(son);
 }
 
+void ScopeMeasure::log_summary()
+{
+	scopemeasure::bridged::log_summary();
+}
+
+void ScopeMeasure::enable_trace()
+{
+	scopemeasure::bridged::enable_trace();
+}
+
+bool ScopeMeasure::write_trace(const std::string& path,
+	std::string& error_message)
+{
+	rust::String message;
+	const bool result = scopemeasure::bridged::write_trace(path, message);
+	error_message = std::string(message);
+	return result;
+}
+
 } // namespace newsboat
@@ -395,6 +395,78 @@ std::string utils::run_program(const char* argv[], const std::string& input)
 	return std::string(utils::bridged::run_program(rs_argv, input));
 }
 
+nonstd::expected<utils::QueryUrlParts, std::string> utils::parse_query_url(
+	const std::string& url)
+{
+	rust::String title;
+	rust::String expression;
+	rust::String error_message;
+	if (!utils::bridged::parse_query_url(url, title, expression, error_message)) {
+		return nonstd::make_unexpected(std::string(error_message));
+	}
+	return QueryUrlParts{std::string(title), std::string(expression)};
+}
+
+nonstd::expected<utils::FeedSource, std::string> utils::parse_feed_source(
+	const std::string& url)
+{
+	utils::bridged::FeedSourceParts parts{};
+	rust::String error_message;
+	if (!utils::bridged::parse_feed_source(url, parts, error_message)) {
+		return nonstd::make_unexpected(std::string(error_message));
+	}
+	return FeedSource{
+		static_cast<FeedSourceKind>(parts.kind),
+		std::string(parts.location),
+		std::string(parts.command),
+		std::string(parts.title),
+		std::string(parts.expression)};
+}
+
+static utils::CommandOutput convert_command_output(
+	const utils::bridged::CommandOutput& result)
+{
+	utils::CommandOutput output;
+	if (result.exited) {
+		output.exit_code = result.exit_code;
+	}
+	output.output = std::string(result.output);
+	output.error_output = std::string(result.error_output);
+	output.timed_out = result.timed_out;
+	output.truncated = result.truncated;
+	return output;
+}
+
+nonstd::expected<utils::CommandOutput, std::string>
+utils::get_command_output_with_limits(const std::string& cmd,
+	std::chrono::seconds timeout)
+{
+	utils::bridged::CommandOutput result;
+	rust::String error_message;
+	if (!utils::bridged::get_command_output_with_limits(cmd, timeout.count(),
+			result, error_message)) {
+		return nonstd::make_unexpected(std::string(error_message));
+	}
+	return convert_command_output(result);
+}
+
+nonstd::expected<utils::CommandOutput, std::string>
+utils::run_program_with_limits(const char* argv[], const std::string& input,
+	std::chrono::seconds timeout)
+{
+	rust::Vec<rust::String> rs_argv;
+	for (; *argv; ++argv) {
+		rs_argv.emplace_back(*argv);
+	}
+	utils::bridged::CommandOutput result;
+	rust::String error_message;
+	if (!utils::bridged::run_program_with_limits(rs_argv, input, timeout.count(),
+			result, error_message)) {
+		return nonstd::make_unexpected(std::string(error_message));
+	}
+	return convert_command_output(result);
+}
+
 std::string utils::resolve_tilde(const std::string& str)
 {
 	return std::string(utils::bridged::resolve_tilde(str));
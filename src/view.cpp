@@ -6,12 +6,14 @@
 #include <cstring>
 #include <curses.h>
 #include <dirent.h>
+#include <fcntl.h>
 #include <fstream>
 #include <grp.h>
 #include <iomanip>
 #include <iostream>
 #include <libgen.h>
 #include <ncurses.h>
+#include <poll.h>
 #include <pwd.h>
 #include <string.h>
 #include <sys/param.h>
@@ -72,15 +74,35 @@ View::View(Controller* c)
 	, rsscache(nullptr)
 	, filters(ctrl->get_filtercontainer())
 	, colorman(ctrl->get_colormanager())
+	, quit_requested(false)
+	, wakeup_pipe{-1, -1}
 {
 	if (getenv("ESCDELAY") == nullptr) {
 		set_escdelay(25);
 	}
+
+	if (::pipe(wakeup_pipe) == 0) {
+		for (const int fd : wakeup_pipe) {
+			::fcntl(fd, F_SETFL, ::fcntl(fd, F_GETFL) | O_NONBLOCK);
+			::fcntl(fd, F_SETFD, FD_CLOEXEC);
+		}
+	} else {
+		LOG(Level::ERROR,
+			"View::View: couldn't create the wake-up pipe: %s",
+			strerror(errno));
+		wakeup_pipe[0] = wakeup_pipe[1] = -1;
+	}
 }
 
 View::~View()
 {
 	Stfl::reset();
+
+	for (const int fd : wakeup_pipe) {
+		if (fd != -1) {
+			::close(fd);
+		}
+	}
 }
 
 void View::set_config_container(ConfigContainer* cfgcontainer)
@@ -137,6 +159,34 @@ bool View::run_commands(const std::vector<MacroCmd>& commands)
 	return true;
 }
 
+void View::run_macro(const std::string& key,
+	const std::vector<std::string>& args)
+{
+	// Settings are read when the macro runs, so that a macro can e.g. change
+	// the browser and then restore the previous one with `${browser}`
+	std::map<std::string, std::string> variables;
+	for (const auto& name : keys->get_macro_variable_names(key)) {
+		variables[name] = cfg->get_configvalue(name);
+	}
+
+	const auto commands = keys->expand_macro(key, args, variables);
+	if (!commands) {
+		set_status(strprintf::fmt(_("Error: can't run macro %s: %s"), key,
+				commands.error()));
+		return;
+	}
+	run_commands(commands.value());
+}
+
+void View::show_read_only_banner(std::shared_ptr<FormAction> fa)
+{
+	const std::string banner = _("[read-only] ");
+	const std::string head = fa->get_value("head");
+	if (head.compare(0, banner.length(), banner) != 0) {
+		fa->set_value("head", banner + head);
+	}
+}
+
 int View::run()
 {
 	bool have_macroprefix = false;
@@ -164,14 +214,29 @@ int View::run()
 	 */
 
 	while (formaction_stack_size() > 0) {
+		// requests from other processes change what's on the screen, so
+		// handle them before drawing it
+		ctrl->handle_control_requests();
+
+		if (quit_requested) {
+			Stfl::reset();
+			return EXIT_SUCCESS;
+		}
+
 		// first, we take the current formaction.
 		std::shared_ptr<FormAction> fa = get_current_formaction();
 
 		// we signal "oh, you will receive an operation soon"
 		fa->prepare();
 
-		// we then receive the event and ignore timeouts.
-		const std::string event = fa->draw_form_wait_for_event(60000);
+		if (ctrl->is_read_only()) {
+			show_read_only_banner(fa);
+		}
+
+		// we then draw the form and receive the input that's already there.
+		// If there's none, we sleep until there is, or until another thread
+		// wakes us up. STFL can't wait for the latter, hence the short timeout.
+		const std::string event = fa->draw_form_wait_for_event(10);
 
 		if (ctrl_c_hit) {
 			ctrl_c_hit = false;
@@ -187,6 +252,7 @@ int View::run()
 		}
 
 		if (event.empty() || event == "TIMEOUT") {
+			wait_for_input_or_wake_up(60000);
 			continue;
 		}
 
@@ -204,10 +270,17 @@ int View::run()
 		if (have_macroprefix) {
 			have_macroprefix = false;
 			set_status("");
-			LOG(Level::DEBUG,
-				"View::run: running macro `%s'",
-				event);
-			run_commands(keys->get_macro(event));
+			if (keys->macro_takes_arguments(event)) {
+				LOG(Level::DEBUG,
+					"View::run: asking for arguments of macro `%s'",
+					event);
+				fa->start_macro_qna(event);
+			} else {
+				LOG(Level::DEBUG,
+					"View::run: running macro `%s'",
+					event);
+				run_macro(event, {});
+			}
 		} else {
 			const Operation op = keys->get_operation(event, fa->id());
 
@@ -1198,4 +1271,41 @@ void View::ctrl_c_action(int /* sig */)
 	ctrl_c_hit = true;
 }
 
+void View::request_quit()
+{
+	quit_requested = true;
+	wake_up();
+}
+
+void View::wake_up()
+{
+	if (wakeup_pipe[1] != -1) {
+		// If the pipe is full, the main loop is going to wake up anyway
+		const char byte = 0;
+		if (::write(wakeup_pipe[1], &byte, 1) == -1 && errno != EAGAIN) {
+			LOG(Level::ERROR,
+				"View::wake_up: couldn't write to the wake-up pipe: %s",
+				strerror(errno));
+		}
+	}
+}
+
+void View::wait_for_input_or_wake_up(int timeout_ms)
+{
+	struct pollfd fds[2];
+	fds[0].fd = STDIN_FILENO;
+	fds[0].events = POLLIN;
+	fds[1].fd = wakeup_pipe[0];
+	fds[1].events = POLLIN;
+
+	// Signals like SIGINT and SIGWINCH interrupt the wait, which is fine:
+	// the main loop checks for them right after.
+	if (::poll(fds, 2, timeout_ms) > 0 && (fds[1].revents & POLLIN)) {
+		char buf[64];
+		while (::read(wakeup_pipe[0], buf, sizeof(buf)) > 0) {
+			// drain the pipe, so that the next call waits again
+		}
+	}
+}
+
 } // namespace newsboat
@@ -1,12 +1,17 @@
 #include "cache.h"
 
+#include <algorithm>
+#include <fstream>
 #include <sstream>
 
 #include "3rd-party/catch.hpp"
+#include "cliargsparser.h"
 #include "configcontainer.h"
+#include "dbexception.h"
 #include "rssfeed.h"
 #include "rssignores.h"
 #include "rssparser.h"
+#include "test-helpers/opts.h"
 #include "test-helpers/tempfile.h"
 
 using namespace newsboat;
@@ -483,6 +488,24 @@ TEST_CASE("mark_items_read_by_guid marks items with given GUIDs as unread ",
 	}
 }
 
+TEST_CASE("get_unknown_item_guids returns GUIDs that aren't in the cache",
+	"[Cache]")
+{
+	ConfigContainer cfg;
+	Cache rsscache(":memory:", &cfg);
+	auto feedurl = "file://data/rss.xml";
+	RssParser parser(feedurl, &rsscache, &cfg, nullptr);
+	std::shared_ptr<RssFeed> feed = parser.parse();
+	rsscache.externalize_rssfeed(feed, false);
+
+	REQUIRE(rsscache.get_unknown_item_guids({}).empty());
+
+	const auto known = feed->items()[1]->guid();
+	REQUIRE(rsscache.get_unknown_item_guids({"a", known, "b"}) ==
+		std::vector<std::string>({"a", "b"}));
+	REQUIRE(rsscache.get_unknown_item_guids({known}).empty());
+}
+
 TEST_CASE(
 	"remove_old_deleted_items removes deleted items that belong to the given "
 	"feed, but aren't mentioned in the given RssFeed object",
@@ -1040,3 +1063,98 @@ TEST_CASE("search_in_items returns empty set if input set is empty", "[Cache]")
 	const guids result = rsscache.search_in_items("Botox", empty);
 	REQUIRE(result.empty());
 }
+
+TEST_CASE("Read-only cache doesn't write anything to the DB", "[Cache]")
+{
+	TestHelpers::TempFile dbfile;
+	ConfigContainer cfg;
+
+	{
+		Cache rsscache(dbfile.get_path(), &cfg);
+		RssParser parser("file://data/rss.xml", &rsscache, &cfg, nullptr);
+		std::shared_ptr<RssFeed> feed = parser.parse();
+		rsscache.externalize_rssfeed(feed, false);
+	}
+
+	{
+		Cache rsscache(dbfile.get_path(), &cfg, true);
+		std::shared_ptr<RssFeed> feed =
+			rsscache.internalize_rssfeed("file://data/rss.xml", nullptr);
+		REQUIRE(feed->total_item_count() == 8);
+
+		rsscache.mark_all_read();
+		rsscache.mark_item_deleted(feed->items()[0]->guid(), true);
+		REQUIRE(rsscache.cleanup_cache({}, true) == 0);
+	}
+
+	Cache rsscache(dbfile.get_path(), &cfg);
+	std::shared_ptr<RssFeed> feed =
+		rsscache.internalize_rssfeed("file://data/rss.xml", nullptr);
+	REQUIRE(feed->total_item_count() == 8);
+	for (const auto& item : feed->items()) {
+		REQUIRE(item->unread());
+	}
+}
+
+TEST_CASE("Read-only cache can't be opened if the DB doesn't exist", "[Cache]")
+{
+	TestHelpers::TempFile dbfile;
+	ConfigContainer cfg;
+
+	REQUIRE_THROWS_AS(Cache(dbfile.get_path(), &cfg, true), DbException);
+}
+
+TEST_CASE("Queries run by `newsboat query` work on caches created by Cache",
+	"[Cache]")
+{
+	// `newsboat query` reads the cache with its own SQL, which lives in
+	// rust/libnewsboat/src/cachequery/queries.rs. This checks that it agrees
+	// with the schema that Cache creates.
+	TestHelpers::TempFile dbfile;
+	TestHelpers::TempFile url_file;
+	ConfigContainer cfg;
+	const std::string feedurl = "file://data/rss.xml";
+	{
+		Cache rsscache(dbfile.get_path(), &cfg);
+		RssParser parser(feedurl, &rsscache, &cfg, nullptr);
+		std::shared_ptr<RssFeed> feed = parser.parse();
+		rsscache.externalize_rssfeed(feed, false);
+
+		rsscache.mark_items_read_by_guid({
+			"http://www.blogger.com/feeds/33750310/posts/full/115902176438316101"});
+		rsscache.mark_item_deleted(
+			"http://www.blogger.com/feeds/33750310/posts/full/115822000722667899",
+			true);
+	}
+	{
+		std::ofstream urls(url_file.get_path());
+		urls << feedurl << " photos" << std::endl;
+	}
+
+	const auto query = [&](TestHelpers::Opts opts) {
+		CliArgsParser args(opts.argc(), opts.argv());
+		const auto answer = args.run_query(dbfile.get_path(),
+				url_file.get_path());
+		REQUIRE(answer.has_value());
+		return answer.value();
+	};
+
+	REQUIRE(query({"newsboat", "query", "feeds", "--format", "json"}) ==
+		"[{\"rssurl\":\"file://data/rss.xml\",\"title\":\"AK's moblog\","
+		"\"unread\":6,\"total\":7}]\n");
+	REQUIRE(query({"newsboat", "query", "tags"}) == "6\t7\tphotos\n");
+
+	const auto items = query({"newsboat", "query", "items"});
+	REQUIRE(std::count(items.begin(), items.end(), '\n') == 6);
+	REQUIRE(items.find("Kurios") == std::string::npos);
+	REQUIRE(items.find("Botox") == std::string::npos);
+
+	// The newest article comes first, and its line starts with its ID
+	REQUIRE(items.find("Teh Saxxi") < items.find('\n'));
+	const auto id = items.substr(0, items.find('\t'));
+	const auto item = query({"newsboat", "query", "item", id, "--format", "json"});
+	REQUIRE(item.find("\"title\":\"Teh Saxxi\",\"author\":\"ak\",") !=
+		std::string::npos);
+	REQUIRE(item.find("\"feedtitle\":\"AK's moblog\"") != std::string::npos);
+	REQUIRE(item.find("\"unread\":true") != std::string::npos);
+}
@@ -1,12 +1,17 @@
 #include "3rd-party/catch.hpp"
 
 #include <cstring>
+#include <fstream>
 
+#include "cache.h"
 #include "cliargsparser.h"
+#include "configcontainer.h"
+#include "rssparser.h"
 #include "test-helpers/envvar.h"
 #include "test-helpers/opts.h"
 #include "test-helpers/stringmaker/optional.h"
 #include "test-helpers/tempdir.h"
+#include "test-helpers/tempfile.h"
 
 using namespace newsboat;
 
@@ -614,6 +619,24 @@ TEST_CASE("Sets `log_file` if -d/--log-file is provided",
 	}
 }
 
+TEST_CASE("Sets `trace_file` if --trace-file is provided", "[CliArgsParser]")
+{
+	SECTION("not provided") {
+		TestHelpers::Opts opts = {"newsboat"};
+		CliArgsParser args(opts.argc(), opts.argv());
+
+		REQUIRE_FALSE(args.trace_file().has_value());
+	}
+
+	SECTION("provided") {
+		const std::string filename("trace file.json");
+		TestHelpers::Opts opts = {"newsboat", "--trace-file=" + filename};
+		CliArgsParser args(opts.argc(), opts.argv());
+
+		REQUIRE(args.trace_file() == filename);
+	}
+}
+
 TEST_CASE("Resolves tilde to homedir in -d/--log-file", "[CliArgsParser]")
 {
 	TestHelpers::TempDir tmp;
@@ -699,6 +722,411 @@ TEST_CASE(
 	}
 }
 
+TEST_CASE("Sets `log_filter` if -l/--log-level is provided", "[CliArgsParser]")
+{
+	SECTION("Plain log level") {
+		TestHelpers::Opts opts = {"newsboat", "-l4"};
+		CliArgsParser args(opts.argc(), opts.argv());
+
+		REQUIRE(args.log_level() == Level::WARN);
+		REQUIRE(args.log_filter() == "4");
+	}
+
+	SECTION("Per-module log levels") {
+		TestHelpers::Opts opts = {"newsboat", "--log-level=info,libnewsboat::fslock=debug"};
+		CliArgsParser args(opts.argc(), opts.argv());
+
+		REQUIRE(args.log_level() == Level::INFO);
+		REQUIRE(args.log_filter() == "5,libnewsboat::fslock=6");
+	}
+
+	SECTION("Only per-module log levels") {
+		TestHelpers::Opts opts = {"newsboat", "--log-level=libnewsboat=3"};
+		CliArgsParser args(opts.argc(), opts.argv());
+
+		REQUIRE_FALSE(args.log_level().has_value());
+		REQUIRE(args.log_filter() == "libnewsboat=3");
+	}
+}
+
+TEST_CASE("Sets `log_format` if --log-format is provided", "[CliArgsParser]")
+{
+	auto check = [](TestHelpers::Opts opts, LogFormat expected) {
+		CliArgsParser args(opts.argc(), opts.argv());
+
+		REQUIRE(args.log_format() == expected);
+	};
+
+	SECTION("Not set by default") {
+		TestHelpers::Opts opts = {"newsboat"};
+		CliArgsParser args(opts.argc(), opts.argv());
+
+		REQUIRE_FALSE(args.log_format().has_value());
+	}
+
+	SECTION("--log-format=text") {
+		check({"newsboat", "--log-format=text"}, LogFormat::TEXT);
+	}
+
+	SECTION("--log-format json") {
+		check({"newsboat", "--log-format", "json"}, LogFormat::JSON);
+	}
+
+	SECTION("Unknown format is an error") {
+		TestHelpers::Opts opts = {"newsboat", "--log-format=xml"};
+		CliArgsParser args(opts.argc(), opts.argv());
+
+		REQUIRE_FALSE(args.log_format().has_value());
+		REQUIRE_FALSE(args.display_msg() == "");
+		REQUIRE(args.return_code() == EXIT_FAILURE);
+	}
+}
+
+TEST_CASE("Sets `log_rotation` if --log-max-size is provided",
+	"[CliArgsParser]")
+{
+	SECTION("Not set by default") {
+		TestHelpers::Opts opts = {"newsboat"};
+		CliArgsParser args(opts.argc(), opts.argv());
+
+		REQUIRE_FALSE(args.log_rotation().has_value());
+	}
+
+	SECTION("--log-max-size alone keeps 5 files") {
+		TestHelpers::Opts opts = {"newsboat", "--log-max-size=2"};
+		CliArgsParser args(opts.argc(), opts.argv());
+
+		REQUIRE(args.log_rotation().has_value());
+		REQUIRE(args.log_rotation()->max_size == 2 * 1024 * 1024);
+		REQUIRE(args.log_rotation()->max_files == 5);
+	}
+
+	SECTION("--log-max-size and --log-max-files") {
+		TestHelpers::Opts opts = {"newsboat", "--log-max-size=1", "--log-max-files=3"};
+		CliArgsParser args(opts.argc(), opts.argv());
+
+		REQUIRE(args.log_rotation().has_value());
+		REQUIRE(args.log_rotation()->max_size == 1024 * 1024);
+		REQUIRE(args.log_rotation()->max_files == 3);
+	}
+
+	SECTION("Invalid size is an error") {
+		TestHelpers::Opts opts = {"newsboat", "--log-max-size=0"};
+		CliArgsParser args(opts.argc(), opts.argv());
+
+		REQUIRE_FALSE(args.log_rotation().has_value());
+		REQUIRE_FALSE(args.display_msg() == "");
+		REQUIRE(args.return_code() == EXIT_FAILURE);
+	}
+}
+
+TEST_CASE("Sets `keymap_dump_format` and requests silent mode if --dump-keymap "
+	"is provided",
+	"[CliArgsParser]")
+{
+	auto check = [](TestHelpers::Opts opts, KeymapDumpFormat expected) {
+		CliArgsParser args(opts.argc(), opts.argv());
+
+		REQUIRE(args.keymap_dump_format() == expected);
+		REQUIRE(args.silent());
+	};
+
+	SECTION("--dump-keymap=config") {
+		check({"newsboat", "--dump-keymap=config"}, KeymapDumpFormat::CONFIG);
+	}
+
+	SECTION("--dump-keymap json") {
+		check({"newsboat", "--dump-keymap", "json"}, KeymapDumpFormat::JSON);
+	}
+}
+
+TEST_CASE("Sets `wait_for_lock` if --wait-for-lock is provided",
+	"[CliArgsParser]")
+{
+	SECTION("Not set by default") {
+		TestHelpers::Opts opts = {"newsboat"};
+		CliArgsParser args(opts.argc(), opts.argv());
+
+		REQUIRE_FALSE(args.wait_for_lock().has_value());
+	}
+
+	SECTION("--wait-for-lock=30") {
+		TestHelpers::Opts opts = {"newsboat", "--wait-for-lock=30"};
+		CliArgsParser args(opts.argc(), opts.argv());
+
+		REQUIRE(args.wait_for_lock() == std::chrono::seconds(30));
+		REQUIRE_FALSE(args.return_code().has_value());
+	}
+
+	SECTION("--wait-for-lock=soon") {
+		TestHelpers::Opts opts = {"newsboat", "--wait-for-lock=soon"};
+		CliArgsParser args(opts.argc(), opts.argv());
+
+		REQUIRE_FALSE(args.wait_for_lock().has_value());
+		REQUIRE(args.return_code() == EXIT_FAILURE);
+		REQUIRE_FALSE(args.display_msg().empty());
+	}
+}
+
+TEST_CASE("Sets `profile` and `using_nonstandard_configs` if --profile is "
+	"provided",
+	"[CliArgsParser]")
+{
+	SECTION("Not set by default") {
+		TestHelpers::Opts opts = {"newsboat"};
+		CliArgsParser args(opts.argc(), opts.argv());
+
+		REQUIRE_FALSE(args.profile().has_value());
+	}
+
+	SECTION("--profile=work") {
+		TestHelpers::Opts opts = {"newsboat", "--profile=work"};
+		CliArgsParser args(opts.argc(), opts.argv());
+
+		REQUIRE(args.profile() == "work");
+		REQUIRE(args.using_nonstandard_configs());
+		REQUIRE_FALSE(args.return_code().has_value());
+	}
+
+	SECTION("--profile=../work") {
+		TestHelpers::Opts opts = {"newsboat", "--profile=../work"};
+		CliArgsParser args(opts.argc(), opts.argv());
+
+		REQUIRE_FALSE(args.profile().has_value());
+		REQUIRE(args.return_code() == EXIT_FAILURE);
+		REQUIRE_FALSE(args.display_msg().empty());
+	}
+}
+
+TEST_CASE("Sets `list_profiles` if --list-profiles is provided",
+	"[CliArgsParser]")
+{
+	TestHelpers::Opts opts = {"newsboat", "--list-profiles"};
+	CliArgsParser args(opts.argc(), opts.argv());
+
+	REQUIRE(args.list_profiles());
+}
+
+TEST_CASE("Sets `last_crash_report` if --last-crash-report is provided",
+	"[CliArgsParser]")
+{
+	TestHelpers::Opts opts = {"newsboat", "--last-crash-report"};
+	CliArgsParser args(opts.argc(), opts.argv());
+
+	REQUIRE(args.last_crash_report());
+}
+
+TEST_CASE("Generates a completion script if --completions is provided",
+	"[CliArgsParser]")
+{
+	SECTION("No option") {
+		TestHelpers::Opts opts = {"newsboat"};
+		CliArgsParser args(opts.argc(), opts.argv());
+
+		REQUIRE_FALSE(args.completions_script().has_value());
+	}
+
+	SECTION("--completions zsh") {
+		TestHelpers::Opts opts = {"newsboat", "--completions", "zsh"};
+		CliArgsParser args(opts.argc(), opts.argv());
+
+		REQUIRE(args.completions_script().has_value());
+		const auto script = args.completions_script().value();
+		REQUIRE(script.find("#compdef newsboat") == 0);
+		REQUIRE(script.find("'--url-file=[]: :_files'") != std::string::npos);
+		REQUIRE_FALSE(args.return_code().has_value());
+	}
+
+	SECTION("--completions csh") {
+		TestHelpers::Opts opts = {"newsboat", "--completions", "csh"};
+		CliArgsParser args(opts.argc(), opts.argv());
+
+		REQUIRE_FALSE(args.completions_script().has_value());
+		REQUIRE_FALSE(args.display_msg().empty());
+		REQUIRE(args.return_code() == EXIT_FAILURE);
+	}
+}
+
+TEST_CASE("Answers queries about the cache if `query` subcommand is provided",
+	"[CliArgsParser]")
+{
+	ConfigContainer cfg;
+	TestHelpers::TempFile cache_file;
+	TestHelpers::TempFile url_file;
+	const std::string feedurl = "file://data/rss.xml";
+	{
+		Cache rsscache(cache_file.get_path(), &cfg);
+		RssParser parser(feedurl, &rsscache, &cfg, nullptr);
+		rsscache.externalize_rssfeed(parser.parse(), false);
+	}
+	{
+		std::ofstream urls(url_file.get_path());
+		urls << feedurl << " photos" << std::endl;
+	}
+
+	SECTION("No subcommand") {
+		TestHelpers::Opts opts = {"newsboat"};
+		CliArgsParser args(opts.argc(), opts.argv());
+
+		REQUIRE_FALSE(args.has_query());
+	}
+
+	SECTION("query feeds --format json") {
+		TestHelpers::Opts opts = {"newsboat", "query", "feeds", "--format", "json"};
+		CliArgsParser args(opts.argc(), opts.argv());
+
+		REQUIRE(args.has_query());
+		const auto answer = args.run_query(cache_file.get_path(),
+				url_file.get_path());
+		REQUIRE(answer.has_value());
+		REQUIRE(answer.value() ==
+			"[{\"rssurl\":\"file://data/rss.xml\",\"title\":\"AK's moblog\","
+			"\"unread\":8,\"total\":8}]\n");
+	}
+
+	SECTION("query tags") {
+		TestHelpers::Opts opts = {"newsboat", "query", "tags"};
+		CliArgsParser args(opts.argc(), opts.argv());
+
+		const auto answer = args.run_query(cache_file.get_path(),
+				url_file.get_path());
+		REQUIRE(answer.has_value());
+		REQUIRE(answer.value() == "8\t8\tphotos\n");
+	}
+
+	SECTION("query items --filter") {
+		TestHelpers::Opts opts = {"newsboat", "query", "items", "--filter", "title =~ \"Maroni\""};
+		CliArgsParser args(opts.argc(), opts.argv());
+
+		const auto answer = args.run_query(cache_file.get_path(),
+				url_file.get_path());
+		REQUIRE(answer.has_value());
+		REQUIRE(answer.value().find("Maronistand im Spaetsommer") !=
+			std::string::npos);
+		REQUIRE(answer.value().find("Kurios") == std::string::npos);
+	}
+
+	SECTION("query item with an unknown ID") {
+		TestHelpers::Opts opts = {"newsboat", "query", "item", "1000"};
+		CliArgsParser args(opts.argc(), opts.argv());
+
+		const auto answer = args.run_query(cache_file.get_path(),
+				url_file.get_path());
+		REQUIRE_FALSE(answer.has_value());
+		REQUIRE(answer.error().find("1000") != std::string::npos);
+	}
+}
+
+TEST_CASE("Subcommands map onto the same settings as the flags",
+	"[CliArgsParser]")
+{
+	SECTION("reload") {
+		TestHelpers::Opts opts = {"newsboat", "reload"};
+		CliArgsParser args(opts.argc(), opts.argv());
+
+		REQUIRE(args.cmds_to_execute() == std::vector<std::string>({"reload"}));
+		REQUIRE(args.silent());
+	}
+
+	SECTION("export opml") {
+		TestHelpers::Opts opts = {"newsboat", "export", "opml"};
+		CliArgsParser args(opts.argc(), opts.argv());
+
+		REQUIRE(args.do_export());
+	}
+
+	SECTION("import opml") {
+		TestHelpers::Opts opts = {"newsboat", "import", "opml", "feeds.opml"};
+		CliArgsParser args(opts.argc(), opts.argv());
+
+		REQUIRE(args.do_import());
+		REQUIRE(args.importfile() == "feeds.opml");
+	}
+
+	SECTION("readinfo export") {
+		TestHelpers::Opts opts = {"newsboat", "readinfo", "export", "read.txt"};
+		CliArgsParser args(opts.argc(), opts.argv());
+
+		REQUIRE(args.readinfo_export_file() == "read.txt");
+	}
+
+	SECTION("cache vacuum") {
+		TestHelpers::Opts opts = {"newsboat", "cache", "vacuum"};
+		CliArgsParser args(opts.argc(), opts.argv());
+
+		REQUIRE(args.do_vacuum());
+	}
+
+	SECTION("cache cleanup") {
+		TestHelpers::Opts opts = {"newsboat", "cache", "cleanup"};
+		CliArgsParser args(opts.argc(), opts.argv());
+
+		REQUIRE(args.do_cleanup());
+	}
+}
+
+TEST_CASE("Sets `migration_dry_run` if --migration-dry-run is provided",
+	"[CliArgsParser]")
+{
+	TestHelpers::Opts opts = {"newsboat", "--migration-dry-run"};
+	CliArgsParser args(opts.argc(), opts.argv());
+
+	REQUIRE(args.migration_dry_run());
+}
+
+TEST_CASE("Sets `check_config` if --check-config is provided",
+	"[CliArgsParser]")
+{
+	SECTION("Not set by default") {
+		TestHelpers::Opts opts = {"newsboat"};
+		CliArgsParser args(opts.argc(), opts.argv());
+
+		REQUIRE_FALSE(args.check_config());
+	}
+
+	SECTION("--check-config") {
+		TestHelpers::Opts opts = {"newsboat", "--check-config"};
+		CliArgsParser args(opts.argc(), opts.argv());
+
+		REQUIRE(args.check_config());
+	}
+}
+
+TEST_CASE("Sets `read_only` if --read-only is provided", "[CliArgsParser]")
+{
+	SECTION("Not set by default") {
+		TestHelpers::Opts opts = {"newsboat"};
+		CliArgsParser args(opts.argc(), opts.argv());
+
+		REQUIRE_FALSE(args.read_only());
+	}
+
+	SECTION("--read-only") {
+		TestHelpers::Opts opts = {"newsboat", "--read-only"};
+		CliArgsParser args(opts.argc(), opts.argv());
+
+		REQUIRE(args.read_only());
+	}
+}
+
+TEST_CASE("Sets `take_over_lock` if --take-over-lock is provided",
+	"[CliArgsParser]")
+{
+	SECTION("Not set by default") {
+		TestHelpers::Opts opts = {"newsboat"};
+		CliArgsParser args(opts.argc(), opts.argv());
+
+		REQUIRE_FALSE(args.take_over_lock());
+	}
+
+	SECTION("--take-over-lock") {
+		TestHelpers::Opts opts = {"newsboat", "--take-over-lock"};
+		CliArgsParser args(opts.argc(), opts.argv());
+
+		REQUIRE(args.take_over_lock());
+	}
+}
+
 TEST_CASE("Sets `program_name` to the first string of the options list",
 	"[CliArgsParser]")
 {
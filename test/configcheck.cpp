@@ -0,0 +1,93 @@
+#include "configcheck.h"
+
+#include <fstream>
+
+#include "3rd-party/catch.hpp"
+#include "configpaths.h"
+#include "controller.h"
+#include "test-helpers/tempfile.h"
+
+using namespace newsboat;
+
+namespace {
+
+std::vector<std::string> check(const std::string& config)
+{
+	TestHelpers::TempFile config_file;
+	{
+		std::ofstream out(config_file.get_path());
+		out << config;
+	}
+
+	ConfigPaths paths;
+	Controller c(paths);
+	ConfigChecker checker;
+	c.register_config_checks(checker);
+
+	const auto problems = checker.check(config_file.get_path());
+	REQUIRE(problems.has_value());
+	return problems.value();
+}
+
+} // namespace
+
+TEST_CASE("check() accepts settings, operations and contexts known to "
+	"ConfigContainer and KeyMap",
+	"[ConfigChecker]")
+{
+	const auto problems = check(
+			"auto-reload yes\n"
+			"reload-time 30\n"
+			"proxy-type socks5\n"
+			"articlelist-format \"%4i %f %D %?T?|%-17T| ?%t\"\n"
+			"bind-key ^R reload-all feedlist\n"
+			"macro v set browser \"mpv %u\" ; open-in-browser\n");
+	REQUIRE(problems.empty());
+}
+
+TEST_CASE("check() accepts the commands and color elements that Controller "
+	"registers",
+	"[ConfigChecker]")
+{
+	const auto problems = check(
+			"color listnormal_unread red default bold\n"
+			"color end-of-text-marker blue default\n"
+			"highlight all \"foo\" red\n"
+			"highlight-article \"title = \\\"x\\\"\" red default\n"
+			"ignore-article \"*\" \"title = \\\"x\\\"\"\n"
+			"always-download https://example.com/feed.xml\n"
+			"reset-unread-on-update https://example.com/feed.xml\n"
+			"define-filter \"Unread\" \"unread = \\\"yes\\\"\"\n"
+			"unbind-key ^R\n"
+			"run-on-startup reload-all\n"
+			"color no-such-element red default\n");
+	REQUIRE(problems.size() == 1);
+	REQUIRE(problems[0].find("line 11: ") != std::string::npos);
+}
+
+TEST_CASE("check() reports every problem along with its line",
+	"[ConfigChecker]")
+{
+	const auto problems = check(
+			"auto-reload maybe\n"
+			"reload-time 30\n"
+			"proxy-type carrier-pigeon\n"
+			"bind-key ^R reload-everything\n"
+			"articlelist-format \"%4i %\"\n"
+			"no-such-setting yes\n");
+	REQUIRE(problems.size() == 5);
+	REQUIRE(problems[0].find("line 1: ") != std::string::npos);
+	REQUIRE(problems[1].find("line 3: ") != std::string::npos);
+	REQUIRE(problems[2].find("line 4: ") != std::string::npos);
+	REQUIRE(problems[3].find("line 5: ") != std::string::npos);
+	REQUIRE(problems[4].find("line 6: ") != std::string::npos);
+}
+
+TEST_CASE("check() returns an error if the config file doesn't exist",
+	"[ConfigChecker]")
+{
+	TestHelpers::TempFile config_file;
+
+	ConfigChecker checker;
+	REQUIRE_FALSE(checker.check(config_file.get_path()).has_value());
+}
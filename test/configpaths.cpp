@@ -36,10 +36,19 @@ TEST_CASE("ConfigPaths returns paths to Newsboat dotdir if no Newsboat dirs "
 	REQUIRE(paths.url_file() == newsboat_dir + "/urls");
 	REQUIRE(paths.cache_file() == newsboat_dir + "/cache.db");
 	REQUIRE(paths.lock_file() == newsboat_dir + "/cache.db.lock");
+	REQUIRE(paths.control_socket_file() == newsboat_dir + "/cache.db.sock");
 	REQUIRE(paths.config_file() == newsboat_dir + "/config");
 	REQUIRE(paths.queue_file() == newsboat_dir + "/queue");
 	REQUIRE(paths.search_file() == newsboat_dir + "/history.search");
 	REQUIRE(paths.cmdline_file() == newsboat_dir + "/history.cmdline");
+	REQUIRE(paths.article_search_file() ==
+		newsboat_dir + "/history.search.articles");
+	REQUIRE(paths.feed_search_file() ==
+		newsboat_dir + "/history.search.feeds");
+	REQUIRE(paths.filter_file() == newsboat_dir + "/history.filter");
+	REQUIRE(paths.imported_read_guids_file() ==
+		newsboat_dir + "/imported-read-guids");
+	REQUIRE(paths.crash_reports_dir() == newsboat_dir + "/crash-reports");
 }
 
 TEST_CASE("ConfigPaths returns paths to Newsboat XDG dirs if they exist and "
@@ -69,9 +78,18 @@ TEST_CASE("ConfigPaths returns paths to Newsboat XDG dirs if they exist and "
 		REQUIRE(paths.url_file() == config_dir + "/urls");
 		REQUIRE(paths.cache_file() == data_dir + "/cache.db");
 		REQUIRE(paths.lock_file() == data_dir + "/cache.db.lock");
+		REQUIRE(paths.control_socket_file() == data_dir + "/cache.db.sock");
 		REQUIRE(paths.queue_file() == data_dir + "/queue");
 		REQUIRE(paths.search_file() == data_dir + "/history.search");
 		REQUIRE(paths.cmdline_file() == data_dir + "/history.cmdline");
+		REQUIRE(paths.article_search_file() ==
+			data_dir + "/history.search.articles");
+		REQUIRE(paths.feed_search_file() ==
+			data_dir + "/history.search.feeds");
+		REQUIRE(paths.filter_file() == data_dir + "/history.filter");
+		REQUIRE(paths.imported_read_guids_file() ==
+			data_dir + "/imported-read-guids");
+		REQUIRE(paths.crash_reports_dir() == data_dir + "/crash-reports");
 	};
 
 	SECTION("XDG_CONFIG_HOME is set") {
@@ -139,9 +157,113 @@ TEST_CASE("ConfigPaths::process_args replaces paths with the ones supplied by "
 	REQUIRE(paths.url_file() == url_file);
 	REQUIRE(paths.cache_file() == cache_file);
 	REQUIRE(paths.lock_file() == lock_file);
+	REQUIRE(paths.control_socket_file() == cache_file + ".sock");
 	REQUIRE(paths.config_file() == config_file);
 }
 
+TEST_CASE("ConfigPaths::process_args puts paths into the subdirectory of the "
+	"profile chosen via CliArgsParser",
+	"[ConfigPaths]")
+{
+	TestHelpers::TempDir tmp;
+	const auto newsboat_dir = tmp.get_path() + ".newsboat";
+	REQUIRE(0 == utils::mkdir_parents(newsboat_dir, 0700));
+
+	TestHelpers::EnvVar home("HOME");
+	home.set(tmp.get_path());
+
+	// ConfigPaths rely on these variables, so let's sanitize them to ensure
+	// that the tests aren't affected
+	TestHelpers::EnvVar xdg_config("XDG_CONFIG_HOME");
+	xdg_config.unset();
+	TestHelpers::EnvVar xdg_data("XDG_DATA_HOME");
+	xdg_data.unset();
+
+	ConfigPaths paths;
+	REQUIRE(paths.initialized());
+	REQUIRE(paths.profile().empty());
+	REQUIRE(paths.list_profiles().empty());
+
+	TestHelpers::Opts opts({"newsboat", "--profile=work"});
+	CliArgsParser parser(opts.argc(), opts.argv());
+	paths.process_args(parser);
+
+	const auto profile_dir = newsboat_dir + "/profiles/work";
+	REQUIRE(paths.profile() == "work");
+	REQUIRE(paths.url_file() == profile_dir + "/urls");
+	REQUIRE(paths.config_file() == profile_dir + "/config");
+	REQUIRE(paths.cache_file() == profile_dir + "/cache.db");
+	REQUIRE(paths.lock_file() == profile_dir + "/cache.db.lock");
+	// The queue is shared with Podboat, which doesn't know about profiles
+	REQUIRE(paths.queue_file() == newsboat_dir + "/queue");
+	REQUIRE(paths.cmdline_file() == profile_dir + "/history.cmdline");
+
+	REQUIRE(paths.create_dirs());
+	REQUIRE(0 == utils::mkdir_parents(newsboat_dir + "/profiles/personal", 0700));
+	REQUIRE(paths.list_profiles() == std::vector<std::string>({"personal", "work"}));
+}
+
+TEST_CASE("ConfigPaths returns paths from NEWSBOAT_* environment variables",
+	"[ConfigPaths]")
+{
+	TestHelpers::TempDir tmp;
+	const auto config_dir = tmp.get_path() + "config";
+	const auto data_dir = tmp.get_path() + "data";
+
+	TestHelpers::EnvVar home("HOME");
+	home.set(tmp.get_path());
+
+	// ConfigPaths rely on these variables, so let's sanitize them to ensure
+	// that the tests aren't affected
+	TestHelpers::EnvVar xdg_config("XDG_CONFIG_HOME");
+	xdg_config.unset();
+	TestHelpers::EnvVar xdg_data("XDG_DATA_HOME");
+	xdg_data.unset();
+	TestHelpers::EnvVar url_file("NEWSBOAT_URL_FILE");
+	url_file.unset();
+	TestHelpers::EnvVar config_file("NEWSBOAT_CONFIG_FILE");
+	config_file.unset();
+	TestHelpers::EnvVar queue_file("NEWSBOAT_QUEUE_FILE");
+	queue_file.unset();
+
+	TestHelpers::EnvVar newsboat_config_dir("NEWSBOAT_CONFIG_DIR");
+	newsboat_config_dir.set(config_dir);
+	TestHelpers::EnvVar newsboat_data_dir("NEWSBOAT_DATA_DIR");
+	newsboat_data_dir.set(data_dir);
+
+	SECTION("Directories are taken from the environment") {
+		TestHelpers::EnvVar cache_file("NEWSBOAT_CACHE_FILE");
+		cache_file.unset();
+
+		ConfigPaths paths;
+		REQUIRE(paths.initialized());
+		REQUIRE(paths.url_file() == config_dir + "/urls");
+		REQUIRE(paths.config_file() == config_dir + "/config");
+		REQUIRE(paths.cache_file() == data_dir + "/cache.db");
+		REQUIRE(paths.queue_file() == data_dir + "/queue");
+		REQUIRE_FALSE(paths.has_explicit_cache_file());
+	}
+
+	SECTION("Files can be overridden individually") {
+		const auto cache = tmp.get_path() + "elsewhere/cache.db";
+		TestHelpers::EnvVar cache_file("NEWSBOAT_CACHE_FILE");
+		cache_file.set(cache);
+
+		ConfigPaths paths;
+		REQUIRE(paths.initialized());
+		REQUIRE(paths.cache_file() == cache);
+		REQUIRE(paths.lock_file() == cache + ".lock");
+		REQUIRE(paths.has_explicit_cache_file());
+
+		SECTION("Command-line options take precedence") {
+			TestHelpers::Opts opts({"newsboat", "-c", "/tmp/other.db"});
+			CliArgsParser args(opts.argc(), opts.argv());
+			paths.process_args(args);
+			REQUIRE(paths.cache_file() == "/tmp/other.db");
+		}
+	}
+}
+
 TEST_CASE("ConfigPaths::set_cache_file changes paths to cache and lock files",
 	"[ConfigPaths]")
 {
@@ -163,11 +285,13 @@ TEST_CASE("ConfigPaths::set_cache_file changes paths to cache and lock files",
 
 	REQUIRE(paths.cache_file() == newsboat_dir + "cache.db");
 	REQUIRE(paths.lock_file() == newsboat_dir + "cache.db.lock");
+	REQUIRE(paths.control_socket_file() == newsboat_dir + "cache.db.sock");
 
 	const auto new_cache = std::string("something/entirely different.sqlite3");
 	paths.set_cache_file(new_cache);
 	REQUIRE(paths.cache_file() == new_cache);
 	REQUIRE(paths.lock_file() == new_cache + ".lock");
+	REQUIRE(paths.control_socket_file() == new_cache + ".sock");
 }
 
 TEST_CASE("ConfigPaths::create_dirs() returns true if both config and data dirs "
@@ -197,6 +321,12 @@ TEST_CASE("ConfigPaths::create_dirs() returns true if both config and data dirs
 		REQUIRE(TestHelpers::starts_with(paths.queue_file(), tmp.get_path()));
 		REQUIRE(TestHelpers::starts_with(paths.search_file(), tmp.get_path()));
 		REQUIRE(TestHelpers::starts_with(paths.cmdline_file(), tmp.get_path()));
+		REQUIRE(TestHelpers::starts_with(paths.article_search_file(),
+				tmp.get_path()));
+		REQUIRE(TestHelpers::starts_with(paths.feed_search_file(),
+				tmp.get_path()));
+		REQUIRE(TestHelpers::starts_with(paths.filter_file(),
+				tmp.get_path()));
 
 		REQUIRE(paths.create_dirs());
 
@@ -762,6 +892,39 @@ TEST_CASE("try_migrate_from_newsbeuter() migrates Newsbeuter dotdir from "
 		sentries.cmdline);
 }
 
+TEST_CASE("describe_migration_plan() describes migration of Newsbeuter dotdir "
+	"without touching any files",
+	"[ConfigPaths]")
+{
+	TestHelpers::TempDir tmp;
+
+	TestHelpers::EnvVar home("HOME");
+	home.set(tmp.get_path());
+	INFO("Temporary directory (used as HOME): " << tmp.get_path());
+
+	// ConfigPaths rely on these variables, so let's sanitize them to ensure
+	// that the tests aren't affected
+	TestHelpers::EnvVar xdg_config("XDG_CONFIG_HOME");
+	xdg_config.unset();
+	TestHelpers::EnvVar xdg_data("XDG_DATA_HOME");
+	xdg_data.unset();
+
+	FileSentries sentries;
+	mock_newsbeuter_dotdir(tmp, sentries);
+
+	ConfigPaths paths;
+	REQUIRE(paths.initialized());
+
+	const auto description = paths.describe_migration_plan();
+	REQUIRE(description.find(
+			"Would migrate configs and data from ~/.newsbeuter/:\n") == 0);
+	REQUIRE(description.find(
+			"copy `" + tmp.get_path() + ".newsbeuter/urls' to `" +
+			tmp.get_path() + ".newsboat/urls'") != std::string::npos);
+
+	REQUIRE_FALSE(0 == access((tmp.get_path() + ".newsboat").c_str(), F_OK));
+}
+
 TEST_CASE("try_migrate_from_newsbeuter() migrates Newsbeuter XDG dirs from "
 	"their default location to default locations of Newsboat XDG dirs",
 	"[ConfigPaths]")
@@ -0,0 +1,70 @@
+#include "controlsocket.h"
+
+#include <thread>
+
+#include "3rd-party/catch.hpp"
+#include "test-helpers/tempdir.h"
+
+using namespace newsboat;
+
+TEST_CASE("Commands forwarded by a client are received by ControlServer",
+	"[ControlServer]")
+{
+	const TestHelpers::TempDir tmp;
+	const std::string socket_path = tmp.get_path() + "cache.db.sock";
+
+	ControlServer server(socket_path);
+	REQUIRE(server.is_listening());
+
+	std::vector<ControlRequest> received;
+	std::thread server_thread([&]() {
+		for (int i = 0; i < 2; ++i) {
+			const auto request = server.next_request(std::chrono::seconds(30));
+			if (!request.has_value()) {
+				return;
+			}
+			received.push_back(request.value());
+			if (request->type == ControlRequestType::STATUS) {
+				server.respond(true, "5 unread articles");
+			} else {
+				server.respond(true);
+			}
+		}
+	});
+
+	std::string error;
+	const auto messages = controlsocket::forward_commands(socket_path,
+	{"reload", "print-unread"}, error);
+	server_thread.join();
+
+	REQUIRE(error.empty());
+	REQUIRE(messages == std::vector<std::string>({"", "5 unread articles"}));
+
+	REQUIRE(received.size() == 2);
+	REQUIRE(received[0].type == ControlRequestType::RELOAD);
+	REQUIRE(received[0].url.empty());
+	REQUIRE(received[1].type == ControlRequestType::STATUS);
+}
+
+TEST_CASE("controlsocket::forward_commands() sets error message if nobody "
+	"listens on the socket",
+	"[ControlServer]")
+{
+	const TestHelpers::TempDir tmp;
+
+	std::string error;
+	const auto messages = controlsocket::forward_commands(
+			tmp.get_path() + "cache.db.sock", {"reload"}, error);
+
+	REQUIRE(messages.empty());
+	REQUIRE_FALSE(error.empty());
+}
+
+TEST_CASE("ControlServer reports an error if it can't listen", "[ControlServer]")
+{
+	const TestHelpers::TempDir tmp;
+
+	ControlServer server(tmp.get_path() + "does-not-exist/cache.db.sock");
+	REQUIRE_FALSE(server.is_listening());
+	REQUIRE_FALSE(server.error_message().empty());
+}
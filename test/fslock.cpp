@@ -160,3 +160,18 @@ TEST_CASE("try_lock() succeeds if lock file location is valid and not locked by
 	}
 }
 
+
+TEST_CASE("try_lock_shared() succeeds while a different process holds the exclusive lock",
+	"[FsLock]")
+{
+	const TestHelpers::TempFile lock_location;
+	LockProcess lock_process(lock_location.get_path());
+
+	FsLock lock;
+	std::string error_message;
+	REQUIRE(lock.try_lock_shared(lock_location.get_path(), error_message));
+	REQUIRE(error_message.empty());
+
+	// The readers lock file outlives the lock, so clean it up by hand
+	::unlink((lock_location.get_path() + ".readers").c_str());
+}
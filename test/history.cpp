@@ -1,5 +1,6 @@
 #include "history.h"
 
+#include <fstream>
 #include <unistd.h>
 
 #include "3rd-party/catch.hpp"
@@ -96,3 +97,43 @@ TEST_CASE("Only the most recent lines are saved when limiting history",
 		}
 	}
 }
+
+TEST_CASE("set_timestamps() controls whether timestamps are saved", "[History]")
+{
+	TestHelpers::TempDir tmp;
+	const auto filepath = tmp.get_path() + "history.cmdline";
+
+	History h;
+	h.add_line("#123");
+
+	SECTION("Timestamps are not saved by default") {
+		h.save_to_file(filepath, 10);
+
+		std::ifstream file(filepath);
+		std::string line;
+		REQUIRE(std::getline(file, line));
+		REQUIRE(line == "#123");
+		REQUIRE_FALSE(std::getline(file, line));
+	}
+
+	SECTION("Timestamps are saved if enabled") {
+		h.set_timestamps(true);
+		h.save_to_file(filepath, 10);
+
+		std::ifstream file(filepath);
+		std::string line;
+		REQUIRE(std::getline(file, line));
+		REQUIRE(line == "#newsboat-history-timestamps");
+	}
+
+	SECTION("Entries that look like timestamps are loaded as they were") {
+		h.set_timestamps(true);
+		h.save_to_file(filepath, 10);
+
+		History loaded_h;
+		loaded_h.load_from_file(filepath);
+		REQUIRE(loaded_h.previous_line() == "#123");
+		REQUIRE(loaded_h.next_line() == "#123");
+		REQUIRE(loaded_h.next_line() == "");
+	}
+}
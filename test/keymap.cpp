@@ -488,6 +488,36 @@ TEST_CASE("dump_config() returns a line for each keybind and macro", "[KeyMap]")
 	}
 }
 
+TEST_CASE("dump_effective_bindings() serializes bindings and macros in the given format",
+	"[KeyMap]")
+{
+	KeyMap k(KM_NEWSBOAT);
+	k.unset_all_keys("all");
+
+	SECTION("no bindings and no macros") {
+		REQUIRE(k.dump_effective_bindings(KeymapDumpFormat::CONFIG) == "");
+		REQUIRE(k.dump_effective_bindings(KeymapDumpFormat::JSON) ==
+			"{\"bindings\":{},\"macros\":{}}\n");
+	}
+
+	SECTION("a few bindings and macros") {
+		k.set_key(OP_OPEN, "ENTER", "feedlist");
+		k.set_key(OP_NEXT, "j", "articlelist");
+		k.unset_key("j", "articlelist");
+		k.set_key(OP_PREV, "k", "articlelist");
+		k.handle_action("macro", R"(o set browser "firefox --new-tab" ; open-in-browser)");
+
+		REQUIRE(k.dump_effective_bindings(KeymapDumpFormat::CONFIG) ==
+			"bind-key k prev articlelist\n"
+			"bind-key ENTER open feedlist\n"
+			"macro o set browser \"firefox --new-tab\" ; open-in-browser\n");
+		REQUIRE(k.dump_effective_bindings(KeymapDumpFormat::JSON) ==
+			R"({"bindings":{"articlelist":{"k":"prev"},"feedlist":{"ENTER":"open"}},)"
+			R"("macros":{"o":[["set","browser","firefox --new-tab"],["open-in-browser"]]}})"
+			"\n");
+	}
+}
+
 TEST_CASE("Regression test for https://github.com/newsboat/newsboat/issues/702",
 	"[KeyMap]")
 {
@@ -611,3 +641,146 @@ TEST_CASE("Semicolons in operation's arguments don't break parsing of a macro",
 	REQUIRE(macro[1].op == OP_OPENINBROWSER);
 	REQUIRE(macro[1].args == std::vector<std::string>({}));
 }
+
+TEST_CASE("Macros with placeholders are expanded when they're run", "[KeyMap]")
+{
+	KeyMap k(KM_NEWSBOAT);
+
+	k.handle_action("macro",
+		R"(o set browser "${1:-lynx} ${2:+--profile $2}" ; open-in-browser)");
+
+	REQUIRE(k.macro_takes_arguments("o"));
+	REQUIRE(k.get_macro_variable_names("o").empty());
+	REQUIRE(k.get_macro("o").empty());
+
+	SECTION("with no arguments, defaults are used") {
+		const auto macro = k.expand_macro("o", {}, {});
+		REQUIRE(macro);
+		REQUIRE(macro->size() == 2);
+		REQUIRE(macro->at(0).op == OP_INT_SET);
+		REQUIRE(macro->at(0).args == std::vector<std::string>({"browser", "lynx "}));
+		REQUIRE(macro->at(1).op == OP_OPENINBROWSER);
+		REQUIRE(macro->at(1).args == std::vector<std::string>({}));
+	}
+
+	SECTION("arguments replace placeholders") {
+		const auto macro = k.expand_macro("o", {"firefox", "work"}, {});
+		REQUIRE(macro);
+		REQUIRE(macro->size() == 2);
+		REQUIRE(macro->at(0).op == OP_INT_SET);
+		REQUIRE(macro->at(0).args == std::vector<std::string>({"browser", "firefox --profile work"}));
+	}
+}
+
+TEST_CASE("Operations of a macro can be made conditional on its arguments",
+	"[KeyMap]")
+{
+	KeyMap k(KM_NEWSBOAT);
+
+	k.handle_action("macro", "o ${1:+set} browser $1 ; open-in-browser");
+
+	SECTION("operation whose name expands to nothing is skipped") {
+		const auto macro = k.expand_macro("o", {}, {});
+		REQUIRE(macro);
+		REQUIRE(macro->size() == 1);
+		REQUIRE(macro->at(0).op == OP_OPENINBROWSER);
+	}
+
+	SECTION("empty argument counts as not given") {
+		const auto macro = k.expand_macro("o", {""}, {});
+		REQUIRE(macro);
+		REQUIRE(macro->size() == 1);
+		REQUIRE(macro->at(0).op == OP_OPENINBROWSER);
+	}
+
+	SECTION("operation is run if the argument is given") {
+		const auto macro = k.expand_macro("o", {"w3m"}, {});
+		REQUIRE(macro);
+		REQUIRE(macro->size() == 2);
+		REQUIRE(macro->at(0).op == OP_INT_SET);
+		REQUIRE(macro->at(0).args == std::vector<std::string>({"browser", "w3m"}));
+		REQUIRE(macro->at(1).op == OP_OPENINBROWSER);
+	}
+}
+
+TEST_CASE("Named placeholders in macros are filled from variables", "[KeyMap]")
+{
+	KeyMap k(KM_NEWSBOAT);
+
+	k.handle_action("macro",
+		R"(o set browser lynx ; open-in-browser ; set browser "${browser}")");
+
+	REQUIRE_FALSE(k.macro_takes_arguments("o"));
+	REQUIRE(k.get_macro_variable_names("o") == std::vector<std::string>({"browser"}));
+
+	const auto macro = k.expand_macro("o", {}, {{"browser", "firefox %u"}});
+	REQUIRE(macro);
+	REQUIRE(macro->size() == 3);
+	REQUIRE(macro->at(2).op == OP_INT_SET);
+	REQUIRE(macro->at(2).args == std::vector<std::string>({"browser", "firefox %u"}));
+}
+
+TEST_CASE("expand_macro() returns an error if the macro can't be expanded",
+	"[KeyMap]")
+{
+	KeyMap k(KM_NEWSBOAT);
+
+	SECTION("missing argument") {
+		k.handle_action("macro", "o set browser $1");
+		const auto macro = k.expand_macro("o", {}, {});
+		REQUIRE_FALSE(macro);
+		REQUIRE(macro.error().find("$1") != std::string::npos);
+	}
+
+	SECTION("operation name that expands to an invalid operation") {
+		k.handle_action("macro", "o ${1:-open}");
+		REQUIRE(k.expand_macro("o", {}, {}));
+		const auto macro = k.expand_macro("o", {"fly-away"}, {});
+		REQUIRE_FALSE(macro);
+		REQUIRE(macro.error().find("fly-away") != std::string::npos);
+	}
+}
+
+TEST_CASE("Macros with placeholders are checked when they're defined", "[KeyMap]")
+{
+	KeyMap k(KM_NEWSBOAT);
+
+	REQUIRE_THROWS_AS(k.handle_action("macro", "o set browser $1 ; fly-away"),
+		ConfigHandlerException);
+	REQUIRE_NOTHROW(k.handle_action("macro", "o set browser $1 ; ${2:-open}"));
+}
+
+TEST_CASE("Redefining a macro replaces it whether or not it has placeholders",
+	"[KeyMap]")
+{
+	KeyMap k(KM_NEWSBOAT);
+
+	k.handle_action("macro", "o set browser $1");
+	k.handle_action("macro", "o open");
+	REQUIRE_FALSE(k.macro_takes_arguments("o"));
+	REQUIRE(k.get_macro("o").size() == 1);
+
+	k.handle_action("macro", "o set browser $1");
+	REQUIRE(k.macro_takes_arguments("o"));
+	REQUIRE(k.get_macro("o").empty());
+}
+
+TEST_CASE("Macros with placeholders are dumped as they were written", "[KeyMap]")
+{
+	KeyMap k(KM_NEWSBOAT);
+	k.unset_all_keys("all");
+
+	k.handle_action("macro", R"(o set browser "${1:-lynx}" ; open-in-browser)");
+	k.handle_action("macro", "q quit");
+
+	std::vector<std::string> dumpOutput;
+	k.dump_config(dumpOutput);
+	REQUIRE(dumpOutput == std::vector<std::string>({
+		"macro q quit",
+		R"(macro o set browser "${1:-lynx}" ; open-in-browser)",
+	}));
+
+	REQUIRE(k.dump_effective_bindings(KeymapDumpFormat::CONFIG) ==
+		"macro o set browser \"${1:-lynx}\" ; open-in-browser\n"
+		"macro q quit\n");
+}
@@ -3,10 +3,12 @@
 #include "3rd-party/catch.hpp"
 
 #include <fstream>
+#include <iterator>
 
 #include "logger.h"
 
 #include "test-helpers/loggerresetter.h"
+#include "test-helpers/tempdir.h"
 #include "test-helpers/tempfile.h"
 
 using namespace newsboat;
@@ -81,3 +83,36 @@ TEST_CASE("stopover() adds an extra line to the log upon each call",
 
 	REQUIRE(file_lines_count(tmp.get_path()) == expected_line_count);
 }
+
+TEST_CASE("write_trace() saves recorded scopes to a file", "[ScopeMeasure]")
+{
+	TestHelpers::TempFile tmp;
+
+	ScopeMeasure::enable_trace();
+	{
+		ScopeMeasure sm("traced scope");
+		sm.stopover("here");
+	}
+
+	std::string error_message;
+	REQUIRE(ScopeMeasure::write_trace(tmp.get_path(), error_message));
+	REQUIRE(error_message.empty());
+
+	std::ifstream in(tmp.get_path());
+	const std::string trace((std::istreambuf_iterator<char>(in)),
+		std::istreambuf_iterator<char>());
+	REQUIRE(trace.find("\"traceEvents\"") != std::string::npos);
+	REQUIRE(trace.find("\"name\":\"traced scope\"") != std::string::npos);
+	REQUIRE(trace.find("\"name\":\"traced scope: here\"") != std::string::npos);
+}
+
+TEST_CASE("write_trace() reports an error if the file can't be written",
+	"[ScopeMeasure]")
+{
+	TestHelpers::TempDir tmp;
+	const std::string path = tmp.get_path() + "nonexistent/trace.json";
+
+	std::string error_message;
+	REQUIRE_FALSE(ScopeMeasure::write_trace(path, error_message));
+	REQUIRE_FALSE(error_message.empty());
+}
@@ -530,6 +530,30 @@ TEST_CASE("get_command_output()", "[utils]")
 	REQUIRE(utils::get_command_output("echo c\" d e") == "");
 }
 
+TEST_CASE("get_command_output_with_limits() captures stdout, stderr and "
+	"exit code", "[utils]")
+{
+	const auto result = utils::get_command_output_with_limits(
+			"echo out; echo err >&2; exit 3", std::chrono::seconds(0));
+	REQUIRE(result.has_value());
+	REQUIRE(result->exit_code == 3);
+	REQUIRE(result->output == "out\n");
+	REQUIRE(result->error_output == "err\n");
+	REQUIRE_FALSE(result->timed_out);
+	REQUIRE_FALSE(result->truncated);
+}
+
+TEST_CASE("get_command_output_with_limits() kills the command once "
+	"the timeout expires", "[utils]")
+{
+	const auto result = utils::get_command_output_with_limits(
+			"echo started; sleep 30", std::chrono::seconds(1));
+	REQUIRE(result.has_value());
+	REQUIRE(result->timed_out);
+	REQUIRE_FALSE(result->exit_code.has_value());
+	REQUIRE(result->output == "started\n");
+}
+
 TEST_CASE("extract_filter()", "[utils]")
 {
 	{
@@ -564,6 +588,94 @@ TEST_CASE("extract_filter()", "[utils]")
 	}
 }
 
+TEST_CASE("parse_feed_source()", "[utils]")
+{
+	SECTION("http:// and https:// URLs") {
+		const auto source = utils::parse_feed_source("https://newsboat.org");
+		REQUIRE(source.has_value());
+		REQUIRE(source->kind == utils::FeedSourceKind::URL);
+		REQUIRE(source->location == "https://newsboat.org");
+	}
+
+	SECTION("file:// URLs") {
+		const auto source = utils::parse_feed_source("file:///tmp/feed.xml");
+		REQUIRE(source.has_value());
+		REQUIRE(source->kind == utils::FeedSourceKind::LOCAL_FILE);
+		REQUIRE(source->location == "/tmp/feed.xml");
+	}
+
+	SECTION("queries") {
+		const auto source =
+			utils::parse_feed_source("query:Unread:unread = \"yes\"");
+		REQUIRE(source.has_value());
+		REQUIRE(source->kind == utils::FeedSourceKind::QUERY);
+		REQUIRE(source->title == "Unread");
+		REQUIRE(source->expression == "unread = \"yes\"");
+	}
+
+	SECTION("filters") {
+		const auto source =
+			utils::parse_feed_source("filter:~/bin/script.sh:https://newsboat.org");
+		REQUIRE(source.has_value());
+		REQUIRE(source->kind == utils::FeedSourceKind::FILTER);
+		REQUIRE(source->command == "~/bin/script.sh");
+		REQUIRE(source->location == "https://newsboat.org");
+	}
+
+	SECTION("filters can download from anything curl supports") {
+		const auto source =
+			utils::parse_feed_source("filter:script.sh:ftp://newsboat.org/feed.xml");
+		REQUIRE(source.has_value());
+		REQUIRE(source->kind == utils::FeedSourceKind::FILTER);
+		REQUIRE(source->location == "ftp://newsboat.org/feed.xml");
+	}
+
+	SECTION("commands") {
+		const auto source = utils::parse_feed_source("exec:~/bin/feed.sh");
+		REQUIRE(source.has_value());
+		REQUIRE(source->kind == utils::FeedSourceKind::EXEC);
+		REQUIRE(source->command == "~/bin/feed.sh");
+	}
+
+	SECTION("malformed URLs are reported") {
+		REQUIRE_FALSE(utils::parse_feed_source("filter::https://newsboat.org"));
+		REQUIRE_FALSE(utils::parse_feed_source("filter:https://newsboat.org"));
+		REQUIRE_FALSE(utils::parse_feed_source("filter:foo:"));
+		REQUIRE_FALSE(utils::parse_feed_source("filter:foo:exec:ls"));
+		REQUIRE_FALSE(utils::parse_feed_source("filter:"));
+		REQUIRE_FALSE(utils::parse_feed_source("exec:"));
+
+		const auto source = utils::parse_feed_source("filter:");
+		REQUIRE_FALSE(source.error().empty());
+	}
+
+	SECTION("unsupported URLs are reported") {
+		REQUIRE_FALSE(utils::parse_feed_source("ftp://newsboat.org"));
+		REQUIRE_FALSE(utils::parse_feed_source("newsboat.org"));
+	}
+}
+
+TEST_CASE("parse_query_url()", "[utils]")
+{
+	SECTION("expression can contain colons") {
+		const auto parts = utils::parse_query_url(
+				"query:Title:unread = \"yes\" and age between 0:7");
+		REQUIRE(parts.has_value());
+		REQUIRE(parts->title == "Title");
+		REQUIRE(parts->expression == "unread = \"yes\" and age between 0:7");
+	}
+
+	SECTION("malformed URLs are reported") {
+		REQUIRE_FALSE(utils::parse_query_url("query:"));
+		REQUIRE_FALSE(utils::parse_query_url("query:Title"));
+		REQUIRE_FALSE(utils::parse_query_url("query::unread = \"yes\""));
+	}
+
+	SECTION("other URLs are reported") {
+		REQUIRE_FALSE(utils::parse_query_url("https://newsboat.org"));
+	}
+}
+
 TEST_CASE("run_program()", "[utils]")
 {
 	const char* argv[4];
@@ -580,6 +692,24 @@ TEST_CASE("run_program()", "[utils]")
 	REQUIRE(utils::run_program(argv, "") == "hello world");
 }
 
+TEST_CASE("run_program_with_limits()", "[utils]")
+{
+	const char* argv[4];
+	argv[0] = "cat";
+	argv[1] = nullptr;
+	const auto result = utils::run_program_with_limits(argv,
+			"this is a multine-line\ntest string", std::chrono::seconds(10));
+	REQUIRE(result.has_value());
+	REQUIRE(result->exit_code == 0);
+	REQUIRE(result->output == "this is a multine-line\ntest string");
+
+	argv[0] = "a-program-that-is-guaranteed-to-not-exists";
+	const auto error = utils::run_program_with_limits(argv, "",
+			std::chrono::seconds(10));
+	REQUIRE_FALSE(error.has_value());
+	REQUIRE_FALSE(error.error().empty());
+}
+
 TEST_CASE("run_command() executes the given command with a given argument",
 	"[utils]")
 {